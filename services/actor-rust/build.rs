@@ -1,13 +1,13 @@
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Generate protobuf code for engine and replay services
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Generate protobuf code for engine and replay services
     tonic_build::configure()
         .build_server(true)
-        .compile(
-            &[
-                "../../proto/engine/v1/engine.proto",
-                "../../proto/replay/v1/replay.proto",
-            ],
-            &["../../proto"],
-        )?;
-    Ok(())
+        .compile(
+            &[
+                "../../proto/engine/v1/engine.proto",
+                "../../proto/replay/v1/replay.proto",
+            ],
+            &["../../proto"],
+        )?;
+    Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,390 @@
+use crate::proto::engine::v1::{capabilities, Capabilities};
+use anyhow::{anyhow, Result};
+
+/// Typed action expressed as indices or values rather than wire bytes
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Index into a discrete action space
+    Discrete(u32),
+    /// One index per dimension of a multi-discrete action space
+    MultiDiscrete(Vec<u32>),
+    /// One value per dimension of a continuous action space
+    Continuous(Vec<f32>),
+}
+
+/// Action space fetched from the engine's capabilities
+///
+/// Wire encoding follows the engine's `decode_action` conventions:
+/// - Discrete indices are little-endian in the narrowest unsigned width that
+///   fits `n` (u8 for n <= 256, u16 for n <= 65536, otherwise u32), so a
+///   TicTacToe move is a single byte.
+/// - Multi-discrete actions concatenate each index using the width for its own `n`.
+/// - Continuous actions are little-endian f32 values.
+/// - Discrete indices of an engine whose action encoding is
+///   `VARINT_ACTION_ENCODING` are unsigned LEB128 instead (`DiscreteVarint`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionSpace {
+    Discrete { n: u32 },
+    /// Discrete space whose indices are encoded as LEB128 varints
+    DiscreteVarint { n: u32 },
+    MultiDiscrete { nvec: Vec<u32> },
+    Continuous { low: Vec<f32>, high: Vec<f32> },
+}
+
+impl ActionSpace {
+    pub fn from_capabilities(capabilities: &Capabilities) -> Result<Self> {
+        match &capabilities.action_space {
+            Some(capabilities::ActionSpace::DiscreteN(n)) => {
+                let varint = capabilities
+                    .enc
+                    .as_ref()
+                    .is_some_and(|enc| enc.action == VARINT_ACTION_ENCODING);
+                Ok(if varint {
+                    ActionSpace::DiscreteVarint { n: *n }
+                } else {
+                    ActionSpace::Discrete { n: *n }
+                })
+            }
+            Some(capabilities::ActionSpace::Multi(multi)) => Ok(ActionSpace::MultiDiscrete {
+                nvec: multi.nvec.clone(),
+            }),
+            Some(capabilities::ActionSpace::Continuous(box_spec)) => Ok(ActionSpace::Continuous {
+                low: box_spec.low.clone(),
+                high: box_spec.high.clone(),
+            }),
+            None => Err(anyhow!("No action space specified in capabilities")),
+        }
+    }
+
+    /// Encode a typed action into the wire bytes expected by the engine
+    pub fn encode(&self, action: &Action) -> Result<Vec<u8>> {
+        match (self, action) {
+            (ActionSpace::Discrete { n }, Action::Discrete(index)) => {
+                let mut out = Vec::with_capacity(discrete_width(*n));
+                encode_index(*index, *n, &mut out)?;
+                Ok(out)
+            }
+            (ActionSpace::DiscreteVarint { n }, Action::Discrete(index)) => {
+                let mut out = Vec::with_capacity(MAX_VARINT_LEN);
+                encode_varint(*index, *n, &mut out)?;
+                Ok(out)
+            }
+            (ActionSpace::MultiDiscrete { nvec }, Action::MultiDiscrete(indices)) => {
+                if indices.len() != nvec.len() {
+                    return Err(anyhow!(
+                        "Multi-discrete action has {} dimensions, expected {}",
+                        indices.len(),
+                        nvec.len()
+                    ));
+                }
+                let mut out = Vec::with_capacity(nvec.iter().map(|&n| discrete_width(n)).sum());
+                for (&index, &n) in indices.iter().zip(nvec.iter()) {
+                    encode_index(index, n, &mut out)?;
+                }
+                Ok(out)
+            }
+            (ActionSpace::Continuous { low, high }, Action::Continuous(values)) => {
+                if values.len() != low.len() || values.len() != high.len() {
+                    return Err(anyhow!(
+                        "Continuous action has {} dimensions, expected {}",
+                        values.len(),
+                        low.len()
+                    ));
+                }
+                let mut out = Vec::with_capacity(values.len() * 4);
+                for ((&value, &low_val), &high_val) in values.iter().zip(low).zip(high) {
+                    if !(low_val..=high_val).contains(&value) {
+                        return Err(anyhow!(
+                            "Continuous action value {} outside bounds [{}, {}]",
+                            value,
+                            low_val,
+                            high_val
+                        ));
+                    }
+                    out.extend_from_slice(&value.to_le_bytes());
+                }
+                Ok(out)
+            }
+            (space, action) => Err(anyhow!(
+                "Action {:?} does not match action space {:?}",
+                action,
+                space
+            )),
+        }
+    }
+
+    /// Decode wire bytes back into a typed action
+    #[allow(dead_code)]
+    pub fn decode(&self, bytes: &[u8]) -> Result<Action> {
+        match self {
+            ActionSpace::Discrete { n } => {
+                let width = discrete_width(*n);
+                if bytes.len() != width {
+                    return Err(anyhow!(
+                        "Discrete action expected {} bytes, got {}",
+                        width,
+                        bytes.len()
+                    ));
+                }
+                Ok(Action::Discrete(decode_index(bytes, *n)?))
+            }
+            ActionSpace::DiscreteVarint { n } => Ok(Action::Discrete(decode_varint(bytes, *n)?)),
+            ActionSpace::MultiDiscrete { nvec } => {
+                let expected: usize = nvec.iter().map(|&n| discrete_width(n)).sum();
+                if bytes.len() != expected {
+                    return Err(anyhow!(
+                        "Multi-discrete action expected {} bytes, got {}",
+                        expected,
+                        bytes.len()
+                    ));
+                }
+                let mut offset = 0;
+                let mut indices = Vec::with_capacity(nvec.len());
+                for &n in nvec {
+                    let width = discrete_width(n);
+                    indices.push(decode_index(&bytes[offset..offset + width], n)?);
+                    offset += width;
+                }
+                Ok(Action::MultiDiscrete(indices))
+            }
+            ActionSpace::Continuous { low, .. } => {
+                if bytes.len() != low.len() * 4 {
+                    return Err(anyhow!(
+                        "Continuous action expected {} bytes, got {}",
+                        low.len() * 4,
+                        bytes.len()
+                    ));
+                }
+                let values = bytes
+                    .chunks_exact(4)
+                    .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+                    .collect();
+                Ok(Action::Continuous(values))
+            }
+        }
+    }
+}
+
+/// Action encoding name of engines that send discrete actions as LEB128
+///
+/// Mirrors `engine_core::varint::VARINT_ACTION_ENCODING`.
+pub const VARINT_ACTION_ENCODING: &str = "discrete_varint:v1";
+
+/// Longest LEB128 encoding of a `u32`
+const MAX_VARINT_LEN: usize = 5;
+
+/// Metadata key holding the actions legal in a transition's `next_state`
+pub const NEXT_LEGAL_ACTIONS_METADATA_KEY: &str = "next_legal_actions";
+
+impl ActionSpace {
+    /// Decode a step's `info` bitmask into the legal action indices
+    ///
+    /// Bit `i` set means index `i` is legal. Only the low `n` bits are read
+    /// (at most 64), since games may pack other fields above the mask. Returns
+    /// `None` for non-discrete spaces, whose `info` has no legal-move layout.
+    pub fn legal_actions(&self, info: u64) -> Option<Vec<u32>> {
+        match self {
+            ActionSpace::Discrete { n } | ActionSpace::DiscreteVarint { n } => Some(
+                (0..(*n).min(u64::BITS))
+                    .filter(|&index| info & (1 << index) != 0)
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// Compact metadata form of an index list, e.g. `"0,2,5"`
+pub fn format_indices(indices: &[u32]) -> String {
+    indices
+        .iter()
+        .map(|index| index.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Byte width used to encode an index into a discrete space of size `n`
+pub fn discrete_width(n: u32) -> usize {
+    if n <= 1 << 8 {
+        1
+    } else if n <= 1 << 16 {
+        2
+    } else {
+        4
+    }
+}
+
+fn encode_index(index: u32, n: u32, out: &mut Vec<u8>) -> Result<()> {
+    if index >= n {
+        return Err(anyhow!("Action index {} out of range for n={}", index, n));
+    }
+    let bytes = index.to_le_bytes();
+    out.extend_from_slice(&bytes[..discrete_width(n)]);
+    Ok(())
+}
+
+/// Append `index` as LEB128: seven bits per byte, low bits first, with the
+/// high bit set on every byte but the last
+fn encode_varint(index: u32, n: u32, out: &mut Vec<u8>) -> Result<()> {
+    if index >= n {
+        return Err(anyhow!("Action index {} out of range for n={}", index, n));
+    }
+    let mut rest = index;
+    while rest >= 0x80 {
+        out.push(rest as u8 | 0x80);
+        rest >>= 7;
+    }
+    out.push(rest as u8);
+    Ok(())
+}
+
+/// Decode a LEB128 index spanning all of `bytes`, accepting only the
+/// shortest encoding like the engine does
+fn decode_varint(bytes: &[u8], n: u32) -> Result<u32> {
+    let len = bytes
+        .iter()
+        .position(|byte| byte & 0x80 == 0)
+        .map(|end| end + 1)
+        .ok_or_else(|| anyhow!("Varint action {:02x?} is unterminated", bytes))?;
+    if len != bytes.len() || len > MAX_VARINT_LEN || (len > 1 && bytes[len - 1] == 0) {
+        return Err(anyhow!("Varint action {:02x?} is malformed", bytes));
+    }
+    let index = bytes.iter().enumerate().try_fold(0u32, |index, (i, &byte)| {
+        let group = u32::from(byte & 0x7f);
+        let shift = i as u32 * 7;
+        let shifted = group << shift;
+        (shifted >> shift == group).then_some(index | shifted)
+    });
+    match index {
+        Some(index) if index < n => Ok(index),
+        Some(index) => Err(anyhow!("Action index {} out of range for n={}", index, n)),
+        None => Err(anyhow!("Varint action {:02x?} overflows a u32", bytes)),
+    }
+}
+
+fn decode_index(bytes: &[u8], n: u32) -> Result<u32> {
+    let mut padded = [0u8; 4];
+    padded[..bytes.len()].copy_from_slice(bytes);
+    let index = u32::from_le_bytes(padded);
+    if index >= n {
+        return Err(anyhow!("Action index {} out of range for n={}", index, n));
+    }
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::engine::v1::Encoding;
+
+    #[test]
+    fn test_discrete_roundtrip_matches_engine_byte_width() {
+        let space = ActionSpace::Discrete { n: 9 };
+        let bytes = space.encode(&Action::Discrete(4)).unwrap();
+
+        // TicTacToe's decode_action expects exactly one byte
+        assert_eq!(bytes, vec![4]);
+        assert_eq!(space.decode(&bytes).unwrap(), Action::Discrete(4));
+
+        let wide = ActionSpace::Discrete { n: 1000 };
+        let bytes = wide.encode(&Action::Discrete(999)).unwrap();
+        assert_eq!(bytes.len(), 2);
+        assert_eq!(wide.decode(&bytes).unwrap(), Action::Discrete(999));
+    }
+
+    #[test]
+    fn test_varint_roundtrip_across_byte_width_boundaries() {
+        let space = ActionSpace::DiscreteVarint { n: 20_000 };
+        for (index, len) in [(0, 1), (127, 1), (128, 2), (16_383, 2), (16_384, 3)] {
+            let bytes = space.encode(&Action::Discrete(index)).unwrap();
+            assert_eq!(bytes.len(), len, "index {}", index);
+            assert_eq!(space.decode(&bytes).unwrap(), Action::Discrete(index));
+        }
+
+        assert!(space.encode(&Action::Discrete(20_000)).is_err());
+        assert!(space.decode(&[0x80]).is_err());
+        assert!(space.decode(&[0x80, 0x00]).is_err());
+        assert!(space.decode(&[0x01, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_varint_space_is_selected_by_action_encoding() {
+        let mut capabilities = Capabilities {
+            enc: Some(Encoding {
+                action: VARINT_ACTION_ENCODING.to_string(),
+                ..Default::default()
+            }),
+            action_space: Some(capabilities::ActionSpace::DiscreteN(300)),
+            ..Default::default()
+        };
+        assert_eq!(
+            ActionSpace::from_capabilities(&capabilities).unwrap(),
+            ActionSpace::DiscreteVarint { n: 300 }
+        );
+
+        capabilities.enc = None;
+        assert_eq!(
+            ActionSpace::from_capabilities(&capabilities).unwrap(),
+            ActionSpace::Discrete { n: 300 }
+        );
+    }
+
+    #[test]
+    fn test_multi_discrete_roundtrip() {
+        let space = ActionSpace::MultiDiscrete {
+            nvec: vec![2, 300, 100_000],
+        };
+        let action = Action::MultiDiscrete(vec![1, 299, 99_999]);
+        let bytes = space.encode(&action).unwrap();
+
+        assert_eq!(bytes.len(), 1 + 2 + 4);
+        assert_eq!(space.decode(&bytes).unwrap(), action);
+    }
+
+    #[test]
+    fn test_continuous_roundtrip() {
+        let space = ActionSpace::Continuous {
+            low: vec![-1.0, 0.0],
+            high: vec![1.0, 2.0],
+        };
+        let action = Action::Continuous(vec![0.5, 1.25]);
+        let bytes = space.encode(&action).unwrap();
+
+        assert_eq!(bytes.len(), 8);
+        assert_eq!(space.decode(&bytes).unwrap(), action);
+    }
+
+    #[test]
+    fn test_encode_rejects_invalid_actions() {
+        let discrete = ActionSpace::Discrete { n: 9 };
+        assert!(discrete.encode(&Action::Discrete(9)).is_err());
+        assert!(discrete.encode(&Action::Continuous(vec![0.0])).is_err());
+
+        let multi = ActionSpace::MultiDiscrete { nvec: vec![2, 3] };
+        assert!(multi.encode(&Action::MultiDiscrete(vec![1])).is_err());
+
+        let continuous = ActionSpace::Continuous {
+            low: vec![0.0],
+            high: vec![1.0],
+        };
+        assert!(continuous.encode(&Action::Continuous(vec![2.0])).is_err());
+    }
+
+    #[test]
+    fn test_legal_actions_from_info_bitmask() {
+        // TicTacToe packs the current player above its 9-bit mask
+        let space = ActionSpace::Discrete { n: 9 };
+        let info = 0b1_0110_0101 | (2 << 16);
+        let legal = space.legal_actions(info).unwrap();
+
+        assert_eq!(legal, vec![0, 2, 5, 6, 8]);
+        assert_eq!(format_indices(&legal), "0,2,5,6,8");
+        assert_eq!(space.legal_actions(0), Some(vec![]));
+
+        let wide = ActionSpace::Discrete { n: 100 };
+        assert_eq!(wide.legal_actions(1 << 63), Some(vec![63]));
+
+        let multi = ActionSpace::MultiDiscrete { nvec: vec![2, 3] };
+        assert_eq!(multi.legal_actions(u64::MAX), None);
+    }
+}
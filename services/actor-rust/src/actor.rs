@@ -1,262 +1,740 @@
-use anyhow::{anyhow, Result};
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use tokio::time::{interval, timeout};
-use tonic::{transport::Channel, Request};
-use tracing::{debug, error, info};
-
-use crate::config::Config;
-use crate::policy::{Policy, RandomPolicy};
-use crate::proto::engine::v1::{
-    engine_client::EngineClient, EngineId, ResetRequest, StepRequest,
-};
-use crate::proto::replay::v1::{
-    replay_client::ReplayClient, StoreBatchRequest, Transition,
-};
-
-pub struct Actor {
-    config: Config,
-    engine_client: EngineClient<Channel>,
-    replay_client: ReplayClient<Channel>,
-    policy: Arc<Mutex<Box<dyn Policy>>>,
-    episode_count: Arc<Mutex<u32>>,
-    transition_buffer: Arc<Mutex<Vec<Transition>>>,
-    shutdown_signal: Arc<Mutex<bool>>,
-}
-
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::{interval, timeout, Instant};
+use tokio_util::sync::CancellationToken;
+use tonic::{transport::Channel, Code, Request, Status};
+use tracing::{debug, error, info, warn};
+
+use crate::action::{format_indices, ActionSpace, NEXT_LEGAL_ACTIONS_METADATA_KEY};
+use crate::batching::AdaptiveBatchSize;
+use crate::compat;
+use crate::config::Config;
+use crate::health::HealthState;
+use crate::policy::{Policy, RandomPolicy, ScriptEnd, ScriptedPolicy};
+use crate::priority::{Priority, PriorityFn};
+use crate::proto::engine::v1::{
+    engine_client::EngineClient, EngineId, ResetRequest, StepRequest,
+};
+use crate::proto::replay::v1::{replay_client::ReplayClient, Transition};
+use crate::seed::derive_episode_seed;
+use crate::sink::{spill_to_file, ReplaySink, StoreOutcome, TransitionSink};
+use crate::reward::{
+    n_step_returns, RewardNormalizer, N_STEP_RETURN_METADATA_KEY, RAW_REWARD_METADATA_KEY,
+};
+use crate::proto::engine::v1::Capabilities;
+use crate::transition::TransitionBuilder;
+use crate::transport;
+use crate::warmup::WarmupDiscard;
+
+/// Metadata key holding the player who acted in a self-play transition
+pub const PLAYER_METADATA_KEY: &str = "player";
+
+/// gRPC metadata header carrying a step's correlation id to the engine
+pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
+
+/// Metadata key holding the correlation id of the step that produced a transition
+pub const CORRELATION_ID_METADATA_KEY: &str = "correlation_id";
+
+/// Metadata key holding how many engine steps a transition spans under action repeat
+pub const ACTION_REPEATS_METADATA_KEY: &str = "action_repeats";
+
+/// Prefix of the metadata keys holding a step's `step_info` entries, e.g. `info.captured`
+pub const STEP_INFO_METADATA_PREFIX: &str = "info.";
+
+/// `step_info` key under which a game reports the other player's reward for a
+/// final step whose outcome is not zero-sum, e.g. a draw that pays both players
+pub const OPPONENT_REWARD_STEP_INFO_KEY: &str = "opponent_reward";
+
+/// Metadata key set to `true` on the final transition of an episode that was
+/// cut short, by the engine or by `max_steps`, rather than terminated
+pub const TRUNCATED_METADATA_KEY: &str = "truncated";
+
+/// Times a step is retried after the engine evicted the episode's game
+const MAX_EVICTION_RETRIES: u32 = 3;
+
+/// Whether `status` is the engine reporting that its cached game is gone
+///
+/// The engine drops cached games that sit idle for longer than its
+/// `ENGINE_GAME_TTL_SECS`. If ours is evicted between our reset and a step,
+/// the step fails with this specific `FAILED_PRECONDITION`; any other status
+/// is a genuine error.
+fn is_game_evicted(status: &Status) -> bool {
+    status.code() == Code::FailedPrecondition && status.message().contains("not initialized")
+}
+
+/// Observes each transition just before it is queued for replay
+pub type TransitionHook = Box<dyn Fn(&Transition) + Send + Sync>;
+
+/// Decides whether a transition is sent to replay; `false` drops it
+pub type TransitionFilter = Box<dyn Fn(&Transition) -> bool + Send + Sync>;
+
+/// How an episode ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EpisodeEnd {
+    /// The environment reported `done`
+    Completed,
+    /// The episode was cancelled before finishing; it is not counted
+    Cancelled,
+}
+
+pub struct Actor {
+    config: Config,
+    engine_client: EngineClient<Channel>,
+    /// Where flushed batches go; the replay service unless overridden
+    sink: Box<dyn TransitionSink>,
+    policy: Arc<Mutex<Box<dyn Policy>>>,
+    /// Drives every player other than the first in self-play mode
+    opponent: Option<Arc<Mutex<Box<dyn Policy>>>>,
+    episode_count: Arc<Mutex<u32>>,
+    transition_buffer: Arc<Mutex<Vec<Transition>>>,
+    /// Flush size tracking replay latency; `None` flushes at `batch_size`
+    adaptive_batch: Option<Mutex<AdaptiveBatchSize>>,
+    /// Cancelled on shutdown; parent of every episode token
+    shutdown_token: CancellationToken,
+    /// Token for the in-flight episode
+    episode_token: Mutex<CancellationToken>,
+    reward_normalizer: Option<Arc<Mutex<RewardNormalizer>>>,
+    /// Decodes legal moves from step `info`; `None` when the space is unknown
+    action_space: Option<ActionSpace>,
+    /// Called for every transition that survives `transition_filter`
+    transition_hook: Option<TransitionHook>,
+    transition_filter: Option<TransitionFilter>,
+    /// Sets the replay priority of every transition before filtering
+    priority_fn: PriorityFn,
+    /// Drops transitions until `--warmup-discard` is used up; `None` when unset
+    warmup: Option<Mutex<WarmupDiscard>>,
+    health: Arc<HealthState>,
+}
+
 impl Actor {
-    pub async fn new(config: Config) -> Result<Self> {
-        // Connect to engine service
-        info!("Connecting to engine service at {}", config.engine_addr);
-        let engine_channel = tonic::transport::Endpoint::new(config.engine_addr.clone())?
-            .connect()
-            .await
-            .map_err(|e| anyhow!("Failed to connect to engine at {}: {}", config.engine_addr, e))?;
-
-        // Connect to replay service
-        info!("Connecting to replay service at {}", config.replay_addr);
-        let replay_channel = tonic::transport::Endpoint::new(config.replay_addr.clone())?
-            .connect()
-            .await
-            .map_err(|e| anyhow!("Failed to connect to replay at {}: {}", config.replay_addr, e))?;
-
-        let mut engine_client = EngineClient::new(engine_channel);
-        let replay_client = ReplayClient::new(replay_channel);
-
-        // Get game capabilities to configure policy
-        info!("Fetching capabilities for environment: {}", config.env_id);
-        let capabilities_request = Request::new(EngineId {
-            env_id: config.env_id.clone(),
-            build_id: "actor-rust".to_string(),
-        });
-
-        let capabilities_response = engine_client
-            .get_capabilities(capabilities_request)
-            .await
-            .map_err(|e| anyhow!("Failed to get capabilities for {}: {}", config.env_id, e))?;
-
-        let capabilities = capabilities_response.into_inner();
-
-        // Create random policy based on action space
-        let policy = RandomPolicy::new(&capabilities)
-            .map_err(|e| anyhow!("Failed to create policy: {}", e))?;
-
-        info!(
-            "Actor {} initialized for environment {}",
-            config.actor_id, config.env_id
-        );
-        info!(
-            "Game capabilities: max_horizon={}, preferred_batch={}",
-            capabilities.max_horizon, capabilities.preferred_batch
-        );
-
-        Ok(Self {
-            config,
-            engine_client,
-            replay_client,
-            policy: Arc::new(Mutex::new(Box::new(policy))),
-            episode_count: Arc::new(Mutex::new(0)),
-            transition_buffer: Arc::new(Mutex::new(Vec::new())),
-            shutdown_signal: Arc::new(Mutex::new(false)),
-        })
-    }
-
-    pub async fn run(&self) -> Result<()> {
-        info!("Actor {} starting main loop", self.config.actor_id);
-
-        // Setup flush timer for partial batches
-        let mut flush_timer = interval(self.config.flush_interval());
-
-        loop {
-            // Check shutdown signal
-            if *self.shutdown_signal.lock().unwrap() {
-                info!("Shutdown signal received, stopping actor");
-                break;
-            }
-
-            tokio::select! {
-                _ = flush_timer.tick() => {
-                    // Flush partial batches periodically
-                    let buffer_len = self.transition_buffer.lock().unwrap().len();
-                    if buffer_len > 0 {
-                        debug!("Periodic flush: {} transitions in buffer", buffer_len);
-                        if let Err(e) = self.flush_buffer().await {
-                            error!("Failed to flush buffer: {}", e);
-                        }
-                    }
-                }
-
-                _ = tokio::time::sleep(Duration::from_millis(1)) => {
-                    // Check episode limit
-                    let current_episode_count = *self.episode_count.lock().unwrap();
-                    if self.config.max_episodes > 0 && current_episode_count >= self.config.max_episodes as u32 {
-                        info!("Reached maximum episodes ({}), stopping", self.config.max_episodes);
-                        break;
-                    }
-
-                    // Run an episode
-                    match self.run_episode().await {
-                        Ok(_) => {
-                            let mut count = self.episode_count.lock().unwrap();
-                            *count += 1;
-                            if *count % 10 == 0 {
-                                info!("Completed {} episodes", *count);
-                            }
-                        }
-                        Err(e) => {
-                            let count = *self.episode_count.lock().unwrap();
-                            error!("Episode {} failed: {}", count + 1, e);
-                            // Continue with next episode rather than stopping
-                        }
-                    }
-                }
-            }
-        }
-
-        // Flush any remaining transitions
-        self.flush_buffer().await?;
-        info!("Actor stopped gracefully");
-        Ok(())
-    }
-
-    pub async fn shutdown(&self) {
-        *self.shutdown_signal.lock().unwrap() = true;
-        info!("Shutdown signal set");
-    }
-
-    async fn run_episode(&self) -> Result<()> {
-        let episode_count = *self.episode_count.lock().unwrap();
-
-        // Reset the game
-        let reset_request = Request::new(ResetRequest {
-            id: Some(EngineId {
-                env_id: self.config.env_id.clone(),
-                build_id: "actor-rust".to_string(),
-            }),
-            seed: SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64,
-            hint: vec![],
-        });
-
-        let reset_response = timeout(
-            self.config.episode_timeout(),
-            self.engine_client.clone().reset(reset_request),
-        )
-        .await
-        .map_err(|_| anyhow!("Reset timed out"))?
-        .map_err(|e| anyhow!("Failed to reset game: {}", e))?;
-
-        let reset_data = reset_response.into_inner();
-        let episode_id = format!("{}-ep-{}-{}",
-            self.config.actor_id,
-            episode_count,
-            SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs()
-        );
-
-        let mut current_state = reset_data.state;
-        let mut current_obs = reset_data.obs;
-        let mut step_number = 0u32;
-
-        debug!("Started episode {}", episode_id);
-
-        loop {
-            // Select action using policy
-            let action = {
-                let mut policy = self.policy.lock().unwrap();
-                policy.select_action(&current_obs)
-                    .map_err(|e| anyhow!("Failed to select action: {}", e))?
-            };
-
-            // Take step in environment
-            let step_request = Request::new(StepRequest {
-                id: Some(EngineId {
-                    env_id: self.config.env_id.clone(),
-                    build_id: "actor-rust".to_string(),
-                }),
-                state: current_state.clone(),
-                action: action.clone(),
-            });
-
-            let step_response = timeout(
-                self.config.episode_timeout(),
-                self.engine_client.clone().step(step_request),
-            )
-            .await
-            .map_err(|_| anyhow!("Step timed out"))?
-            .map_err(|e| anyhow!("Failed to step environment: {}", e))?;
-
-            let step_data = step_response.into_inner();
-
-            // Create transition
-            let transition = Transition {
-                id: format!("{}-step-{}", episode_id, step_number),
-                env_id: self.config.env_id.clone(),
-                episode_id: episode_id.clone(),
-                step_number,
-                state: current_state.clone(),
-                action,
-                next_state: step_data.state.clone(),
-                observation: current_obs.clone(),
-                next_observation: step_data.obs.clone(),
-                reward: step_data.reward,
-                done: step_data.done,
-                priority: 1.0, // Default priority
-                timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
-                metadata: std::collections::HashMap::new(),
-            };
-
-            // Add to buffer
-            {
-                let mut buffer = self.transition_buffer.lock().unwrap();
-                buffer.push(transition);
-
-                // Flush buffer if full
-                if buffer.len() >= self.config.batch_size {
-                    drop(buffer); // Release lock before async call
-                    self.flush_buffer().await?;
-                }
-            }
-
-            // Check if episode is done
-            if step_data.done {
-                debug!(
-                    "Episode {} completed in {} steps, final reward: {:.2}",
-                    episode_id,
-                    step_number + 1,
-                    step_data.reward
-                );
-                break;
-            }
-
-            // Update state for next step
-            current_state = step_data.state;
-            current_obs = step_data.obs;
-            step_number += 1;
-        }
-
-        Ok(())
-    }
-
-    async fn flush_buffer(&self) -> Result<()> {
+    pub async fn new(config: Config) -> Result<Self> {
+        // Connect to engine service
+        info!("Connecting to engine service at {}", config.engine_addr);
+        let engine_channel = transport::connect(&config.engine_addr, &config)
+            .await
+            .map_err(|e| anyhow!("Failed to connect to engine at {}: {}", config.engine_addr, e))?;
+
+        // Connect to replay service
+        info!("Connecting to replay service at {}", config.replay_addr);
+        let replay_channel = transport::connect(&config.replay_addr, &config)
+            .await
+            .map_err(|e| anyhow!("Failed to connect to replay at {}: {}", config.replay_addr, e))?;
+
+        let mut engine_client = EngineClient::new(engine_channel);
+        let replay_client = ReplayClient::new(replay_channel);
+
+        // Get game capabilities to configure policy
+        info!("Fetching capabilities for environment: {}", config.env_id);
+        let capabilities_request = Request::new(EngineId {
+            env_id: config.env_id.clone(),
+            build_id: "actor-rust".to_string(),
+        });
+
+        let capabilities_response = engine_client
+            .get_capabilities(capabilities_request)
+            .await
+            .map_err(|e| anyhow!("Failed to get capabilities for {}: {}", config.env_id, e))?;
+
+        let capabilities = capabilities_response.into_inner();
+        compat::check_wire_compatibility(&config.env_id, &capabilities)?;
+
+        let policy = build_policy(config.script_file.as_deref(), &config, &capabilities)?;
+        let opponent = if config.self_play {
+            info!("Self-play enabled: the second player uses its own policy");
+            let opponent =
+                build_policy(config.opponent_script_file.as_deref(), &config, &capabilities)?;
+            Some(Arc::new(Mutex::new(opponent)))
+        } else {
+            None
+        };
+
+        info!(
+            "Actor {} initialized for environment {}",
+            config.actor_id, config.env_id
+        );
+        info!(
+            "Game capabilities: max_horizon={}, preferred_batch={}",
+            capabilities.max_horizon, capabilities.preferred_batch
+        );
+        if !capabilities.obs_layout.is_empty() {
+            let segments: Vec<String> = capabilities
+                .obs_layout
+                .iter()
+                .map(|segment| format!("{}={}", segment.name, segment.size))
+                .collect();
+            info!("Observation layout: {}", segments.join(", "));
+        }
+
+        let reward_normalizer = config
+            .normalize_rewards
+            .then(|| Arc::new(Mutex::new(RewardNormalizer::new())));
+
+        let action_space = ActionSpace::from_capabilities(&capabilities).ok();
+
+        let priority: Priority = config.priority.parse()?;
+        let adaptive_batch = config.adaptive_batch.then(|| {
+            Mutex::new(AdaptiveBatchSize::new(
+                config.batch_size,
+                config.min_batch_size,
+                config.max_batch_size,
+                config.batch_latency_target(),
+            ))
+        });
+        let warmup = (config.warmup_discard > 0).then(|| {
+            Mutex::new(WarmupDiscard::new(
+                config.warmup_discard,
+                config.warmup_whole_episodes,
+            ))
+        });
+        let health = Arc::new(HealthState::new(config.readiness_staleness()));
+        health.mark_connected();
+
+        Ok(Self {
+            config,
+            engine_client,
+            sink: Box::new(ReplaySink::new(replay_client)),
+            policy: Arc::new(Mutex::new(policy)),
+            opponent,
+            episode_count: Arc::new(Mutex::new(0)),
+            transition_buffer: Arc::new(Mutex::new(Vec::new())),
+            adaptive_batch,
+            shutdown_token: CancellationToken::new(),
+            episode_token: Mutex::new(CancellationToken::new()),
+            reward_normalizer,
+            action_space,
+            transition_hook: None,
+            transition_filter: None,
+            priority_fn: priority.priority_fn(),
+            warmup,
+            health,
+        })
+    }
+
+    /// Observe every transition before it is queued for replay
+    ///
+    /// The hook sees transitions after episode post-processing (n-step
+    /// returns, self-play reward attribution) and after any filter. It runs
+    /// on the actor's task without holding the transition buffer lock.
+    #[allow(dead_code)]
+    pub fn with_transition_hook(
+        mut self,
+        hook: impl Fn(&Transition) + Send + Sync + 'static,
+    ) -> Self {
+        self.transition_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Drop transitions for which `filter` returns `false` before they reach replay
+    #[allow(dead_code)]
+    pub fn with_transition_filter(
+        mut self,
+        filter: impl Fn(&Transition) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.transition_filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Compute each transition's replay priority with `priority_fn`
+    ///
+    /// Replaces the function selected by `--priority`. It sees transitions
+    /// after episode post-processing, so n-step returns are in the metadata.
+    #[allow(dead_code)]
+    pub fn with_priority_fn(
+        mut self,
+        priority_fn: impl Fn(&Transition) -> f32 + Send + Sync + 'static,
+    ) -> Self {
+        self.priority_fn = Box::new(priority_fn);
+        self
+    }
+
+    /// Send flushed batches to `sink` instead of the replay service
+    ///
+    /// Batching, periodic flushes, and the final flush on shutdown are
+    /// unchanged; only the destination of each batch differs.
+    #[allow(dead_code)]
+    pub fn with_transition_sink(mut self, sink: impl TransitionSink + 'static) -> Self {
+        self.sink = Box::new(sink);
+        self
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        info!("Actor {} starting main loop", self.config.actor_id);
+
+        // Setup flush timer for partial batches
+        let mut flush_timer = interval(self.config.flush_interval());
+
+        loop {
+            // Check shutdown signal
+            if self.shutdown_token.is_cancelled() {
+                info!("Shutdown signal received, stopping actor");
+                break;
+            }
+
+            tokio::select! {
+                _ = flush_timer.tick() => {
+                    // Flush partial batches periodically
+                    let buffer_len = self.transition_buffer.lock().unwrap().len();
+                    if buffer_len > 0 {
+                        debug!("Periodic flush: {} transitions in buffer", buffer_len);
+                        if let Err(e) = self.flush_buffer().await {
+                            error!("Failed to flush buffer: {}", e);
+                        }
+                    }
+                }
+
+                _ = tokio::time::sleep(Duration::from_millis(1)) => {
+                    // Check episode limit
+                    let current_episode_count = *self.episode_count.lock().unwrap();
+                    if self.config.max_episodes > 0 && current_episode_count >= self.config.max_episodes as u32 {
+                        info!("Reached maximum episodes ({}), stopping", self.config.max_episodes);
+                        break;
+                    }
+
+                    // Run an episode that shutdown or cancel_episode can abort
+                    let cancel = self.shutdown_token.child_token();
+                    *self.episode_token.lock().unwrap() = cancel.clone();
+
+                    match self.run_episode(&cancel).await {
+                        Ok(EpisodeEnd::Completed) => {
+                            self.health.record_episode();
+                            let mut count = self.episode_count.lock().unwrap();
+                            *count += 1;
+                            if *count % 10 == 0 {
+                                info!("Completed {} episodes", *count);
+                            }
+                        }
+                        Ok(EpisodeEnd::Cancelled) => {
+                            let count = *self.episode_count.lock().unwrap();
+                            info!("Episode {} cancelled", count + 1);
+                        }
+                        Err(e) => {
+                            let count = *self.episode_count.lock().unwrap();
+                            error!("Episode {} failed: {}", count + 1, e);
+                            // Continue with next episode rather than stopping
+                        }
+                    }
+                }
+            }
+        }
+
+        self.drain().await?;
+        info!("Actor stopped gracefully");
+        Ok(())
+    }
+
+    pub async fn shutdown(&self) {
+        self.shutdown_token.cancel();
+        info!("Shutdown signal set");
+    }
+
+    /// Liveness and readiness state served by the health endpoints
+    pub fn health(&self) -> Arc<HealthState> {
+        Arc::clone(&self.health)
+    }
+
+    /// Abort the in-flight episode without stopping the actor
+    ///
+    /// Transitions collected so far are flushed and the episode is not counted.
+    pub fn cancel_episode(&self) {
+        self.episode_token.lock().unwrap().cancel();
+        info!("Cancel requested for the current episode");
+    }
+
+    async fn run_episode(&self, cancel: &CancellationToken) -> Result<EpisodeEnd> {
+        let episode_count = *self.episode_count.lock().unwrap();
+
+        // Seeded runs are reproducible per actor; otherwise seed from the clock
+        let (seed, strict_seed) = match self.config.base_seed {
+            Some(base_seed) => (
+                derive_episode_seed(base_seed, &self.config.actor_id, episode_count),
+                true,
+            ),
+            None => (SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64, false),
+        };
+
+        // Reset the game
+        let reset_message = ResetRequest {
+            id: Some(EngineId {
+                env_id: self.config.env_id.clone(),
+                build_id: "actor-rust".to_string(),
+            }),
+            seed,
+            hint: vec![],
+            strict_seed,
+            return_state: None,
+        };
+        let reset_request = Request::new(reset_message.clone());
+
+        let mut engine_client = self.engine_client.clone();
+        let reset_response = tokio::select! {
+            _ = cancel.cancelled() => return Ok(EpisodeEnd::Cancelled),
+            response = timeout(
+                self.config.episode_timeout(),
+                engine_client.reset(reset_request),
+            ) => response
+                .map_err(|_| anyhow!("Reset timed out"))?
+                .map_err(|e| anyhow!("Failed to reset game: {}", e))?,
+        };
+
+        let reset_data = reset_response.into_inner();
+        let episode_id = format!("{}-ep-{}-{}",
+            self.config.actor_id,
+            episode_count,
+            SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs()
+        );
+
+        let mut current_state = reset_data.state;
+        let mut current_obs = reset_data.obs;
+        let mut step_number = 0u32;
+        // With n-step returns or self-play the whole episode is held here until it ends
+        let mut episode_transitions = Vec::new();
+
+        // In self-play the player who moves first is driven by the main policy
+        let first_player = reset_data.current_player;
+        let mut current_player = first_player;
+        let mut must_pass = reset_data.must_pass;
+        if self.opponent.is_some() && first_player.is_none() {
+            return Err(anyhow!(
+                "Self-play requires {} to report current_player",
+                self.config.env_id
+            ));
+        }
+
+        debug!("Started episode {}", episode_id);
+        self.policy.lock().unwrap().start_episode();
+        if let Some(opponent) = &self.opponent {
+            opponent.lock().unwrap().start_episode();
+        }
+
+        // Steps run in their own block so that an episode failing part-way
+        // still hands its held-back transitions to `finish_episode`, as a
+        // cancelled one does
+        let played: Result<EpisodeEnd> = async {
+            loop {
+                // Select action using the acting player's policy. A player the
+                // engine reports as having no legal moves passes with an empty
+                // action instead, without consulting (or advancing) its policy.
+                let action = if must_pass {
+                    Vec::new()
+                } else {
+                    let acting_policy = match &self.opponent {
+                        Some(opponent) if current_player != first_player => opponent,
+                        _ => &self.policy,
+                    };
+                    let mut policy = acting_policy.lock().unwrap();
+                    policy.select_action(&current_obs)
+                        .map_err(|e| anyhow!("Failed to select action: {}", e))?
+                };
+
+                // Take step in environment, tagged so the engine's logs can be
+                // matched to the stored transition. With action repeat the action
+                // is stepped up to `action_repeat` times under the same id, stopping
+                // early once the episode ends or the next player must pass.
+                let correlation_id = format!("{:016x}", rand::random::<u64>());
+                let mut sub_state = current_state.clone();
+                let mut raw_reward = 0.0;
+                let mut sub_steps = 0;
+                let step_data = loop {
+                    let step_message = StepRequest {
+                        id: Some(EngineId {
+                            env_id: self.config.env_id.clone(),
+                            build_id: "actor-rust".to_string(),
+                        }),
+                        state: sub_state,
+                        action: action.clone(),
+                        return_state: Some(true),
+                        delta_obs: false,
+                        prev_obs_hash: 0,
+                        step_seed: None,
+                    };
+
+                    // Steps carry the full state, so if the engine evicted our game we
+                    // only need to re-create it with the same reset and replay the step
+                    let mut evictions = 0;
+                    let step_response = loop {
+                        let mut step_request = Request::new(step_message.clone());
+                        step_request
+                            .metadata_mut()
+                            .insert(CORRELATION_ID_HEADER, correlation_id.parse()?);
+
+                        // Dropping the in-flight step on cancel leaves the buffer untouched,
+                        // since transitions are only pushed once a step has completed
+                        let result = tokio::select! {
+                            _ = cancel.cancelled() => {
+                                debug!("Episode {} cancelled after {} steps", episode_id, step_number);
+                                self.finish_episode(std::mem::take(&mut episode_transitions))
+                                    .await?;
+                                self.flush_buffer().await?;
+                                return Ok(EpisodeEnd::Cancelled);
+                            }
+                            response = timeout(
+                                self.config.episode_timeout(),
+                                engine_client.step(step_request),
+                            ) => response.map_err(|_| anyhow!("Step timed out"))?,
+                        };
+
+                        match result {
+                            Ok(response) => break response,
+                            Err(status) if is_game_evicted(&status) && evictions < MAX_EVICTION_RETRIES => {
+                                evictions += 1;
+                                warn!(
+                                    "Engine evicted the game during episode {} at step {}, resetting (retry {}/{})",
+                                    episode_id, step_number, evictions, MAX_EVICTION_RETRIES
+                                );
+                                timeout(
+                                    self.config.episode_timeout(),
+                                    engine_client.reset(Request::new(reset_message.clone())),
+                                )
+                                .await
+                                .map_err(|_| anyhow!("Reset timed out"))?
+                                .map_err(|e| anyhow!("Failed to reset evicted game: {}", e))?;
+                            }
+                            Err(status) => {
+                                return Err(anyhow!("Failed to step environment: {}", status));
+                            }
+                        }
+                    };
+
+                    let step_data = step_response.into_inner();
+                    raw_reward += step_data.reward;
+                    sub_steps += 1;
+                    if step_data.done || step_data.must_pass || sub_steps >= self.config.action_repeat {
+                        break step_data;
+                    }
+                    sub_state = step_data.state;
+                };
+
+                // `max_steps` ends the episode like an engine time limit would: the
+                // final transition is `done` and marked truncated
+                let capped = self
+                    .config
+                    .max_steps
+                    .is_some_and(|max_steps| step_number + 1 >= max_steps);
+                let truncated = step_data.truncated || (capped && !step_data.done);
+                let done = step_data.done || capped;
+
+                // Normalize reward if enabled, keeping the raw value in metadata
+                let mut metadata = HashMap::new();
+                metadata.insert(CORRELATION_ID_METADATA_KEY.to_string(), correlation_id);
+                let reward = match &self.reward_normalizer {
+                    Some(normalizer) => {
+                        metadata.insert(RAW_REWARD_METADATA_KEY.to_string(), raw_reward.to_string());
+                        normalizer.lock().unwrap().normalize(raw_reward)
+                    }
+                    None => raw_reward,
+                };
+                if truncated {
+                    metadata.insert(TRUNCATED_METADATA_KEY.to_string(), true.to_string());
+                }
+                if self.config.action_repeat > 1 {
+                    metadata.insert(ACTION_REPEATS_METADATA_KEY.to_string(), sub_steps.to_string());
+                }
+                if let (Some(_), Some(player)) = (&self.opponent, current_player) {
+                    metadata.insert(PLAYER_METADATA_KEY.to_string(), player.to_string());
+                }
+                // Discrete games report the legal moves after this step in `info`
+                if let Some(legal) = self
+                    .action_space
+                    .as_ref()
+                    .and_then(|space| space.legal_actions(step_data.info))
+                {
+                    metadata.insert(
+                        NEXT_LEGAL_ACTIONS_METADATA_KEY.to_string(),
+                        format_indices(&legal),
+                    );
+                }
+                for (key, value) in &step_data.step_info {
+                    metadata.insert(format!("{}{}", STEP_INFO_METADATA_PREFIX, key), value.to_string());
+                }
+
+                // Create transition
+                let transition =
+                    TransitionBuilder::new(self.config.env_id.clone(), episode_id.clone(), step_number)
+                        .states(current_state.clone(), step_data.state.clone())
+                        .observations(current_obs.clone(), step_data.obs.clone())
+                        .action(action)
+                        .outcome(reward, done)
+                        .metadata(metadata)
+                        .build()?;
+
+                if self.holds_episodes() {
+                    episode_transitions.push(transition);
+                } else {
+                    self.buffer_transitions(vec![transition]).await?;
+                }
+
+                // Check if episode is done
+                if done {
+                    self.finish_episode(std::mem::take(&mut episode_transitions)).await?;
+                    debug!(
+                        "Episode {} {} in {} steps, final reward: {:.2}",
+                        episode_id,
+                        if truncated { "truncated" } else { "completed" },
+                        step_number + 1,
+                        step_data.reward
+                    );
+                    break;
+                }
+
+                // Update state for next step
+                current_state = step_data.state;
+                current_obs = step_data.obs;
+                current_player = step_data.current_player;
+                must_pass = step_data.must_pass;
+                step_number += 1;
+            }
+
+            Ok(EpisodeEnd::Completed)
+        }
+        .await;
+        if played.is_err() {
+            if let Err(e) = self.finish_episode(episode_transitions).await {
+                warn!("Failed to keep the transitions of a failed episode: {}", e);
+            }
+        }
+        played
+    }
+
+    /// Whether transitions are held until their episode ends
+    fn holds_episodes(&self) -> bool {
+        self.config.n_step.is_some() || self.opponent.is_some()
+    }
+
+    /// Post-process a held-back episode and queue it for replay
+    ///
+    /// Self-play episodes get the final reward attributed to both players, and
+    /// n-step returns are attached when enabled. Returns are truncated at the
+    /// last collected transition, so a cancelled episode never bootstraps past
+    /// the point it was stopped.
+    async fn finish_episode(&self, mut transitions: Vec<Transition>) -> Result<()> {
+        if transitions.is_empty() {
+            return Ok(());
+        }
+
+        if self.opponent.is_some() {
+            attribute_final_reward(&mut transitions);
+        }
+
+        if let Some(n) = self.config.n_step {
+            let rewards: Vec<f32> = transitions.iter().map(|t| t.reward).collect();
+            let dones: Vec<bool> = transitions.iter().map(|t| t.done).collect();
+            let returns = n_step_returns(&rewards, &dones, n, self.config.gamma);
+            for (transition, value) in transitions.iter_mut().zip(returns) {
+                transition
+                    .metadata
+                    .insert(N_STEP_RETURN_METADATA_KEY.to_string(), value.to_string());
+            }
+        }
+
+        self.buffer_transitions(transitions).await
+    }
+
+    /// Queue transitions for replay, flushing whenever a full batch is ready
+    async fn buffer_transitions(&self, mut transitions: Vec<Transition>) -> Result<()> {
+        if let Some(warmup) = &self.warmup {
+            let mut warmup = warmup.lock().unwrap();
+            if !warmup.is_over() {
+                let dropped = warmup.retain(&mut transitions);
+                if dropped > 0 {
+                    debug!("Warm-up discarded {} transitions", dropped);
+                }
+                if warmup.is_over() {
+                    info!(
+                        "Warm-up over: discarded the first {} transitions",
+                        warmup.discarded()
+                    );
+                }
+            }
+        }
+
+        // User callbacks run before the buffer lock is taken
+        for transition in &mut transitions {
+            transition.priority = (self.priority_fn)(transition);
+        }
+        if let Some(filter) = &self.transition_filter {
+            transitions.retain(|transition| filter(transition));
+        }
+        if let Some(hook) = &self.transition_hook {
+            for transition in &transitions {
+                hook(transition);
+            }
+        }
+
+        let full = {
+            let mut buffer = self.transition_buffer.lock().unwrap();
+            buffer.extend(transitions);
+            buffer.len() >= self.batch_size()
+        };
+
+        // Lock is released before the async flush
+        if full {
+            self.flush_buffer().await?;
+        }
+        Ok(())
+    }
+
+    /// Transitions to buffer before flushing
+    fn batch_size(&self) -> usize {
+        match &self.adaptive_batch {
+            Some(adaptive) => adaptive.lock().unwrap().size(),
+            None => self.config.batch_size,
+        }
+    }
+
+    /// Flush the remaining transitions on shutdown
+    ///
+    /// The flush may take at most `drain_timeout`. If it times out or fails,
+    /// the transitions are appended to `spill_file` instead of being lost, and
+    /// only a failure to write that file is returned.
+    async fn drain(&self) -> Result<()> {
+        let mut transitions = std::mem::take(&mut *self.transition_buffer.lock().unwrap());
+        if transitions.is_empty() {
+            return Ok(());
+        }
+
+        let sent = transitions.len();
+        debug!("Draining {} transitions to replay", sent);
+        let reason = match timeout(
+            self.config.drain_timeout(),
+            self.sink.send_batch(transitions.clone()),
+        )
+        .await
+        {
+            Ok(Ok(StoreOutcome::Stored)) => return Ok(()),
+            Ok(Ok(StoreOutcome::Partial {
+                stored,
+                errors,
+                unstored,
+            })) => {
+                let Some(unstored) = unstored else {
+                    warn!(
+                        "Final flush stored only {} of {} transitions without saying which: {}",
+                        stored,
+                        sent,
+                        errors.join("; ")
+                    );
+                    return Ok(());
+                };
+                transitions = unstored;
+                format!("stored only {} of {} transitions ({})", stored, sent, errors.join("; "))
+            }
+            Ok(Err(e)) => format!("failed: {}", e),
+            Err(_) => format!("timed out after {}s", self.config.drain_timeout_secs),
+        };
+
+        spill_to_file(&self.config.spill_file, &transitions)?;
+        warn!(
+            "Final flush {}; spilled {} transitions to {}",
+            reason,
+            transitions.len(),
+            self.config.spill_file
+        );
+        Ok(())
+    }
+
+    async fn flush_buffer(&self) -> Result<()> {
         let transitions = {
             let mut buffer = self.transition_buffer.lock().unwrap();
             if buffer.is_empty() {
@@ -264,18 +742,105 @@ impl Actor {
             }
             std::mem::take(&mut *buffer)
         };
-
-        debug!("Flushing {} transitions to replay service", transitions.len());
-
-        let request = Request::new(StoreBatchRequest { transitions });
-
-        self.replay_client
-            .clone()
-            .store_batch(request)
-            .await
-            .map_err(|e| anyhow!("Failed to store batch: {}", e))?;
-
-        Ok(())
+
+        let sent = transitions.len();
+        debug!("Flushing {} transitions to replay", sent);
+        let started = Instant::now();
+        let outcome = self.sink.send_batch(transitions).await?;
+        if let Some(adaptive) = &self.adaptive_batch {
+            let latency = started.elapsed();
+            let size = adaptive.lock().unwrap().observe(latency);
+            debug!("Stored {} transitions in {:?}; next batch size {}", sent, latency, size);
+        }
+
+        let StoreOutcome::Partial {
+            stored,
+            errors,
+            unstored,
+        } = outcome
+        else {
+            return Ok(());
+        };
+
+        warn!(
+            "Replay stored only {} of {} transitions: {}",
+            stored,
+            sent,
+            errors.join("; ")
+        );
+        match unstored {
+            // Only the transitions replay did not keep are retried, ahead of
+            // newer ones, with the next flush
+            Some(unstored) => {
+                debug!("Re-queueing {} unstored transitions", unstored.len());
+                self.transition_buffer.lock().unwrap().splice(0..0, unstored);
+            }
+            None => warn!("Replay did not identify the failed transitions; they are not retried"),
+        }
+        Ok(())
+    }
+}
+
+/// Build the configured policy: a script when `script_file` is set, otherwise random
+fn build_policy(
+    script_file: Option<&str>,
+    config: &Config,
+    capabilities: &Capabilities,
+) -> Result<Box<dyn Policy>> {
+    // Replay a fixed action script when configured, otherwise act randomly
+    match script_file {
+        Some(path) => {
+            let on_end = if config.script_cycle {
+                ScriptEnd::Cycle
+            } else {
+                ScriptEnd::Error
+            };
+            info!("Replaying scripted actions from {}", path);
+            Ok(Box::new(
+                ScriptedPolicy::from_file(path, on_end)
+                    .map_err(|e| anyhow!("Failed to create policy: {:#}", e))?,
+            ))
+        }
+        None => Ok(Box::new(
+            RandomPolicy::new(capabilities)
+                .map_err(|e| anyhow!("Failed to create policy: {}", e))?,
+        )),
+    }
+}
+
+/// Give the opponent of the final mover their side of the result
+///
+/// Engines report reward from the perspective of the player who just moved,
+/// so only the final transition carries the outcome. The other player's last
+/// transition receives the reward the engine reported for it under
+/// `OPPONENT_REWARD_STEP_INFO_KEY`, or the negated final reward when the game
+/// reports none and the result is assumed zero-sum, so each player's
+/// transitions read from that player's own perspective. Episodes that did not
+/// finish, or were truncated before reaching an outcome, are left untouched.
+fn attribute_final_reward(transitions: &mut [Transition]) {
+    let Some(last) = transitions.last() else {
+        return;
+    };
+    if !last.done || last.metadata.contains_key(TRUNCATED_METADATA_KEY) {
+        return;
+    }
+
+    let opponent_key = format!(
+        "{}{}",
+        STEP_INFO_METADATA_PREFIX, OPPONENT_REWARD_STEP_INFO_KEY
+    );
+    let opponent_reward = last
+        .metadata
+        .get(&opponent_key)
+        .and_then(|reward| reward.parse::<f32>().ok())
+        .unwrap_or(-last.reward);
+    let final_mover = last.metadata.get(PLAYER_METADATA_KEY).cloned();
+    if let Some(previous) = transitions
+        .iter_mut()
+        .rev()
+        .find(|t| t.metadata.get(PLAYER_METADATA_KEY) != final_mover.as_ref())
+    {
+        previous.reward += opponent_reward;
     }
 }
 
@@ -283,6 +848,13 @@ impl Actor {
 mod tests {
     use super::*;
     use crate::proto::engine::v1::engine_client::EngineClient;
+    use crate::proto::engine::v1::engine_server::{Engine, EngineServer};
+    use crate::proto::engine::v1::{
+        capabilities, Capabilities, DrainBuffersRequest, DrainBuffersResponse,
+        GetPayloadStatsRequest, GetPayloadStatsResponse, GetServerInfoRequest, GetServerInfoResponse, LegalActionCountRequest, LegalActionCountResponse, ListCachedGamesRequest, ListCachedGamesResponse,
+        MultiStepRequest, MultiStepResponse, ReseedRequest, ReseedResponse, ResetResponse, StepAutoResetRequest, StepAutoResetResponse, StepResponse,
+        GetSupportedVersionsResponse, StreamEpisodeRequest, WarmCacheRequest, WarmCacheResponse,
+    };
     use crate::proto::replay::v1::replay_client::ReplayClient;
     use crate::proto::replay::v1::replay_server::{Replay, ReplayServer};
     use crate::proto::replay::v1::{
@@ -292,15 +864,20 @@ mod tests {
         UpdatePrioritiesResponse,
     };
     use std::collections::HashMap;
-    use std::net::TcpListener;
+    use std::net::{SocketAddr, TcpListener};
+    use std::sync::atomic::{AtomicU32, Ordering};
     use std::sync::{Arc, Mutex};
-    use tokio::sync::oneshot;
+    use tokio::sync::{oneshot, Notify};
+    use tokio::task::JoinHandle;
     use tonic::transport::{Endpoint, Server};
     use tonic::{Response, Status};
 
+    /// Replay that stores each batch, or only its first `store_limit`
+    /// transitions, reporting the ids it kept
     #[derive(Clone, Default)]
     struct MockReplay {
         stored: Arc<Mutex<Vec<Transition>>>,
+        store_limit: Option<usize>,
     }
 
     #[tonic::async_trait]
@@ -317,12 +894,21 @@ mod tests {
             request: tonic::Request<StoreBatchRequest>,
         ) -> Result<Response<StoreBatchResponse>, Status> {
             let mut stored = self.stored.lock().unwrap();
-            let transitions = request.into_inner().transitions;
+            let mut transitions = request.into_inner().transitions;
+            let failed = transitions.len().saturating_sub(self.store_limit.unwrap_or(usize::MAX));
+            transitions.truncate(transitions.len() - failed);
+            let transition_ids = transitions.iter().map(|t| t.id.clone()).collect();
             let count = transitions.len();
             stored.extend(transitions);
             Ok(Response::new(StoreBatchResponse {
+                transition_ids,
                 stored_count: count as u32,
-                ..Default::default()
+                failed_count: failed as u32,
+                error_messages: if failed > 0 {
+                    vec!["replay full".to_string()]
+                } else {
+                    Vec::new()
+                },
             }))
         }
 
@@ -357,64 +943,434 @@ mod tests {
         }
     }
 
-    struct TestPolicy;
+    /// The engine calls actor tests script; both default to unimplemented
+    #[tonic::async_trait]
+    trait MockEngine: Send + Sync + 'static {
+        async fn reset(
+            &self,
+            _request: tonic::Request<ResetRequest>,
+        ) -> Result<Response<ResetResponse>, Status> {
+            Err(Status::unimplemented("reset not implemented in tests"))
+        }
 
-    impl Policy for TestPolicy {
-        fn select_action(&mut self, _observation: &[u8]) -> Result<Vec<u8>> {
-            Ok(vec![])
+        async fn step(
+            &self,
+            _request: tonic::Request<StepRequest>,
+        ) -> Result<Response<StepResponse>, Status> {
+            Err(Status::unimplemented("step not implemented in tests"))
         }
     }
 
-    #[tokio::test]
-    async fn flush_buffer_clears_queue_and_delivers_transitions() {
-        let stored_transitions = Arc::new(Mutex::new(Vec::new()));
-        let replay_service = MockReplay {
-            stored: stored_transitions.clone(),
-        };
+    /// Serves no engine calls, for tests that only talk to replay
+    struct NoEngine;
+
+    impl MockEngine for NoEngine {}
+
+    /// `Engine` for a `MockEngine`: every other RPC is unimplemented
+    struct MockEngineService<E>(E);
+
+    #[tonic::async_trait]
+    impl<E: MockEngine> Engine for MockEngineService<E> {
+        async fn get_capabilities(
+            &self,
+            _request: tonic::Request<EngineId>,
+        ) -> Result<Response<Capabilities>, Status> {
+            Err(Status::unimplemented("get_capabilities not implemented in tests"))
+        }
+
+        async fn get_supported_versions(
+            &self,
+            _request: tonic::Request<EngineId>,
+        ) -> Result<Response<GetSupportedVersionsResponse>, Status> {
+            Err(Status::unimplemented("get_supported_versions not implemented in tests"))
+        }
+
+        async fn reset(
+            &self,
+            request: tonic::Request<ResetRequest>,
+        ) -> Result<Response<ResetResponse>, Status> {
+            self.0.reset(request).await
+        }
+
+        async fn step(
+            &self,
+            request: tonic::Request<StepRequest>,
+        ) -> Result<Response<StepResponse>, Status> {
+            self.0.step(request).await
+        }
+
+        async fn reseed(
+            &self,
+            _request: tonic::Request<ReseedRequest>,
+        ) -> Result<Response<ReseedResponse>, Status> {
+            Err(Status::unimplemented("reseed not implemented in tests"))
+        }
 
+        async fn step_auto_reset(
+            &self,
+            _request: tonic::Request<StepAutoResetRequest>,
+        ) -> Result<Response<StepAutoResetResponse>, Status> {
+            Err(Status::unimplemented("step_auto_reset not implemented in tests"))
+        }
+
+        async fn drain_buffers(
+            &self,
+            _request: tonic::Request<DrainBuffersRequest>,
+        ) -> Result<Response<DrainBuffersResponse>, Status> {
+            Err(Status::unimplemented("drain_buffers not implemented in tests"))
+        }
+
+        async fn list_cached_games(
+            &self,
+            _request: tonic::Request<ListCachedGamesRequest>,
+        ) -> Result<Response<ListCachedGamesResponse>, Status> {
+            Err(Status::unimplemented("list_cached_games not implemented in tests"))
+        }
+
+        async fn warm_cache(
+            &self,
+            _request: tonic::Request<WarmCacheRequest>,
+        ) -> Result<Response<WarmCacheResponse>, Status> {
+            Err(Status::unimplemented("warm_cache not implemented in tests"))
+        }
+
+        async fn get_server_info(
+            &self,
+            _request: tonic::Request<GetServerInfoRequest>,
+        ) -> Result<Response<GetServerInfoResponse>, Status> {
+            Err(Status::unimplemented("get_server_info not implemented in tests"))
+        }
+
+        async fn multi_step(
+            &self,
+            _request: tonic::Request<MultiStepRequest>,
+        ) -> Result<Response<MultiStepResponse>, Status> {
+            Err(Status::unimplemented("multi_step not implemented in tests"))
+        }
+
+        async fn get_payload_stats(
+            &self,
+            _request: tonic::Request<GetPayloadStatsRequest>,
+        ) -> Result<Response<GetPayloadStatsResponse>, Status> {
+            Err(Status::unimplemented("get_payload_stats not implemented in tests"))
+        }
+
+        async fn legal_action_count(
+            &self,
+            _request: tonic::Request<LegalActionCountRequest>,
+        ) -> Result<Response<LegalActionCountResponse>, Status> {
+            Err(Status::unimplemented("legal_action_count not implemented in tests"))
+        }
+
+        type StreamEpisodeStream =
+            futures::stream::Empty<Result<crate::proto::engine::v1::Transition, Status>>;
+
+        async fn stream_episode(
+            &self,
+            _request: tonic::Request<StreamEpisodeRequest>,
+        ) -> Result<Response<Self::StreamEpisodeStream>, Status> {
+            Err(Status::unimplemented("stream_episode not implemented in tests"))
+        }
+    }
+
+    /// Stops the server started by `spawn_test_server`
+    struct TestServerShutdown {
+        tx: oneshot::Sender<()>,
+        handle: JoinHandle<()>,
+    }
+
+    impl TestServerShutdown {
+        async fn shutdown(self) {
+            self.tx.send(()).unwrap();
+            self.handle.await.unwrap();
+        }
+    }
+
+    /// Serve `engine` and `replay` on a free local port
+    fn spawn_test_server(
+        engine: impl MockEngine,
+        replay: MockReplay,
+    ) -> (SocketAddr, TestServerShutdown) {
         let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind test listener");
         let addr = listener.local_addr().unwrap();
         drop(listener);
-        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let (tx, rx) = oneshot::channel();
 
-        let server_handle = tokio::spawn(async move {
+        let handle = tokio::spawn(async move {
             Server::builder()
-                .add_service(ReplayServer::new(replay_service))
+                .add_service(ReplayServer::new(replay))
+                .add_service(EngineServer::new(MockEngineService(engine)))
                 .serve_with_shutdown(addr, async {
-                    let _ = shutdown_rx.await;
+                    let _ = rx.await;
                 })
                 .await
                 .unwrap();
         });
+        (addr, TestServerShutdown { tx, handle })
+    }
 
-        let endpoint = Endpoint::new(format!("http://{}", addr)).unwrap();
-        let replay_client = ReplayClient::new(endpoint.connect_lazy());
+    /// Engine whose steps succeed quickly until `fast_steps`, then hang
+    #[derive(Clone, Default)]
+    struct SlowEngine {
+        fast_steps: u32,
+        steps: Arc<AtomicU32>,
+        slow_step_started: Arc<Notify>,
+    }
 
-        let engine_client = {
-            let engine_endpoint = Endpoint::new("http://127.0.0.1:50051".to_string()).unwrap();
-            EngineClient::new(engine_endpoint.connect_lazy())
-        };
+    #[tonic::async_trait]
+    impl MockEngine for SlowEngine {
+        async fn reset(
+            &self,
+            _request: tonic::Request<ResetRequest>,
+        ) -> Result<Response<ResetResponse>, Status> {
+            Ok(Response::new(ResetResponse {
+                state: b"state0".to_vec(),
+                obs: b"obs0".to_vec(),
+                actual_seed: 1,
+                current_player: None,
+                must_pass: false,
+            }))
+        }
+
+        async fn step(
+            &self,
+            _request: tonic::Request<StepRequest>,
+        ) -> Result<Response<StepResponse>, Status> {
+            let step = self.steps.fetch_add(1, Ordering::SeqCst) + 1;
+            if step > self.fast_steps {
+                self.slow_step_started.notify_one();
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            }
+            Ok(Response::new(StepResponse {
+                state: format!("state{}", step).into_bytes(),
+                obs: format!("obs{}", step).into_bytes(),
+                reward: 1.0,
+                ..Default::default()
+            }))
+        }
+    }
+
+    /// Engine that pays out `rewards` in order and reports `done` on the last one
+    ///
+    /// Records the correlation id and action of each step, echoing the id like
+    /// the real engine, and reports `must_pass` after the steps listed in `passes`.
+    /// Step `evict_at` fails as if the game were evicted until `evictions` runs out.
+    #[derive(Clone, Default)]
+    struct RewardSequenceEngine {
+        rewards: Vec<f32>,
+        passes: Vec<usize>,
+        evict_at: Option<usize>,
+        evictions: Arc<AtomicU32>,
+        resets: Arc<AtomicU32>,
+        steps: Arc<AtomicU32>,
+        correlation_ids: Arc<Mutex<Vec<String>>>,
+        actions: Arc<Mutex<Vec<Vec<u8>>>>,
+    }
+
+    #[tonic::async_trait]
+    impl MockEngine for RewardSequenceEngine {
+        async fn reset(
+            &self,
+            _request: tonic::Request<ResetRequest>,
+        ) -> Result<Response<ResetResponse>, Status> {
+            self.resets.fetch_add(1, Ordering::SeqCst);
+            Ok(Response::new(ResetResponse {
+                state: b"state0".to_vec(),
+                obs: b"obs0".to_vec(),
+                actual_seed: 1,
+                current_player: None,
+                must_pass: false,
+            }))
+        }
+
+        async fn step(
+            &self,
+            request: tonic::Request<StepRequest>,
+        ) -> Result<Response<StepResponse>, Status> {
+            let step = self.steps.load(Ordering::SeqCst) as usize;
+            let evicted = self.evict_at == Some(step)
+                && self
+                    .evictions
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                    .is_ok();
+            if evicted {
+                return Err(Status::failed_precondition(
+                    "Game not initialized - call reset before step",
+                ));
+            }
+            self.steps.fetch_add(1, Ordering::SeqCst);
+
+            let correlation_id = request.metadata().get(CORRELATION_ID_HEADER).cloned();
+            if let Some(id) = &correlation_id {
+                self.correlation_ids
+                    .lock()
+                    .unwrap()
+                    .push(id.to_str().unwrap().to_string());
+            }
+
+            self.actions.lock().unwrap().push(request.get_ref().action.clone());
 
-        let actor = Actor {
+            let mut response = Response::new(StepResponse {
+                reward: self.rewards[step],
+                done: step + 1 == self.rewards.len(),
+                must_pass: self.passes.contains(&step),
+                step_info: HashMap::from([("step".to_string(), step as f32)]),
+                ..Default::default()
+            });
+            if let Some(id) = correlation_id {
+                response.metadata_mut().insert(CORRELATION_ID_HEADER, id);
+            }
+            Ok(response)
+        }
+    }
+
+    /// Two-player engine where X takes the top row while O plays the middle row
+    ///
+    /// Records each action byte and reports the player to move like tictactoe:
+    /// X (1) starts, turns alternate, and the fifth move wins for X. With
+    /// `draw_reward` set the fifth move draws instead, paying both players.
+    #[derive(Clone, Default)]
+    struct TwoPlayerEngine {
+        actions: Arc<Mutex<Vec<u8>>>,
+        draw_reward: Option<f32>,
+    }
+
+    #[tonic::async_trait]
+    impl MockEngine for TwoPlayerEngine {
+        async fn reset(
+            &self,
+            _request: tonic::Request<ResetRequest>,
+        ) -> Result<Response<ResetResponse>, Status> {
+            Ok(Response::new(ResetResponse {
+                actual_seed: 1,
+                current_player: Some(1),
+                ..Default::default()
+            }))
+        }
+
+        async fn step(
+            &self,
+            request: tonic::Request<StepRequest>,
+        ) -> Result<Response<StepResponse>, Status> {
+            let moves = {
+                let mut actions = self.actions.lock().unwrap();
+                actions.push(request.into_inner().action[0]);
+                actions.len()
+            };
+            let done = moves == 5;
+            let (reward, step_info) = match (done, self.draw_reward) {
+                (false, _) => (0.0, HashMap::new()),
+                (true, None) => (1.0, HashMap::new()),
+                (true, Some(draw)) => (
+                    draw,
+                    HashMap::from([(OPPONENT_REWARD_STEP_INFO_KEY.to_string(), draw)]),
+                ),
+            };
+            Ok(Response::new(StepResponse {
+                reward,
+                done,
+                step_info,
+                // The winner stays to move once the game is over
+                current_player: Some(if moves % 2 == 1 && !done { 2 } else { 1 }),
+                ..Default::default()
+            }))
+        }
+    }
+
+    struct TestPolicy;
+
+    impl Policy for TestPolicy {
+        fn select_action(&mut self, _observation: &[u8]) -> Result<Vec<u8>> {
+            Ok(vec![])
+        }
+    }
+
+    fn test_actor(
+        addr: SocketAddr,
+        engine_client: EngineClient<Channel>,
+        replay_client: ReplayClient<Channel>,
+        episode_timeout_secs: u64,
+    ) -> Actor {
+        Actor {
             config: Config {
                 engine_addr: format!("http://{}", addr),
                 replay_addr: format!("http://{}", addr),
                 actor_id: "test-actor".into(),
                 env_id: "test-env".into(),
                 max_episodes: 1,
-                episode_timeout_secs: 1,
+                episode_timeout_secs,
                 batch_size: 2,
+                adaptive_batch: false,
+                min_batch_size: 8,
+                max_batch_size: 512,
+                batch_latency_target_ms: 200,
                 flush_interval_secs: 1,
+                drain_timeout_secs: 10,
+                spill_file: "actor-spill.pb".into(),
                 log_level: "info".into(),
+                normalize_rewards: false,
+                tls_ca_cert: None,
+                tls_client_cert: None,
+                tls_client_key: None,
+                script_file: None,
+                script_cycle: false,
+                n_step: None,
+                gamma: 0.99,
+                health_addr: None,
+                readiness_stale_secs: 120,
+                self_play: false,
+                opponent_script_file: None,
+                action_repeat: 1,
+                max_steps: None,
+                priority: "constant".into(),
+                base_seed: None,
+                warmup_discard: 0,
+                warmup_whole_episodes: false,
+                config: None,
             },
             engine_client,
-            replay_client,
+            sink: Box::new(ReplaySink::new(replay_client)),
             policy: Arc::new(Mutex::new(Box::new(TestPolicy))),
+            opponent: None,
             episode_count: Arc::new(Mutex::new(0)),
             transition_buffer: Arc::new(Mutex::new(Vec::new())),
-            shutdown_signal: Arc::new(Mutex::new(false)),
+            adaptive_batch: None,
+            shutdown_token: CancellationToken::new(),
+            episode_token: Mutex::new(CancellationToken::new()),
+            reward_normalizer: None,
+            action_space: None,
+            transition_hook: None,
+            transition_filter: None,
+            priority_fn: Priority::Constant.priority_fn(),
+            warmup: None,
+            health: Arc::new(HealthState::new(Duration::from_secs(120))),
+        }
+    }
+
+    /// `test_actor` talking to the engine and replay served at `addr`
+    fn connected_test_actor(addr: SocketAddr, episode_timeout_secs: u64) -> Actor {
+        let channel = Endpoint::new(format!("http://{}", addr))
+            .unwrap()
+            .connect_lazy();
+        test_actor(
+            addr,
+            EngineClient::new(channel.clone()),
+            ReplayClient::new(channel),
+            episode_timeout_secs,
+        )
+    }
+
+    #[tokio::test]
+    async fn flush_buffer_clears_queue_and_delivers_transitions() {
+        let stored_transitions = Arc::new(Mutex::new(Vec::new()));
+        let replay_service = MockReplay {
+            stored: stored_transitions.clone(),
+            ..Default::default()
         };
 
+        let (addr, server) = spawn_test_server(NoEngine, replay_service);
+        let actor = connected_test_actor(addr, 1);
+
         let first_transition = Transition {
             id: "t1".into(),
             env_id: "env".into(),
@@ -448,13 +1404,711 @@ mod tests {
             "buffer should be empty after flush"
         );
 
-        let received = stored_transitions.lock().unwrap();
-        assert_eq!(received.len(), 2, "replay should receive both transitions");
-        assert_eq!(received[0], first_transition);
-        assert_eq!(received[1], second_transition);
+        {
+            let received = stored_transitions.lock().unwrap();
+            assert_eq!(received.len(), 2, "replay should receive both transitions");
+            assert_eq!(received[0], first_transition);
+            assert_eq!(received[1], second_transition);
+        }
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn partially_stored_batch_requeues_only_unstored_transitions() {
+        let stored_transitions = Arc::new(Mutex::new(Vec::new()));
+        let replay_service = MockReplay {
+            stored: stored_transitions.clone(),
+            store_limit: Some(2),
+        };
+
+        let (addr, server) = spawn_test_server(NoEngine, replay_service);
+
+        let actor = connected_test_actor(addr, 1);
+
+        let transitions: Vec<Transition> = (0..3)
+            .map(|step| {
+                TransitionBuilder::new("env", "ep", step)
+                    .states(Vec::new(), Vec::new())
+                    .observations(Vec::new(), Vec::new())
+                    .action(vec![step as u8])
+                    .outcome(0.0, step == 2)
+                    .build()
+                    .unwrap()
+            })
+            .collect();
+        actor.transition_buffer.lock().unwrap().extend(transitions.clone());
+
+        // Replay keeps two; the third waits in the buffer for the next flush
+        actor.flush_buffer().await.unwrap();
+        let requeued: Vec<String> = actor
+            .transition_buffer
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|t| t.id.clone())
+            .collect();
+        assert_eq!(requeued, vec!["ep-step-2"]);
+
+        actor.flush_buffer().await.unwrap();
+        assert!(actor.transition_buffer.lock().unwrap().is_empty());
+        assert_eq!(*stored_transitions.lock().unwrap(), transitions);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn cancel_during_slow_step_ends_episode_and_flushes_partial_transitions() {
+        let stored_transitions = Arc::new(Mutex::new(Vec::new()));
+        let replay_service = MockReplay {
+            stored: stored_transitions.clone(),
+            ..Default::default()
+        };
+        // Three fast steps: the first two fill a batch, the third stays buffered
+        let engine_service = SlowEngine {
+            fast_steps: 3,
+            ..Default::default()
+        };
+        let slow_step_started = engine_service.slow_step_started.clone();
+
+        let (addr, server) = spawn_test_server(engine_service, replay_service);
+
+        let actor = connected_test_actor(addr, 30);
+
+        let cancel = CancellationToken::new();
+        let cancel_on_slow_step = async {
+            slow_step_started.notified().await;
+            cancel.cancel();
+        };
+
+        let (result, _) = timeout(Duration::from_secs(5), async {
+            tokio::join!(actor.run_episode(&cancel), cancel_on_slow_step)
+        })
+        .await
+        .expect("cancelled episode should end promptly");
+
+        assert_eq!(result.unwrap(), EpisodeEnd::Cancelled);
+        assert_eq!(*actor.episode_count.lock().unwrap(), 0);
+        assert!(actor.transition_buffer.lock().unwrap().is_empty());
+
+        let steps: Vec<u32> = stored_transitions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|t| t.step_number)
+            .collect();
+        assert_eq!(steps, vec![0, 1, 2], "each completed step is flushed exactly once");
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn n_step_returns_are_attached_when_the_episode_ends() {
+        let stored_transitions = Arc::new(Mutex::new(Vec::new()));
+        let replay_service = MockReplay {
+            stored: stored_transitions.clone(),
+            ..Default::default()
+        };
+        let engine_service = RewardSequenceEngine {
+            rewards: vec![1.0, 2.0, 3.0, 4.0],
+            ..Default::default()
+        };
+
+        let (addr, server) = spawn_test_server(engine_service, replay_service);
+
+        let mut actor = connected_test_actor(addr, 30);
+        actor.config.n_step = Some(2);
+        actor.config.gamma = 0.5;
+
+        let result = actor.run_episode(&CancellationToken::new()).await;
+        assert_eq!(result.unwrap(), EpisodeEnd::Completed);
+
+        // G_t = r_t + 0.5 * r_{t+1}, with nothing past the final step
+        let returns: Vec<String> = stored_transitions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|t| t.metadata[N_STEP_RETURN_METADATA_KEY].clone())
+            .collect();
+        assert_eq!(returns, vec!["2", "3.5", "5", "4"]);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn step_correlation_ids_are_stored_with_their_transitions() {
+        let stored_transitions = Arc::new(Mutex::new(Vec::new()));
+        let replay_service = MockReplay {
+            stored: stored_transitions.clone(),
+            ..Default::default()
+        };
+        let engine_service = RewardSequenceEngine {
+            rewards: vec![0.0, 0.0, 1.0],
+            ..Default::default()
+        };
+        let engine_ids = engine_service.correlation_ids.clone();
+
+        let (addr, server) = spawn_test_server(engine_service, replay_service);
+
+        let mut actor = connected_test_actor(addr, 30);
+        actor.config.batch_size = 10;
+
+        let result = actor.run_episode(&CancellationToken::new()).await;
+        assert_eq!(result.unwrap(), EpisodeEnd::Completed);
+        actor.flush_buffer().await.unwrap();
+
+        let stored_ids: Vec<String> = stored_transitions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|t| t.metadata[CORRELATION_ID_METADATA_KEY].clone())
+            .collect();
+        let engine_ids = engine_ids.lock().unwrap().clone();
+        assert_eq!(stored_ids, engine_ids, "each transition keeps the id its step sent");
+        assert_eq!(stored_ids.len(), 3);
+        assert!(stored_ids[0] != stored_ids[1], "ids are generated per step");
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn step_info_is_forwarded_to_prefixed_metadata() {
+        let engine_service = RewardSequenceEngine {
+            rewards: vec![0.0, 0.0, 1.0],
+            ..Default::default()
+        };
+        let stored = run_episode_with(engine_service, |actor| actor).await;
+
+        let steps: Vec<&str> = stored.iter().map(|t| t.metadata["info.step"].as_str()).collect();
+        assert_eq!(steps, vec!["0", "1", "2"]);
+    }
+
+    /// Serve `engine_service` and a recording replay, run one episode with
+    /// the actor `configure` returns, and hand back what replay stored
+    async fn run_episode_with(
+        engine_service: RewardSequenceEngine,
+        configure: impl FnOnce(Actor) -> Actor,
+    ) -> Vec<Transition> {
+        let (result, stored) = try_episode_with(engine_service, configure).await;
+        assert_eq!(result.unwrap(), EpisodeEnd::Completed);
+        stored
+    }
+
+    /// Like `run_episode_with`, but hands back the episode result as well
+    async fn try_episode_with(
+        engine_service: RewardSequenceEngine,
+        configure: impl FnOnce(Actor) -> Actor,
+    ) -> (Result<EpisodeEnd>, Vec<Transition>) {
+        let stored_transitions = Arc::new(Mutex::new(Vec::new()));
+        let replay_service = MockReplay {
+            stored: stored_transitions.clone(),
+            ..Default::default()
+        };
+
+        let (addr, server) = spawn_test_server(engine_service, replay_service);
+
+        let mut actor = connected_test_actor(addr, 30);
+        actor.config.batch_size = 10;
+        let actor = configure(actor);
+
+        let result = actor.run_episode(&CancellationToken::new()).await;
+        actor.flush_buffer().await.unwrap();
+        let stored = stored_transitions.lock().unwrap().clone();
+
+        server.shutdown().await;
+        (result, stored)
+    }
+
+    /// Sink that keeps every batch in memory
+    #[derive(Clone, Default)]
+    struct MemorySink {
+        batches: Arc<Mutex<Vec<Vec<Transition>>>>,
+    }
+
+    #[tonic::async_trait]
+    impl TransitionSink for MemorySink {
+        async fn send_batch(&self, transitions: Vec<Transition>) -> Result<StoreOutcome> {
+            self.batches.lock().unwrap().push(transitions);
+            Ok(StoreOutcome::Stored)
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_sink_receives_every_transition_in_batches() {
+        let engine_service = RewardSequenceEngine {
+            rewards: vec![0.0, 0.0, 0.0, 0.0, 1.0],
+            ..Default::default()
+        };
+        let sink = MemorySink::default();
+        let batches = sink.batches.clone();
+
+        let stored = run_episode_with(engine_service, |mut actor| {
+            actor.config.batch_size = 2;
+            actor.with_transition_sink(sink)
+        })
+        .await;
+
+        // Nothing reaches the replay service once a sink is installed
+        assert!(stored.is_empty());
+        let batches = batches.lock().unwrap();
+        let sizes: Vec<usize> = batches.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![2, 2, 1]);
+        let steps: Vec<u32> = batches.iter().flatten().map(|t| t.step_number).collect();
+        assert_eq!(steps, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn warmup_discard_drops_the_first_transitions() {
+        let engine_service = RewardSequenceEngine {
+            rewards: vec![0.0, 0.0, 0.0, 0.0, 1.0],
+            ..Default::default()
+        };
+
+        let stored = run_episode_with(engine_service, |mut actor| {
+            actor.config.batch_size = 1;
+            actor.warmup = Some(Mutex::new(WarmupDiscard::new(3, false)));
+            actor
+        })
+        .await;
+
+        let steps: Vec<u32> = stored.iter().map(|t| t.step_number).collect();
+        assert_eq!(steps, vec![3, 4]);
+    }
+
+    /// Sink whose batches never complete, like a hung replay service
+    struct StalledSink;
+
+    #[tonic::async_trait]
+    impl TransitionSink for StalledSink {
+        async fn send_batch(&self, _transitions: Vec<Transition>) -> Result<StoreOutcome> {
+            std::future::pending().await
+        }
+    }
+
+    /// Sink that takes `step` longer to store each batch than the one before
+    #[derive(Clone)]
+    struct SlowingSink {
+        step: Duration,
+        batches: Arc<Mutex<Vec<usize>>>,
+    }
+
+    #[tonic::async_trait]
+    impl TransitionSink for SlowingSink {
+        async fn send_batch(&self, transitions: Vec<Transition>) -> Result<StoreOutcome> {
+            let delay = {
+                let mut batches = self.batches.lock().unwrap();
+                let delay = self.step * batches.len() as u32;
+                batches.push(transitions.len());
+                delay
+            };
+            tokio::time::sleep(delay).await;
+            Ok(StoreOutcome::Stored)
+        }
+    }
+
+    #[tokio::test]
+    async fn adaptive_batch_size_shrinks_within_bounds_as_latency_rises() {
+        let channel = Endpoint::new("http://127.0.0.1:1".to_string())
+            .unwrap()
+            .connect_lazy();
+        let sink = SlowingSink {
+            step: Duration::from_millis(15),
+            batches: Arc::new(Mutex::new(Vec::new())),
+        };
+        let batches = sink.batches.clone();
+        let mut actor = test_actor(
+            "127.0.0.1:1".parse().unwrap(),
+            EngineClient::new(channel.clone()),
+            ReplayClient::new(channel),
+            30,
+        )
+        .with_transition_sink(sink);
+        actor.adaptive_batch = Some(Mutex::new(AdaptiveBatchSize::new(
+            16,
+            2,
+            16,
+            Duration::from_millis(20),
+        )));
+
+        for step in 0..80 {
+            let transition = TransitionBuilder::new("tictactoe", "ep", step)
+                .states(Vec::new(), Vec::new())
+                .observations(Vec::new(), Vec::new())
+                .action(vec![0])
+                .outcome(0.0, false)
+                .build()
+                .unwrap();
+            actor.buffer_transitions(vec![transition]).await.unwrap();
+        }
+
+        let sizes = batches.lock().unwrap().clone();
+        assert_eq!(sizes[0], 16);
+        assert!(sizes.windows(2).all(|pair| pair[1] <= pair[0]), "{:?}", sizes);
+        assert!(sizes.iter().all(|size| (2..=16).contains(size)), "{:?}", sizes);
+        assert_eq!(sizes.last(), Some(&2), "{:?}", sizes);
+    }
+
+    #[tokio::test]
+    async fn final_flush_timeout_spills_transitions_to_file() {
+        use prost::Message;
+
+        let channel = Endpoint::new("http://127.0.0.1:1".to_string())
+            .unwrap()
+            .connect_lazy();
+        let mut actor = test_actor(
+            "127.0.0.1:1".parse().unwrap(),
+            EngineClient::new(channel.clone()),
+            ReplayClient::new(channel),
+            30,
+        )
+        .with_transition_sink(StalledSink);
+        let path = std::env::temp_dir().join(format!("actor-spill-{}.pb", std::process::id()));
+        actor.config.drain_timeout_secs = 1;
+        actor.config.spill_file = path.to_string_lossy().into_owned();
+
+        let transitions: Vec<Transition> = (0..3)
+            .map(|step| {
+                TransitionBuilder::new("tictactoe", "ep", step)
+                    .states(vec![step as u8], vec![step as u8 + 1])
+                    .observations(Vec::new(), Vec::new())
+                    .action(vec![step as u8])
+                    .outcome(step as f32, step == 2)
+                    .build()
+                    .unwrap()
+            })
+            .collect();
+        actor.transition_buffer.lock().unwrap().extend(transitions.clone());
+
+        actor.drain().await.unwrap();
+
+        assert!(actor.transition_buffer.lock().unwrap().is_empty());
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut buf = bytes.as_slice();
+        let mut spilled = Vec::new();
+        while !buf.is_empty() {
+            spilled.push(Transition::decode_length_delimited(&mut buf).unwrap());
+        }
+        assert_eq!(spilled, transitions);
+    }
+
+    #[tokio::test]
+    async fn evicted_game_is_reset_and_the_step_replayed() {
+        let engine_service = RewardSequenceEngine {
+            rewards: vec![0.0, 0.0, 1.0],
+            evict_at: Some(1),
+            evictions: Arc::new(AtomicU32::new(1)),
+            ..Default::default()
+        };
+        let resets = engine_service.resets.clone();
+
+        let stored = run_episode_with(engine_service, |actor| actor).await;
+
+        assert_eq!(resets.load(Ordering::SeqCst), 2);
+        let steps: Vec<u32> = stored.iter().map(|t| t.step_number).collect();
+        assert_eq!(steps, vec![0, 1, 2]);
+        assert!(stored[2].done);
+    }
+
+    #[tokio::test]
+    async fn action_repeat_sums_rewards_and_stops_at_done() {
+        // Five moves end the game, so the second decision terminates mid-repeat
+        let engine_service = RewardSequenceEngine {
+            rewards: vec![0.0, 0.5, 0.0, -0.5, 1.0],
+            ..Default::default()
+        };
+        let steps = engine_service.steps.clone();
+        let correlation_ids = engine_service.correlation_ids.clone();
+
+        let stored = run_episode_with(engine_service, |mut actor| {
+            actor.config.env_id = "tictactoe".into();
+            actor.config.action_repeat = 3;
+            actor
+        })
+        .await;
+
+        assert_eq!(steps.load(Ordering::SeqCst), 5);
+        let summary: Vec<(u32, f32, bool, &str)> = stored
+            .iter()
+            .map(|t| {
+                (
+                    t.step_number,
+                    t.reward,
+                    t.done,
+                    t.metadata[ACTION_REPEATS_METADATA_KEY].as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(summary, vec![(0, 0.5, false, "3"), (1, 0.5, true, "2")]);
+
+        // Every engine step of a decision shares its transition's correlation id
+        let expected: Vec<&String> = [(0, 3), (1, 2)]
+            .iter()
+            .flat_map(|&(i, n)| vec![&stored[i].metadata[CORRELATION_ID_METADATA_KEY]; n])
+            .collect();
+        let ids = correlation_ids.lock().unwrap();
+        assert_eq!(ids.iter().collect::<Vec<_>>(), expected);
+    }
+
+    #[tokio::test]
+    async fn repeated_evictions_fail_the_episode_after_bounded_retries() {
+        let engine_service = RewardSequenceEngine {
+            rewards: vec![0.0, 1.0],
+            evict_at: Some(0),
+            evictions: Arc::new(AtomicU32::new(u32::MAX)),
+            ..Default::default()
+        };
+        let resets = engine_service.resets.clone();
+
+        let (result, stored) = try_episode_with(engine_service, |actor| actor).await;
+
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("not initialized"), "unexpected error: {}", err);
+        assert_eq!(resets.load(Ordering::SeqCst), 1 + MAX_EVICTION_RETRIES);
+        assert!(stored.is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_failed_n_step_episode_keeps_the_transitions_it_collected() {
+        let engine_service = RewardSequenceEngine {
+            rewards: vec![1.0, 2.0, 3.0, 4.0],
+            evict_at: Some(2),
+            evictions: Arc::new(AtomicU32::new(u32::MAX)),
+            ..Default::default()
+        };
+
+        let (result, stored) = try_episode_with(engine_service, |mut actor| {
+            actor.config.n_step = Some(2);
+            actor.config.gamma = 0.5;
+            actor
+        })
+        .await;
+
+        assert!(result.is_err());
+        // Returns stop at the last collected step, as for a cancelled episode
+        let returns: Vec<String> = stored
+            .iter()
+            .map(|t| t.metadata[N_STEP_RETURN_METADATA_KEY].clone())
+            .collect();
+        assert_eq!(returns, vec!["2", "2"]);
+    }
+
+    #[test]
+    fn only_the_not_initialized_precondition_counts_as_eviction() {
+        assert!(is_game_evicted(&Status::failed_precondition(
+            "Game not initialized - call reset before step"
+        )));
+        assert!(!is_game_evicted(&Status::failed_precondition(
+            "Invalid state for this game"
+        )));
+        assert!(!is_game_evicted(&Status::internal("Game not initialized")));
+        assert!(!is_game_evicted(&Status::invalid_argument("Bad action")));
+    }
+
+    #[tokio::test]
+    async fn max_steps_truncates_the_episode() {
+        let engine_service = RewardSequenceEngine {
+            rewards: vec![0.0, 0.0, 0.5, 0.0, 1.0],
+            ..Default::default()
+        };
+        let steps = engine_service.steps.clone();
+
+        let stored = run_episode_with(engine_service, |mut actor| {
+            actor.config.env_id = "tictactoe".into();
+            actor.config.max_steps = Some(3);
+            actor.config.n_step = Some(5);
+            actor.config.gamma = 0.5;
+            actor
+        })
+        .await;
+
+        assert_eq!(steps.load(Ordering::SeqCst), 3);
+        let summary: Vec<(u32, bool, Option<&str>)> = stored
+            .iter()
+            .map(|t| {
+                let truncated = t.metadata.get(TRUNCATED_METADATA_KEY).map(String::as_str);
+                (t.step_number, t.done, truncated)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![(0, false, None), (1, false, None), (2, true, Some("true"))]
+        );
+        // Returns stop at the cap rather than including the unplayed rewards
+        assert_eq!(stored[0].metadata[N_STEP_RETURN_METADATA_KEY], "0.125");
+    }
+
+    #[tokio::test]
+    async fn transition_hook_sees_every_transition_before_replay() {
+        let engine_service = RewardSequenceEngine {
+            rewards: vec![0.0, 0.0, 1.0],
+            ..Default::default()
+        };
+        let seen = Arc::new(AtomicU32::new(0));
+        let hook_seen = seen.clone();
+
+        let stored = run_episode_with(engine_service, |actor| {
+            actor.with_transition_hook(move |_| {
+                hook_seen.fetch_add(1, Ordering::SeqCst);
+            })
+        })
+        .await;
+
+        assert_eq!(seen.load(Ordering::SeqCst), 3);
+        assert_eq!(stored.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn abs_reward_priority_uses_the_reward_magnitude() {
+        let engine_service = RewardSequenceEngine {
+            rewards: vec![-1.0, 0.5, 2.0],
+            ..Default::default()
+        };
+
+        let stored = run_episode_with(engine_service, |actor| {
+            actor.with_priority_fn(Priority::AbsReward.priority_fn())
+        })
+        .await;
+
+        assert_eq!(stored.len(), 3);
+        for transition in &stored {
+            assert_eq!(transition.priority, transition.reward.abs());
+        }
+        let priorities: Vec<f32> = stored.iter().map(|t| t.priority).collect();
+        assert_eq!(priorities, vec![1.0, 0.5, 2.0]);
+    }
+
+    #[tokio::test]
+    async fn transition_filter_drops_rejected_transitions() {
+        let engine_service = RewardSequenceEngine {
+            rewards: vec![0.0, 0.0, 1.0],
+            ..Default::default()
+        };
+
+        let stored = run_episode_with(engine_service, |actor| {
+            actor.with_transition_filter(|transition| !transition.done)
+        })
+        .await;
+
+        let steps: Vec<u32> = stored.iter().map(|t| t.step_number).collect();
+        assert_eq!(steps, vec![0, 1]);
+        assert!(stored.iter().all(|t| !t.done));
+    }
+
+    #[tokio::test]
+    async fn players_without_legal_moves_pass_with_an_empty_action() {
+        let stored_transitions = Arc::new(Mutex::new(Vec::new()));
+        let replay_service = MockReplay {
+            stored: stored_transitions.clone(),
+            ..Default::default()
+        };
+        let engine_service = RewardSequenceEngine {
+            rewards: vec![0.0, 0.0, 0.0, 1.0],
+            passes: vec![1],
+            ..Default::default()
+        };
+        let engine_actions = engine_service.actions.clone();
+
+        let (addr, server) = spawn_test_server(engine_service, replay_service);
+
+        let mut actor = connected_test_actor(addr, 30);
+        let capabilities = Capabilities {
+            action_space: Some(capabilities::ActionSpace::DiscreteN(9)),
+            ..Default::default()
+        };
+        actor.policy = Arc::new(Mutex::new(Box::new(
+            RandomPolicy::with_seed(&capabilities, 3).unwrap(),
+        )));
+
+        let result = actor.run_episode(&CancellationToken::new()).await;
+        assert_eq!(result.unwrap(), EpisodeEnd::Completed);
+
+        // Only the step after the engine asked for a pass sends an empty action
+        let actions = engine_actions.lock().unwrap().clone();
+        let action_lens: Vec<usize> = actions.iter().map(Vec::len).collect();
+        assert_eq!(action_lens, vec![1, 1, 0, 1]);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn self_play_routes_turns_and_attributes_rewards_per_player() {
+        let stored_transitions = Arc::new(Mutex::new(Vec::new()));
+        let replay_service = MockReplay {
+            stored: stored_transitions.clone(),
+            ..Default::default()
+        };
+        let engine_service = TwoPlayerEngine::default();
+        let engine_actions = engine_service.actions.clone();
+
+        let (addr, server) = spawn_test_server(engine_service, replay_service);
+
+        let mut actor = connected_test_actor(addr, 30);
+        let x_moves = ScriptedPolicy::new(vec![vec![0], vec![1], vec![2]], ScriptEnd::Error);
+        let o_moves = ScriptedPolicy::new(vec![vec![3], vec![4]], ScriptEnd::Error);
+        actor.config.self_play = true;
+        actor.policy = Arc::new(Mutex::new(Box::new(x_moves.unwrap())));
+        actor.opponent = Some(Arc::new(Mutex::new(Box::new(o_moves.unwrap()))));
+
+        let result = actor.run_episode(&CancellationToken::new()).await;
+        assert_eq!(result.unwrap(), EpisodeEnd::Completed);
+        assert_eq!(*engine_actions.lock().unwrap(), vec![0, 3, 1, 4, 2]);
+
+        let transitions: Vec<(String, f32)> = stored_transitions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|t| (t.metadata[PLAYER_METADATA_KEY].clone(), t.reward))
+            .collect();
+        let expected = [("1", 0.0), ("2", 0.0), ("1", 0.0), ("2", -1.0), ("1", 1.0)];
+        assert_eq!(
+            transitions,
+            expected
+                .iter()
+                .map(|&(player, reward)| (player.to_string(), reward))
+                .collect::<Vec<_>>(),
+            "X's winning move is +1 and O's last move is -1"
+        );
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn self_play_pays_a_non_zero_draw_reward_to_both_players() {
+        let stored_transitions = Arc::new(Mutex::new(Vec::new()));
+        let replay_service = MockReplay {
+            stored: stored_transitions.clone(),
+            ..Default::default()
+        };
+        let engine_service = TwoPlayerEngine {
+            draw_reward: Some(0.5),
+            ..Default::default()
+        };
+
+        let (addr, server) = spawn_test_server(engine_service, replay_service);
+
+        let mut actor = connected_test_actor(addr, 30);
+        let x_moves = ScriptedPolicy::new(vec![vec![0], vec![1], vec![2]], ScriptEnd::Error);
+        let o_moves = ScriptedPolicy::new(vec![vec![3], vec![4]], ScriptEnd::Error);
+        actor.config.self_play = true;
+        actor.policy = Arc::new(Mutex::new(Box::new(x_moves.unwrap())));
+        actor.opponent = Some(Arc::new(Mutex::new(Box::new(o_moves.unwrap()))));
+
+        let result = actor.run_episode(&CancellationToken::new()).await;
+        assert_eq!(result.unwrap(), EpisodeEnd::Completed);
+
+        let rewards: Vec<f32> = stored_transitions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|t| t.reward)
+            .collect();
+        assert_eq!(
+            rewards,
+            vec![0.0, 0.0, 0.0, 0.5, 0.5],
+            "a draw is not zero-sum, so O's last move takes the engine's reward, not -0.5"
+        );
 
-        drop(received);
-        shutdown_tx.send(()).unwrap();
-        server_handle.await.unwrap();
+        server.shutdown().await;
     }
 }
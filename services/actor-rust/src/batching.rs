@@ -0,0 +1,87 @@
+//! Flush batch sizing driven by replay latency
+//!
+//! With `--adaptive-batch` the actor sizes its flushes by how long replay
+//! takes to store them instead of using a fixed `batch_size`.
+//! `AdaptiveBatchSize` smooths each `StoreBatch` latency into a moving
+//! average and compares it with the configured target:
+//!
+//! - above the target, replay is falling behind and the size is halved;
+//! - below half the target, replay has headroom and the size grows by an
+//!   eighth (at least one);
+//! - in between, the size is held.
+//!
+//! Halving cuts the latency of the next batches roughly in half, which lands
+//! in the hold band rather than straight back below it, so the size settles
+//! instead of oscillating around the target. The size never leaves
+//! `[min, max]`.
+
+use std::time::Duration;
+
+/// Weight of the newest latency in the moving average
+const SMOOTHING: f64 = 0.5;
+
+/// Batch size that adapts to replay's store latency
+#[derive(Debug, Clone)]
+pub struct AdaptiveBatchSize {
+    size: usize,
+    min: usize,
+    max: usize,
+    target: Duration,
+    /// Moving average of store latency in seconds; `None` before the first batch
+    smoothed: Option<f64>,
+}
+
+impl AdaptiveBatchSize {
+    /// Start at `initial`, clamped to `[min, max]`
+    pub fn new(initial: usize, min: usize, max: usize, target: Duration) -> Self {
+        Self {
+            size: initial.clamp(min, max),
+            min,
+            max,
+            target,
+            smoothed: None,
+        }
+    }
+
+    /// Transitions to buffer before flushing
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Record how long a batch took to store and return the new size
+    pub fn observe(&mut self, latency: Duration) -> usize {
+        let latency = latency.as_secs_f64();
+        let smoothed = match self.smoothed {
+            Some(previous) => SMOOTHING * latency + (1.0 - SMOOTHING) * previous,
+            None => latency,
+        };
+        self.smoothed = Some(smoothed);
+
+        let target = self.target.as_secs_f64();
+        if smoothed > target {
+            self.size = (self.size / 2).max(self.min);
+        } else if smoothed < target / 2.0 {
+            self.size = (self.size + (self.size / 8).max(1)).min(self.max);
+        }
+        self.size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_grows_when_fast_and_holds_near_the_target() {
+        let mut batch = AdaptiveBatchSize::new(16, 4, 20, Duration::from_millis(100));
+        let fast = Duration::from_millis(10);
+        let sizes: Vec<usize> = (0..4).map(|_| batch.observe(fast)).collect();
+        assert_eq!(sizes, vec![18, 20, 20, 20]);
+
+        // Latency between half the target and the target holds the size
+        let mut batch = AdaptiveBatchSize::new(16, 4, 64, Duration::from_millis(100));
+        for _ in 0..10 {
+            assert_eq!(batch.observe(Duration::from_millis(75)), 16);
+        }
+    }
+}
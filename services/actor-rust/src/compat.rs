@@ -0,0 +1,120 @@
+use crate::proto::engine::v1::{capabilities, Capabilities, Encoding};
+use anyhow::{anyhow, Result};
+use tracing::info;
+
+/// Wire format this actor was built against for environments it knows
+///
+/// The actor encodes actions and reads observations assuming these formats,
+/// so an engine reporting anything else would silently feed it garbage.
+pub fn expected_capabilities(env_id: &str) -> Option<Capabilities> {
+    match env_id {
+        "tictactoe" => Some(Capabilities {
+            enc: Some(Encoding {
+                state: "tictactoe_state:v1".to_string(),
+                action: "discrete_position:v1".to_string(),
+                obs: "f32x29:v1".to_string(),
+                schema_version: 1,
+            }),
+            action_space: Some(capabilities::ActionSpace::DiscreteN(9)),
+            ..Default::default()
+        }),
+        _ => None,
+    }
+}
+
+/// Whether data encoded for `a` can be exchanged with `b` unchanged
+///
+/// Mirrors `engine_core::typed::Capabilities::is_wire_compatible`: only the
+/// encodings, schema version, and action space are compared. Engine id,
+/// `max_horizon`, `preferred_batch`, and the other descriptive fields may
+/// differ.
+pub fn is_wire_compatible(a: &Capabilities, b: &Capabilities) -> bool {
+    a.enc == b.enc && a.action_space == b.action_space
+}
+
+/// Refuse to run against an engine whose wire format differs from the
+/// compiled-in expectations for `env_id`
+///
+/// Environments without compiled-in expectations are accepted as-is.
+pub fn check_wire_compatibility(env_id: &str, actual: &Capabilities) -> Result<()> {
+    let Some(expected) = expected_capabilities(env_id) else {
+        info!(
+            "No compiled-in wire format for {}, skipping compatibility check",
+            env_id
+        );
+        return Ok(());
+    };
+
+    if is_wire_compatible(&expected, actual) {
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "Engine for {} is not wire-compatible with this actor (expected encoding {:?} and action space {:?}, got {:?} and {:?})",
+        env_id,
+        expected.enc,
+        expected.action_space,
+        actual.enc,
+        actual.action_space
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::engine::v1::{EngineId, MultiDiscrete};
+
+    fn tictactoe() -> Capabilities {
+        expected_capabilities("tictactoe").unwrap()
+    }
+
+    #[test]
+    fn test_non_wire_fields_are_ignored() {
+        let mut served = tictactoe();
+        served.id = Some(EngineId {
+            env_id: "tictactoe".to_string(),
+            build_id: "0.1.0".to_string(),
+        });
+        served.max_horizon = 9;
+        served.preferred_batch = 64;
+        served.action_names = vec!["a1".to_string(); 9];
+
+        assert!(is_wire_compatible(&tictactoe(), &served));
+        assert!(check_wire_compatibility("tictactoe", &served).is_ok());
+    }
+
+    #[test]
+    fn test_differing_obs_encoding_is_rejected() {
+        let mut served = tictactoe();
+        served.enc.as_mut().unwrap().obs = "stack2(f32x29:v1)".to_string();
+
+        assert!(!is_wire_compatible(&tictactoe(), &served));
+        let err = check_wire_compatibility("tictactoe", &served).unwrap_err();
+        assert!(err.to_string().contains("not wire-compatible"));
+    }
+
+    #[test]
+    fn test_differing_schema_version_is_rejected() {
+        let mut served = tictactoe();
+        served.enc.as_mut().unwrap().schema_version = 3;
+
+        assert!(!is_wire_compatible(&tictactoe(), &served));
+    }
+
+    #[test]
+    fn test_differing_action_space_is_rejected() {
+        let mut served = tictactoe();
+        served.action_space = Some(capabilities::ActionSpace::DiscreteN(10));
+        assert!(!is_wire_compatible(&tictactoe(), &served));
+
+        served.action_space = Some(capabilities::ActionSpace::Multi(MultiDiscrete {
+            nvec: vec![3, 3],
+        }));
+        assert!(!is_wire_compatible(&tictactoe(), &served));
+    }
+
+    #[test]
+    fn test_unknown_env_is_not_checked() {
+        assert!(check_wire_compatibility("unknown", &Capabilities::default()).is_ok());
+    }
+}
@@ -1,83 +1,444 @@
-use anyhow::{anyhow, Result};
-use clap::Parser;
-use serde::{Deserialize, Serialize};
-use std::time::Duration;
-
-#[derive(Parser, Debug, Clone, Serialize, Deserialize)]
-#[command(name = "actor")]
-#[command(about = "Cartridge RL Actor Service")]
-#[command(long_about = "Actor service that runs game episodes and collects experience data.
-
-The actor connects to the engine service to simulate games and sends
-transition data to the replay service for training.")]
-pub struct Config {
-    /// Engine service address
-    #[arg(long, env = "ACTOR_ENGINE_ADDR", default_value = "http://localhost:50051")]
-    pub engine_addr: String,
-
-    /// Replay service address
-    #[arg(long, env = "ACTOR_REPLAY_ADDR", default_value = "http://localhost:8080")]
-    pub replay_addr: String,
-
-    /// Unique actor identifier
-    #[arg(long, env = "ACTOR_ACTOR_ID", default_value = "actor-rust-1")]
-    pub actor_id: String,
-
-    /// Environment ID to run (e.g., tictactoe)
-    #[arg(long, env = "ACTOR_ENV_ID", default_value = "tictactoe")]
-    pub env_id: String,
-
-    /// Maximum episodes to run (-1 for unlimited)
-    #[arg(long, env = "ACTOR_MAX_EPISODES", default_value = "-1")]
-    pub max_episodes: i32,
-
-    /// Timeout per episode in seconds
-    #[arg(long, env = "ACTOR_EPISODE_TIMEOUT", default_value = "30")]
-    pub episode_timeout_secs: u64,
-
-    /// Batch size for replay buffer
-    #[arg(long, env = "ACTOR_BATCH_SIZE", default_value = "32")]
-    pub batch_size: usize,
-
-    /// Interval to flush partial batches in seconds
-    #[arg(long, env = "ACTOR_FLUSH_INTERVAL", default_value = "5")]
-    pub flush_interval_secs: u64,
-
-    /// Log level (trace, debug, info, warn, error)
-    #[arg(long, env = "ACTOR_LOG_LEVEL", default_value = "info")]
-    pub log_level: String,
-}
-
-impl Config {
-    pub fn validate(&self) -> Result<()> {
-        if self.actor_id.is_empty() {
-            return Err(anyhow!("actor_id cannot be empty"));
-        }
-
-        if self.env_id.is_empty() {
-            return Err(anyhow!("env_id cannot be empty"));
-        }
-
-        if self.batch_size == 0 {
-            return Err(anyhow!("batch_size must be greater than 0"));
-        }
-
-        if self.episode_timeout_secs == 0 {
-            return Err(anyhow!("episode_timeout_secs must be greater than 0"));
-        }
-
-        if self.flush_interval_secs == 0 {
-            return Err(anyhow!("flush_interval_secs must be greater than 0"));
-        }
-
-        Ok(())
-    }
-
-    pub fn episode_timeout(&self) -> Duration {
-        Duration::from_secs(self.episode_timeout_secs)
-    }
-
-    pub fn flush_interval(&self) -> Duration {
-        Duration::from_secs(self.flush_interval_secs)
-    }
-}
\ No newline at end of file
+use anyhow::{anyhow, Context, Result};
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs;
+use std::time::Duration;
+use tracing_subscriber::filter::LevelFilter;
+
+use crate::priority::Priority;
+
+#[derive(Parser, Debug, Clone, Serialize, Deserialize)]
+#[command(name = "actor")]
+#[command(about = "Cartridge RL Actor Service")]
+#[command(long_about = "Actor service that runs game episodes and collects experience data.
+
+The actor connects to the engine service to simulate games and sends
+transition data to the replay service for training.")]
+pub struct Config {
+    /// Engine service address
+    #[arg(long, env = "ACTOR_ENGINE_ADDR", default_value = "http://localhost:50051")]
+    pub engine_addr: String,
+
+    /// Replay service address
+    #[arg(long, env = "ACTOR_REPLAY_ADDR", default_value = "http://localhost:8080")]
+    pub replay_addr: String,
+
+    /// Unique actor identifier
+    #[arg(long, env = "ACTOR_ACTOR_ID", default_value = "actor-rust-1")]
+    pub actor_id: String,
+
+    /// Environment ID to run (e.g., tictactoe)
+    #[arg(long, env = "ACTOR_ENV_ID", default_value = "tictactoe")]
+    pub env_id: String,
+
+    /// Maximum episodes to run (-1 for unlimited)
+    #[arg(long, env = "ACTOR_MAX_EPISODES", default_value = "-1")]
+    pub max_episodes: i32,
+
+    /// Timeout per episode in seconds
+    #[arg(long, env = "ACTOR_EPISODE_TIMEOUT", default_value = "30")]
+    pub episode_timeout_secs: u64,
+
+    /// Batch size for replay buffer
+    #[arg(long, env = "ACTOR_BATCH_SIZE", default_value = "32")]
+    pub batch_size: usize,
+
+    /// Adapt the flush batch size to replay's store latency instead of fixing it at batch_size
+    #[arg(long, env = "ACTOR_ADAPTIVE_BATCH")]
+    pub adaptive_batch: bool,
+
+    /// Smallest flush batch size in adaptive mode
+    #[arg(long, env = "ACTOR_MIN_BATCH_SIZE", default_value = "8")]
+    pub min_batch_size: usize,
+
+    /// Largest flush batch size in adaptive mode
+    #[arg(long, env = "ACTOR_MAX_BATCH_SIZE", default_value = "512")]
+    pub max_batch_size: usize,
+
+    /// Store latency in milliseconds above which adaptive mode shrinks batches
+    #[arg(long, env = "ACTOR_BATCH_LATENCY_TARGET_MS", default_value = "200")]
+    pub batch_latency_target_ms: u64,
+
+    /// Interval to flush partial batches in seconds
+    #[arg(long, env = "ACTOR_FLUSH_INTERVAL", default_value = "5")]
+    pub flush_interval_secs: u64,
+
+    /// Seconds the final flush on shutdown may take before transitions are spilled
+    #[arg(long, env = "ACTOR_DRAIN_TIMEOUT", default_value = "10")]
+    pub drain_timeout_secs: u64,
+
+    /// File that transitions the final flush could not deliver are appended to
+    #[arg(long, env = "ACTOR_SPILL_FILE", default_value = "actor-spill.pb")]
+    pub spill_file: String,
+
+    /// Log level (trace, debug, info, warn, error)
+    #[arg(long, env = "ACTOR_LOG_LEVEL", default_value = "info")]
+    pub log_level: String,
+
+    /// Scale rewards by a running standard deviation (raw reward kept in metadata)
+    #[arg(long, env = "ACTOR_NORMALIZE_REWARDS")]
+    pub normalize_rewards: bool,
+
+    /// CA certificate (PEM) used to verify https:// engine and replay servers
+    #[arg(long, env = "ACTOR_TLS_CA_CERT")]
+    pub tls_ca_cert: Option<String>,
+
+    /// Client certificate (PEM) presented for mutual TLS
+    #[arg(long, env = "ACTOR_TLS_CLIENT_CERT")]
+    pub tls_client_cert: Option<String>,
+
+    /// Client private key (PEM) for mutual TLS
+    #[arg(long, env = "ACTOR_TLS_CLIENT_KEY")]
+    pub tls_client_key: Option<String>,
+
+    /// Replay hex-encoded actions from this file (one per line) instead of a random policy
+    #[arg(long, env = "ACTOR_SCRIPT_FILE")]
+    pub script_file: Option<String>,
+
+    /// Restart the action script when it runs out instead of failing the episode
+    #[arg(long, env = "ACTOR_SCRIPT_CYCLE")]
+    pub script_cycle: bool,
+
+    /// Buffer whole episodes and attach N-step discounted returns to each transition
+    #[arg(long, env = "ACTOR_N_STEP")]
+    pub n_step: Option<usize>,
+
+    /// Discount factor used for n-step returns
+    #[arg(long, env = "ACTOR_GAMMA", default_value = "0.99")]
+    pub gamma: f32,
+
+    /// Address for the /healthz and /readyz HTTP server (disabled when unset)
+    #[arg(long, env = "ACTOR_HEALTH_ADDR")]
+    pub health_addr: Option<String>,
+
+    /// Report not ready when no episode has completed for this many seconds
+    #[arg(long, env = "ACTOR_READINESS_STALE_SECS", default_value = "120")]
+    pub readiness_stale_secs: u64,
+
+    /// Drive the two players of a two-player game with separate policies
+    #[arg(long, env = "ACTOR_SELF_PLAY")]
+    pub self_play: bool,
+
+    /// Script for the second player in self-play (random when unset)
+    #[arg(long, env = "ACTOR_OPPONENT_SCRIPT_FILE")]
+    pub opponent_script_file: Option<String>,
+
+    /// Engine steps taken with each selected action, summing their rewards into one transition
+    #[arg(long, env = "ACTOR_ACTION_REPEAT", default_value = "1")]
+    pub action_repeat: u32,
+
+    /// End episodes after this many transitions, marking the last one truncated
+    #[arg(long, env = "ACTOR_MAX_STEPS")]
+    pub max_steps: Option<u32>,
+
+    /// Replay priority of each transition (constant, abs-reward, td-error)
+    #[arg(long, env = "ACTOR_PRIORITY", default_value = "constant")]
+    pub priority: String,
+
+    /// Run-level seed; episode seeds are derived from it, the actor id, and the episode index
+    #[arg(long, env = "ACTOR_BASE_SEED")]
+    pub base_seed: Option<u64>,
+
+    /// Drop this many transitions before storing any, e.g. while a policy warms up
+    #[arg(long, env = "ACTOR_WARMUP_DISCARD", default_value = "0")]
+    pub warmup_discard: u64,
+
+    /// Extend the warm-up to the end of the episode it ends in, so no episode is stored partially
+    #[arg(long, env = "ACTOR_WARMUP_WHOLE_EPISODES")]
+    pub warmup_whole_episodes: bool,
+
+    /// TOML file with values for options not set on the command line or in the environment
+    #[arg(long, env = "ACTOR_CONFIG")]
+    pub config: Option<String>,
+}
+
+impl Config {
+    /// Parse the process arguments, filling unset options from `--config`
+    pub fn load() -> Result<Self> {
+        Self::load_from(std::env::args_os())
+    }
+
+    /// Parse `args`, filling options they leave unset from `--config`
+    ///
+    /// Precedence, highest first: command-line flags, `ACTOR_*` environment
+    /// variables, the config file, built-in defaults. File keys are the field
+    /// names (e.g. `batch_size = 64`); each value is parsed exactly as its
+    /// flag would be, and unknown keys are rejected.
+    pub fn load_from<I, T>(args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let mut args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+        let command = Self::command();
+        let matches = command.clone().get_matches_from(&args);
+        let config = Self::from_arg_matches(&matches)?;
+        let Some(path) = config.config.clone() else {
+            return Ok(config);
+        };
+
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file {}", path))?;
+        let file: toml::Table = toml::from_str(&text)
+            .with_context(|| format!("Failed to parse config file {}", path))?;
+
+        // File values become flags, but only where neither the command line
+        // nor the environment already decided the option
+        for (key, value) in file {
+            let arg = command
+                .get_arguments()
+                .find(|arg| arg.get_id() == key.as_str() && key != "config")
+                .ok_or_else(|| anyhow!("Unknown option '{}' in config file {}", key, path))?;
+            if matches!(
+                matches.value_source(&key),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            ) {
+                continue;
+            }
+
+            let long = arg.get_long().expect("every config option has a long flag");
+            let value = match value {
+                toml::Value::String(value) => value,
+                toml::Value::Integer(value) => value.to_string(),
+                toml::Value::Float(value) => value.to_string(),
+                toml::Value::Boolean(value) if !arg.get_action().takes_values() => {
+                    if value {
+                        args.push(format!("--{}", long).into());
+                    }
+                    continue;
+                }
+                toml::Value::Boolean(value) => value.to_string(),
+                other => {
+                    return Err(anyhow!(
+                        "Option '{}' in config file {} must be a string, number, or boolean, got {}",
+                        key,
+                        path,
+                        other.type_str()
+                    ))
+                }
+            };
+            args.push(format!("--{}={}", long, value).into());
+        }
+
+        let matches = command
+            .try_get_matches_from(args)
+            .map_err(|e| anyhow!("Invalid value in config file {}: {}", path, e))?;
+        Ok(Self::from_arg_matches(&matches)?)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.actor_id.is_empty() {
+            return Err(anyhow!("actor_id cannot be empty"));
+        }
+
+        if self.env_id.is_empty() {
+            return Err(anyhow!("env_id cannot be empty"));
+        }
+
+        if self.batch_size == 0 {
+            return Err(anyhow!("batch_size must be greater than 0"));
+        }
+
+        if self.adaptive_batch {
+            if self.min_batch_size == 0 {
+                return Err(anyhow!("min_batch_size must be greater than 0"));
+            }
+            if self.min_batch_size > self.max_batch_size {
+                return Err(anyhow!(
+                    "min_batch_size ({}) cannot exceed max_batch_size ({})",
+                    self.min_batch_size,
+                    self.max_batch_size
+                ));
+            }
+            if self.batch_latency_target_ms == 0 {
+                return Err(anyhow!("batch_latency_target_ms must be greater than 0"));
+            }
+        }
+
+        if self.episode_timeout_secs == 0 {
+            return Err(anyhow!("episode_timeout_secs must be greater than 0"));
+        }
+
+        if self.flush_interval_secs == 0 {
+            return Err(anyhow!("flush_interval_secs must be greater than 0"));
+        }
+
+        if self.drain_timeout_secs == 0 {
+            return Err(anyhow!("drain_timeout_secs must be greater than 0"));
+        }
+
+        if self.spill_file.is_empty() {
+            return Err(anyhow!("spill_file cannot be empty"));
+        }
+
+        if self.log_level.parse::<LevelFilter>().is_err() {
+            return Err(anyhow!(
+                "log_level '{}' is invalid (expected one of: off, trace, debug, info, warn, error)",
+                self.log_level
+            ));
+        }
+
+        self.priority.parse::<Priority>()?;
+
+        if self.tls_client_cert.is_some() != self.tls_client_key.is_some() {
+            return Err(anyhow!("tls_client_cert and tls_client_key must be set together"));
+        }
+
+        if self.script_cycle && self.script_file.is_none() {
+            return Err(anyhow!("script_cycle requires script_file"));
+        }
+
+        if self.n_step == Some(0) {
+            return Err(anyhow!("n_step must be greater than 0"));
+        }
+
+        if !(0.0..=1.0).contains(&self.gamma) {
+            return Err(anyhow!("gamma must be between 0 and 1, got {}", self.gamma));
+        }
+
+        if self.readiness_stale_secs == 0 {
+            return Err(anyhow!("readiness_stale_secs must be greater than 0"));
+        }
+
+        if self.max_steps == Some(0) {
+            return Err(anyhow!("max_steps must be greater than 0"));
+        }
+
+        if self.action_repeat == 0 {
+            return Err(anyhow!("action_repeat must be greater than 0"));
+        }
+
+        // Repeating an action would replay it on the other player's turn
+        if self.self_play && self.action_repeat > 1 {
+            return Err(anyhow!("action_repeat is not supported with self_play"));
+        }
+
+        if self.opponent_script_file.is_some() && !self.self_play {
+            return Err(anyhow!("opponent_script_file requires self_play"));
+        }
+
+        // Returns over interleaved turns would mix both players' rewards
+        if self.self_play && self.n_step.is_some() {
+            return Err(anyhow!("n_step is not supported with self_play"));
+        }
+
+        Ok(())
+    }
+
+    pub fn episode_timeout(&self) -> Duration {
+        Duration::from_secs(self.episode_timeout_secs)
+    }
+
+    pub fn flush_interval(&self) -> Duration {
+        Duration::from_secs(self.flush_interval_secs)
+    }
+
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.drain_timeout_secs)
+    }
+
+    pub fn batch_latency_target(&self) -> Duration {
+        Duration::from_millis(self.batch_latency_target_ms)
+    }
+
+    pub fn readiness_staleness(&self) -> Duration {
+        Duration::from_secs(self.readiness_stale_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> Config {
+        Config::parse_from(["actor"])
+    }
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(test_config().validate().is_ok());
+    }
+
+    #[test]
+    fn test_log_level_accepts_known_levels() {
+        for level in ["trace", "debug", "info", "warn", "error", "off", "DEBUG"] {
+            let mut config = test_config();
+            config.log_level = level.to_string();
+            assert!(config.validate().is_ok(), "level '{}' should be accepted", level);
+        }
+    }
+
+    #[test]
+    fn test_invalid_log_level_rejected() {
+        let mut config = test_config();
+        config.log_level = "verbose".to_string();
+
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("log_level 'verbose' is invalid"));
+    }
+
+    /// Write `contents` to a config file unique to `name`
+    fn config_file(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "actor-config-{}-{}.toml",
+            name,
+            std::process::id()
+        ));
+        fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_config_file_fills_options_and_cli_flags_win() {
+        let path = config_file(
+            "precedence",
+            "env_id = \"connect4\"\nbatch_size = 64\nmax_episodes = -1\ngamma = 0.5\nnormalize_rewards = true\n",
+        );
+
+        let config =
+            Config::load_from(["actor", "--config", path.as_str(), "--batch-size", "8"]).unwrap();
+        assert_eq!(config.batch_size, 8, "command line beats the file");
+        assert_eq!(config.env_id, "connect4", "file beats the default");
+        assert_eq!(config.max_episodes, -1);
+        assert_eq!(config.gamma, 0.5);
+        assert!(config.normalize_rewards);
+        assert_eq!(config.flush_interval_secs, 5, "unset everywhere keeps the default");
+        assert!(config.validate().is_ok());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_config_file_rejects_unknown_and_malformed_options() {
+        let unknown = config_file("unknown", "batch_sise = 64\n");
+        let err = Config::load_from(["actor", "--config", unknown.as_str()]).unwrap_err();
+        assert!(err.to_string().contains("Unknown option 'batch_sise'"), "{}", err);
+
+        let malformed = config_file("malformed", "batch_size = \"many\"\n");
+        let err = Config::load_from(["actor", "--config", malformed.as_str()]).unwrap_err();
+        assert!(err.to_string().contains("Invalid value in config file"), "{}", err);
+
+        // The merged config is still subject to validation
+        let invalid = config_file("invalid", "batch_size = 0\n");
+        let config = Config::load_from(["actor", "--config", invalid.as_str()]).unwrap();
+        assert!(config.validate().is_err());
+
+        for path in [unknown, malformed, invalid] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_client_cert_requires_key() {
+        let mut config = test_config();
+        config.tls_client_cert = Some("client.pem".to_string());
+
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("must be set together"));
+
+        config.tls_client_key = Some("client.key".to_string());
+        assert!(config.validate().is_ok());
+    }
+}
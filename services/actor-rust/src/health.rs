@@ -0,0 +1,153 @@
+use anyhow::{Context, Result};
+use axum::{extract::State, http::StatusCode, routing::get, Router};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+/// Liveness and readiness signals shared between the actor and the HTTP probe server
+///
+/// The actor is ready once it has connected to the engine and replay services
+/// and keeps completing episodes. Before the first episode completes, the
+/// staleness window is measured from when the actor connected.
+#[derive(Debug)]
+pub struct HealthState {
+    connected: AtomicBool,
+    last_progress: Mutex<Instant>,
+    staleness: Duration,
+}
+
+impl HealthState {
+    pub fn new(staleness: Duration) -> Self {
+        Self {
+            connected: AtomicBool::new(false),
+            last_progress: Mutex::new(Instant::now()),
+            staleness,
+        }
+    }
+
+    /// Record that the engine and replay connections are established
+    pub fn mark_connected(&self) {
+        *self.last_progress.lock().unwrap() = Instant::now();
+        self.connected.store(true, Ordering::SeqCst);
+    }
+
+    /// Record that an episode just completed
+    pub fn record_episode(&self) {
+        *self.last_progress.lock().unwrap() = Instant::now();
+    }
+
+    /// Why the actor is not ready, or `None` if it is
+    pub fn not_ready_reason(&self) -> Option<String> {
+        if !self.connected.load(Ordering::SeqCst) {
+            return Some("not connected to engine and replay".to_string());
+        }
+
+        let idle = self.last_progress.lock().unwrap().elapsed();
+        if idle > self.staleness {
+            return Some(format!(
+                "no episode completed in the last {}s",
+                idle.as_secs()
+            ));
+        }
+
+        None
+    }
+}
+
+/// Routes for `/healthz` (process alive) and `/readyz` (connected and making progress)
+pub fn router(state: Arc<HealthState>) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(state)
+}
+
+/// Serve the probe endpoints on `listener` until `shutdown` is cancelled
+pub async fn serve(
+    listener: TcpListener,
+    state: Arc<HealthState>,
+    shutdown: CancellationToken,
+) -> Result<()> {
+    let addr = listener.local_addr()?;
+    info!("Health server listening on {}", addr);
+
+    axum::Server::from_tcp(listener)
+        .with_context(|| format!("Failed to start health server on {}", addr))?
+        .serve(router(state).into_make_service())
+        .with_graceful_shutdown(async move { shutdown.cancelled().await })
+        .await
+        .context("Health server failed")?;
+
+    info!("Health server stopped");
+    Ok(())
+}
+
+async fn healthz() -> &'static str {
+    "ok"
+}
+
+async fn readyz(State(state): State<Arc<HealthState>>) -> (StatusCode, String) {
+    match state.not_ready_reason() {
+        None => (StatusCode::OK, "ready".to_string()),
+        Some(reason) => (StatusCode::SERVICE_UNAVAILABLE, reason),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    async fn get_status(addr: SocketAddr, path: &str) -> u16 {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            path, addr
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse().ok())
+            .expect("response should start with a status line")
+    }
+
+    #[tokio::test]
+    async fn test_probes_report_liveness_and_staleness() {
+        let state = Arc::new(HealthState::new(Duration::from_secs(60)));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let shutdown = CancellationToken::new();
+        let server = tokio::spawn(serve(listener, state.clone(), shutdown.clone()));
+
+        assert_eq!(get_status(addr, "/healthz").await, 200);
+        assert_eq!(get_status(addr, "/readyz").await, 503, "not connected yet");
+
+        state.mark_connected();
+        state.record_episode();
+        assert_eq!(get_status(addr, "/readyz").await, 200);
+
+        // Force the last episode outside the staleness window
+        *state.last_progress.lock().unwrap() = Instant::now() - Duration::from_secs(120);
+        assert_eq!(get_status(addr, "/readyz").await, 503);
+        assert_eq!(
+            get_status(addr, "/healthz").await,
+            200,
+            "stale actors are still alive"
+        );
+
+        shutdown.cancel();
+        server
+            .await
+            .unwrap()
+            .expect("health server should shut down cleanly");
+    }
+}
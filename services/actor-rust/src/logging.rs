@@ -0,0 +1,94 @@
+use anyhow::{anyhow, Result};
+use std::env;
+use std::str::FromStr;
+use tracing::Subscriber;
+use tracing_subscriber::EnvFilter;
+
+/// Environment variable selecting the log output format
+pub const LOG_FORMAT_ENV: &str = "LOG_FORMAT";
+
+/// Supported log output formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable single-line output
+    #[default]
+    Pretty,
+    /// Newline-delimited JSON for log aggregators
+    Json,
+}
+
+impl LogFormat {
+    /// Read the format from `LOG_FORMAT`, defaulting to `Pretty` when unset
+    pub fn from_env() -> Result<Self> {
+        match env::var(LOG_FORMAT_ENV) {
+            Ok(value) => value.parse(),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            other => Err(anyhow!(
+                "Invalid {}: '{}' (expected 'pretty' or 'json')",
+                LOG_FORMAT_ENV,
+                other
+            )),
+        }
+    }
+}
+
+/// Build the filter for the actor: `RUST_LOG` wins when set, otherwise `log_level`
+pub fn build_filter(log_level: &str) -> Result<EnvFilter> {
+    match env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(directives) => EnvFilter::try_new(&directives)
+            .map_err(|e| anyhow!("Invalid {} '{}': {}", EnvFilter::DEFAULT_ENV, directives, e)),
+        Err(_) => EnvFilter::try_new(log_level)
+            .map_err(|e| anyhow!("Invalid log level '{}': {}", log_level, e)),
+    }
+}
+
+/// Build a subscriber for the given format and filter without installing it
+pub fn build_subscriber(format: LogFormat, filter: EnvFilter) -> Box<dyn Subscriber + Send + Sync> {
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    match format {
+        LogFormat::Pretty => Box::new(builder.finish()),
+        LogFormat::Json => Box::new(builder.json().finish()),
+    }
+}
+
+/// Install the global tracing subscriber from `LOG_FORMAT` and the configured log level
+pub fn init_tracing(log_level: &str) -> Result<()> {
+    let format = LogFormat::from_env()?;
+    let filter = build_filter(log_level)?;
+
+    tracing::subscriber::set_global_default(build_subscriber(format, filter))
+        .map_err(|e| anyhow!("Failed to install tracing subscriber: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!("pretty".parse::<LogFormat>().unwrap(), LogFormat::Pretty);
+        assert_eq!(" Json ".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert!("logfmt".parse::<LogFormat>().is_err());
+    }
+
+    #[test]
+    fn test_build_subscriber_both_formats() {
+        for format in [LogFormat::Pretty, LogFormat::Json] {
+            let subscriber = build_subscriber(format, EnvFilter::new("debug"));
+            tracing::subscriber::with_default(subscriber, || {
+                tracing::debug!(format = ?format, "subscriber constructed");
+            });
+        }
+    }
+}
@@ -1,68 +1,116 @@
-use anyhow::Result;
-use clap::Parser;
-use std::sync::Arc;
-use tokio::signal;
-use tracing::{info, error};
-
-mod actor;
-mod config;
-mod policy;
-mod proto {
-    pub mod engine {
-        pub mod v1 {
-            tonic::include_proto!("engine.v1");
-        }
-    }
-    pub mod replay {
-        pub mod v1 {
-            tonic::include_proto!("replay.v1");
-        }
-    }
-}
-
-use crate::actor::Actor;
-use crate::config::Config;
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
-
-    // Parse configuration
-    let config = Config::parse();
-
-    // Validate configuration
-    config.validate()?;
-
-    info!("Starting actor {} for environment {}", config.actor_id, config.env_id);
-    info!("Engine: {}, Replay: {}", config.engine_addr, config.replay_addr);
-
-    // Create actor instance
-    let actor = Actor::new(config).await?;
-    let actor = Arc::new(actor);
-
-    // Setup graceful shutdown
-    let shutdown_actor = Arc::clone(&actor);
-    let shutdown_handle = tokio::spawn(async move {
-        signal::ctrl_c().await.expect("Failed to listen for ctrl+c");
-        info!("Shutdown signal received, stopping actor...");
-        shutdown_actor.shutdown().await;
-    });
-
-    // Run the actor
-    let run_result = actor.run().await;
-
-    // Wait for shutdown to complete
-    shutdown_handle.abort();
-
-    match run_result {
-        Ok(_) => {
-            info!("Actor completed successfully");
-            Ok(())
-        }
-        Err(e) => {
-            error!("Actor failed: {}", e);
-            Err(e)
-        }
-    }
+use anyhow::{Context, Result};
+use std::sync::Arc;
+use tokio::signal;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, error};
+
+mod action;
+mod actor;
+mod batching;
+mod compat;
+mod config;
+mod health;
+mod logging;
+mod policy;
+mod priority;
+mod reward;
+mod seed;
+mod sink;
+mod transition;
+mod transport;
+mod warmup;
+mod proto {
+    pub mod engine {
+        pub mod v1 {
+            tonic::include_proto!("engine.v1");
+        }
+    }
+    pub mod replay {
+        pub mod v1 {
+            tonic::include_proto!("replay.v1");
+        }
+    }
+}
+
+use crate::actor::Actor;
+use crate::config::Config;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Parse and validate configuration first so the log level can drive the subscriber
+    let config = Config::load()?;
+    config.validate()?;
+
+    // Initialize tracing (LOG_FORMAT selects pretty or json output)
+    logging::init_tracing(&config.log_level)?;
+
+    info!("Starting actor {} for environment {}", config.actor_id, config.env_id);
+    info!("Engine: {}, Replay: {}", config.engine_addr, config.replay_addr);
+
+    // Bind the probe server before connecting so a bad address fails fast
+    let health_listener = match &config.health_addr {
+        Some(addr) => Some(
+            std::net::TcpListener::bind(addr)
+                .with_context(|| format!("Failed to bind health server to {}", addr))?,
+        ),
+        None => None,
+    };
+
+    // Create actor instance
+    let actor = Actor::new(config).await?;
+    let actor = Arc::new(actor);
+
+    // Serve /healthz and /readyz until the actor stops
+    let health_shutdown = CancellationToken::new();
+    let health_handle = health_listener.map(|listener| {
+        tokio::spawn(health::serve(listener, actor.health(), health_shutdown.clone()))
+    });
+
+    // Setup graceful shutdown
+    let shutdown_actor = Arc::clone(&actor);
+    let shutdown_handle = tokio::spawn(async move {
+        signal::ctrl_c().await.expect("Failed to listen for ctrl+c");
+        info!("Shutdown signal received, stopping actor...");
+        shutdown_actor.shutdown().await;
+    });
+
+    // SIGUSR1 aborts a stuck episode without stopping the actor
+    #[cfg(unix)]
+    let cancel_handle = {
+        let cancel_actor = Arc::clone(&actor);
+        let mut user_signal = signal::unix::signal(signal::unix::SignalKind::user_defined1())?;
+        tokio::spawn(async move {
+            while user_signal.recv().await.is_some() {
+                cancel_actor.cancel_episode();
+            }
+        })
+    };
+
+    // Run the actor
+    let run_result = actor.run().await;
+
+    // Wait for shutdown to complete
+    shutdown_handle.abort();
+    #[cfg(unix)]
+    cancel_handle.abort();
+
+    health_shutdown.cancel();
+    if let Some(handle) = health_handle {
+        match handle.await {
+            Ok(Err(e)) => error!("{:#}", e),
+            Err(e) => error!("Health server task failed: {}", e),
+            Ok(Ok(())) => {}
+        }
+    }
+
+    match run_result {
+        Ok(_) => {
+            info!("Actor completed successfully");
+            Ok(())
+        }
+        Err(e) => {
+            error!("Actor failed: {}", e);
+            Err(e)
+        }
+    }
 }
\ No newline at end of file
@@ -1,203 +1,441 @@
-use anyhow::{anyhow, Result};
-use rand::prelude::*;
-use rand_chacha::ChaCha20Rng;
-use crate::proto::engine::v1::Capabilities;
-
-/// Trait for action selection policies
-pub trait Policy: Send + Sync {
-    /// Select an action given an observation
-    fn select_action(&mut self, observation: &[u8]) -> Result<Vec<u8>>;
-}
-
-/// Random policy that selects actions uniformly at random
-pub struct RandomPolicy {
-    rng: ChaCha20Rng,
-    action_space: ActionSpace,
-}
-
-#[derive(Debug, Clone)]
-enum ActionSpace {
-    Discrete { n: u32 },
-    MultiDiscrete { nvec: Vec<u32> },
-    Continuous { low: Vec<f32>, high: Vec<f32> },
-}
-
-impl RandomPolicy {
-    pub fn new(capabilities: &Capabilities) -> Result<Self> {
-        let action_space = match &capabilities.action_space {
-            Some(crate::proto::engine::v1::capabilities::ActionSpace::DiscreteN(n)) => {
-                ActionSpace::Discrete { n: *n }
-            }
-            Some(crate::proto::engine::v1::capabilities::ActionSpace::Multi(multi)) => {
-                ActionSpace::MultiDiscrete {
-                    nvec: multi.nvec.clone()
-                }
-            }
-            Some(crate::proto::engine::v1::capabilities::ActionSpace::Continuous(box_spec)) => {
-                ActionSpace::Continuous {
-                    low: box_spec.low.clone(),
-                    high: box_spec.high.clone(),
-                }
-            }
-            None => {
-                return Err(anyhow!("No action space specified in capabilities"));
-            }
-        };
-
-        // Use a random seed for the RNG - in production this could be configurable
-        let rng = ChaCha20Rng::from_entropy();
-
-        Ok(Self { rng, action_space })
-    }
-
-    #[allow(dead_code)]
-    pub fn with_seed(capabilities: &Capabilities, seed: u64) -> Result<Self> {
-        let action_space = match &capabilities.action_space {
-            Some(crate::proto::engine::v1::capabilities::ActionSpace::DiscreteN(n)) => {
-                ActionSpace::Discrete { n: *n }
-            }
-            Some(crate::proto::engine::v1::capabilities::ActionSpace::Multi(multi)) => {
-                ActionSpace::MultiDiscrete {
-                    nvec: multi.nvec.clone()
-                }
-            }
-            Some(crate::proto::engine::v1::capabilities::ActionSpace::Continuous(box_spec)) => {
-                ActionSpace::Continuous {
-                    low: box_spec.low.clone(),
-                    high: box_spec.high.clone(),
-                }
-            }
-            None => {
-                return Err(anyhow!("No action space specified in capabilities"));
-            }
-        };
-
-        let rng = ChaCha20Rng::seed_from_u64(seed);
-
-        Ok(Self { rng, action_space })
-    }
-}
-
-impl Policy for RandomPolicy {
-    fn select_action(&mut self, _observation: &[u8]) -> Result<Vec<u8>> {
-        match &self.action_space {
-            ActionSpace::Discrete { n } => {
-                if *n == 0 {
-                    return Err(anyhow!("Discrete action space must have n > 0"));
-                }
-                let action = self.rng.gen_range(0..*n);
-                Ok(action.to_le_bytes().to_vec())
-            }
-            ActionSpace::MultiDiscrete { nvec } => {
-                let mut action_bytes = Vec::new();
-                for &n in nvec {
-                    if n == 0 {
-                        return Err(anyhow!("Multi-discrete action space must have all n > 0"));
-                    }
-                    let action = self.rng.gen_range(0..n);
-                    action_bytes.extend_from_slice(&action.to_le_bytes());
-                }
-                Ok(action_bytes)
-            }
-            ActionSpace::Continuous { low, high } => {
-                if low.len() != high.len() {
-                    return Err(anyhow!("Continuous action space low and high bounds must have same length"));
-                }
-                let mut action_bytes = Vec::new();
-                for (&low_val, &high_val) in low.iter().zip(high.iter()) {
-                    if low_val >= high_val {
-                        return Err(anyhow!("Continuous action space low bound must be less than high bound"));
-                    }
-                    let action: f32 = self.rng.gen_range(low_val..high_val);
-                    action_bytes.extend_from_slice(&action.to_le_bytes());
-                }
-                Ok(action_bytes)
-            }
-        }
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::proto::engine::v1::{Capabilities, EngineId, Encoding, MultiDiscrete, BoxSpec};
-
-    fn create_test_capabilities(action_space: crate::proto::engine::v1::capabilities::ActionSpace) -> Capabilities {
-        Capabilities {
-            id: Some(EngineId {
-                env_id: "test".to_string(),
-                build_id: "0.1.0".to_string(),
-            }),
-            enc: Some(Encoding {
-                state: "test:v1".to_string(),
-                action: "test:v1".to_string(),
-                obs: "test:v1".to_string(),
-                schema_version: 1,
-            }),
-            max_horizon: 100,
-            action_space: Some(action_space),
-            preferred_batch: 32,
-        }
-    }
-
-    #[test]
-    fn test_discrete_action_space() {
-        let caps = create_test_capabilities(
-            crate::proto::engine::v1::capabilities::ActionSpace::DiscreteN(4)
-        );
-        let mut policy = RandomPolicy::with_seed(&caps, 42).unwrap();
-
-        for _ in 0..10 {
-            let action_bytes = policy.select_action(&[]).unwrap();
-            assert_eq!(action_bytes.len(), 4); // u32 = 4 bytes
-            let action = u32::from_le_bytes(action_bytes.try_into().unwrap());
-            assert!(action < 4);
-        }
-    }
-
-    #[test]
-    fn test_multi_discrete_action_space() {
-        let caps = create_test_capabilities(
-            crate::proto::engine::v1::capabilities::ActionSpace::Multi(MultiDiscrete {
-                nvec: vec![2, 3, 4],
-            })
-        );
-        let mut policy = RandomPolicy::with_seed(&caps, 42).unwrap();
-
-        for _ in 0..10 {
-            let action_bytes = policy.select_action(&[]).unwrap();
-            assert_eq!(action_bytes.len(), 12); // 3 * u32 = 12 bytes
-
-            let action1 = u32::from_le_bytes(action_bytes[0..4].try_into().unwrap());
-            let action2 = u32::from_le_bytes(action_bytes[4..8].try_into().unwrap());
-            let action3 = u32::from_le_bytes(action_bytes[8..12].try_into().unwrap());
-
-            assert!(action1 < 2);
-            assert!(action2 < 3);
-            assert!(action3 < 4);
-        }
-    }
-
-    #[test]
-    fn test_continuous_action_space() {
-        let caps = create_test_capabilities(
-            crate::proto::engine::v1::capabilities::ActionSpace::Continuous(BoxSpec {
-                low: vec![-1.0, 0.0],
-                high: vec![1.0, 2.0],
-                shape: vec![2],
-            })
-        );
-        let mut policy = RandomPolicy::with_seed(&caps, 42).unwrap();
-
-        for _ in 0..10 {
-            let action_bytes = policy.select_action(&[]).unwrap();
-            assert_eq!(action_bytes.len(), 8); // 2 * f32 = 8 bytes
-
-            let action1 = f32::from_le_bytes(action_bytes[0..4].try_into().unwrap());
-            let action2 = f32::from_le_bytes(action_bytes[4..8].try_into().unwrap());
-
-            assert!(action1 >= -1.0 && action1 < 1.0);
-            assert!(action2 >= 0.0 && action2 < 2.0);
-        }
-    }
-}
\ No newline at end of file
+use anyhow::{anyhow, Context, Result};
+use rand::prelude::*;
+use rand_chacha::ChaCha20Rng;
+use std::fs;
+use crate::action::{Action, ActionSpace};
+use crate::proto::engine::v1::Capabilities;
+
+/// Trait for action selection policies
+pub trait Policy: Send + Sync {
+    /// Select an action given an observation
+    fn select_action(&mut self, observation: &[u8]) -> Result<Vec<u8>>;
+
+    /// Called before the first action of each episode
+    fn start_episode(&mut self) {}
+}
+
+/// Random policy that selects actions uniformly at random
+pub struct RandomPolicy {
+    rng: ChaCha20Rng,
+    action_space: ActionSpace,
+}
+
+impl RandomPolicy {
+    pub fn new(capabilities: &Capabilities) -> Result<Self> {
+        let action_space = ActionSpace::from_capabilities(capabilities)?;
+
+        // Use a random seed for the RNG - in production this could be configurable
+        let rng = ChaCha20Rng::from_entropy();
+
+        Ok(Self { rng, action_space })
+    }
+
+    #[allow(dead_code)]
+    pub fn with_seed(capabilities: &Capabilities, seed: u64) -> Result<Self> {
+        let action_space = ActionSpace::from_capabilities(capabilities)?;
+
+        let rng = ChaCha20Rng::seed_from_u64(seed);
+
+        Ok(Self { rng, action_space })
+    }
+
+    /// Random permutation of `legal`, for consumers that order every legal action
+    ///
+    /// A Fisher–Yates shuffle drawing from the policy's RNG, so policies
+    /// created with the same seed produce the same sequence of permutations.
+    #[allow(dead_code)]
+    pub fn shuffled_legal_actions(&mut self, legal: &[u32]) -> Vec<u32> {
+        let mut shuffled = legal.to_vec();
+        for i in (1..shuffled.len()).rev() {
+            let j = self.rng.gen_range(0..=i);
+            shuffled.swap(i, j);
+        }
+        shuffled
+    }
+
+    /// Sample a typed action uniformly from the action space
+    fn sample_action(&mut self) -> Result<Action> {
+        match &self.action_space {
+            ActionSpace::Discrete { n } | ActionSpace::DiscreteVarint { n } => {
+                if *n == 0 {
+                    return Err(anyhow!("Discrete action space must have n > 0"));
+                }
+                Ok(Action::Discrete(self.rng.gen_range(0..*n)))
+            }
+            ActionSpace::MultiDiscrete { nvec } => {
+                let mut indices = Vec::with_capacity(nvec.len());
+                for &n in nvec {
+                    if n == 0 {
+                        return Err(anyhow!("Multi-discrete action space must have all n > 0"));
+                    }
+                    indices.push(self.rng.gen_range(0..n));
+                }
+                Ok(Action::MultiDiscrete(indices))
+            }
+            ActionSpace::Continuous { low, high } => {
+                if low.len() != high.len() {
+                    return Err(anyhow!("Continuous action space low and high bounds must have same length"));
+                }
+                let mut values = Vec::with_capacity(low.len());
+                for (&low_val, &high_val) in low.iter().zip(high.iter()) {
+                    if low_val >= high_val {
+                        return Err(anyhow!("Continuous action space low bound must be less than high bound"));
+                    }
+                    values.push(self.rng.gen_range(low_val..high_val));
+                }
+                Ok(Action::Continuous(values))
+            }
+        }
+    }
+}
+
+impl Policy for RandomPolicy {
+    fn select_action(&mut self, _observation: &[u8]) -> Result<Vec<u8>> {
+        let action = self.sample_action()?;
+        self.action_space.encode(&action)
+    }
+}
+
+/// What a scripted policy does once every action has been played
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptEnd {
+    /// Start again from the first action
+    Cycle,
+    /// Fail the next action selection
+    Error,
+}
+
+/// Policy that replays a fixed sequence of pre-encoded actions
+///
+/// Used for deterministic integration tests and for reproducing regressions.
+/// Actions are returned in order across episodes; the script does not restart
+/// when a new episode begins.
+pub struct ScriptedPolicy {
+    actions: Vec<Vec<u8>>,
+    position: usize,
+    episode_step: usize,
+    on_end: ScriptEnd,
+}
+
+impl ScriptedPolicy {
+    pub fn new(actions: Vec<Vec<u8>>, on_end: ScriptEnd) -> Result<Self> {
+        if actions.is_empty() {
+            return Err(anyhow!("Action script must contain at least one action"));
+        }
+
+        Ok(Self {
+            actions,
+            position: 0,
+            episode_step: 0,
+            on_end,
+        })
+    }
+
+    /// Load a script with one hex-encoded action per line
+    ///
+    /// Bytes may be separated by whitespace (`0a 01` or `0a01`). Blank lines
+    /// and lines starting with `#` are ignored.
+    pub fn from_file(path: &str, on_end: ScriptEnd) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read action script {}", path))?;
+        let actions = parse_script(&contents)
+            .with_context(|| format!("Invalid action script {}", path))?;
+        Self::new(actions, on_end)
+    }
+}
+
+impl Policy for ScriptedPolicy {
+    fn select_action(&mut self, _observation: &[u8]) -> Result<Vec<u8>> {
+        if self.position == self.actions.len() {
+            match self.on_end {
+                ScriptEnd::Cycle => self.position = 0,
+                ScriptEnd::Error => {
+                    return Err(anyhow!(
+                        "Action script exhausted after {} actions at step {} of the current episode",
+                        self.actions.len(),
+                        self.episode_step
+                    ));
+                }
+            }
+        }
+
+        let action = self.actions[self.position].clone();
+        self.position += 1;
+        self.episode_step += 1;
+        Ok(action)
+    }
+
+    fn start_episode(&mut self) {
+        self.episode_step = 0;
+    }
+}
+
+fn parse_script(contents: &str) -> Result<Vec<Vec<u8>>> {
+    let mut actions = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let digits: String = line.split_whitespace().collect();
+        let action = digits
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| match pair {
+                [hi, lo] => u8::from_str_radix(&format!("{}{}", *hi as char, *lo as char), 16)
+                    .map_err(|e| anyhow!("line {}: {}", index + 1, e)),
+                _ => Err(anyhow!("line {}: odd number of hex digits", index + 1)),
+            })
+            .collect::<Result<Vec<u8>>>()?;
+        actions.push(action);
+    }
+    Ok(actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::engine::v1::{Capabilities, EngineId, Encoding, MultiDiscrete, BoxSpec};
+
+    fn create_test_capabilities(action_space: crate::proto::engine::v1::capabilities::ActionSpace) -> Capabilities {
+        Capabilities {
+            id: Some(EngineId {
+                env_id: "test".to_string(),
+                build_id: "0.1.0".to_string(),
+            }),
+            enc: Some(Encoding {
+                state: "test:v1".to_string(),
+                action: "test:v1".to_string(),
+                obs: "test:v1".to_string(),
+                schema_version: 1,
+            }),
+            max_horizon: 100,
+            action_space: Some(action_space),
+            preferred_batch: 32,
+            sizes: None,
+            initial_state_kind: 0,
+            action_names: Vec::new(),
+            obs_length_prefixed: false,
+            state_space_estimate: None,
+            obs_quantization: None,
+            obs_layout: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_discrete_action_space() {
+        let caps = create_test_capabilities(
+            crate::proto::engine::v1::capabilities::ActionSpace::DiscreteN(4)
+        );
+        let mut policy = RandomPolicy::with_seed(&caps, 42).unwrap();
+
+        for _ in 0..10 {
+            let action_bytes = policy.select_action(&[]).unwrap();
+            assert_eq!(action_bytes.len(), 1); // n <= 256 encodes as a single byte
+            assert!(action_bytes[0] < 4);
+        }
+    }
+
+    #[test]
+    fn test_shuffled_legal_actions_is_reproducible_per_seed() {
+        let caps = create_test_capabilities(
+            crate::proto::engine::v1::capabilities::ActionSpace::DiscreteN(9)
+        );
+        let legal = [0, 2, 3, 5, 6, 7, 8];
+        let shuffle = |seed| {
+            let mut policy = RandomPolicy::with_seed(&caps, seed).unwrap();
+            (0..3)
+                .map(|_| policy.shuffled_legal_actions(&legal))
+                .collect::<Vec<_>>()
+        };
+
+        let first = shuffle(42);
+        assert_eq!(first, shuffle(42));
+        assert_ne!(first, shuffle(43));
+        for permutation in &first {
+            let mut sorted = permutation.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, legal);
+        }
+        // Successive shuffles keep drawing from the RNG
+        assert_ne!(first[0], first[1]);
+
+        let mut policy = RandomPolicy::with_seed(&caps, 42).unwrap();
+        assert!(policy.shuffled_legal_actions(&[]).is_empty());
+        assert_eq!(policy.shuffled_legal_actions(&[4]), vec![4]);
+    }
+
+    #[test]
+    fn test_multi_discrete_action_space() {
+        let caps = create_test_capabilities(
+            crate::proto::engine::v1::capabilities::ActionSpace::Multi(MultiDiscrete {
+                nvec: vec![2, 3, 4],
+            })
+        );
+        let mut policy = RandomPolicy::with_seed(&caps, 42).unwrap();
+
+        for _ in 0..10 {
+            let action_bytes = policy.select_action(&[]).unwrap();
+            assert_eq!(action_bytes.len(), 3); // one byte per dimension
+
+            assert!(action_bytes[0] < 2);
+            assert!(action_bytes[1] < 3);
+            assert!(action_bytes[2] < 4);
+        }
+    }
+
+    #[test]
+    fn test_continuous_action_space() {
+        let caps = create_test_capabilities(
+            crate::proto::engine::v1::capabilities::ActionSpace::Continuous(BoxSpec {
+                low: vec![-1.0, 0.0],
+                high: vec![1.0, 2.0],
+                shape: vec![2],
+            })
+        );
+        let mut policy = RandomPolicy::with_seed(&caps, 42).unwrap();
+
+        for _ in 0..10 {
+            let action_bytes = policy.select_action(&[]).unwrap();
+            assert_eq!(action_bytes.len(), 8); // 2 * f32 = 8 bytes
+
+            let action1 = f32::from_le_bytes(action_bytes[0..4].try_into().unwrap());
+            let action2 = f32::from_le_bytes(action_bytes[4..8].try_into().unwrap());
+
+            assert!(action1 >= -1.0 && action1 < 1.0);
+            assert!(action2 >= 0.0 && action2 < 2.0);
+        }
+    }
+
+    #[test]
+    fn test_scripted_policy_drives_tictactoe_win() {
+        use engine_core::registry::create_game;
+        use engine_server::registry_init::initialize_registry;
+
+        // X takes the top row while O plays the middle row
+        let script = "# X wins across the top\n00\n03\n01\n04\n02\n";
+        let mut policy = ScriptedPolicy::new(parse_script(script).unwrap(), ScriptEnd::Error).unwrap();
+        policy.start_episode();
+
+        initialize_registry();
+        let mut game = create_game("tictactoe").unwrap();
+        let (mut state, mut obs) = (Vec::new(), Vec::new());
+        game.reset(1, &[], &mut state, &mut obs).unwrap();
+
+        // A move onto an occupied cell is a no-op, which would delay the win
+        let (mut next_state, mut next_obs) = (Vec::new(), Vec::new());
+        for moves in 1..=5 {
+            let action = policy.select_action(&obs).unwrap();
+            let outcome = game.step(&state, &action, &mut next_state, &mut next_obs).unwrap();
+            assert_eq!(outcome.done, moves == 5, "move {}", moves);
+            if outcome.done {
+                assert_eq!(outcome.reward, 1.0, "X wins the game");
+            }
+            std::mem::swap(&mut state, &mut next_state);
+            std::mem::swap(&mut obs, &mut next_obs);
+        }
+    }
+
+    #[test]
+    fn test_scripted_policy_errors_when_exhausted_mid_episode() {
+        let mut policy = ScriptedPolicy::new(vec![vec![0], vec![1]], ScriptEnd::Error).unwrap();
+        policy.start_episode();
+        policy.select_action(&[]).unwrap();
+        policy.select_action(&[]).unwrap();
+
+        let err = policy.select_action(&[]).unwrap_err();
+        assert!(err.to_string().contains("exhausted after 2 actions at step 2"));
+    }
+
+    #[test]
+    fn test_scripted_policy_cycles() {
+        let mut policy = ScriptedPolicy::new(vec![vec![0], vec![1]], ScriptEnd::Cycle).unwrap();
+        let actions: Vec<Vec<u8>> = (0..5).map(|_| policy.select_action(&[]).unwrap()).collect();
+        assert_eq!(actions, vec![vec![0], vec![1], vec![0], vec![1], vec![0]]);
+    }
+
+    #[test]
+    fn test_parse_script_accepts_spaced_bytes_and_rejects_bad_hex() {
+        assert_eq!(parse_script("0a 01\n\n0a01\n").unwrap(), vec![vec![10, 1], vec![10, 1]]);
+        assert!(parse_script("0g\n").unwrap_err().to_string().contains("line 1"));
+        assert!(parse_script("abc\n").is_err());
+        assert!(ScriptedPolicy::new(Vec::new(), ScriptEnd::Cycle).is_err());
+    }
+
+    /// Actions the random policy emits must decode in every game the engine
+    /// registers, so a change on either side of the wire that breaks the
+    /// action contract (e.g. a discrete index encoded wider than the game
+    /// decodes) fails here. New games are covered as soon as
+    /// `initialize_registry` registers them.
+    #[tokio::test]
+    async fn test_random_policy_actions_decode_for_every_registered_game() {
+        use engine_core::erased::ErasedGameError;
+        use engine_core::registry::{create_game, list_registered_games};
+        use engine_proto::engine_server::Engine;
+        use engine_server::registry_init::initialize_registry;
+        use engine_server::EngineService;
+        use prost::Message;
+
+        const ACTIONS_PER_GAME: usize = 1_000;
+
+        initialize_registry();
+        let service = EngineService::new();
+
+        let mut env_ids = list_registered_games();
+        env_ids.sort();
+        assert!(!env_ids.is_empty(), "no games registered");
+
+        for env_id in env_ids {
+            let engine_capabilities = service
+                .get_capabilities(tonic::Request::new(engine_proto::EngineId {
+                    env_id: env_id.clone(),
+                    build_id: "random-policy-test".to_string(),
+                }))
+                .await
+                .unwrap_or_else(|e| panic!("{}: capabilities unavailable: {}", env_id, e))
+                .into_inner();
+            // Same message, generated separately on each side of the wire
+            let capabilities =
+                Capabilities::decode(engine_capabilities.encode_to_vec().as_slice()).unwrap();
+            let expected_len = capabilities.sizes.as_ref().and_then(|sizes| sizes.action);
+
+            let mut policy = RandomPolicy::with_seed(&capabilities, 7).unwrap_or_else(|e| {
+                panic!("{}: RandomPolicy rejected capabilities: {}", env_id, e)
+            });
+
+            let mut game = create_game(&env_id).unwrap();
+            let (mut state, mut obs) = (Vec::new(), Vec::new());
+            game.reset(1, &[], &mut state, &mut obs)
+                .unwrap_or_else(|e| panic!("{}: reset failed: {}", env_id, e));
+
+            let (mut next_state, mut next_obs) = (Vec::new(), Vec::new());
+            for i in 0..ACTIONS_PER_GAME {
+                let action = policy.select_action(&obs).unwrap();
+
+                if let Some(len) = expected_len {
+                    assert_eq!(
+                        action.len(),
+                        len as usize,
+                        "{}: action {} is {:02x?}, expected {} bytes",
+                        env_id,
+                        i,
+                        action,
+                        len
+                    );
+                }
+
+                // Every action is tried from the initial state; only wire-format
+                // failures matter here, not whether the move is legal
+                if let Err(e @ (ErasedGameError::InvalidAction(_) | ErasedGameError::Decoding(_))) =
+                    game.step(&state, &action, &mut next_state, &mut next_obs)
+                {
+                    panic!(
+                        "{}: action {} ({:02x?}) did not decode: {}",
+                        env_id, i, action, e
+                    );
+                }
+            }
+        }
+    }
+}
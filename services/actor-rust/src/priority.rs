@@ -0,0 +1,104 @@
+//! Replay priorities for transitions
+//!
+//! Every transition is given a priority by a `PriorityFn` just before it is
+//! queued for replay, after episode post-processing, so functions can read
+//! the n-step return and any value estimate from its metadata. `--priority`
+//! selects one of the built-in `Priority` functions; the default keeps the
+//! constant `DEFAULT_PRIORITY` of 1.0.
+
+use anyhow::{anyhow, Error};
+use std::str::FromStr;
+
+use crate::proto::replay::v1::Transition;
+use crate::reward::N_STEP_RETURN_METADATA_KEY;
+use crate::transition::DEFAULT_PRIORITY;
+
+/// Metadata key holding the policy's value estimate for a transition's state
+pub const VALUE_METADATA_KEY: &str = "value";
+
+/// Computes the replay priority of a transition
+pub type PriorityFn = Box<dyn Fn(&Transition) -> f32 + Send + Sync>;
+
+/// Built-in priority functions selectable with `--priority`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Priority {
+    /// `DEFAULT_PRIORITY` for every transition
+    #[default]
+    Constant,
+    /// The absolute reward
+    AbsReward,
+    /// The absolute TD error `|target - value|`, where the target is the
+    /// n-step return when present and the reward otherwise
+    ///
+    /// Transitions without a parsable `value` metadata entry fall back to
+    /// `DEFAULT_PRIORITY`.
+    TdError,
+}
+
+impl Priority {
+    /// The function computing this priority
+    pub fn priority_fn(self) -> PriorityFn {
+        match self {
+            Priority::Constant => Box::new(|_| DEFAULT_PRIORITY),
+            Priority::AbsReward => Box::new(|transition| transition.reward.abs()),
+            Priority::TdError => Box::new(td_error),
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "constant" => Ok(Priority::Constant),
+            "abs-reward" => Ok(Priority::AbsReward),
+            "td-error" => Ok(Priority::TdError),
+            _ => Err(anyhow!(
+                "priority '{}' is invalid (expected one of: constant, abs-reward, td-error)",
+                s
+            )),
+        }
+    }
+}
+
+fn metadata_f32(transition: &Transition, key: &str) -> Option<f32> {
+    transition.metadata.get(key)?.parse().ok()
+}
+
+fn td_error(transition: &Transition) -> f32 {
+    let Some(value) = metadata_f32(transition, VALUE_METADATA_KEY) else {
+        return DEFAULT_PRIORITY;
+    };
+    let target = metadata_f32(transition, N_STEP_RETURN_METADATA_KEY).unwrap_or(transition.reward);
+    (target - value).abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn transition(reward: f32, metadata: &[(&str, &str)]) -> Transition {
+        Transition {
+            reward,
+            metadata: metadata
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_td_error_prefers_n_step_return_and_needs_a_value() {
+        let td_error = Priority::TdError.priority_fn();
+        assert_eq!(td_error(&transition(0.5, &[])), DEFAULT_PRIORITY);
+        assert_eq!(td_error(&transition(0.5, &[("value", "2")])), 1.5);
+        assert_eq!(
+            td_error(&transition(0.5, &[("value", "2"), ("n_step_return", "1.25")])),
+            0.75
+        );
+        assert_eq!(td_error(&transition(0.5, &[("value", "nan?")])), DEFAULT_PRIORITY);
+    }
+}
@@ -0,0 +1,179 @@
+/// Metadata key holding the unnormalized reward of a transition
+pub const RAW_REWARD_METADATA_KEY: &str = "raw_reward";
+
+/// Metadata key holding the n-step discounted return of a transition
+pub const N_STEP_RETURN_METADATA_KEY: &str = "n_step_return";
+
+/// Small constant added to the variance to avoid division by zero
+const DEFAULT_EPSILON: f64 = 1e-8;
+
+/// Running reward statistics used to scale rewards to unit variance
+///
+/// Mean and variance are tracked with Welford's online algorithm in `f64`,
+/// which stays numerically stable for long runs and large reward offsets.
+/// The statistics persist across episodes for the lifetime of the actor.
+#[derive(Debug, Clone)]
+pub struct RewardNormalizer {
+    count: u64,
+    mean: f64,
+    m2: f64,
+    epsilon: f64,
+}
+
+impl RewardNormalizer {
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            epsilon: DEFAULT_EPSILON,
+        }
+    }
+
+    /// Fold a reward into the running statistics
+    pub fn update(&mut self, reward: f32) {
+        let value = reward as f64;
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// Number of rewards observed so far
+    #[allow(dead_code)]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Running mean of observed rewards
+    #[allow(dead_code)]
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Running population variance of observed rewards
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            self.m2 / self.count as f64
+        }
+    }
+
+    /// Update the statistics with `reward` and return it scaled by the running std
+    ///
+    /// Until at least two rewards have been seen the reward is returned unscaled.
+    pub fn normalize(&mut self, reward: f32) -> f32 {
+        self.update(reward);
+        if self.count < 2 {
+            return reward;
+        }
+        let std = (self.variance() + self.epsilon).sqrt();
+        (reward as f64 / std) as f32
+    }
+}
+
+impl Default for RewardNormalizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compute n-step discounted returns for a sequence of transitions
+///
+/// `G_t = r_t + gamma * r_{t+1} + ... + gamma^(n-1) * r_{t+n-1}`, truncated at
+/// the first transition marked `done` and at the end of the sequence. Nothing
+/// is bootstrapped past a terminal step.
+pub fn n_step_returns(rewards: &[f32], dones: &[bool], n: usize, gamma: f32) -> Vec<f32> {
+    debug_assert_eq!(rewards.len(), dones.len());
+    (0..rewards.len())
+        .map(|t| {
+            let mut total = 0.0f64;
+            let mut discount = 1.0f64;
+            let end = rewards.len().min(t + n);
+            for (&reward, &done) in rewards[t..end].iter().zip(&dones[t..end]) {
+                total += discount * reward as f64;
+                if done {
+                    break;
+                }
+                discount *= gamma as f64;
+            }
+            total as f32
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+    use rand_chacha::ChaCha20Rng;
+
+    fn variance(values: &[f64]) -> f64 {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64
+    }
+
+    #[test]
+    fn test_normalized_rewards_have_unit_variance() {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let mut normalizer = RewardNormalizer::new();
+
+        let normalized: Vec<f64> = (0..20_000)
+            .map(|_| {
+                let reward: f32 = rng.gen_range(-5.0..15.0);
+                normalizer.normalize(reward) as f64
+            })
+            .skip(1_000)
+            .collect();
+
+        let var = variance(&normalized);
+        assert!((var - 1.0).abs() < 0.05, "variance was {}", var);
+    }
+
+    #[test]
+    fn test_welford_is_stable_with_large_offset() {
+        let mut normalizer = RewardNormalizer::new();
+        for reward in [4.0f64, 7.0, 13.0, 16.0] {
+            normalizer.update((1e6 + reward) as f32);
+        }
+
+        assert_eq!(normalizer.count(), 4);
+        assert!((normalizer.mean() - (1e6 + 10.0)).abs() < 1e-6);
+        assert!((normalizer.variance() - 22.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_first_reward_passes_through() {
+        let mut normalizer = RewardNormalizer::new();
+        assert_eq!(normalizer.normalize(3.0), 3.0);
+    }
+
+    #[test]
+    fn test_n_step_returns_match_hand_computed_values() {
+        let rewards = [1.0, 0.0, 2.0, -1.0, 4.0];
+        let dones = [false, false, false, false, true];
+
+        // gamma = 0.5, n = 3
+        //   G0 = 1 + 0.5*0 + 0.25*2    = 1.5
+        //   G1 = 0 + 0.5*2 + 0.25*(-1) = 0.75
+        //   G2 = 2 + 0.5*(-1) + 0.25*4 = 2.5
+        //   G3 = -1 + 0.5*4            = 1.0  (episode ends)
+        //   G4 = 4                            (episode ends)
+        let returns = n_step_returns(&rewards, &dones, 3, 0.5);
+        assert_eq!(returns, vec![1.5, 0.75, 2.5, 1.0, 4.0]);
+
+        // One-step returns are just the rewards
+        assert_eq!(n_step_returns(&rewards, &dones, 1, 0.5), rewards.to_vec());
+    }
+
+    #[test]
+    fn test_n_step_returns_stop_at_done() {
+        // Two episodes back to back: nothing from the second leaks into the first
+        let rewards = [1.0, 1.0, 10.0, 10.0];
+        let dones = [false, true, false, true];
+
+        let returns = n_step_returns(&rewards, &dones, 4, 1.0);
+        assert_eq!(returns, vec![2.0, 1.0, 20.0, 10.0]);
+    }
+}
@@ -0,0 +1,86 @@
+//! Reproducible per-episode reset seeds
+//!
+//! With a run-level base seed configured, every episode's reset seed is
+//! derived from `(base_seed, actor_id, episode)`:
+//!
+//! ```text
+//! stream = splitmix64(base_seed ^ fnv1a64(actor_id))
+//! seed   = splitmix64(stream + episode * 0x9e3779b97f4a7c15)   (wrapping)
+//! ```
+//!
+//! FNV-1a gives each actor id a stable 64-bit key, and `splitmix64`'s
+//! finalizer is a bijection, so two episodes only share a seed if their
+//! pre-mix inputs are equal. Restarting an actor with the same base seed and
+//! id replays its seed stream exactly, while different ids get unrelated
+//! streams. This derivation is part of the reproducibility contract: changing
+//! it changes every seeded run.
+
+/// Increment between consecutive episodes in a stream (2^64 / golden ratio)
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Stable 64-bit FNV-1a hash of an actor id
+///
+/// Mirrors `engine_core::adapter::stable_hash`, which the actor does not link.
+fn fnv1a64(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(PRIME)
+    })
+}
+
+/// SplitMix64 finalizer: a bijective 64-bit mix
+fn splitmix64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Reset seed for `episode` of `actor_id` in a run seeded with `base_seed`
+pub fn derive_episode_seed(base_seed: u64, actor_id: &str, episode: u32) -> u64 {
+    let stream = splitmix64(base_seed ^ fnv1a64(actor_id.as_bytes()));
+    splitmix64(stream.wrapping_add((episode as u64).wrapping_mul(GOLDEN_GAMMA)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn sequence(base_seed: u64, actor_id: &str) -> Vec<u64> {
+        (0..1000)
+            .map(|episode| derive_episode_seed(base_seed, actor_id, episode))
+            .collect()
+    }
+
+    #[test]
+    fn test_distinct_actor_ids_get_disjoint_seed_sequences() {
+        let ids = ["actor-0", "actor-1", "actor-10", "learner-a"];
+        let mut seen = HashSet::new();
+        for id in ids {
+            for seed in sequence(7, id) {
+                assert!(seen.insert(seed), "{} reused seed {:#x}", id, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn test_actor_reproduces_its_seed_sequence() {
+        assert_eq!(sequence(7, "actor-1"), sequence(7, "actor-1"));
+        assert_ne!(sequence(7, "actor-1"), sequence(8, "actor-1"));
+
+        // Pinned so an accidental change to the derivation is caught
+        assert_eq!(derive_episode_seed(42, "actor-1", 3), 0xb583_4795_9534_eca9);
+    }
+
+    #[test]
+    fn test_actor_id_hash_matches_the_engine_stable_hash() {
+        for id in ["", "actor-1", "learner-a"] {
+            assert_eq!(
+                fnv1a64(id.as_bytes()),
+                engine_core::adapter::stable_hash(id.as_bytes())
+            );
+        }
+    }
+}
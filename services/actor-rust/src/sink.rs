@@ -0,0 +1,160 @@
+use crate::proto::replay::v1::{
+    replay_client::ReplayClient, StoreBatchRequest, StoreBatchResponse, Transition,
+};
+use anyhow::{anyhow, Context, Result};
+use prost::Message;
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::Write;
+use tonic::transport::Channel;
+use tonic::Request;
+
+/// Destination for batches of transitions flushed by the actor
+///
+/// The actor buffers transitions and hands each full (or periodic) batch to
+/// its sink, awaiting the send before collecting more, so a slow sink slows
+/// episode generation rather than growing the buffer. The default sink is the
+/// replay service; alternatives (files, message queues, custom replay formats)
+/// are installed with `Actor::with_transition_sink`.
+#[tonic::async_trait]
+pub trait TransitionSink: Send + Sync {
+    /// Deliver one batch
+    ///
+    /// Fails if the batch could not be delivered at all; a destination that
+    /// accepted only part of it reports `StoreOutcome::Partial` instead.
+    async fn send_batch(&self, transitions: Vec<Transition>) -> Result<StoreOutcome>;
+}
+
+/// What a sink did with a delivered batch
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoreOutcome {
+    /// Every transition in the batch was stored
+    Stored,
+    /// Only `stored` transitions of the batch were stored
+    Partial {
+        stored: usize,
+        /// The destination's explanation of the failures
+        errors: Vec<String>,
+        /// The transitions that were not stored, or `None` when the
+        /// destination did not say which ones failed
+        unstored: Option<Vec<Transition>>,
+    },
+}
+
+impl StoreOutcome {
+    /// Interpret replay's response to storing `sent`
+    ///
+    /// Unstored transitions are only identified when the response lists the
+    /// id of every stored transition and all of them were sent, so a
+    /// transition replay kept is never reported as unstored.
+    pub fn from_response(sent: Vec<Transition>, response: StoreBatchResponse) -> Self {
+        let stored = response.stored_count as usize;
+        if stored >= sent.len() {
+            return StoreOutcome::Stored;
+        }
+
+        let expected = sent.len() - stored;
+        let stored_ids: HashSet<&str> = response.transition_ids.iter().map(String::as_str).collect();
+        let unstored = (stored_ids.len() == stored)
+            .then(|| {
+                sent.iter()
+                    .filter(|t| !stored_ids.contains(t.id.as_str()))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .filter(|unstored| unstored.len() == expected);
+        StoreOutcome::Partial {
+            stored,
+            errors: response.error_messages,
+            unstored,
+        }
+    }
+}
+
+/// Sends batches to the replay service with `StoreBatch`
+pub struct ReplaySink {
+    client: ReplayClient<Channel>,
+}
+
+impl ReplaySink {
+    pub fn new(client: ReplayClient<Channel>) -> Self {
+        Self { client }
+    }
+}
+
+#[tonic::async_trait]
+impl TransitionSink for ReplaySink {
+    async fn send_batch(&self, transitions: Vec<Transition>) -> Result<StoreOutcome> {
+        // The batch is kept until replay answers, so a partial store can hand
+        // back the transitions it did not keep
+        let response = self
+            .client
+            .clone()
+            .store_batch(Request::new(StoreBatchRequest {
+                transitions: transitions.clone(),
+            }))
+            .await
+            .map_err(|e| anyhow!("Failed to store batch: {}", e))?;
+        Ok(StoreOutcome::from_response(transitions, response.into_inner()))
+    }
+}
+
+/// Append `transitions` to `path` as length-delimited `Transition` messages
+///
+/// The last resort for transitions the final flush could not deliver. The
+/// file is created if missing, and each spill is written with a single call
+/// so earlier spills stay readable; replay them with
+/// `Transition::decode_length_delimited` until the file is exhausted.
+pub fn spill_to_file(path: &str, transitions: &[Transition]) -> Result<()> {
+    let mut buf = Vec::new();
+    for transition in transitions {
+        transition.encode_length_delimited(&mut buf)?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(&buf))
+        .with_context(|| format!("Failed to spill {} transitions to {}", transitions.len(), path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(ids: &[&str]) -> Vec<Transition> {
+        ids.iter()
+            .map(|id| Transition {
+                id: id.to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_unstored_transitions_need_matching_ids() {
+        let response = |ids: &[&str]| StoreBatchResponse {
+            transition_ids: ids.iter().map(|id| id.to_string()).collect(),
+            stored_count: ids.len() as u32,
+            failed_count: 1,
+            error_messages: vec!["full".to_string()],
+        };
+
+        let outcome = StoreOutcome::from_response(batch(&["a", "b"]), response(&["b"]));
+        assert_eq!(
+            outcome,
+            StoreOutcome::Partial {
+                stored: 1,
+                errors: vec!["full".to_string()],
+                unstored: Some(batch(&["a"])),
+            }
+        );
+
+        // Ids replay assigned itself cannot tell which transitions it kept
+        let outcome = StoreOutcome::from_response(batch(&["a", "b"]), response(&["x"]));
+        assert!(matches!(outcome, StoreOutcome::Partial { unstored: None, .. }));
+
+        let outcome = StoreOutcome::from_response(batch(&["a"]), response(&["a"]));
+        assert_eq!(outcome, StoreOutcome::Stored);
+    }
+}
@@ -0,0 +1,180 @@
+//! Construction of replay transitions
+//!
+//! `TransitionBuilder` keeps the defaults the actor applies to every
+//! transition in one place: priority 1.0, the current time in seconds since
+//! the Unix epoch, and no metadata. The payload fields have no sensible
+//! default, so `build` fails if any of them was never set.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::proto::replay::v1::Transition;
+
+/// Priority given to transitions unless the caller sets one
+pub const DEFAULT_PRIORITY: f32 = 1.0;
+
+/// Builder for `Transition` with the actor's defaults
+#[derive(Debug, Clone)]
+pub struct TransitionBuilder {
+    env_id: String,
+    episode_id: String,
+    step_number: u32,
+    state: Option<Vec<u8>>,
+    action: Option<Vec<u8>>,
+    next_state: Option<Vec<u8>>,
+    observation: Option<Vec<u8>>,
+    next_observation: Option<Vec<u8>>,
+    reward: Option<f32>,
+    done: Option<bool>,
+    priority: f32,
+    /// `None` stamps the transition with the time `build` is called
+    timestamp: Option<u64>,
+    metadata: HashMap<String, String>,
+}
+
+impl TransitionBuilder {
+    /// Start the transition for `step_number` of `episode_id`
+    ///
+    /// The transition id is derived as `{episode_id}-step-{step_number}`.
+    pub fn new(env_id: impl Into<String>, episode_id: impl Into<String>, step_number: u32) -> Self {
+        Self {
+            env_id: env_id.into(),
+            episode_id: episode_id.into(),
+            step_number,
+            state: None,
+            action: None,
+            next_state: None,
+            observation: None,
+            next_observation: None,
+            reward: None,
+            done: None,
+            priority: DEFAULT_PRIORITY,
+            timestamp: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// Encoded states before and after the step
+    pub fn states(mut self, state: Vec<u8>, next_state: Vec<u8>) -> Self {
+        self.state = Some(state);
+        self.next_state = Some(next_state);
+        self
+    }
+
+    /// Encoded observations before and after the step
+    pub fn observations(mut self, observation: Vec<u8>, next_observation: Vec<u8>) -> Self {
+        self.observation = Some(observation);
+        self.next_observation = Some(next_observation);
+        self
+    }
+
+    /// Encoded action taken
+    pub fn action(mut self, action: Vec<u8>) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    /// Reward received and whether the episode ended
+    pub fn outcome(mut self, reward: f32, done: bool) -> Self {
+        self.reward = Some(reward);
+        self.done = Some(done);
+        self
+    }
+
+    /// Replay priority, instead of `DEFAULT_PRIORITY`
+    #[allow(dead_code)]
+    pub fn priority(mut self, priority: f32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Seconds since the Unix epoch, instead of the time of `build`
+    #[allow(dead_code)]
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// String metadata stored with the transition, replacing any set before
+    pub fn metadata(mut self, metadata: HashMap<String, String>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Build the transition
+    ///
+    /// Fails if a payload field (states, observations, action, or outcome)
+    /// was never set, or if the system clock is before the Unix epoch.
+    pub fn build(self) -> Result<Transition> {
+        let missing = |field: &str| anyhow!("Transition is missing its {}", field);
+        let timestamp = match self.timestamp {
+            Some(timestamp) => timestamp,
+            None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        };
+
+        Ok(Transition {
+            id: format!("{}-step-{}", self.episode_id, self.step_number),
+            env_id: self.env_id,
+            episode_id: self.episode_id,
+            step_number: self.step_number,
+            state: self.state.ok_or_else(|| missing("state"))?,
+            action: self.action.ok_or_else(|| missing("action"))?,
+            next_state: self.next_state.ok_or_else(|| missing("next_state"))?,
+            observation: self.observation.ok_or_else(|| missing("observation"))?,
+            next_observation: self.next_observation.ok_or_else(|| missing("next_observation"))?,
+            reward: self.reward.ok_or_else(|| missing("reward"))?,
+            done: self.done.ok_or_else(|| missing("done"))?,
+            priority: self.priority,
+            timestamp,
+            metadata: self.metadata,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_matches_inline_construction() {
+        let metadata = HashMap::from([("player".to_string(), "1".to_string())]);
+        let before = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let built = TransitionBuilder::new("tictactoe", "ep", 3)
+            .states(b"state1".to_vec(), b"state2".to_vec())
+            .observations(b"obs1".to_vec(), b"obs2".to_vec())
+            .action(vec![4])
+            .outcome(0.5, true)
+            .metadata(metadata.clone())
+            .build()
+            .unwrap();
+        let after = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        assert!((before..=after).contains(&built.timestamp));
+
+        let expected = Transition {
+            id: "ep-step-3".into(),
+            env_id: "tictactoe".into(),
+            episode_id: "ep".into(),
+            step_number: 3,
+            state: b"state1".to_vec(),
+            action: vec![4],
+            next_state: b"state2".to_vec(),
+            observation: b"obs1".to_vec(),
+            next_observation: b"obs2".to_vec(),
+            reward: 0.5,
+            done: true,
+            priority: 1.0,
+            timestamp: built.timestamp,
+            metadata,
+        };
+        assert_eq!(built, expected);
+
+        let err = TransitionBuilder::new("tictactoe", "ep", 0)
+            .states(Vec::new(), Vec::new())
+            .observations(Vec::new(), Vec::new())
+            .outcome(0.0, false)
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("action"), "{}", err);
+    }
+}
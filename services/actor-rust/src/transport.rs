@@ -0,0 +1,63 @@
+use crate::config::Config;
+use anyhow::{anyhow, Context, Result};
+use std::fs;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity};
+
+/// Build an endpoint for `addr`, enabling TLS for `https://` addresses
+///
+/// Plain `http://` addresses connect without TLS. For `https://` the CA from
+/// `tls_ca_cert` verifies the server, and the optional client certificate and
+/// key are presented for mutual TLS.
+pub fn endpoint(addr: &str, config: &Config) -> Result<Endpoint> {
+    let endpoint = Endpoint::new(addr.to_string())?;
+    if !addr.starts_with("https://") {
+        return Ok(endpoint);
+    }
+
+    endpoint
+        .tls_config(client_tls_config(config)?)
+        .map_err(|e| anyhow!("Invalid TLS configuration for {}: {}", addr, e))
+}
+
+/// Connect to `addr`, using TLS when the address is `https://`
+pub async fn connect(addr: &str, config: &Config) -> Result<Channel> {
+    Ok(endpoint(addr, config)?.connect().await?)
+}
+
+fn client_tls_config(config: &Config) -> Result<ClientTlsConfig> {
+    let ca_path = config
+        .tls_ca_cert
+        .as_ref()
+        .ok_or_else(|| anyhow!("tls_ca_cert must be set to connect to https:// addresses"))?;
+
+    let mut tls = ClientTlsConfig::new().ca_certificate(Certificate::from_pem(read_pem(ca_path)?));
+
+    if let (Some(cert), Some(key)) = (&config.tls_client_cert, &config.tls_client_key) {
+        tls = tls.identity(Identity::from_pem(read_pem(cert)?, read_pem(key)?));
+    }
+
+    Ok(tls)
+}
+
+fn read_pem(path: &str) -> Result<Vec<u8>> {
+    fs::read(path).with_context(|| format!("Failed to read {}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_plaintext_endpoint_needs_no_tls_config() {
+        let config = Config::parse_from(["actor"]);
+        assert!(endpoint("http://localhost:50051", &config).is_ok());
+    }
+
+    #[test]
+    fn test_https_endpoint_requires_ca() {
+        let config = Config::parse_from(["actor"]);
+        let err = endpoint("https://localhost:50051", &config).unwrap_err();
+        assert!(err.to_string().contains("tls_ca_cert"));
+    }
+}
@@ -0,0 +1,118 @@
+//! Dropping the transitions of the warm-up period
+//!
+//! With `--warmup-discard N` the first N transitions an actor produces are
+//! dropped before they reach the replay buffer, e.g. while a policy loaded
+//! from a checkpoint is still behaving like its untrained self. Episodes
+//! played during the warm-up still count towards `--max-episodes` and
+//! readiness.
+//!
+//! By default the warm-up ends exactly after N transitions, so the first
+//! stored transition may come from the middle of an episode. With
+//! `--warmup-whole-episodes` an episode that starts during the warm-up is
+//! dropped entirely, so at least N transitions are discarded and the first
+//! stored transition is always the first of its episode.
+
+use crate::proto::replay::v1::Transition;
+
+/// Which transitions still fall in the warm-up period
+#[derive(Debug, Clone)]
+pub struct WarmupDiscard {
+    /// Transitions left to drop before the warm-up ends
+    remaining: u64,
+    whole_episodes: bool,
+    /// Episode being dropped in whole-episode mode
+    episode: Option<String>,
+    discarded: u64,
+}
+
+impl WarmupDiscard {
+    /// Drop the first `count` transitions, extended to the end of their
+    /// episode when `whole_episodes` is set
+    pub fn new(count: u64, whole_episodes: bool) -> Self {
+        Self {
+            remaining: count,
+            whole_episodes,
+            episode: None,
+            discarded: 0,
+        }
+    }
+
+    /// Transitions dropped so far
+    pub fn discarded(&self) -> u64 {
+        self.discarded
+    }
+
+    /// Whether the warm-up has ended and no transition will be dropped again
+    pub fn is_over(&self) -> bool {
+        self.remaining == 0 && self.episode.is_none()
+    }
+
+    /// Remove the transitions that fall in the warm-up, in order
+    ///
+    /// Returns how many were removed.
+    pub fn retain(&mut self, transitions: &mut Vec<Transition>) -> usize {
+        let before = transitions.len();
+        transitions.retain(|transition| !self.discard(transition));
+        before - transitions.len()
+    }
+
+    fn discard(&mut self, transition: &Transition) -> bool {
+        let discard = if self.whole_episodes {
+            if self.episode.as_deref() == Some(transition.episode_id.as_str()) {
+                true
+            } else if self.remaining > 0 {
+                self.episode = Some(transition.episode_id.clone());
+                true
+            } else {
+                // A new episode started after the warm-up ran out
+                self.episode = None;
+                false
+            }
+        } else {
+            self.remaining > 0
+        };
+        if discard {
+            self.remaining = self.remaining.saturating_sub(1);
+            self.discarded += 1;
+        }
+        discard
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transition::TransitionBuilder;
+
+    fn transitions(episode: &str, steps: u32) -> Vec<Transition> {
+        (0..steps)
+            .map(|step| {
+                TransitionBuilder::new("tictactoe", episode, step)
+                    .states(Vec::new(), Vec::new())
+                    .observations(Vec::new(), Vec::new())
+                    .action(vec![0])
+                    .outcome(0.0, false)
+                    .build()
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_whole_episode_mode_drops_the_episode_the_warmup_ends_in() {
+        let mut warmup = WarmupDiscard::new(4, true);
+
+        let mut first = transitions("ep-1", 3);
+        assert_eq!(warmup.retain(&mut first), 3);
+        // The warm-up runs out one step into ep-2, which is still dropped
+        let mut second = transitions("ep-2", 3);
+        assert_eq!(warmup.retain(&mut second), 3);
+        assert!(second.is_empty());
+
+        let mut third = transitions("ep-3", 2);
+        assert_eq!(warmup.retain(&mut third), 0);
+        assert_eq!(third.len(), 2);
+        assert_eq!(warmup.discarded(), 6);
+        assert!(warmup.is_over());
+    }
+}
@@ -0,0 +1,133 @@
+//! Generic range checks for discrete action indices
+//!
+//! Games decode action bytes themselves, and nothing forces a decoder to
+//! reject an index at or past the end of its action space. `ActionBounds`
+//! reads the indices with the wire conventions every client uses, so
+//! `GameAdapter` can reject out-of-range actions before any game sees them:
+//!
+//! - A discrete index is little-endian in the narrowest unsigned width that
+//!   fits `n` (u8 for n <= 256, u16 for n <= 65536, otherwise u32), or LEB128
+//!   when the action encoding is `varint::VARINT_ACTION_ENCODING`.
+//! - A multi-discrete action concatenates its indices, each in the width for
+//!   its own `n`.
+//!
+//! Bytes that do not have the conventional length are left to the game's
+//! decoder, which owns the encoding and reports malformed input itself.
+//! Continuous actions are not checked.
+
+use crate::typed::{ActionSpace, Capabilities};
+use crate::varint::{decode_discrete_action, VARINT_ACTION_ENCODING};
+
+/// Range check derived from a game's declared action space
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionBounds {
+    /// The action space has no indices to check
+    Unchecked,
+    /// A single index below `n`
+    Discrete { n: u32, varint: bool },
+    /// One index per dimension, each below its entry
+    MultiDiscrete(Vec<u32>),
+}
+
+impl ActionBounds {
+    /// Bounds for the action space and encoding in `capabilities`
+    pub fn new(capabilities: &Capabilities) -> Self {
+        match &capabilities.action_space {
+            ActionSpace::Discrete(n) => ActionBounds::Discrete {
+                n: *n,
+                varint: capabilities.encoding.action == VARINT_ACTION_ENCODING,
+            },
+            ActionSpace::MultiDiscrete(nvec) => ActionBounds::MultiDiscrete(nvec.clone()),
+            ActionSpace::Continuous { .. } => ActionBounds::Unchecked,
+        }
+    }
+
+    /// Check the indices in `action`, describing the first one out of range
+    pub fn check(&self, action: &[u8]) -> Result<(), String> {
+        match self {
+            ActionBounds::Unchecked => Ok(()),
+            ActionBounds::Discrete { n, varint: true } => {
+                match decode_discrete_action(action, u32::MAX) {
+                    Ok(index) => check_index(index, *n),
+                    Err(_) => Ok(()),
+                }
+            }
+            ActionBounds::Discrete { n, varint: false } => {
+                if action.len() != index_width(*n) {
+                    return Ok(());
+                }
+                check_index(read_index(action), *n)
+            }
+            ActionBounds::MultiDiscrete(nvec) => {
+                if action.len() != nvec.iter().map(|&n| index_width(n)).sum::<usize>() {
+                    return Ok(());
+                }
+                let mut rest = action;
+                for &n in nvec {
+                    let (index, tail) = rest.split_at(index_width(n));
+                    check_index(read_index(index), n)?;
+                    rest = tail;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn check_index(index: u32, n: u32) -> Result<(), String> {
+    if index >= n {
+        return Err(format!("Action index {} out of range for n={}", index, n));
+    }
+    Ok(())
+}
+
+/// Bytes of the narrowest unsigned integer holding every index below `n`
+fn index_width(n: u32) -> usize {
+    match n {
+        0..=256 => 1,
+        257..=65_536 => 2,
+        _ => 4,
+    }
+}
+
+fn read_index(bytes: &[u8]) -> u32 {
+    let mut padded = [0u8; 4];
+    padded[..bytes.len()].copy_from_slice(bytes);
+    u32::from_le_bytes(padded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_only_conventionally_encoded_indices_out_of_range() {
+        let discrete = ActionBounds::Discrete {
+            n: 9,
+            varint: false,
+        };
+        assert!(discrete.check(&[8]).is_ok());
+        assert!(discrete.check(&[9]).is_err());
+        // Unconventional lengths are the game's to judge
+        assert!(discrete.check(&[9, 9]).is_ok());
+
+        let wide = ActionBounds::Discrete {
+            n: 1000,
+            varint: false,
+        };
+        assert!(wide.check(&999u16.to_le_bytes()).is_ok());
+        assert!(wide.check(&1000u16.to_le_bytes()).is_err());
+
+        let varint = ActionBounds::Discrete {
+            n: 200,
+            varint: true,
+        };
+        assert!(varint.check(&[0xc7, 0x01]).is_ok());
+        assert!(varint.check(&[0xc8, 0x01]).is_err());
+
+        let multi = ActionBounds::MultiDiscrete(vec![2, 300]);
+        assert!(multi.check(&[1, 0x2b, 0x01]).is_ok());
+        assert!(multi.check(&[2, 0, 0]).is_err());
+        assert!(multi.check(&[1, 0x2c, 0x01]).is_err());
+    }
+}
@@ -0,0 +1,356 @@
+//! Action history observations for recurrent policies
+//!
+//! `ActionHistory<G, K, N>` wraps a game with a discrete action space of `N`
+//! actions and appends its last `K` actions to every observation, each as a
+//! one-hot block of `N` little-endian `f32` values, oldest first. Like
+//! `ObsStack`, the history travels inside the encoded state, so the server
+//! stays stateless. Reset starts from an empty history: every block is zero
+//! until `K` moves have been played.
+//!
+//! Action indices are read from the game's encoded action, which for discrete
+//! games is the index as a little-endian unsigned integer. An action that
+//! encodes to no bytes (a pass) is recorded as an all-zero block.
+//!
+//! ```rust,ignore
+//! register_game("tictactoe-history3".to_string(), || {
+//!     Box::new(GameAdapter::new(ActionHistory::<TicTacToe, 3, 9>::new(TicTacToe::new())))
+//! });
+//! ```
+
+use std::collections::VecDeque;
+
+use rand_chacha::ChaCha20Rng;
+
+use crate::obs_stack::{read_chunk, write_len, LEN_PREFIX};
+use crate::typed::{
+    ActionSpace, Capabilities, DecodeError, EncodeError, EngineId, Game, InitialStateKind,
+    ObsBounds, ObsLayout, StepResult,
+};
+
+/// Bytes of one encoded history slot
+const SLOT_SIZE: usize = std::mem::size_of::<u32>();
+
+/// Slot value marking "no action" (before `K` moves, or a pass)
+const EMPTY_SLOT: u32 = u32::MAX;
+
+const F32_SIZE: usize = std::mem::size_of::<f32>();
+
+/// Game wrapper whose observation ends with its last `K` actions, one-hot over `N`
+#[derive(Clone)]
+pub struct ActionHistory<G: Game, const K: usize, const N: usize> {
+    inner: G,
+}
+
+impl<G: Game, const K: usize, const N: usize> ActionHistory<G, K, N> {
+    /// Track the last `K` actions of `inner`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `K` or `N` is zero, or if `inner` does not report a discrete
+    /// action space of exactly `N` actions.
+    pub fn new(inner: G) -> Self {
+        assert!(K > 0, "ActionHistory needs at least one slot");
+        match inner.capabilities().action_space {
+            ActionSpace::Discrete(n) if n as usize == N && N > 0 => {}
+            other => panic!(
+                "ActionHistory::<_, {}, {}> needs a Discrete({}) action space, got {:?}",
+                K, N, N, other
+            ),
+        }
+        Self { inner }
+    }
+
+    /// The wrapped game
+    pub fn inner(&self) -> &G {
+        &self.inner
+    }
+
+    /// Index of `action`, or `None` when it encodes to no bytes
+    fn action_index(action: &G::Action) -> Option<u32> {
+        let mut encoded = Vec::with_capacity(SLOT_SIZE);
+        G::encode_action(action, &mut encoded).unwrap_or_else(|e| {
+            panic!("ActionHistory: wrapped game failed to encode action: {}", e)
+        });
+        if encoded.is_empty() {
+            return None;
+        }
+        assert!(
+            encoded.len() <= SLOT_SIZE,
+            "ActionHistory: discrete action encoded to {} bytes",
+            encoded.len()
+        );
+
+        let mut index = [0; SLOT_SIZE];
+        index[..encoded.len()].copy_from_slice(&encoded);
+        let index = u32::from_le_bytes(index);
+        assert!(
+            (index as usize) < N,
+            "ActionHistory: action index {} out of range for {} actions",
+            index,
+            N
+        );
+        Some(index)
+    }
+
+    /// Encoded inner observation followed by the one-hot history
+    fn history_obs(obs: &G::Obs, history: &VecDeque<Option<u32>>) -> Vec<u8> {
+        let mut out = Vec::with_capacity(G::OBS_ENCODED_SIZE.unwrap_or(0) + K * N * F32_SIZE);
+        // Observations are encoded eagerly so the history can be appended
+        G::encode_obs(obs, &mut out)
+            .unwrap_or_else(|e| panic!("ActionHistory: wrapped game failed to encode obs: {}", e));
+        for &slot in history {
+            for action in 0..N as u32 {
+                let hot = if slot == Some(action) { 1.0f32 } else { 0.0 };
+                out.extend_from_slice(&hot.to_le_bytes());
+            }
+        }
+        out
+    }
+}
+
+/// State of a history game: the wrapped state plus the last `K` action indices
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryState<S> {
+    pub inner: S,
+    /// Action indices, oldest first; `None` for an empty slot or a pass
+    pub actions: VecDeque<Option<u32>>,
+}
+
+/// Layout: `[u32 len][inner state]` followed by `K` x `u32` action indices,
+/// little-endian, with `u32::MAX` marking an empty slot
+impl<G: Game, const K: usize, const N: usize> Game for ActionHistory<G, K, N> {
+    type State = HistoryState<G::State>;
+    type Action = G::Action;
+    /// Encoded wrapped observation followed by `K * N` one-hot `f32` values
+    type Obs = Vec<u8>;
+
+    const STATE_ENCODED_SIZE: Option<usize> = match G::STATE_ENCODED_SIZE {
+        Some(state) => Some(LEN_PREFIX + state + K * SLOT_SIZE),
+        None => None,
+    };
+    const OBS_ENCODED_SIZE: Option<usize> = match G::OBS_ENCODED_SIZE {
+        Some(obs) => Some(obs + K * N * F32_SIZE),
+        None => None,
+    };
+    const ACTION_ENCODED_SIZE: Option<usize> = G::ACTION_ENCODED_SIZE;
+
+    fn engine_id(&self) -> EngineId {
+        self.inner.engine_id()
+    }
+
+    /// The wrapped capabilities with the history declared in the encodings
+    ///
+    /// `history3x9(f32x29:v1)` names a `f32x29:v1` observation followed by
+    /// three one-hot blocks of nine `f32` values.
+    fn capabilities(&self) -> Capabilities {
+        let mut caps = self.inner.capabilities();
+        caps.encoding.state = format!("history{}({})", K, caps.encoding.state);
+        caps.encoding.obs = format!("history{}x{}({})", K, N, caps.encoding.obs);
+        caps
+    }
+
+    fn initial_state_kind(&self) -> InitialStateKind {
+        self.inner.initial_state_kind()
+    }
+
+    fn action_names(&self) -> Option<Vec<String>> {
+        self.inner.action_names()
+    }
+
+    /// The wrapped bounds followed by `[0, 1]` for every history value
+    fn obs_bounds(&self) -> Option<ObsBounds> {
+        self.inner.obs_bounds().map(|mut bounds| {
+            bounds.low.resize(bounds.low.len() + K * N, 0.0);
+            bounds.high.resize(bounds.high.len() + K * N, 1.0);
+            bounds
+        })
+    }
+
+    /// The wrapped segments followed by `action_history`
+    fn obs_layout(&self) -> Option<ObsLayout> {
+        self.inner.obs_layout().map(|mut layout| {
+            layout.segments.push(("action_history".to_string(), K * N));
+            layout
+        })
+    }
+
+    fn is_deterministic(&self) -> bool {
+        self.inner.is_deterministic()
+    }
+
+    fn is_state_complete(&self) -> bool {
+        self.inner.is_state_complete()
+    }
+
+    fn current_player(&self, state: &Self::State) -> Option<u32> {
+        self.inner.current_player(&state.inner)
+    }
+
+    fn must_pass(&self, state: &Self::State) -> bool {
+        self.inner.must_pass(&state.inner)
+    }
+
+    fn legal_actions(&self, state: &Self::State) -> Option<Vec<Self::Action>> {
+        self.inner.legal_actions(&state.inner)
+    }
+
+    fn legal_action_count(&self, state: &Self::State) -> Option<u32> {
+        self.inner.legal_action_count(&state.inner)
+    }
+
+    fn pass_action(&self) -> Option<Self::Action> {
+        self.inner.pass_action()
+    }
+
+    fn step_info(&self, state: &Self::State) -> Vec<(String, f32)> {
+        self.inner.step_info(&state.inner)
+    }
+
+    fn reset(&mut self, rng: &mut ChaCha20Rng, hint: &[u8]) -> (Self::State, Self::Obs) {
+        let (inner, obs) = self.inner.reset(rng, hint);
+        let actions: VecDeque<Option<u32>> = vec![None; K].into();
+        let obs = Self::history_obs(&obs, &actions);
+        (HistoryState { inner, actions }, obs)
+    }
+
+    fn step(
+        &mut self,
+        state: &mut Self::State,
+        action: Self::Action,
+        rng: &mut ChaCha20Rng,
+    ) -> StepResult<Self::Obs> {
+        let index = Self::action_index(&action);
+        let result = self.inner.step(&mut state.inner, action, rng);
+        state.actions.pop_front();
+        state.actions.push_back(index);
+        StepResult {
+            obs: Self::history_obs(&result.obs, &state.actions),
+            reward: result.reward,
+            done: result.done,
+            truncated: result.truncated,
+            info: result.info,
+        }
+    }
+
+    fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        if state.actions.len() != K {
+            return Err(EncodeError::InvalidData(format!(
+                "History state holds {} actions, expected {}",
+                state.actions.len(),
+                K
+            )));
+        }
+
+        let start = out.len();
+        out.extend_from_slice(&[0; LEN_PREFIX]);
+        G::encode_state(&state.inner, out)?;
+        write_len(out, start)?;
+
+        for slot in &state.actions {
+            out.extend_from_slice(&slot.unwrap_or(EMPTY_SLOT).to_le_bytes());
+        }
+        Ok(())
+    }
+
+    fn decode_state(buf: &[u8]) -> Result<Self::State, DecodeError> {
+        let (inner, rest) = read_chunk(buf)?;
+        let inner = G::decode_state(inner)?;
+
+        if rest.len() != K * SLOT_SIZE {
+            return Err(DecodeError::InvalidLength {
+                expected: K * SLOT_SIZE,
+                actual: rest.len(),
+            });
+        }
+        let mut actions = VecDeque::with_capacity(K);
+        for slot in rest.chunks_exact(SLOT_SIZE) {
+            let index = u32::from_le_bytes(slot.try_into().unwrap());
+            actions.push_back(match index {
+                EMPTY_SLOT => None,
+                index if (index as usize) < N => Some(index),
+                index => {
+                    return Err(DecodeError::CorruptedData(format!(
+                        "History holds action {} but the game has {} actions",
+                        index, N
+                    )))
+                }
+            });
+        }
+        Ok(HistoryState { inner, actions })
+    }
+
+    fn encode_action(action: &Self::Action, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        G::encode_action(action, out)
+    }
+
+    fn decode_action(buf: &[u8]) -> Result<Self::Action, DecodeError> {
+        G::decode_action(buf)
+    }
+
+    fn encode_obs(obs: &Self::Obs, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        out.extend_from_slice(obs);
+        Ok(())
+    }
+
+    fn warm_up() {
+        G::warm_up()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_game::ValuesGame;
+    use rand::SeedableRng;
+
+    type History = ActionHistory<ValuesGame<3>, 2, 3>;
+
+    fn one_hot(index: Option<usize>) -> Vec<u8> {
+        (0..3)
+            .flat_map(|i| (if Some(i) == index { 1.0f32 } else { 0.0 }).to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    #[should_panic(expected = "needs a Discrete(4) action space")]
+    fn test_mismatched_action_count_panics() {
+        ActionHistory::<ValuesGame<3>, 2, 4>::new(ValuesGame::new([0.0; 3]));
+    }
+
+    #[test]
+    fn test_pass_is_recorded_as_an_empty_block() {
+        let mut game = History::new(ValuesGame::new([0.0; 3]));
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let (mut state, _) = game.reset(&mut rng, &[]);
+        let frame_len = 3 * F32_SIZE;
+
+        game.step(&mut state, Some(2), &mut rng);
+        let result = game.step(&mut state, None, &mut rng);
+        assert_eq!(state.actions, [Some(2), None]);
+        assert_eq!(
+            &result.obs[frame_len..],
+            [one_hot(Some(2)), one_hot(None)].concat()
+        );
+
+        let mut buf = Vec::new();
+        History::encode_state(&state, &mut buf).unwrap();
+        assert_eq!(History::decode_state(&buf).unwrap(), state);
+    }
+
+    #[test]
+    fn test_decode_rejects_an_out_of_range_action() {
+        let state = HistoryState {
+            inner: [0.0; 3],
+            actions: VecDeque::from([None, Some(1)]),
+        };
+        let mut buf = Vec::new();
+        History::encode_state(&state, &mut buf).unwrap();
+        let last = buf.len() - SLOT_SIZE;
+        buf[last..].copy_from_slice(&3u32.to_le_bytes());
+
+        assert!(matches!(
+            History::decode_state(&buf),
+            Err(DecodeError::CorruptedData(_))
+        ));
+    }
+}
@@ -7,8 +7,18 @@
 use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
 
-use crate::erased::{ErasedGame, ErasedGameError};
-use crate::typed::{Capabilities, EngineId, Game};
+use crate::action_bounds::ActionBounds;
+use crate::erased::{
+    EncodedSizes, ErasedGame, ErasedGameError, GameErrorContext, ResetOutcome, StepOutcome,
+};
+use crate::obs_frame::{finish_frame, FRAME_HEADER_LEN};
+use crate::endian::Endianness;
+use crate::obs_cache::ObsCache;
+use crate::obs_normalize::ObsNormalize;
+use crate::typed::{
+    ActionSpace, Capabilities, EngineId, Game, InitialStateKind, ObsLayout, ObsQuantization,
+    StepResult,
+};
 
 /// Adapter that converts typed games to erased interface
 ///
@@ -20,6 +30,10 @@ use crate::typed::{Capabilities, EngineId, Game};
 /// ensuring deterministic behavior while providing the stateless interface
 /// expected by the gRPC layer.
 ///
+/// Encoded state is prefixed with a single schema version byte taken from the
+/// game's capabilities. State carrying an older version is upgraded through
+/// `Game::migrate_state` before decoding.
+///
 /// # Example
 ///
 /// ```rust
@@ -28,21 +42,36 @@ use crate::typed::{Capabilities, EngineId, Game};
 /// # use engine_core::erased::ErasedGame;
 /// # use rand_chacha::ChaCha20Rng;
 ///
-/// # #[derive(Default)]
+/// # #[derive(Default, Clone)]
 /// # struct MyGame;
 /// # impl Game for MyGame {
 /// #     type State = u32;
 /// #     type Action = u8;
 /// #     type Obs = Vec<f32>;
-/// #     fn engine_id(&self) -> EngineId { todo!() }
-/// #     fn capabilities(&self) -> Capabilities { todo!() }
+/// #     fn engine_id(&self) -> EngineId {
+/// #         EngineId { env_id: "my_game".to_string(), build_id: "0.1.0".to_string() }
+/// #     }
+/// #     fn capabilities(&self) -> Capabilities {
+/// #         Capabilities {
+/// #             id: self.engine_id(),
+/// #             encoding: Encoding {
+/// #                 state: "u32:v1".to_string(),
+/// #                 action: "u8:v1".to_string(),
+/// #                 obs: "f32_vec:v1".to_string(),
+/// #                 schema_version: 1,
+/// #             },
+/// #             max_horizon: 100,
+/// #             action_space: ActionSpace::Discrete(4),
+/// #             preferred_batch: 32,
+/// #         }
+/// #     }
 /// #     fn reset(&mut self, rng: &mut ChaCha20Rng, hint: &[u8]) -> (Self::State, Self::Obs) { todo!() }
 /// #     fn step(
 /// #         &mut self,
 /// #         state: &mut Self::State,
 /// #         action: Self::Action,
 /// #         rng: &mut ChaCha20Rng,
-/// #     ) -> (Self::Obs, f32, bool, u64) { todo!() }
+/// #     ) -> StepResult<Self::Obs> { todo!() }
 /// #     fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> { todo!() }
 /// #     fn decode_state(buf: &[u8]) -> Result<Self::State, DecodeError> { todo!() }
 /// #     fn encode_action(action: &Self::Action, out: &mut Vec<u8>) -> Result<(), EncodeError> { todo!() }
@@ -59,7 +88,38 @@ use crate::typed::{Capabilities, EngineId, Game};
 /// ```
 pub struct GameAdapter<T: Game> {
     game: T,
+    env_id: String,
     rng: ChaCha20Rng,
+    schema_version: u32,
+    obs_normalize: Option<ObsNormalize>,
+    /// Prefix each encoded observation with its length
+    frame_obs: bool,
+    /// Byte order of emitted observation values
+    obs_endianness: Endianness,
+    /// Encoded observations of recently seen states, when enabled
+    obs_cache: Option<ObsCache>,
+    /// XORed into every reset seed; zero unless env seed mixing is enabled
+    seed_salt: u64,
+    /// RNG words consumed by the most recent reset or step; `None` unless
+    /// RNG auditing is enabled
+    rng_draws: Option<u64>,
+    /// Range check applied to action bytes before the game decodes them
+    action_bounds: ActionBounds,
+    /// `Game::step_info` of the state the most recent step produced
+    step_info: Vec<(String, f32)>,
+}
+
+/// Stable 64-bit FNV-1a hash, identical across runs and platforms
+///
+/// Used wherever bytes need a hash that other processes can reproduce: env
+/// seed mixing, golden trajectories, and the server's observation deltas.
+pub fn stable_hash(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(PRIME)
+    })
 }
 
 impl<T: Game> GameAdapter<T> {
@@ -68,12 +128,118 @@ impl<T: Game> GameAdapter<T> {
     /// The adapter starts with a default-seeded RNG that will be re-seeded
     /// on the first reset call.
     pub fn new(game: T) -> Self {
+        let capabilities = game.capabilities();
+        let schema_version = capabilities.encoding.schema_version;
+        let action_bounds = ActionBounds::new(&capabilities);
+        let env_id = game.engine_id().env_id;
         Self {
             game,
+            env_id,
             rng: ChaCha20Rng::seed_from_u64(0), // Will be re-seeded on reset
+            schema_version,
+            obs_normalize: None,
+            frame_obs: false,
+            obs_endianness: Endianness::Little,
+            obs_cache: None,
+            seed_salt: 0,
+            rng_draws: None,
+            action_bounds,
+            step_info: Vec::new(),
         }
     }
 
+    /// Mix the game's `env_id` into every reset seed
+    ///
+    /// The effective seed becomes `seed ^ stable_hash(env_id)`, so different
+    /// environments given the same numeric seed draw uncorrelated streams while
+    /// each environment stays reproducible. Off by default to keep existing
+    /// seeds byte-exact.
+    pub fn with_env_seed_mixing(mut self) -> Self {
+        self.seed_salt = stable_hash(self.env_id.as_bytes());
+        self
+    }
+
+    /// Normalize encoded observations to `[0, 1]` using the game's `obs_bounds`
+    ///
+    /// Games that declare no bounds are unaffected.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ErasedGameError` if the declared bounds are inconsistent
+    pub fn with_obs_normalization(mut self) -> Result<Self, ErasedGameError> {
+        self.obs_normalize = self
+            .game
+            .obs_bounds()
+            .map(|bounds| ObsNormalize::new(&bounds))
+            .transpose()?;
+        Ok(self)
+    }
+
+    /// Prefix every encoded observation with its byte length
+    ///
+    /// The prefix is a little-endian `u32` (see `obs_frame::split_frame`),
+    /// letting streaming consumers delimit observations without consulting
+    /// the capabilities. Off by default, so observation bytes are exactly
+    /// what `Game::encode_obs` writes.
+    pub fn with_obs_framing(mut self) -> Self {
+        self.frame_obs = true;
+        self
+    }
+
+    /// Emit observation values in the given byte order
+    ///
+    /// The game's little-endian observation is byte-swapped per 32-bit value
+    /// after normalization, and the capabilities report the obs encoding with
+    /// a `be` suffix (see `Endianness::encoding_name`). The framing prefix
+    /// stays little-endian. `Endianness::Little` is the default and leaves
+    /// observation bytes exactly as the game wrote them.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ErasedGameError::Encoding` for big-endian output when the
+    /// game reports `obs_quantization`: its observation is single bytes, not
+    /// 32-bit values, and swapping them would scramble it.
+    pub fn with_obs_endianness(mut self, endianness: Endianness) -> Result<Self, ErasedGameError> {
+        if endianness == Endianness::Big && self.game.obs_quantization().is_some() {
+            return Err(ErasedGameError::Encoding(
+                "Quantized observations are bytes and cannot be emitted big-endian".to_string(),
+            ));
+        }
+        self.obs_endianness = endianness;
+        Ok(self)
+    }
+
+    /// Record how many RNG words each reset and step consumes
+    ///
+    /// A debugging aid for hidden nondeterminism: the count is read back with
+    /// `ErasedGame::last_rng_draws`, and `determinism::verify_determinism`
+    /// compares it across two runs. Draws are counted in 32-bit words of the
+    /// seeded ChaCha20 stream, so a `u64` sample counts as two. Entropy taken
+    /// from anywhere else (`thread_rng`, the clock) is not counted, which is
+    /// what lets two otherwise identical runs expose it.
+    pub fn with_rng_audit(mut self) -> Self {
+        self.rng_draws = Some(0);
+        self
+    }
+
+    /// Serve repeated observation encodes of the same state from a cache
+    ///
+    /// Keeps the encoded observations of up to `capacity` states, keyed by
+    /// the encoded state. Only games whose observation is a pure function of
+    /// the encoded state can be cached, so games that are not both
+    /// `is_deterministic` and `is_state_complete` are left uncached, as is a
+    /// zero `capacity`. Paths that skip state encoding never use the cache.
+    pub fn with_obs_cache(mut self, capacity: usize) -> Self {
+        let cacheable = self.game.is_deterministic() && self.game.is_state_complete();
+        self.obs_cache = (cacheable && capacity > 0).then(|| ObsCache::new(capacity));
+        self
+    }
+
+    /// The observation cache, if enabled
+    pub fn obs_cache(&self) -> Option<&ObsCache> {
+        self.obs_cache.as_ref()
+    }
+
     /// Get a reference to the underlying game
     pub fn game(&self) -> &T {
         &self.game
@@ -88,15 +254,265 @@ impl<T: Game> GameAdapter<T> {
     pub fn into_inner(self) -> T {
         self.game
     }
+
+    /// Strip the observation from a typed step result
+    fn outcome(&self, state: &T::State, result: StepResult<T::Obs>) -> StepOutcome {
+        StepOutcome {
+            reward: result.reward,
+            done: result.done,
+            truncated: result.truncated,
+            // A truncated episode stopped at a live state that can still be bootstrapped
+            terminal_obs: result.done && !result.truncated,
+            info: result.info,
+            current_player: self.game.current_player(state),
+            must_pass: !result.done && self.game.must_pass(state),
+        }
+    }
+
+    /// Outcome reported for a freshly reset state
+    fn reset_outcome(&self, state: &T::State) -> ResetOutcome {
+        ResetOutcome {
+            current_player: self.game.current_player(state),
+            must_pass: self.game.must_pass(state),
+        }
+    }
+
+    /// Context identifying this game in error messages
+    fn error_context(&self) -> GameErrorContext {
+        GameErrorContext::new(self.env_id.as_str())
+    }
+
+    /// Encode an observation, applying normalization, byte order, and framing when enabled
+    fn encode_obs(&self, obs: &T::Obs, out: &mut Vec<u8>) -> Result<(), ErasedGameError> {
+        let start = out.len();
+        if self.frame_obs {
+            out.extend_from_slice(&[0; FRAME_HEADER_LEN]);
+        }
+        let payload = out.len();
+
+        T::encode_obs(obs, out)
+            .map_err(|e| ErasedGameError::Encoding(e.to_string()))
+            .and_then(|()| match &self.obs_normalize {
+                Some(normalize) => normalize.apply(&mut out[payload..]),
+                None => Ok(()),
+            })
+            .and_then(|()| self.obs_endianness.convert_le_words(&mut out[payload..]))
+            .and_then(|()| {
+                if self.frame_obs {
+                    finish_frame(out, start)
+                } else {
+                    Ok(())
+                }
+            })
+            .map_err(|e| e.with_context(&self.error_context()))
+    }
+
+    /// Encode the observation of the already encoded `state`, using the obs
+    /// cache when enabled
+    fn encode_obs_cached(
+        &mut self,
+        state: &[u8],
+        obs: &T::Obs,
+        out: &mut Vec<u8>,
+    ) -> Result<(), ErasedGameError> {
+        if let Some(cached) = self.obs_cache.as_mut().and_then(|cache| cache.get(state)) {
+            out.extend_from_slice(cached);
+            return Ok(());
+        }
+
+        let start = out.len();
+        self.encode_obs(obs, out)?;
+        if let Some(cache) = &mut self.obs_cache {
+            cache.insert(state, &out[start..]);
+        }
+        Ok(())
+    }
+
+    /// Decode action bytes, tagging failures with the action length
+    ///
+    /// When the player in `state` must pass, only an empty action is accepted
+    /// and it decodes to the game's `pass_action`. Otherwise discrete indices
+    /// outside the declared action space are rejected before the game's
+    /// decoder sees them (see `ActionBounds`).
+    fn decode_action(&self, state: &T::State, buf: &[u8]) -> Result<T::Action, ErasedGameError> {
+        let context = || self.error_context().with_input("action", buf.len());
+
+        if self.game.must_pass(state) {
+            if !buf.is_empty() {
+                return Err(ErasedGameError::InvalidAction(
+                    "Player has no legal moves and must pass with an empty action".to_string(),
+                )
+                .with_context(&context()));
+            }
+            return self.game.pass_action().ok_or_else(|| {
+                ErasedGameError::GameLogic(
+                    "must_pass is set but the game defines no pass_action".to_string(),
+                )
+                .with_context(&context())
+            });
+        }
+
+        self.action_bounds
+            .check(buf)
+            .map_err(|msg| ErasedGameError::InvalidAction(msg).with_context(&context()))?;
+
+        T::decode_action(buf)
+            .map_err(|e| ErasedGameError::Decoding(e.to_string()).with_context(&context()))
+    }
+
+    /// Encode state prefixed with the current schema version byte
+    fn encode_versioned_state(
+        &self,
+        state: &T::State,
+        out: &mut Vec<u8>,
+    ) -> Result<(), ErasedGameError> {
+        let version = u8::try_from(self.schema_version).map_err(|_| {
+            ErasedGameError::Encoding(format!(
+                "Schema version {} does not fit in the state version byte",
+                self.schema_version
+            ))
+        })?;
+        out.push(version);
+        T::encode_state(state, out).map_err(|e| {
+            ErasedGameError::Encoding(e.to_string()).with_context(&self.error_context())
+        })
+    }
+
+    /// Call into the game with the adapter's RNG, recording the words it
+    /// consumes when auditing is enabled
+    fn with_rng<R>(&mut self, f: impl FnOnce(&mut T, &mut ChaCha20Rng) -> R) -> R {
+        let before = self.rng.get_word_pos();
+        let result = f(&mut self.game, &mut self.rng);
+        if let Some(draws) = &mut self.rng_draws {
+            *draws = (self.rng.get_word_pos() - before) as u64;
+        }
+        result
+    }
+
+    /// Decode version-prefixed state, tagging failures with the state length
+    fn decode_versioned_state(&self, buf: &[u8]) -> Result<T::State, ErasedGameError> {
+        self.decode_versioned_body(buf)
+            .map_err(|e| e.with_context(&self.error_context().with_input("state", buf.len())))
+    }
+
+    /// Decode version-prefixed state, migrating older schema versions first
+    fn decode_versioned_body(&self, buf: &[u8]) -> Result<T::State, ErasedGameError> {
+        let (&version, body) = buf.split_first().ok_or_else(|| {
+            ErasedGameError::Decoding("State is missing its schema version byte".to_string())
+        })?;
+
+        let version = version as u32;
+        if version == self.schema_version {
+            return T::decode_state(body).map_err(|e| ErasedGameError::Decoding(e.to_string()));
+        }
+
+        let migrated =
+            T::migrate_state(body, version).map_err(|e| ErasedGameError::Decoding(e.to_string()))?;
+        T::decode_state(&migrated).map_err(|e| ErasedGameError::Decoding(e.to_string()))
+    }
 }
 
-impl<T: Game> ErasedGame for GameAdapter<T> {
+/// Forking clones the game, so adapted games must be `Clone`
+impl<T: Game + Clone> ErasedGame for GameAdapter<T> {
     fn engine_id(&self) -> EngineId {
         self.game.engine_id()
     }
 
     fn capabilities(&self) -> Capabilities {
-        self.game.capabilities()
+        let mut caps = self.game.capabilities();
+        caps.encoding.obs = self.obs_endianness.encoding_name(&caps.encoding.obs);
+        caps
+    }
+
+    fn encoded_sizes(&self) -> EncodedSizes {
+        EncodedSizes {
+            // Account for the schema version byte prefixed to every state
+            state: T::STATE_ENCODED_SIZE.map(|size| size + 1),
+            obs: T::OBS_ENCODED_SIZE.map(|size| {
+                if self.frame_obs {
+                    size + FRAME_HEADER_LEN
+                } else {
+                    size
+                }
+            }),
+            action: T::ACTION_ENCODED_SIZE,
+        }
+    }
+
+    fn initial_state_kind(&self) -> InitialStateKind {
+        self.game.initial_state_kind()
+    }
+
+    fn action_names(&self) -> Option<Vec<String>> {
+        match self.game.capabilities().action_space {
+            ActionSpace::Discrete(_) => self.game.action_names(),
+            _ => None,
+        }
+    }
+
+    fn state_space_estimate(&self) -> Option<u64> {
+        self.game.state_space_estimate()
+    }
+
+    fn obs_quantization(&self) -> Option<ObsQuantization> {
+        self.game.obs_quantization()
+    }
+
+    fn obs_layout(&self) -> Option<ObsLayout> {
+        self.game.obs_layout()
+    }
+
+    fn supported_schema_versions(&self) -> Vec<u32> {
+        let mut versions = self.game.supported_schema_versions();
+        versions.push(self.schema_version);
+        versions.sort_unstable();
+        versions.dedup();
+        versions
+    }
+
+    fn is_deterministic(&self) -> bool {
+        self.game.is_deterministic()
+    }
+
+    fn is_state_complete(&self) -> bool {
+        self.game.is_state_complete()
+    }
+
+    fn legal_action_count(&self, state: &[u8]) -> Result<Option<u32>, ErasedGameError> {
+        let state = self.decode_versioned_state(state)?;
+        Ok(self.game.legal_action_count(&state))
+    }
+
+    fn legal_actions(&self, state: &[u8]) -> Result<Option<Vec<Vec<u8>>>, ErasedGameError> {
+        let state = self.decode_versioned_state(state)?;
+        self.game
+            .legal_actions(&state)
+            .map(|actions| {
+                actions
+                    .iter()
+                    .map(|action| {
+                        let mut encoded = Vec::new();
+                        T::encode_action(action, &mut encoded).map_err(|e| {
+                            ErasedGameError::Encoding(e.to_string())
+                                .with_context(&self.error_context())
+                        })?;
+                        Ok(encoded)
+                    })
+                    .collect()
+            })
+            .transpose()
+    }
+
+    fn obs_framed(&self) -> bool {
+        self.frame_obs
+    }
+
+    fn last_rng_draws(&self) -> Option<u64> {
+        self.rng_draws
+    }
+
+    fn last_step_info(&self) -> &[(String, f32)] {
+        &self.step_info
     }
 
     fn reset(
@@ -105,23 +521,79 @@ impl<T: Game> ErasedGame for GameAdapter<T> {
         hint: &[u8],
         out_state: &mut Vec<u8>,
         out_obs: &mut Vec<u8>,
-    ) -> Result<(), ErasedGameError> {
+    ) -> Result<ResetOutcome, ErasedGameError> {
         // Re-seed the RNG for deterministic behavior
-        self.rng = ChaCha20Rng::seed_from_u64(seed);
+        self.rng = ChaCha20Rng::seed_from_u64(seed ^ self.seed_salt);
 
         // Clear output buffers
         out_state.clear();
         out_obs.clear();
 
         // Call the typed reset method
-        let (state, obs) = self.game.reset(&mut self.rng, hint);
+        let (state, obs) = self.with_rng(|game, rng| game.reset(rng, hint));
 
         // Encode the results
-        T::encode_state(&state, out_state).map_err(|e| ErasedGameError::Encoding(e.to_string()))?;
+        self.encode_versioned_state(&state, out_state)?;
 
-        T::encode_obs(&obs, out_obs).map_err(|e| ErasedGameError::Encoding(e.to_string()))?;
+        self.encode_obs_cached(out_state, &obs, out_obs)?;
 
-        Ok(())
+        Ok(self.reset_outcome(&state))
+    }
+
+    fn reset_without_state(
+        &mut self,
+        seed: u64,
+        hint: &[u8],
+        out_obs: &mut Vec<u8>,
+    ) -> Result<ResetOutcome, ErasedGameError> {
+        self.rng = ChaCha20Rng::seed_from_u64(seed ^ self.seed_salt);
+        out_obs.clear();
+
+        // Same RNG consumption as a full reset, but skip state encoding
+        let (state, obs) = self.with_rng(|game, rng| game.reset(rng, hint));
+
+        self.encode_obs(&obs, out_obs)?;
+
+        Ok(self.reset_outcome(&state))
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.rng = ChaCha20Rng::seed_from_u64(seed ^ self.seed_salt);
+    }
+
+    fn step_seeded(
+        &mut self,
+        seed: u64,
+        state: &[u8],
+        action: &[u8],
+        out_state: &mut Vec<u8>,
+        out_obs: &mut Vec<u8>,
+    ) -> Result<StepOutcome, ErasedGameError> {
+        let stream = std::mem::replace(
+            &mut self.rng,
+            ChaCha20Rng::seed_from_u64(seed ^ self.seed_salt),
+        );
+        let result = self.step(state, action, out_state, out_obs);
+        self.rng = stream;
+        result
+    }
+
+    /// Clones the game and configuration; the RNG and observation cache start over
+    fn fork(&self) -> Box<dyn ErasedGame> {
+        Box::new(Self {
+            game: self.game.clone(),
+            env_id: self.env_id.clone(),
+            rng: ChaCha20Rng::seed_from_u64(0),
+            schema_version: self.schema_version,
+            obs_normalize: self.obs_normalize.clone(),
+            frame_obs: self.frame_obs,
+            obs_endianness: self.obs_endianness,
+            obs_cache: self.obs_cache.as_ref().map(|cache| ObsCache::new(cache.capacity())),
+            seed_salt: self.seed_salt,
+            rng_draws: self.rng_draws.map(|_| 0),
+            action_bounds: self.action_bounds.clone(),
+            step_info: Vec::new(),
+        })
     }
 
     fn step(
@@ -130,27 +602,47 @@ impl<T: Game> ErasedGame for GameAdapter<T> {
         action: &[u8],
         out_state: &mut Vec<u8>,
         out_obs: &mut Vec<u8>,
-    ) -> Result<(f32, bool), ErasedGameError> {
+    ) -> Result<StepOutcome, ErasedGameError> {
         // Clear output buffers
         out_state.clear();
         out_obs.clear();
 
         // Decode the inputs
-        let mut state =
-            T::decode_state(state).map_err(|e| ErasedGameError::Decoding(e.to_string()))?;
+        let mut state = self.decode_versioned_state(state)?;
 
-        let action =
-            T::decode_action(action).map_err(|e| ErasedGameError::Decoding(e.to_string()))?;
+        let action = self.decode_action(&state, action)?;
 
         // Call the typed step method
-        let (obs, reward, done, info) = self.game.step(&mut state, action, &mut self.rng);
+        let result = self.with_rng(|game, rng| game.step(&mut state, action, rng));
+        self.step_info = self.game.step_info(&state);
 
         // Encode the results
-        T::encode_state(&state, out_state).map_err(|e| ErasedGameError::Encoding(e.to_string()))?;
+        self.encode_versioned_state(&state, out_state)?;
+
+        self.encode_obs_cached(out_state, &result.obs, out_obs)?;
+
+        Ok(self.outcome(&state, result))
+    }
+
+    fn step_without_state(
+        &mut self,
+        state: &[u8],
+        action: &[u8],
+        out_obs: &mut Vec<u8>,
+    ) -> Result<StepOutcome, ErasedGameError> {
+        out_obs.clear();
+
+        let mut state = self.decode_versioned_state(state)?;
 
-        T::encode_obs(&obs, out_obs).map_err(|e| ErasedGameError::Encoding(e.to_string()))?;
+        let action = self.decode_action(&state, action)?;
 
-        Ok((reward, done, info))
+        // Advance the game (and RNG) exactly as a full step would, but skip state encoding
+        let result = self.with_rng(|game, rng| game.step(&mut state, action, rng));
+        self.step_info = self.game.step_info(&state);
+
+        self.encode_obs(&result.obs, out_obs)?;
+
+        Ok(self.outcome(&state, result))
     }
 }
 
@@ -160,11 +652,12 @@ mod tests {
     use crate::typed::{ActionSpace, DecodeError, EncodeError, Encoding};
 
     // Test game implementation
-    #[derive(Debug, PartialEq)]
+    #[derive(Debug, Clone, PartialEq)]
     struct TestGame {
         id: String,
         reset_count: u32,
         step_count: u32,
+        continuous_actions: bool,
     }
 
     impl TestGame {
@@ -173,6 +666,7 @@ mod tests {
                 id,
                 reset_count: 0,
                 step_count: 0,
+                continuous_actions: false,
             }
         }
     }
@@ -199,11 +693,28 @@ mod tests {
                     schema_version: 1,
                 },
                 max_horizon: 100,
-                action_space: ActionSpace::Discrete(4),
+                action_space: if self.continuous_actions {
+                    ActionSpace::Continuous {
+                        low: vec![0.0],
+                        high: vec![1.0],
+                        shape: vec![1],
+                    }
+                } else {
+                    ActionSpace::Discrete(4)
+                },
                 preferred_batch: 32,
             }
         }
 
+        fn action_names(&self) -> Option<Vec<String>> {
+            Some(vec!["a".into(), "b".into(), "c".into(), "d".into()])
+        }
+
+        /// Actions that keep the state within the `done` threshold
+        fn legal_actions(&self, state: &Self::State) -> Option<Vec<Self::Action>> {
+            Some((0..4).filter(|&action| *state + action as u32 <= 20).collect())
+        }
+
         fn reset(&mut self, rng: &mut ChaCha20Rng, _hint: &[u8]) -> (Self::State, Self::Obs) {
             self.reset_count += 1;
             self.step_count = 0;
@@ -220,17 +731,17 @@ mod tests {
             state: &mut Self::State,
             action: Self::Action,
             _rng: &mut ChaCha20Rng,
-        ) -> (Self::Obs, f32, bool, u64) {
+        ) -> StepResult<Self::Obs> {
             self.step_count += 1;
             *state += action as u32;
 
-            let obs = vec![*state as f32, self.step_count as f32];
-            let reward = action as f32;
-            let done = *state >= 20 || self.step_count >= 10;
-
-            let info = ((*state as u64) << 32) | self.step_count as u64;
-
-            (obs, reward, done, info)
+            StepResult {
+                obs: vec![*state as f32, self.step_count as f32],
+                reward: action as f32,
+                done: *state >= 20 || self.step_count >= 10,
+                truncated: false,
+                info: ((*state as u64) << 32) | self.step_count as u64,
+            }
         }
 
         fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> {
@@ -264,9 +775,7 @@ mod tests {
         }
 
         fn encode_obs(obs: &Self::Obs, out: &mut Vec<u8>) -> Result<(), EncodeError> {
-            // Encode length first, then values
-            let len = obs.len() as u32;
-            out.extend_from_slice(&len.to_le_bytes());
+            // Values only; tests that need the length use `with_obs_framing`
             for &value in obs {
                 out.extend_from_slice(&value.to_le_bytes());
             }
@@ -292,7 +801,7 @@ mod tests {
     #[test]
     fn test_adapter_reset() {
         let game = TestGame::new("test".to_string());
-        let mut adapter = GameAdapter::new(game);
+        let mut adapter = GameAdapter::new(game).with_obs_framing();
 
         let mut state_buf = Vec::new();
         let mut obs_buf = Vec::new();
@@ -301,14 +810,15 @@ mod tests {
             .reset(42, &[], &mut state_buf, &mut obs_buf)
             .unwrap();
 
-        // State should be encoded as 4 bytes (u32)
-        assert_eq!(state_buf.len(), 4);
-        let state_value = u32::from_le_bytes(state_buf.try_into().unwrap());
+        // State should be encoded as version byte + 4 bytes (u32)
+        assert_eq!(state_buf.len(), 5);
+        assert_eq!(state_buf[0], 1);
+        let state_value = u32::from_le_bytes(state_buf[1..].try_into().unwrap());
 
-        // Obs should be encoded as length + values
+        // Obs should be framed as byte length + values
         assert!(obs_buf.len() >= 4); // At least length header
         let obs_len = u32::from_le_bytes(obs_buf[0..4].try_into().unwrap());
-        assert_eq!(obs_len, 1); // One f32 value
+        assert_eq!(obs_len, 4); // One f32 value
         assert_eq!(obs_buf.len(), 4 + 4); // Length + one f32
 
         // Verify the observation value matches the state
@@ -319,7 +829,7 @@ mod tests {
     #[test]
     fn test_adapter_step() {
         let game = TestGame::new("test".to_string());
-        let mut adapter = GameAdapter::new(game);
+        let mut adapter = GameAdapter::new(game).with_obs_framing();
 
         // Reset first
         let mut state_buf = Vec::new();
@@ -334,7 +844,7 @@ mod tests {
         // Take a step
         let mut new_state_buf = Vec::new();
         let mut new_obs_buf = Vec::new();
-        let (reward, _done, info) = adapter
+        let outcome = adapter
             .step(
                 &state_buf,
                 &action_bytes,
@@ -344,18 +854,20 @@ mod tests {
             .unwrap();
 
         // Verify reward
-        assert_eq!(reward, 3.0);
-        assert!(info > 0);
-
-        // Decode new state
-        let new_state = u32::from_le_bytes(new_state_buf.try_into().unwrap());
-        let old_state = u32::from_le_bytes(state_buf.try_into().unwrap());
+        assert_eq!(outcome.reward, 3.0);
+        assert!(!outcome.done);
+        assert!(!outcome.truncated);
+        assert!(outcome.info > 0);
+
+        // Decode new state (skipping the version byte)
+        let new_state = u32::from_le_bytes(new_state_buf[1..].try_into().unwrap());
+        let old_state = u32::from_le_bytes(state_buf[1..].try_into().unwrap());
         assert_eq!(new_state, old_state + 3);
 
         // Verify obs structure
         assert!(new_obs_buf.len() >= 4);
         let obs_len = u32::from_le_bytes(new_obs_buf[0..4].try_into().unwrap());
-        assert_eq!(obs_len, 2); // Two f32 values (state and step_count)
+        assert_eq!(obs_len, 8); // Two f32 values (state and step_count)
     }
 
     #[test]
@@ -402,6 +914,32 @@ mod tests {
         assert!(state1 != state2 || obs1 != obs2);
     }
 
+    #[test]
+    fn test_env_seed_mixing_decorrelates_envs() {
+        fn initial_state(env_id: &str, mix: bool) -> Vec<u8> {
+            let mut adapter = GameAdapter::new(TestGame::new(env_id.to_string()));
+            if mix {
+                adapter = adapter.with_env_seed_mixing();
+            }
+            let (mut state, mut obs) = (Vec::new(), Vec::new());
+            adapter.reset(42, &[], &mut state, &mut obs).unwrap();
+            state
+        }
+
+        // Without the flag the env_id has no influence on the seed
+        assert_eq!(initial_state("env-a", false), initial_state("env-b", false));
+
+        assert_ne!(initial_state("env-a", true), initial_state("env-b", true));
+        assert_eq!(initial_state("env-a", true), initial_state("env-a", true));
+    }
+
+    #[test]
+    fn test_stable_hash_is_fixed() {
+        // Reference FNV-1a 64 values; these must never change
+        assert_eq!(stable_hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(stable_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
     #[test]
     fn test_adapter_inner_access() {
         let game = TestGame::new("test".to_string());
@@ -475,4 +1013,493 @@ mod tests {
             _ => panic!("Expected Decoding error"),
         }
     }
+
+    #[test]
+    fn test_adapter_rejects_unmigratable_schema_version() {
+        let game = TestGame::new("test".to_string());
+        let mut adapter = GameAdapter::new(game);
+
+        // Version 7 state for a game at schema version 1 with no migration hook
+        let old_state = vec![7, 0, 0, 0, 0];
+        let mut new_state_buf = Vec::new();
+        let mut new_obs_buf = Vec::new();
+
+        let result = adapter.step(&old_state, &[1], &mut new_state_buf, &mut new_obs_buf);
+
+        match result.unwrap_err() {
+            ErasedGameError::Decoding(msg) => assert!(msg.contains("Unsupported version: 7")),
+            _ => panic!("Expected Decoding error"),
+        }
+    }
+
+    #[test]
+    fn test_obs_framing_prefixes_length_and_strips_back() {
+        let mut plain = GameAdapter::new(TestGame::new("test".to_string()));
+        let mut framed = GameAdapter::new(TestGame::new("test".to_string())).with_obs_framing();
+        assert!(!plain.obs_framed());
+        assert!(framed.obs_framed());
+
+        let (mut state, mut plain_obs, mut framed_obs) = (Vec::new(), Vec::new(), Vec::new());
+        plain.reset(42, &[], &mut state, &mut plain_obs).unwrap();
+        framed.reset(42, &[], &mut state, &mut framed_obs).unwrap();
+
+        let mut next_state = Vec::new();
+        plain.step(&state, &[2], &mut next_state, &mut plain_obs).unwrap();
+        framed
+            .step_without_state(&state, &[2], &mut framed_obs)
+            .unwrap();
+
+        assert_eq!(plain_obs.len(), 8);
+        assert_eq!(&framed_obs[..4], &8u32.to_le_bytes());
+        let (payload, rest) = crate::obs_frame::split_frame(&framed_obs).unwrap();
+        assert_eq!(payload, plain_obs.as_slice());
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn test_adapter_reports_unknown_sizes_by_default() {
+        let adapter = GameAdapter::new(TestGame::new("test".to_string()));
+        assert_eq!(adapter.encoded_sizes(), EncodedSizes::default());
+    }
+
+    #[test]
+    fn test_adapter_drops_action_names_for_non_discrete_spaces() {
+        let adapter = GameAdapter::new(TestGame::new("test".to_string()));
+        assert_eq!(adapter.action_names().map(|names| names.len()), Some(4));
+
+        let mut game = TestGame::new("test".to_string());
+        game.continuous_actions = true;
+        assert_eq!(GameAdapter::new(game).action_names(), None);
+    }
+
+    #[test]
+    fn test_legal_action_count_defaults_to_legal_actions_len() {
+        let game = TestGame::new("test".to_string());
+        assert_eq!(game.legal_action_count(&0), Some(4));
+        assert_eq!(game.legal_action_count(&18), Some(3));
+        assert_eq!(game.legal_action_count(&20), Some(1));
+
+        let adapter = GameAdapter::new(TestGame::new("test".to_string()));
+        let mut state = vec![1];
+        TestGame::encode_state(&19, &mut state).unwrap();
+        assert_eq!(adapter.legal_action_count(&state).unwrap(), Some(2));
+        assert!(adapter.legal_action_count(&[1, 0]).is_err());
+        assert_eq!(
+            adapter.legal_actions(&state).unwrap(),
+            Some(vec![vec![0], vec![1]])
+        );
+    }
+
+    #[test]
+    fn test_continuous_game_reports_no_state_space_estimate() {
+        let mut game = TestGame::new("test".to_string());
+        game.continuous_actions = true;
+        assert_eq!(GameAdapter::new(game).state_space_estimate(), None);
+    }
+
+    /// Each player removes stones from their own pile; an empty pile forces a pass
+    #[derive(Clone)]
+    struct PassGame;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Piles {
+        to_move: u8,
+        stones: [u8; 2],
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum PileAction {
+        Take,
+        Pass,
+    }
+
+    impl Game for PassGame {
+        type State = Piles;
+        type Action = PileAction;
+        type Obs = ();
+
+        fn engine_id(&self) -> EngineId {
+            EngineId {
+                env_id: "piles".to_string(),
+                build_id: "0.1.0".to_string(),
+            }
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                id: self.engine_id(),
+                encoding: Encoding {
+                    state: "piles:v1".to_string(),
+                    action: "take:v1".to_string(),
+                    obs: "empty:v1".to_string(),
+                    schema_version: 1,
+                },
+                max_horizon: 8,
+                action_space: ActionSpace::Discrete(1),
+                preferred_batch: 1,
+            }
+        }
+
+        fn current_player(&self, state: &Self::State) -> Option<u32> {
+            Some(state.to_move as u32)
+        }
+
+        fn must_pass(&self, state: &Self::State) -> bool {
+            state.stones[state.to_move as usize - 1] == 0 && state.stones != [0, 0]
+        }
+
+        fn pass_action(&self) -> Option<Self::Action> {
+            Some(PileAction::Pass)
+        }
+
+        fn reset(&mut self, _rng: &mut ChaCha20Rng, _hint: &[u8]) -> (Self::State, Self::Obs) {
+            let piles = Piles {
+                to_move: 1,
+                stones: [1, 3],
+            };
+            (piles, ())
+        }
+
+        fn step(
+            &mut self,
+            state: &mut Self::State,
+            action: Self::Action,
+            _rng: &mut ChaCha20Rng,
+        ) -> StepResult<Self::Obs> {
+            if action == PileAction::Take {
+                state.stones[state.to_move as usize - 1] -= 1;
+            }
+            state.to_move = 3 - state.to_move;
+            StepResult {
+                obs: (),
+                reward: 0.0,
+                done: state.stones == [0, 0],
+                truncated: false,
+                info: 0,
+            }
+        }
+
+        fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+            out.extend_from_slice(&[state.to_move, state.stones[0], state.stones[1]]);
+            Ok(())
+        }
+
+        fn decode_state(buf: &[u8]) -> Result<Self::State, DecodeError> {
+            match buf {
+                &[to_move, first, second] => Ok(Piles {
+                    to_move,
+                    stones: [first, second],
+                }),
+                _ => Err(DecodeError::InvalidLength {
+                    expected: 3,
+                    actual: buf.len(),
+                }),
+            }
+        }
+
+        fn encode_action(action: &Self::Action, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+            match action {
+                PileAction::Take => out.push(0),
+                PileAction::Pass => {}
+            }
+            Ok(())
+        }
+
+        fn decode_action(buf: &[u8]) -> Result<Self::Action, DecodeError> {
+            match buf {
+                [0] => Ok(PileAction::Take),
+                _ => Err(DecodeError::CorruptedData(format!("Invalid action: {:?}", buf))),
+            }
+        }
+
+        fn encode_obs(_obs: &Self::Obs, _out: &mut Vec<u8>) -> Result<(), EncodeError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_adapter_steps_an_empty_action_as_a_pass() {
+        let mut adapter = GameAdapter::new(PassGame);
+        let (mut state, mut obs) = (Vec::new(), Vec::new());
+        let reset = adapter.reset(0, &[], &mut state, &mut obs).unwrap();
+        assert!(!reset.must_pass);
+
+        // Passing is only allowed when the player has no moves
+        let err = adapter.step(&state, &[], &mut Vec::new(), &mut obs).unwrap_err();
+        assert!(matches!(err, ErasedGameError::Decoding(_)));
+
+        // X empties its pile, then O takes; X now has nothing to play
+        let mut next = Vec::new();
+        adapter.step(&state, &[0], &mut next, &mut obs).unwrap();
+        let outcome = adapter.step(&next, &[0], &mut state, &mut obs).unwrap();
+        assert_eq!(outcome.current_player, Some(1));
+        assert!(outcome.must_pass);
+        assert!(!outcome.done);
+
+        let err = adapter.step(&state, &[0], &mut next, &mut obs).unwrap_err();
+        assert!(matches!(err, ErasedGameError::InvalidAction(_)));
+
+        // The pass hands the turn to O without ending the game
+        let outcome = adapter.step(&state, &[], &mut next, &mut obs).unwrap();
+        assert_eq!(outcome.current_player, Some(2));
+        assert!(!outcome.must_pass);
+        assert!(!outcome.done);
+        assert_eq!(PassGame::decode_state(&next[1..]).unwrap().stones, [0, 2]);
+    }
+
+    #[test]
+    fn test_adapter_rejects_empty_state() {
+        let game = TestGame::new("test".to_string());
+        let mut adapter = GameAdapter::new(game);
+
+        let mut new_state_buf = Vec::new();
+        let mut new_obs_buf = Vec::new();
+        let result = adapter.step(&[], &[1], &mut new_state_buf, &mut new_obs_buf);
+
+        assert!(matches!(result, Err(ErasedGameError::Decoding(_))));
+    }
+
+    /// Counter game whose v2 state widened the v1 `u8` count to a little-endian `u16`
+    #[derive(Clone)]
+    struct WideningGame;
+
+    impl Game for WideningGame {
+        type State = u16;
+        type Action = u8;
+        type Obs = ();
+
+        fn engine_id(&self) -> EngineId {
+            EngineId {
+                env_id: "widening".to_string(),
+                build_id: "0.2.0".to_string(),
+            }
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                id: self.engine_id(),
+                encoding: Encoding {
+                    state: "u16_counter:v2".to_string(),
+                    action: "u8:v1".to_string(),
+                    obs: "empty:v1".to_string(),
+                    schema_version: 2,
+                },
+                max_horizon: 1000,
+                action_space: ActionSpace::Discrete(256),
+                preferred_batch: 1,
+            }
+        }
+
+        fn supported_schema_versions(&self) -> Vec<u32> {
+            vec![1, 2]
+        }
+
+        fn reset(&mut self, _rng: &mut ChaCha20Rng, _hint: &[u8]) -> (Self::State, Self::Obs) {
+            (0, ())
+        }
+
+        fn step(
+            &mut self,
+            state: &mut Self::State,
+            action: Self::Action,
+            _rng: &mut ChaCha20Rng,
+        ) -> StepResult<Self::Obs> {
+            *state += action as u16;
+            StepResult {
+                obs: (),
+                reward: 0.0,
+                done: false,
+                truncated: false,
+                info: 0,
+            }
+        }
+
+        fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+            out.extend_from_slice(&state.to_le_bytes());
+            Ok(())
+        }
+
+        fn decode_state(buf: &[u8]) -> Result<Self::State, DecodeError> {
+            let bytes: [u8; 2] = buf.try_into().map_err(|_| DecodeError::InvalidLength {
+                expected: 2,
+                actual: buf.len(),
+            })?;
+            Ok(u16::from_le_bytes(bytes))
+        }
+
+        fn migrate_state(buf: &[u8], from_version: u32) -> Result<Vec<u8>, DecodeError> {
+            match (from_version, buf) {
+                (1, &[count]) => Ok((count as u16).to_le_bytes().to_vec()),
+                (1, _) => Err(DecodeError::InvalidLength {
+                    expected: 1,
+                    actual: buf.len(),
+                }),
+                (version, _) => Err(DecodeError::UnsupportedVersion { version }),
+            }
+        }
+
+        fn encode_action(action: &Self::Action, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+            out.push(*action);
+            Ok(())
+        }
+
+        fn decode_action(buf: &[u8]) -> Result<Self::Action, DecodeError> {
+            match buf {
+                &[action] => Ok(action),
+                _ => Err(DecodeError::InvalidLength {
+                    expected: 1,
+                    actual: buf.len(),
+                }),
+            }
+        }
+
+        fn encode_obs(_obs: &Self::Obs, _out: &mut Vec<u8>) -> Result<(), EncodeError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_adapter_migrates_older_schema_versions_before_stepping() {
+        let mut adapter = GameAdapter::new(WideningGame);
+        assert_eq!(adapter.supported_schema_versions(), vec![1, 2]);
+        let (mut state, mut obs) = (Vec::new(), Vec::new());
+
+        // A v1 state holding 250 steps past the old u8 limit after migration
+        adapter.step(&[1, 250], &[10], &mut state, &mut obs).unwrap();
+        assert_eq!(state, vec![2, 4, 1]);
+
+        // Current states are decoded directly
+        let mut next = Vec::new();
+        adapter.step(&state, &[1], &mut next, &mut obs).unwrap();
+        assert_eq!(next, vec![2, 5, 1]);
+
+        // Malformed v1 payloads and unknown versions are decoding errors
+        let err = adapter.step(&[1, 0, 0], &[1], &mut next, &mut obs).unwrap_err();
+        assert!(matches!(err, ErasedGameError::Decoding(_)));
+        let err = adapter.step(&[3, 0, 0], &[1], &mut next, &mut obs).unwrap_err();
+        assert!(
+            matches!(&err, ErasedGameError::Decoding(msg) if msg.contains("Unsupported version: 3"))
+        );
+    }
+
+    thread_local! {
+        /// Calls to `CountingObsGame::encode_obs` on this test thread
+        static OBS_ENCODES: std::cell::Cell<u32> = const { std::cell::Cell::new(0) };
+    }
+
+    /// Game whose state is the last action and whose `encode_obs` is counted
+    #[derive(Clone)]
+    struct CountingObsGame {
+        pure: bool,
+    }
+
+    impl Game for CountingObsGame {
+        type State = u8;
+        type Action = u8;
+        type Obs = u8;
+
+        fn engine_id(&self) -> EngineId {
+            EngineId {
+                env_id: "counting".to_string(),
+                build_id: "0.1.0".to_string(),
+            }
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                id: self.engine_id(),
+                encoding: Encoding {
+                    state: "u8:v1".to_string(),
+                    action: "u8:v1".to_string(),
+                    obs: "u8:v1".to_string(),
+                    schema_version: 1,
+                },
+                max_horizon: 10,
+                action_space: ActionSpace::Discrete(4),
+                preferred_batch: 1,
+            }
+        }
+
+        fn is_deterministic(&self) -> bool {
+            self.pure
+        }
+
+        fn is_state_complete(&self) -> bool {
+            self.pure
+        }
+
+        fn reset(&mut self, _rng: &mut ChaCha20Rng, _hint: &[u8]) -> (Self::State, Self::Obs) {
+            (0, 0)
+        }
+
+        fn step(
+            &mut self,
+            state: &mut Self::State,
+            action: Self::Action,
+            _rng: &mut ChaCha20Rng,
+        ) -> StepResult<Self::Obs> {
+            *state = action;
+            StepResult {
+                obs: action,
+                reward: 0.0,
+                done: false,
+                truncated: false,
+                info: 0,
+            }
+        }
+
+        fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+            out.push(*state);
+            Ok(())
+        }
+
+        fn decode_state(buf: &[u8]) -> Result<Self::State, DecodeError> {
+            Ok(buf[0])
+        }
+
+        fn encode_action(action: &Self::Action, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+            out.push(*action);
+            Ok(())
+        }
+
+        fn decode_action(buf: &[u8]) -> Result<Self::Action, DecodeError> {
+            Ok(buf[0])
+        }
+
+        fn encode_obs(obs: &Self::Obs, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+            OBS_ENCODES.with(|count| count.set(count.get() + 1));
+            out.push(*obs);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_obs_cache_serves_repeated_states_without_encoding() {
+        let mut adapter = GameAdapter::new(CountingObsGame { pure: true }).with_obs_cache(8);
+        let (mut state, mut obs) = (Vec::new(), Vec::new());
+        let (mut next_state, mut next_obs) = (Vec::new(), Vec::new());
+
+        adapter.reset(1, &[], &mut state, &mut obs).unwrap();
+        adapter.reset(2, &[], &mut state, &mut obs).unwrap();
+        assert_eq!(OBS_ENCODES.with(|count| count.get()), 1);
+
+        adapter.step(&state, &[3], &mut next_state, &mut next_obs).unwrap();
+        adapter.step(&state, &[3], &mut next_state, &mut next_obs).unwrap();
+        assert_eq!(OBS_ENCODES.with(|count| count.get()), 2);
+        assert_eq!(next_obs, vec![3]);
+
+        let cache = adapter.obs_cache().unwrap();
+        assert_eq!((cache.hits(), cache.misses(), cache.len()), (2, 2, 2));
+    }
+
+    #[test]
+    fn test_obs_cache_stays_off_for_impure_games() {
+        let mut adapter = GameAdapter::new(CountingObsGame { pure: false }).with_obs_cache(8);
+        assert!(adapter.obs_cache().is_none());
+
+        let (mut state, mut obs) = (Vec::new(), Vec::new());
+        adapter.reset(1, &[], &mut state, &mut obs).unwrap();
+        adapter.reset(1, &[], &mut state, &mut obs).unwrap();
+        assert_eq!(OBS_ENCODES.with(|count| count.get()), 2);
+    }
 }
@@ -0,0 +1,69 @@
+//! Comparing two builds of a game on the same inputs
+//!
+//! Before replacing a game's logic, `compare_builds` plays a test suite's
+//! seeds and actions through the current build and the candidate in lockstep
+//! and reports the first step and output field where they disagree. The
+//! registry keys games by env_id alone, so the two builds are registered
+//! under separate env_ids and compared as instances:
+//!
+//! ```rust,ignore
+//! let mut baseline = registry.create_game("tictactoe").unwrap();
+//! let mut candidate = registry.create_game("tictactoe-next").unwrap();
+//! let comparison = compare_builds(&mut *baseline, &mut *candidate, 42, &[], &actions)?;
+//! assert_eq!(comparison, BuildComparison::Identical);
+//! ```
+
+use crate::determinism::{play_lockstep, Mismatch};
+use crate::erased::{ErasedGame, ErasedGameError};
+
+/// Result of running two builds on the same seed and actions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildComparison {
+    /// Every output of every call matched
+    Identical,
+    /// The builds first disagreed in `field` at `step`
+    ///
+    /// `step` indexes the actions, with `None` meaning the reset. A field of
+    /// `rng_draws` means both builds audit their RNG and consumed different
+    /// amounts of it.
+    Diverged {
+        step: Option<usize>,
+        field: &'static str,
+    },
+}
+
+/// Why two builds could not be compared
+#[derive(Debug, thiserror::Error)]
+#[error("Build comparison failed at {at}: {source}")]
+pub struct BuildCompareError {
+    pub at: String,
+    #[source]
+    pub source: ErasedGameError,
+}
+
+/// Play `actions` from a reset with `seed` on both builds and report where
+/// their outputs first differ
+///
+/// Play stops early when an action ends the episode in the baseline.
+///
+/// # Errors
+///
+/// Returns `BuildCompareError` if either build rejects a call before the
+/// outputs have diverged
+pub fn compare_builds(
+    baseline: &mut dyn ErasedGame,
+    candidate: &mut dyn ErasedGame,
+    seed: u64,
+    hint: &[u8],
+    actions: &[Vec<u8>],
+) -> Result<BuildComparison, BuildCompareError> {
+    match play_lockstep(baseline, candidate, seed, hint, actions) {
+        Ok(()) => Ok(BuildComparison::Identical),
+        Err(Mismatch::Field { step, field, .. }) => Ok(BuildComparison::Diverged { step, field }),
+        Err(Mismatch::RngDraws { step, .. }) => Ok(BuildComparison::Diverged {
+            step,
+            field: "rng_draws",
+        }),
+        Err(Mismatch::Game { at, source }) => Err(BuildCompareError { at, source }),
+    }
+}
@@ -0,0 +1,392 @@
+//! Detecting entropy that bypasses the seeded RNG
+//!
+//! Games receive a seeded `ChaCha20Rng`, but nothing stops one from calling
+//! `thread_rng` or reading the clock, which silently breaks reproducibility.
+//! `verify_determinism` plays the same seed and actions on two instances in
+//! lockstep and compares every output. With RNG auditing enabled on both
+//! (`GameAdapter::with_rng_audit`) it also compares how many RNG words each
+//! call consumed, so a game whose use of the seeded stream depends on hidden
+//! entropy is caught even before its outputs differ.
+//!
+//! ```rust,ignore
+//! let mut first = GameAdapter::new(MyGame::new()).with_rng_audit();
+//! let mut second = GameAdapter::new(MyGame::new()).with_rng_audit();
+//! verify_determinism(&mut first, &mut second, 42, &[], &actions)?;
+//! ```
+
+use crate::erased::{ErasedGame, ErasedGameError};
+use crate::golden::step_label;
+
+/// Why two runs of the same seed and actions did not agree
+#[derive(Debug, thiserror::Error)]
+pub enum DeterminismError {
+    #[error("Determinism check failed at {at}: {source}")]
+    Game {
+        at: String,
+        #[source]
+        source: ErasedGameError,
+    },
+    #[error("Runs consumed different RNG draws at {at} ({first} vs {second}), so the game reads entropy outside its seeded RNG")]
+    RngDraws { at: String, first: u64, second: u64 },
+    #[error("Runs diverged at {at} in {field} despite identical inputs and RNG draws, so the game reads entropy outside its seeded RNG")]
+    Diverged { at: String, field: &'static str },
+}
+
+/// First disagreement between two lockstep runs
+///
+/// `step` indexes the actions, with `None` meaning the reset.
+pub(crate) enum Mismatch {
+    /// Either game rejected a call
+    Game { at: String, source: ErasedGameError },
+    /// The runs consumed different amounts of the seeded stream
+    RngDraws {
+        at: String,
+        step: Option<usize>,
+        first: u64,
+        second: u64,
+    },
+    /// An output differs
+    Field {
+        at: String,
+        step: Option<usize>,
+        field: &'static str,
+    },
+}
+
+impl From<Mismatch> for DeterminismError {
+    fn from(mismatch: Mismatch) -> Self {
+        match mismatch {
+            Mismatch::Game { at, source } => DeterminismError::Game { at, source },
+            Mismatch::RngDraws {
+                at, first, second, ..
+            } => DeterminismError::RngDraws { at, first, second },
+            Mismatch::Field { at, field, .. } => DeterminismError::Diverged { at, field },
+        }
+    }
+}
+
+/// One call's results from both runs
+struct Compared<'a> {
+    at: &'a str,
+    step: Option<usize>,
+    first: &'a dyn ErasedGame,
+    second: &'a dyn ErasedGame,
+}
+
+impl Compared<'_> {
+    /// Compare RNG draws first, since a draw mismatch explains any divergence
+    fn draws(&self) -> Result<(), Mismatch> {
+        match (self.first.last_rng_draws(), self.second.last_rng_draws()) {
+            (Some(first), Some(second)) if first != second => Err(Mismatch::RngDraws {
+                at: self.at.to_string(),
+                step: self.step,
+                first,
+                second,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn field<T: PartialEq>(
+        &self,
+        field: &'static str,
+        first: T,
+        second: T,
+    ) -> Result<(), Mismatch> {
+        if first == second {
+            return Ok(());
+        }
+        Err(Mismatch::Field {
+            at: self.at.to_string(),
+            step: self.step,
+            field,
+        })
+    }
+}
+
+/// Play `actions` from a reset with `seed` on both games and require
+/// identical RNG draws and outputs at every call
+///
+/// `first` and `second` should be fresh instances of the same game. Play
+/// stops early when an action ends the episode. RNG draws are only compared
+/// when both games audit them.
+///
+/// # Errors
+///
+/// Returns the first disagreement: `DeterminismError::RngDraws` if the runs
+/// consumed different amounts of the seeded stream, `Diverged` if their
+/// outputs differ anyway, or `Game` if either game rejects a call
+pub fn verify_determinism(
+    first: &mut dyn ErasedGame,
+    second: &mut dyn ErasedGame,
+    seed: u64,
+    hint: &[u8],
+    actions: &[Vec<u8>],
+) -> Result<(), DeterminismError> {
+    play_lockstep(first, second, seed, hint, actions).map_err(DeterminismError::from)
+}
+
+/// Play the same seed and actions on both games, stopping at the first
+/// mismatch or when an action ends the episode
+pub(crate) fn play_lockstep(
+    first: &mut dyn ErasedGame,
+    second: &mut dyn ErasedGame,
+    seed: u64,
+    hint: &[u8],
+    actions: &[Vec<u8>],
+) -> Result<(), Mismatch> {
+    let mut states = [Vec::new(), Vec::new()];
+    let mut obs = [Vec::new(), Vec::new()];
+    for (game, (state, obs)) in [&mut *first, &mut *second]
+        .into_iter()
+        .zip(states.iter_mut().zip(&mut obs))
+    {
+        game.reset(seed, hint, state, obs)
+            .map_err(|source| Mismatch::Game {
+                at: "reset".to_string(),
+                source,
+            })?;
+    }
+    let reset = Compared {
+        at: "reset",
+        step: None,
+        first: &*first,
+        second: &*second,
+    };
+    reset.draws()?;
+    reset.field("state", &states[0], &states[1])?;
+    reset.field("obs", &obs[0], &obs[1])?;
+
+    for (i, action) in actions.iter().enumerate() {
+        let at = step_label(i, action);
+        let mut outcomes = Vec::with_capacity(2);
+        for (j, game) in [&mut *first, &mut *second].into_iter().enumerate() {
+            let mut next_state = Vec::new();
+            let outcome = game
+                .step(&states[j], action, &mut next_state, &mut obs[j])
+                .map_err(|source| Mismatch::Game {
+                    at: at.clone(),
+                    source,
+                })?;
+            states[j] = next_state;
+            outcomes.push(outcome);
+        }
+
+        let step = Compared {
+            at: &at,
+            step: Some(i),
+            first: &*first,
+            second: &*second,
+        };
+        step.draws()?;
+        step.field("state", &states[0], &states[1])?;
+        step.field("obs", &obs[0], &obs[1])?;
+        step.field("reward", outcomes[0].reward, outcomes[1].reward)?;
+        step.field("done", outcomes[0].done, outcomes[1].done)?;
+        step.field("truncated", outcomes[0].truncated, outcomes[1].truncated)?;
+        step.field("info", outcomes[0].info, outcomes[1].info)?;
+
+        if outcomes[0].done {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::GameAdapter;
+    use crate::typed::{
+        ActionSpace, Capabilities, DecodeError, EncodeError, Encoding, EngineId, Game, StepResult,
+    };
+    use rand::Rng;
+    use rand_chacha::ChaCha20Rng;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Source of "hidden" entropy shared by every instance, like a global RNG
+    static HIDDEN: AtomicU32 = AtomicU32::new(0);
+
+    /// Random walk whose step size comes from the seeded RNG, optionally
+    /// contaminated by `HIDDEN`
+    ///
+    /// Samples with `gen::<u32>()` rather than `gen_range`, whose rejection
+    /// sampling would make the number of words drawn vary with the seed.
+    #[derive(Clone)]
+    struct Walk {
+        /// Add the hidden counter to the position
+        leak_into_state: bool,
+        /// Draw an extra sample on odd hidden counter values
+        leak_into_draws: bool,
+    }
+
+    impl Walk {
+        fn honest() -> Self {
+            Self {
+                leak_into_state: false,
+                leak_into_draws: false,
+            }
+        }
+
+        fn hidden() -> u32 {
+            HIDDEN.fetch_add(1, Ordering::SeqCst)
+        }
+    }
+
+    impl Game for Walk {
+        type State = i64;
+        type Action = u8;
+        type Obs = i64;
+
+        fn engine_id(&self) -> EngineId {
+            EngineId {
+                env_id: "walk".to_string(),
+                build_id: "0.1.0".to_string(),
+            }
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                id: self.engine_id(),
+                encoding: Encoding {
+                    state: "i64:v1".to_string(),
+                    action: "u8:v1".to_string(),
+                    obs: "i64:v1".to_string(),
+                    schema_version: 1,
+                },
+                max_horizon: 10,
+                action_space: ActionSpace::Discrete(2),
+                preferred_batch: 1,
+            }
+        }
+
+        fn reset(&mut self, rng: &mut ChaCha20Rng, _hint: &[u8]) -> (Self::State, Self::Obs) {
+            let start = (rng.gen::<u32>() % 11) as i64 - 5;
+            (start, start)
+        }
+
+        fn step(
+            &mut self,
+            state: &mut Self::State,
+            action: Self::Action,
+            rng: &mut ChaCha20Rng,
+        ) -> StepResult<Self::Obs> {
+            let size = (rng.gen::<u32>() % 3) as i64 + 1;
+            *state += if action == 0 { -size } else { size };
+            if self.leak_into_state {
+                *state += Self::hidden() as i64;
+            }
+            if self.leak_into_draws && Self::hidden() % 2 == 1 {
+                rng.gen::<u32>();
+            }
+            StepResult {
+                obs: *state,
+                reward: 0.0,
+                done: false,
+                truncated: false,
+                info: 0,
+            }
+        }
+
+        fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+            out.extend_from_slice(&state.to_le_bytes());
+            Ok(())
+        }
+
+        fn decode_state(buf: &[u8]) -> Result<Self::State, DecodeError> {
+            let bytes = buf.try_into().map_err(|_| DecodeError::InvalidLength {
+                expected: 8,
+                actual: buf.len(),
+            })?;
+            Ok(i64::from_le_bytes(bytes))
+        }
+
+        fn encode_action(action: &Self::Action, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+            out.push(*action);
+            Ok(())
+        }
+
+        fn decode_action(buf: &[u8]) -> Result<Self::Action, DecodeError> {
+            match buf {
+                [action] => Ok(*action),
+                _ => Err(DecodeError::InvalidLength {
+                    expected: 1,
+                    actual: buf.len(),
+                }),
+            }
+        }
+
+        fn encode_obs(obs: &Self::Obs, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+            out.extend_from_slice(&obs.to_le_bytes());
+            Ok(())
+        }
+    }
+
+    fn audited(game: Walk) -> GameAdapter<Walk> {
+        GameAdapter::new(game).with_rng_audit()
+    }
+
+    fn actions() -> Vec<Vec<u8>> {
+        vec![vec![0], vec![1], vec![1], vec![0]]
+    }
+
+    #[test]
+    fn test_stochastic_game_draws_the_same_count_every_step() {
+        let mut game = audited(Walk::honest());
+        assert_eq!(game.last_rng_draws(), Some(0));
+        assert_eq!(GameAdapter::new(Walk::honest()).last_rng_draws(), None);
+
+        let (mut state, mut obs) = (Vec::new(), Vec::new());
+        game.reset(7, &[], &mut state, &mut obs).unwrap();
+        assert_eq!(game.last_rng_draws(), Some(1));
+        for action in actions() {
+            let mut next_state = Vec::new();
+            game.step(&state, &action, &mut next_state, &mut obs)
+                .unwrap();
+            assert_eq!(game.last_rng_draws(), Some(1));
+            state = next_state;
+        }
+
+        verify_determinism(&mut audited(Walk::honest()), &mut game, 7, &[], &actions()).unwrap();
+    }
+
+    #[test]
+    fn test_hidden_entropy_is_reported() {
+        let leaky_state = Walk {
+            leak_into_state: true,
+            leak_into_draws: false,
+        };
+        let err = verify_determinism(
+            &mut audited(leaky_state.clone()),
+            &mut audited(leaky_state),
+            7,
+            &[],
+            &actions(),
+        )
+        .unwrap_err();
+        assert!(
+            matches!(&err, DeterminismError::Diverged { at, field: "state" } if at == "step 1 (action 00)"),
+            "{}",
+            err
+        );
+
+        // Consecutive hidden values differ in parity, so exactly one run draws extra
+        let leaky_draws = Walk {
+            leak_into_state: false,
+            leak_into_draws: true,
+        };
+        let err = verify_determinism(
+            &mut audited(leaky_draws.clone()),
+            &mut audited(leaky_draws),
+            7,
+            &[],
+            &actions(),
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, DeterminismError::RngDraws { first, second, .. } if first.abs_diff(second) == 1),
+            "{}",
+            err
+        );
+    }
+}
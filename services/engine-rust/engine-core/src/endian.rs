@@ -0,0 +1,120 @@
+//! Byte order of encoded numeric values
+//!
+//! Games encode their state and observations little-endian, and that remains
+//! the default everywhere. Consumers that need big-endian floats can have the
+//! `GameAdapter` emit them with `GameAdapter::with_obs_endianness`, or a game
+//! can write its own fields through the helpers here. A big-endian encoding is
+//! advertised by a `be` suffix on its name: `f32x29:v1` becomes `f32x29be:v1`.
+
+use crate::erased::ErasedGameError;
+
+/// Width of every value the byte swap operates on (`f32`/`u32`)
+const WORD_SIZE: usize = std::mem::size_of::<u32>();
+
+/// Byte order of encoded `f32`/`u32` values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endianness {
+    /// Least significant byte first; the encoding every game uses by default
+    #[default]
+    Little,
+    /// Most significant byte first
+    Big,
+}
+
+impl Endianness {
+    /// Append `value` in this byte order
+    pub fn put_f32(self, value: f32, out: &mut Vec<u8>) {
+        match self {
+            Endianness::Little => out.extend_from_slice(&value.to_le_bytes()),
+            Endianness::Big => out.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+
+    /// Append `value` in this byte order
+    pub fn put_u32(self, value: u32, out: &mut Vec<u8>) {
+        match self {
+            Endianness::Little => out.extend_from_slice(&value.to_le_bytes()),
+            Endianness::Big => out.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+
+    /// Name of `encoding` when its values are written in this byte order
+    ///
+    /// Little-endian names are returned unchanged. Big-endian names gain a
+    /// `be` suffix before the version tag, so `f32x29:v1` becomes
+    /// `f32x29be:v1` and an unversioned `f32x29` becomes `f32x29be`.
+    pub fn encoding_name(self, encoding: &str) -> String {
+        match self {
+            Endianness::Little => encoding.to_string(),
+            Endianness::Big => match encoding.rsplit_once(':') {
+                Some((name, version)) => format!("{}be:{}", name, version),
+                None => format!("{}be", encoding),
+            },
+        }
+    }
+
+    /// Convert packed little-endian 32-bit values in `buf` to this byte order
+    ///
+    /// A no-op for `Little`, so the default path leaves bytes untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ErasedGameError::Encoding` if `buf` is not a whole number of
+    /// 32-bit values and the values would need swapping.
+    pub fn convert_le_words(self, buf: &mut [u8]) -> Result<(), ErasedGameError> {
+        if self == Endianness::Little {
+            return Ok(());
+        }
+        if !buf.chunks_exact(WORD_SIZE).remainder().is_empty() {
+            return Err(ErasedGameError::Encoding(format!(
+                "Cannot byte-swap {} bytes: not a whole number of {}-byte values",
+                buf.len(),
+                WORD_SIZE
+            )));
+        }
+        for word in buf.chunks_exact_mut(WORD_SIZE) {
+            word.reverse();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_helpers_write_the_requested_byte_order() {
+        let mut little = Vec::new();
+        let mut big = Vec::new();
+        Endianness::Little.put_f32(1.5, &mut little);
+        Endianness::Big.put_f32(1.5, &mut big);
+        assert_eq!(little, 1.5f32.to_le_bytes());
+        assert_eq!(big, 1.5f32.to_be_bytes());
+
+        Endianness::default().put_u32(7, &mut little);
+        assert_eq!(&little[4..], &7u32.to_le_bytes());
+
+        let mut swapped = little.clone();
+        Endianness::Big.convert_le_words(&mut swapped).unwrap();
+        assert_eq!(&swapped[..4], &1.5f32.to_be_bytes());
+        assert_eq!(&swapped[4..], &7u32.to_be_bytes());
+    }
+
+    #[test]
+    fn test_encoding_name_marks_big_endian_only() {
+        assert_eq!(Endianness::Little.encoding_name("f32x29:v1"), "f32x29:v1");
+        assert_eq!(Endianness::Big.encoding_name("f32x29:v1"), "f32x29be:v1");
+        assert_eq!(Endianness::Big.encoding_name("f32x29"), "f32x29be");
+    }
+
+    #[test]
+    fn test_swap_rejects_partial_values() {
+        let mut buf = [1, 2, 3];
+        assert!(Endianness::Little.convert_le_words(&mut buf).is_ok());
+        assert!(matches!(
+            Endianness::Big.convert_le_words(&mut buf),
+            Err(ErasedGameError::Encoding(_))
+        ));
+    }
+}
@@ -4,7 +4,9 @@
 //! without generics. All typed games are converted to this interface via the
 //! adapter layer.
 
-use crate::typed::{Capabilities, EngineId};
+use crate::typed::{
+    Capabilities, EngineId, InfoPayload, InitialStateKind, ObsLayout, ObsQuantization,
+};
 
 /// Runtime error for erased game operations
 #[derive(Debug, thiserror::Error)]
@@ -21,6 +23,119 @@ pub enum ErasedGameError {
     GameLogic(String),
 }
 
+impl ErasedGameError {
+    /// Prefix the error message with where it happened, keeping the variant
+    pub fn with_context(self, context: &GameErrorContext) -> Self {
+        match self {
+            Self::Encoding(msg) => Self::Encoding(format!("{}: {}", context, msg)),
+            Self::Decoding(msg) => Self::Decoding(format!("{}: {}", context, msg)),
+            Self::InvalidState(msg) => Self::InvalidState(format!("{}: {}", context, msg)),
+            Self::InvalidAction(msg) => Self::InvalidAction(format!("{}: {}", context, msg)),
+            Self::GameLogic(msg) => Self::GameLogic(format!("{}: {}", context, msg)),
+        }
+    }
+}
+
+/// Triage context attached to erased game errors
+///
+/// Renders as `env=tictactoe, action_len=3`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameErrorContext {
+    pub env_id: String,
+    /// Name and byte length of the input being processed, if any
+    pub input_len: Option<(&'static str, usize)>,
+}
+
+impl GameErrorContext {
+    pub fn new(env_id: impl Into<String>) -> Self {
+        Self {
+            env_id: env_id.into(),
+            input_len: None,
+        }
+    }
+
+    /// Record which input (e.g. `"state"` or `"action"`) was being processed
+    pub fn with_input(mut self, name: &'static str, len: usize) -> Self {
+        self.input_len = Some((name, len));
+        self
+    }
+}
+
+impl std::fmt::Display for GameErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "env={}", self.env_id)?;
+        if let Some((name, len)) = self.input_len {
+            write!(f, ", {}_len={}", name, len)?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of an erased step
+///
+/// The encoded state and observation are written to the caller's buffers;
+/// this carries the remaining scalar results.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepOutcome {
+    /// Reward received from this step
+    pub reward: f32,
+    /// Whether the episode reached a terminal state
+    pub done: bool,
+    /// Whether the episode was cut short rather than terminated
+    pub truncated: bool,
+    /// Whether the observation is of a terminal state (`done && !truncated`),
+    /// so learners must not bootstrap a value from it
+    pub terminal_obs: bool,
+    /// Additional packed info bits for auxiliary signals
+    pub info: InfoPayload,
+    /// Player to move after this step, for multi-player games
+    pub current_player: Option<u32>,
+    /// Whether the next player has no legal moves and must send an empty (pass) action
+    pub must_pass: bool,
+}
+
+/// Outcome of an erased reset
+///
+/// The encoded state and observation are written to the caller's buffers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResetOutcome {
+    /// Player to move first, for multi-player games
+    pub current_player: Option<u32>,
+    /// Whether the first player has no legal moves and must pass
+    pub must_pass: bool,
+}
+
+/// Where `step_auto_reset` starts the next episode when a step ends one
+pub struct AutoReset<'a> {
+    /// Seed for the new episode's reset
+    pub seed: u64,
+    /// Hint passed to the new episode's reset
+    pub hint: &'a [u8],
+    /// Buffer for the new episode's encoded initial state
+    pub out_state: &'a mut Vec<u8>,
+    /// Buffer for the new episode's encoded initial observation
+    pub out_obs: &'a mut Vec<u8>,
+}
+
+/// Outcome of `step_auto_reset`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoResetOutcome {
+    /// Result of the step itself, including the terminal reward and flags
+    pub step: StepOutcome,
+    /// Set when the step ended the episode and the game was reset
+    pub reset: Option<ResetOutcome>,
+}
+
+/// Encoded byte sizes reported by a game
+///
+/// Each field is `None` when the corresponding encoding has a variable size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EncodedSizes {
+    pub state: Option<usize>,
+    pub obs: Option<usize>,
+    pub action: Option<usize>,
+}
+
 /// Erased game trait that works only with bytes
 ///
 /// This trait provides a runtime interface for games without generics,
@@ -48,9 +163,9 @@ pub enum ErasedGameError {
 ///     
 ///     // Take a step (would need valid action bytes)
 ///     let action_bytes = vec![0]; // Placeholder
-///     let (reward, done, info) = game.step(&state_buf, &action_bytes, &mut state_buf, &mut obs_buf)?;
+///     let outcome = game.step(&state_buf, &action_bytes, &mut state_buf, &mut obs_buf)?;
 ///
-///     println!("Reward: {}, Done: {}, Info: {}", reward, done, info);
+///     println!("Reward: {}, Done: {}, Info: {}", outcome.reward, outcome.done, outcome.info);
 ///     Ok(())
 /// }
 /// ```
@@ -61,6 +176,98 @@ pub trait ErasedGame: Send + Sync + 'static {
     /// Get game capabilities and configuration
     fn capabilities(&self) -> Capabilities;
 
+    /// Report the encoded state, observation, and action sizes in bytes
+    ///
+    /// Lets clients size buffers ahead of time. The default reports every size
+    /// as unknown.
+    fn encoded_sizes(&self) -> EncodedSizes {
+        EncodedSizes::default()
+    }
+
+    /// Describe how reset initial states are distributed
+    fn initial_state_kind(&self) -> InitialStateKind {
+        InitialStateKind::default()
+    }
+
+    /// Names for each discrete action index, or `None` if unavailable
+    fn action_names(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Upper bound on the number of distinct states, or `None` if unbounded
+    fn state_space_estimate(&self) -> Option<u64> {
+        None
+    }
+
+    /// How to dequantize the observation, or `None` if it is not quantized
+    fn obs_quantization(&self) -> Option<ObsQuantization> {
+        None
+    }
+
+    /// Named segments of the observation, or `None` if undeclared
+    fn obs_layout(&self) -> Option<ObsLayout> {
+        None
+    }
+
+    /// Schema versions whose encoded state is accepted, ascending
+    ///
+    /// Always includes the current `schema_version`, which is all the
+    /// default reports.
+    fn supported_schema_versions(&self) -> Vec<u32> {
+        vec![self.capabilities().encoding.schema_version]
+    }
+
+    /// Whether step results depend only on `(state, action)` and not the RNG
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+
+    /// Whether the encoded state captures all data `step` depends on
+    fn is_state_complete(&self) -> bool {
+        false
+    }
+
+    /// Number of legal actions in the encoded `state`, or `None` if unknown
+    ///
+    /// # Errors
+    ///
+    /// Returns `ErasedGameError` if the state cannot be decoded
+    fn legal_action_count(&self, _state: &[u8]) -> Result<Option<u32>, ErasedGameError> {
+        Ok(None)
+    }
+
+    /// Legal actions in the encoded `state`, each encoded as `step` expects,
+    /// or `None` if the game does not enumerate them
+    ///
+    /// # Errors
+    ///
+    /// Returns `ErasedGameError` if the state cannot be decoded or an action
+    /// cannot be encoded
+    fn legal_actions(&self, _state: &[u8]) -> Result<Option<Vec<Vec<u8>>>, ErasedGameError> {
+        Ok(None)
+    }
+
+    /// RNG words the most recent reset or step consumed
+    ///
+    /// `None` unless the game audits its RNG (see
+    /// `GameAdapter::with_rng_audit`).
+    fn last_rng_draws(&self) -> Option<u64> {
+        None
+    }
+
+    /// Named diagnostics the game reported for the most recent step
+    ///
+    /// Empty unless the game implements `Game::step_info`.
+    fn last_step_info(&self) -> &[(String, f32)] {
+        &[]
+    }
+
+    /// Whether each encoded observation starts with its byte length as a
+    /// little-endian `u32` (see `obs_frame`)
+    fn obs_framed(&self) -> bool {
+        false
+    }
+
     /// Reset the game to initial state
     ///
     /// # Arguments
@@ -79,7 +286,59 @@ pub trait ErasedGame: Send + Sync + 'static {
         hint: &[u8],
         out_state: &mut Vec<u8>,
         out_obs: &mut Vec<u8>,
-    ) -> Result<(), ErasedGameError>;
+    ) -> Result<ResetOutcome, ErasedGameError>;
+
+    /// Reset the game without encoding the initial state
+    ///
+    /// Used by observation-only clients. The default implementation delegates
+    /// to `reset` and discards the encoded state; implementations can override
+    /// it to skip state encoding entirely.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ErasedGameError` if reset fails or encoding fails
+    fn reset_without_state(
+        &mut self,
+        seed: u64,
+        hint: &[u8],
+        out_obs: &mut Vec<u8>,
+    ) -> Result<ResetOutcome, ErasedGameError> {
+        let mut discarded_state = Vec::new();
+        self.reset(seed, hint, &mut discarded_state, out_obs)
+    }
+
+    /// Re-seed the game's RNG without resetting it
+    ///
+    /// Later steps draw from the new stream; the game state, which lives in
+    /// the encoded buffers passed to `step`, is untouched. The default does
+    /// nothing, which is correct for games that never consult the RNG.
+    fn reseed(&mut self, _seed: u64) {}
+
+    /// Perform one step drawing from an RNG seeded with `seed` instead of the
+    /// game's own stream
+    ///
+    /// The game's stream is left where it was, so later steps draw exactly as
+    /// if this one had not happened, and the same `seed`, state, and action
+    /// always produce the same step. The default ignores `seed` and steps
+    /// normally, which is correct for games that never consult the RNG.
+    fn step_seeded(
+        &mut self,
+        _seed: u64,
+        state: &[u8],
+        action: &[u8],
+        out_state: &mut Vec<u8>,
+        out_obs: &mut Vec<u8>,
+    ) -> Result<StepOutcome, ErasedGameError> {
+        self.step(state, action, out_state, out_obs)
+    }
+
+    /// Create a logically fresh instance of this game
+    ///
+    /// The fork is equivalent to a newly created instance: the same game and
+    /// configuration, an RNG of its own that has not been seeded yet, and no
+    /// caches carried over. Steps take `&mut self`, so parallel workers each
+    /// need their own instance.
+    fn fork(&self) -> Box<dyn ErasedGame>;
 
     /// Perform one simulation step
     ///
@@ -92,10 +351,7 @@ pub trait ErasedGame: Send + Sync + 'static {
     ///
     /// # Returns
     ///
-    /// Returns `Ok((reward, done, info))` on success, where:
-    /// - `reward` - Reward received from this step
-    /// - `done` - Whether the episode has terminated
-    /// - `info` - Additional packed info bits for auxiliary signals
+    /// Returns the `StepOutcome` (reward, done, truncated, info) on success
     ///
     /// # Errors
     ///
@@ -106,7 +362,55 @@ pub trait ErasedGame: Send + Sync + 'static {
         action: &[u8],
         out_state: &mut Vec<u8>,
         out_obs: &mut Vec<u8>,
-    ) -> Result<(f32, bool, u64), ErasedGameError>;
+    ) -> Result<StepOutcome, ErasedGameError>;
+
+    /// Perform one simulation step without encoding the resulting state
+    ///
+    /// Used when the client keeps its own copy of the state and only needs the
+    /// observation and reward. The default implementation delegates to `step`
+    /// and discards the encoded state; implementations can override it to skip
+    /// state encoding entirely.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ErasedGameError` if step fails or encoding/decoding fails
+    fn step_without_state(
+        &mut self,
+        state: &[u8],
+        action: &[u8],
+        out_obs: &mut Vec<u8>,
+    ) -> Result<StepOutcome, ErasedGameError> {
+        let mut discarded_state = Vec::new();
+        self.step(state, action, &mut discarded_state, out_obs)
+    }
+
+    /// Step, then reset immediately if the episode ended
+    ///
+    /// For vectorized environments that auto-reset. The step's state,
+    /// observation, reward, and flags are reported exactly as `step` would;
+    /// when it is `done` or `truncated` the game is reset into the buffers in
+    /// `reset`, which are left empty otherwise. The default implementation
+    /// calls `step` followed by `reset`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ErasedGameError` if the step or the reset fails
+    fn step_auto_reset(
+        &mut self,
+        state: &[u8],
+        action: &[u8],
+        out_state: &mut Vec<u8>,
+        out_obs: &mut Vec<u8>,
+        reset: AutoReset<'_>,
+    ) -> Result<AutoResetOutcome, ErasedGameError> {
+        let step = self.step(state, action, out_state, out_obs)?;
+        let reset = if step.done || step.truncated {
+            Some(self.reset(reset.seed, reset.hint, reset.out_state, reset.out_obs)?)
+        } else {
+            None
+        };
+        Ok(AutoResetOutcome { step, reset })
+    }
 }
 
 #[cfg(test)]
@@ -154,7 +458,7 @@ mod tests {
             _hint: &[u8],
             out_state: &mut Vec<u8>,
             out_obs: &mut Vec<u8>,
-        ) -> Result<(), ErasedGameError> {
+        ) -> Result<ResetOutcome, ErasedGameError> {
             self.step_count = 0;
 
             // Encode state as u32 (step count)
@@ -163,7 +467,11 @@ mod tests {
             // Encode observation as f32
             out_obs.extend_from_slice(&(self.step_count as f32).to_le_bytes());
 
-            Ok(())
+            Ok(ResetOutcome::default())
+        }
+
+        fn fork(&self) -> Box<dyn ErasedGame> {
+            Box::new(MockErasedGame::new())
         }
 
         fn step(
@@ -172,7 +480,7 @@ mod tests {
             _action: &[u8],
             out_state: &mut Vec<u8>,
             out_obs: &mut Vec<u8>,
-        ) -> Result<(f32, bool, u64), ErasedGameError> {
+        ) -> Result<StepOutcome, ErasedGameError> {
             // Decode current state
             if state.len() != 4 {
                 return Err(ErasedGameError::InvalidState(format!(
@@ -190,12 +498,15 @@ mod tests {
             // Encode new observation
             out_obs.extend_from_slice(&(new_step as f32).to_le_bytes());
 
-            let reward = 1.0;
-            let done = new_step >= 5;
-
-            let info = new_step as u64;
-
-            Ok((reward, done, info))
+            Ok(StepOutcome {
+                reward: 1.0,
+                done: new_step >= 5,
+                truncated: false,
+                terminal_obs: new_step >= 5,
+                info: new_step as u64,
+                current_player: None,
+                must_pass: false,
+            })
         }
     }
 
@@ -228,7 +539,7 @@ mod tests {
         let mut new_state_buf = Vec::new();
         let mut new_obs_buf = Vec::new();
 
-        let (reward, done, info) = game
+        let outcome = game
             .step(
                 &state_buf,
                 &action_bytes,
@@ -237,9 +548,10 @@ mod tests {
             )
             .unwrap();
 
-        assert_eq!(reward, 1.0);
-        assert!(!done);
-        assert_eq!(info, 1);
+        assert_eq!(outcome.reward, 1.0);
+        assert!(!outcome.done);
+        assert!(!outcome.truncated);
+        assert_eq!(outcome.info, 1);
         assert_eq!(new_state_buf.len(), 4);
         assert_eq!(new_obs_buf.len(), 4);
 
@@ -247,6 +559,42 @@ mod tests {
         assert_eq!(new_state, 1);
     }
 
+    #[test]
+    fn test_step_auto_reset_resets_only_when_the_episode_ends() {
+        let mut game = MockErasedGame::new();
+        let mut reset_state = Vec::new();
+        let mut reset_obs = Vec::new();
+        let mut state = 3u32.to_le_bytes().to_vec();
+
+        for expect_reset in [false, true] {
+            let mut next_state = Vec::new();
+            let mut obs = Vec::new();
+            let outcome = game
+                .step_auto_reset(
+                    &state,
+                    &[0],
+                    &mut next_state,
+                    &mut obs,
+                    AutoReset {
+                        seed: 7,
+                        hint: &[],
+                        out_state: &mut reset_state,
+                        out_obs: &mut reset_obs,
+                    },
+                )
+                .unwrap();
+
+            assert_eq!(outcome.step.done, expect_reset);
+            assert_eq!(outcome.reset.is_some(), expect_reset);
+            assert_eq!(reset_state.is_empty(), !expect_reset);
+            state = next_state;
+        }
+
+        // The terminal step's own state is still reported, separate from the reset
+        assert_eq!(u32::from_le_bytes(state.try_into().unwrap()), 5);
+        assert_eq!(reset_state, 0u32.to_le_bytes());
+    }
+
     #[test]
     fn test_erased_game_capabilities() {
         let game = MockErasedGame::new();
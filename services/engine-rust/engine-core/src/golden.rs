@@ -0,0 +1,534 @@
+//! Golden-trajectory regression tests
+//!
+//! A golden trajectory records a seed, a sequence of actions, and the hash of
+//! every state and observation the game produced for them, along with the
+//! rewards and termination flags. Replaying it through the erased interface
+//! catches any change to game logic or encoding, and the error names the
+//! first step where the game diverged.
+//!
+//! Trajectories are stored as text so diffs stay reviewable:
+//!
+//! ```text
+//! # Lines starting with '#' are comments
+//! env_id tictactoe
+//! seed 42
+//! hint
+//! reset state=9f0c3e1d2b4a5968 obs=0a1b2c3d4e5f6071
+//! step action=04 state=... obs=... reward=0 done=false truncated=false
+//! ```
+//!
+//! Hashes are 64-bit FNV-1a over the encoded bytes; actions and the hint are
+//! hex. To regenerate a golden after an intentional change, print
+//! `GoldenTrajectory::record(..)` and overwrite the file.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::adapter::stable_hash;
+use crate::erased::{ErasedGame, ErasedGameError};
+
+/// Expected state and observation hashes after reset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GoldenReset {
+    pub state_hash: u64,
+    pub obs_hash: u64,
+}
+
+/// One recorded step: the action taken and what the game returned for it
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoldenStep {
+    pub action: Vec<u8>,
+    pub state_hash: u64,
+    pub obs_hash: u64,
+    pub reward: f32,
+    pub done: bool,
+    pub truncated: bool,
+}
+
+/// A recorded trajectory that a game must reproduce exactly
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoldenTrajectory {
+    pub env_id: String,
+    pub seed: u64,
+    pub hint: Vec<u8>,
+    pub reset: GoldenReset,
+    pub steps: Vec<GoldenStep>,
+}
+
+/// Why a golden trajectory failed to load or replay
+#[derive(Debug, thiserror::Error)]
+pub enum GoldenError {
+    #[error("Malformed golden trajectory at line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error("Golden trajectory is for {expected}, but the game is {actual}")]
+    WrongGame { expected: String, actual: String },
+    #[error("Golden trajectory failed at {at}: {source}")]
+    Game {
+        at: String,
+        #[source]
+        source: ErasedGameError,
+    },
+    #[error("Golden trajectory diverged at {at}: {field} expected {expected}, got {actual}")]
+    Mismatch {
+        /// `reset` or `step N (action XX)`, with steps counted from 1
+        at: String,
+        field: &'static str,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl GoldenTrajectory {
+    /// Play `actions` from a seeded reset and record what the game returns
+    ///
+    /// # Errors
+    ///
+    /// Returns `GoldenError::Game` if the game rejects the reset or an action
+    pub fn record(
+        game: &mut dyn ErasedGame,
+        seed: u64,
+        hint: &[u8],
+        actions: &[Vec<u8>],
+    ) -> Result<Self, GoldenError> {
+        match Self::play(game, seed, hint, actions)? {
+            (golden, None) => Ok(golden),
+            (_, Some(err)) => Err(err),
+        }
+    }
+
+    /// Replay the trajectory on `game` and check every step against it
+    ///
+    /// # Errors
+    ///
+    /// Returns the first divergence: a `GoldenError::Mismatch` naming the step
+    /// and field, or `GoldenError::Game` if the game rejects a recorded action
+    pub fn verify(&self, game: &mut dyn ErasedGame) -> Result<(), GoldenError> {
+        let actual_env = game.engine_id().env_id;
+        if actual_env != self.env_id {
+            return Err(GoldenError::WrongGame {
+                expected: self.env_id.clone(),
+                actual: actual_env,
+            });
+        }
+
+        let actions: Vec<Vec<u8>> = self.steps.iter().map(|s| s.action.clone()).collect();
+        let (actual, failure) = Self::play(game, self.seed, &self.hint, &actions)?;
+
+        check(
+            "reset",
+            "state_hash",
+            Hash(self.reset.state_hash),
+            Hash(actual.reset.state_hash),
+        )?;
+        check(
+            "reset",
+            "obs_hash",
+            Hash(self.reset.obs_hash),
+            Hash(actual.reset.obs_hash),
+        )?;
+        for (i, (expected, actual)) in self.steps.iter().zip(&actual.steps).enumerate() {
+            let at = step_label(i, &expected.action);
+            check(
+                &at,
+                "state_hash",
+                Hash(expected.state_hash),
+                Hash(actual.state_hash),
+            )?;
+            check(
+                &at,
+                "obs_hash",
+                Hash(expected.obs_hash),
+                Hash(actual.obs_hash),
+            )?;
+            check(&at, "reward", expected.reward, actual.reward)?;
+            check(&at, "done", expected.done, actual.done)?;
+            check(&at, "truncated", expected.truncated, actual.truncated)?;
+        }
+
+        match failure {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Replay on `game`, panicking with the first divergence
+    #[track_caller]
+    pub fn assert_replays(&self, game: &mut dyn ErasedGame) {
+        if let Err(err) = self.verify(game) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Record the steps the game accepts
+    ///
+    /// A rejected action ends the trajectory early and is returned alongside
+    /// it, so `verify` can still compare the steps reached before it.
+    fn play(
+        game: &mut dyn ErasedGame,
+        seed: u64,
+        hint: &[u8],
+        actions: &[Vec<u8>],
+    ) -> Result<(Self, Option<GoldenError>), GoldenError> {
+        let mut state = Vec::new();
+        let mut obs = Vec::new();
+        game.reset(seed, hint, &mut state, &mut obs)
+            .map_err(|source| GoldenError::Game {
+                at: "reset".to_string(),
+                source,
+            })?;
+        let mut golden = Self {
+            env_id: game.engine_id().env_id,
+            seed,
+            hint: hint.to_vec(),
+            reset: GoldenReset {
+                state_hash: stable_hash(&state),
+                obs_hash: stable_hash(&obs),
+            },
+            steps: Vec::with_capacity(actions.len()),
+        };
+
+        for (i, action) in actions.iter().enumerate() {
+            let mut next_state = Vec::new();
+            obs.clear();
+            let outcome = match game.step(&state, action, &mut next_state, &mut obs) {
+                Ok(outcome) => outcome,
+                Err(source) => {
+                    let at = step_label(i, action);
+                    return Ok((golden, Some(GoldenError::Game { at, source })));
+                }
+            };
+            golden.steps.push(GoldenStep {
+                action: action.clone(),
+                state_hash: stable_hash(&next_state),
+                obs_hash: stable_hash(&obs),
+                reward: outcome.reward,
+                done: outcome.done,
+                truncated: outcome.truncated,
+            });
+            state = next_state;
+        }
+
+        Ok((golden, None))
+    }
+}
+
+/// Hash rendered in the same hex form the golden file uses
+#[derive(PartialEq)]
+struct Hash(u64);
+
+impl fmt::Debug for Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+pub(crate) fn step_label(index: usize, action: &[u8]) -> String {
+    format!("step {} (action {})", index + 1, to_hex(action))
+}
+
+fn check<T: PartialEq + fmt::Debug>(
+    at: &str,
+    field: &'static str,
+    expected: T,
+    actual: T,
+) -> Result<(), GoldenError> {
+    if expected == actual {
+        return Ok(());
+    }
+    Err(GoldenError::Mismatch {
+        at: at.to_string(),
+        field,
+        expected: format!("{:?}", expected),
+        actual: format!("{:?}", actual),
+    })
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(text: &str) -> Result<Vec<u8>, String> {
+    text.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .filter(|digits| digits.len() == 2)
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| format!("invalid hex {:?}", text))
+        })
+        .collect()
+}
+
+impl fmt::Display for GoldenTrajectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "env_id {}", self.env_id)?;
+        writeln!(f, "seed {}", self.seed)?;
+        if self.hint.is_empty() {
+            writeln!(f, "hint")?;
+        } else {
+            writeln!(f, "hint {}", to_hex(&self.hint))?;
+        }
+        writeln!(
+            f,
+            "reset state={:016x} obs={:016x}",
+            self.reset.state_hash, self.reset.obs_hash
+        )?;
+        for step in &self.steps {
+            writeln!(
+                f,
+                "step action={} state={:016x} obs={:016x} reward={} done={} truncated={}",
+                to_hex(&step.action),
+                step.state_hash,
+                step.obs_hash,
+                step.reward,
+                step.done,
+                step.truncated
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Key/value fields of one `reset` or `step` line
+struct Fields<'a> {
+    line: usize,
+    pairs: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Fields<'a> {
+    fn parse(line: usize, rest: &'a str) -> Result<Self, GoldenError> {
+        let pairs = rest
+            .split_whitespace()
+            .map(|pair| {
+                pair.split_once('=').ok_or_else(|| GoldenError::Parse {
+                    line,
+                    message: format!("expected key=value, got {:?}", pair),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { line, pairs })
+    }
+
+    fn get<T: FromStr>(&self, key: &str) -> Result<T, GoldenError> {
+        self.raw(key)?.parse().map_err(|_| self.invalid(key))
+    }
+
+    fn hash(&self, key: &str) -> Result<u64, GoldenError> {
+        u64::from_str_radix(self.raw(key)?, 16).map_err(|_| self.invalid(key))
+    }
+
+    fn raw(&self, key: &str) -> Result<&'a str, GoldenError> {
+        self.pairs
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v)
+            .ok_or_else(|| GoldenError::Parse {
+                line: self.line,
+                message: format!("missing {}", key),
+            })
+    }
+
+    fn invalid(&self, key: &str) -> GoldenError {
+        GoldenError::Parse {
+            line: self.line,
+            message: format!("invalid {}", key),
+        }
+    }
+}
+
+impl FromStr for GoldenTrajectory {
+    type Err = GoldenError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut env_id = None;
+        let mut seed = None;
+        let mut hint = Vec::new();
+        let mut reset = None;
+        let mut steps = Vec::new();
+
+        for (index, raw) in text.lines().enumerate() {
+            let line = index + 1;
+            let raw = raw.trim();
+            if raw.is_empty() || raw.starts_with('#') {
+                continue;
+            }
+            let (keyword, rest) = raw.split_once(' ').unwrap_or((raw, ""));
+            let parse_error = |message: String| GoldenError::Parse { line, message };
+            match keyword {
+                "env_id" => env_id = Some(rest.trim().to_string()),
+                "seed" => {
+                    seed = Some(
+                        rest.trim()
+                            .parse()
+                            .map_err(|_| parse_error(format!("invalid seed {:?}", rest)))?,
+                    )
+                }
+                "hint" => hint = from_hex(rest.trim()).map_err(parse_error)?,
+                "reset" => {
+                    let fields = Fields::parse(line, rest)?;
+                    reset = Some(GoldenReset {
+                        state_hash: fields.hash("state")?,
+                        obs_hash: fields.hash("obs")?,
+                    });
+                }
+                "step" => {
+                    let fields = Fields::parse(line, rest)?;
+                    steps.push(GoldenStep {
+                        action: from_hex(fields.raw("action")?).map_err(parse_error)?,
+                        state_hash: fields.hash("state")?,
+                        obs_hash: fields.hash("obs")?,
+                        reward: fields.get("reward")?,
+                        done: fields.get("done")?,
+                        truncated: fields.get("truncated")?,
+                    });
+                }
+                other => return Err(parse_error(format!("unknown keyword {:?}", other))),
+            }
+        }
+
+        let missing = |what: &str| GoldenError::Parse {
+            line: 0,
+            message: format!("missing {} line", what),
+        };
+        Ok(Self {
+            env_id: env_id.ok_or_else(|| missing("env_id"))?,
+            seed: seed.ok_or_else(|| missing("seed"))?,
+            hint,
+            reset: reset.ok_or_else(|| missing("reset"))?,
+            steps,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::erased::{ResetOutcome, StepOutcome};
+    use crate::typed::{ActionSpace, Capabilities, Encoding, EngineId};
+
+    /// Counter that adds each action byte; reaching `target` ends the episode
+    struct Counter {
+        target: u8,
+    }
+
+    impl ErasedGame for Counter {
+        fn engine_id(&self) -> EngineId {
+            EngineId {
+                env_id: "counter".to_string(),
+                build_id: "0.1.0".to_string(),
+            }
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                id: self.engine_id(),
+                encoding: Encoding {
+                    state: "u8:v1".to_string(),
+                    action: "u8:v1".to_string(),
+                    obs: "u8:v1".to_string(),
+                    schema_version: 1,
+                },
+                max_horizon: 10,
+                action_space: ActionSpace::Discrete(3),
+                preferred_batch: 1,
+            }
+        }
+
+        fn reset(
+            &mut self,
+            seed: u64,
+            _hint: &[u8],
+            out_state: &mut Vec<u8>,
+            out_obs: &mut Vec<u8>,
+        ) -> Result<ResetOutcome, ErasedGameError> {
+            out_state.push(seed as u8);
+            out_obs.push(seed as u8);
+            Ok(ResetOutcome::default())
+        }
+
+        fn fork(&self) -> Box<dyn ErasedGame> {
+            Box::new(Counter {
+                target: self.target,
+            })
+        }
+
+        fn step(
+            &mut self,
+            state: &[u8],
+            action: &[u8],
+            out_state: &mut Vec<u8>,
+            out_obs: &mut Vec<u8>,
+        ) -> Result<StepOutcome, ErasedGameError> {
+            let [delta] = action else {
+                return Err(ErasedGameError::InvalidAction(format!("{:?}", action)));
+            };
+            let total = state[0].wrapping_add(*delta);
+            out_state.push(total);
+            out_obs.push(total);
+            Ok(StepOutcome {
+                reward: total as f32,
+                done: total >= self.target,
+                truncated: false,
+                terminal_obs: total >= self.target,
+                info: 0,
+                current_player: None,
+                must_pass: false,
+            })
+        }
+    }
+
+    fn actions() -> Vec<Vec<u8>> {
+        vec![vec![1], vec![2], vec![2]]
+    }
+
+    #[test]
+    fn test_golden_roundtrips_through_text_and_replays() {
+        let mut game = Counter { target: 5 };
+        let golden = GoldenTrajectory::record(&mut game, 0, &[7], &actions()).unwrap();
+        assert!(golden.steps.last().unwrap().done);
+
+        let parsed: GoldenTrajectory = golden.to_string().parse().unwrap();
+        assert_eq!(parsed, golden);
+        parsed.verify(&mut game).unwrap();
+    }
+
+    #[test]
+    fn test_golden_reports_first_mismatched_step() {
+        let golden =
+            GoldenTrajectory::record(&mut Counter { target: 5 }, 0, &[], &actions()).unwrap();
+
+        // The same moves no longer finish the game on step 3
+        let err = golden.verify(&mut Counter { target: 6 }).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Golden trajectory diverged at step 3 (action 02): done expected true, got false"
+        );
+    }
+
+    #[test]
+    fn test_golden_compares_steps_before_a_rejected_action() {
+        let mut golden =
+            GoldenTrajectory::record(&mut Counter { target: 5 }, 0, &[], &actions()).unwrap();
+        golden.steps[0].reward = 9.0;
+        golden.steps[1].action = vec![2, 2];
+
+        let err = golden.verify(&mut Counter { target: 5 }).unwrap_err();
+        assert!(
+            matches!(&err, GoldenError::Mismatch { at, field: "reward", .. } if at.starts_with("step 1 ")),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_golden_parse_errors_name_the_line() {
+        let err = "env_id counter\nseed 0\nreset state=zz obs=00\n"
+            .parse::<GoldenTrajectory>()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Malformed golden trajectory at line 3: invalid state"
+        );
+    }
+}
@@ -5,14 +5,46 @@
 //! - `ErasedGame`: Runtime interface that works only with bytes
 //! - `GameAdapter`: Automatic conversion from typed to erased interface
 //! - `Registry`: Static registration system for games
+//! - `endian`: Byte order helpers and the big-endian obs encoding option
+//! - `obs_cache`: Bounded memoization of expensive observation encodes
+//! - `obs_stack`: Frame stacking wrapper for any typed game
+//! - `action_history`: Wrapper appending the recent actions to each observation
+//! - `step_number`: Wrapper appending the normalized step number to each observation
+//! - `obs_mask`: Wrapper zeroing selected observation values for partial observability
+//! - `obs_quantize`: Wrapper quantizing observations to one byte per value
+//! - `golden`: Recorded trajectories that replay to catch logic regressions
+//! - `determinism`: Lockstep runs that expose entropy outside the seeded RNG
+//! - `build_compare`: Lockstep runs that check a new game build against the old
+//! - `action_bounds`: Generic range checks for discrete action indices
+//! - `varint`: LEB128 discrete action encoding for large action spaces
+//! - `serde_state`: Bincode state codec for prototypes (`serde-state` feature)
 
 pub mod typed;
 pub mod erased;
 pub mod adapter;
 pub mod registry;
+pub mod obs_normalize;
+pub mod obs_frame;
+pub mod obs_cache;
+pub mod endian;
+pub mod obs_stack;
+pub mod action_history;
+pub mod step_number;
+pub mod obs_mask;
+pub mod obs_quantize;
+pub mod overrides;
+pub mod golden;
+pub mod determinism;
+pub mod build_compare;
+pub mod varint;
+pub mod action_bounds;
+#[cfg(test)]
+mod test_game;
+#[cfg(feature = "serde-state")]
+pub mod serde_state;
 
 // Re-export main types for convenience
 pub use typed::Game;
 pub use erased::ErasedGame;
 pub use adapter::GameAdapter;
-pub use registry::{register_game, create_game, GameFactory};
\ No newline at end of file
+pub use registry::{register_game, create_game, GameFactory, Registry};
\ No newline at end of file
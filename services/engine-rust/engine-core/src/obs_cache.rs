@@ -0,0 +1,130 @@
+//! Optional memoization of encoded observations
+//!
+//! Games with an expensive `encode_obs` (rendering, feature extraction) can
+//! have the `GameAdapter` remember the encoded observation of recently seen
+//! states. Entries are keyed by a hash of the encoded state, but the full
+//! state bytes are kept and compared on lookup, so a hash collision is a
+//! miss rather than a wrong observation. The cache holds at most `capacity`
+//! entries and evicts the oldest insertion first.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::adapter::stable_hash;
+
+/// Bounded map from encoded state to encoded observation
+#[derive(Debug, Clone)]
+pub struct ObsCache {
+    capacity: usize,
+    /// State hash -> (encoded state, encoded observation)
+    entries: HashMap<u64, (Vec<u8>, Vec<u8>)>,
+    /// Hashes in insertion order, oldest first
+    order: VecDeque<u64>,
+    hits: u64,
+    misses: u64,
+}
+
+impl ObsCache {
+    /// Cache the observations of up to `capacity` states
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "ObsCache needs room for at least one entry");
+        Self {
+            capacity,
+            entries: HashMap::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Encoded observation previously stored for exactly `state`
+    pub fn get(&mut self, state: &[u8]) -> Option<&[u8]> {
+        match self.entries.get(&stable_hash(state)) {
+            Some((cached_state, obs)) if cached_state == state => {
+                self.hits += 1;
+                Some(obs)
+            }
+            _ => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Remember `obs` as the encoded observation of `state`
+    ///
+    /// Evicts the oldest entry when the cache is full. A different state
+    /// whose hash collides with `state` is replaced.
+    pub fn insert(&mut self, state: &[u8], obs: &[u8]) {
+        let key = stable_hash(state);
+        let entry = (state.to_vec(), obs.to_vec());
+        if self.entries.insert(key, entry).is_some() {
+            return;
+        }
+
+        self.order.push_back(key);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// Number of cached observations
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Maximum number of cached observations
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Lookups served from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Lookups that had to encode
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_serves_only_the_exact_state() {
+        let mut cache = ObsCache::new(4);
+        assert!(cache.get(b"state-a").is_none());
+
+        cache.insert(b"state-a", b"obs-a");
+        assert_eq!(cache.get(b"state-a"), Some(&b"obs-a"[..]));
+        assert!(cache.get(b"state-b").is_none());
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+    }
+
+    #[test]
+    fn test_cache_evicts_oldest_entry_when_full() {
+        let mut cache = ObsCache::new(2);
+        cache.insert(b"s1", b"o1");
+        cache.insert(b"s2", b"o2");
+        cache.insert(b"s1", b"o1");
+        cache.insert(b"s3", b"o3");
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(b"s1").is_none());
+        assert_eq!(cache.get(b"s2"), Some(&b"o2"[..]));
+        assert_eq!(cache.get(b"s3"), Some(&b"o3"[..]));
+    }
+}
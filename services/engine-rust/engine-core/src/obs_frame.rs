@@ -0,0 +1,100 @@
+//! Optional length-prefixed observation framing
+//!
+//! Observations are raw bytes whose length is implied by the game's
+//! capabilities. Clients that read observations as a stream can ask the
+//! `GameAdapter` to prefix each one with its byte length (a little-endian
+//! `u32`), so consecutive observations are self-delimiting. Games opt in with
+//! `GameAdapter::with_obs_framing`, and the capabilities report whether
+//! framing is on.
+
+use crate::erased::ErasedGameError;
+
+/// Bytes taken by the length prefix of a framed observation
+pub const FRAME_HEADER_LEN: usize = std::mem::size_of::<u32>();
+
+/// Write the length prefix for the payload that follows `start`
+///
+/// `out[start..start + FRAME_HEADER_LEN]` must be a placeholder reserved
+/// before the payload was appended.
+pub(crate) fn finish_frame(out: &mut [u8], start: usize) -> Result<(), ErasedGameError> {
+    let payload_len = out.len() - start - FRAME_HEADER_LEN;
+    let len = u32::try_from(payload_len).map_err(|_| {
+        ErasedGameError::Encoding(format!(
+            "Observation of {} bytes is too long to frame",
+            payload_len
+        ))
+    })?;
+    out[start..start + FRAME_HEADER_LEN].copy_from_slice(&len.to_le_bytes());
+    Ok(())
+}
+
+/// Split the first framed observation off `buf`
+///
+/// Returns the observation payload and the bytes after it, which hold the
+/// next frame when reading a stream.
+///
+/// # Errors
+///
+/// Returns `ErasedGameError::Decoding` if `buf` is shorter than the header or
+/// the length it declares
+pub fn split_frame(buf: &[u8]) -> Result<(&[u8], &[u8]), ErasedGameError> {
+    if buf.len() < FRAME_HEADER_LEN {
+        return Err(ErasedGameError::Decoding(format!(
+            "Framed observation needs a {}-byte length prefix, got {} bytes",
+            FRAME_HEADER_LEN,
+            buf.len()
+        )));
+    }
+
+    let (header, rest) = buf.split_at(FRAME_HEADER_LEN);
+    let len = u32::from_le_bytes(header.try_into().unwrap()) as usize;
+    if rest.len() < len {
+        return Err(ErasedGameError::Decoding(format!(
+            "Framed observation declares {} bytes but only {} follow",
+            len,
+            rest.len()
+        )));
+    }
+    Ok(rest.split_at(len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(payload: &[u8], out: &mut Vec<u8>) {
+        let start = out.len();
+        out.extend_from_slice(&[0; FRAME_HEADER_LEN]);
+        out.extend_from_slice(payload);
+        finish_frame(out, start).unwrap();
+    }
+
+    #[test]
+    fn test_frames_split_back_out_of_a_stream() {
+        let mut stream = Vec::new();
+        frame(&[1, 2, 3], &mut stream);
+        frame(&[], &mut stream);
+        frame(&[9; 5], &mut stream);
+        assert_eq!(&stream[..4], &3u32.to_le_bytes());
+
+        let (first, rest) = split_frame(&stream).unwrap();
+        let (second, rest) = split_frame(rest).unwrap();
+        let (third, rest) = split_frame(rest).unwrap();
+        assert_eq!(first, &[1, 2, 3]);
+        assert!(second.is_empty());
+        assert_eq!(third, &[9; 5]);
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn test_split_frame_rejects_short_buffers() {
+        assert!(matches!(
+            split_frame(&[1, 0]),
+            Err(ErasedGameError::Decoding(_))
+        ));
+        assert!(matches!(
+            split_frame(&[4, 0, 0, 0, 1, 2]),
+            Err(ErasedGameError::Decoding(_))
+        ));
+    }
+}
@@ -0,0 +1,263 @@
+//! Partial observability by masking observation values
+//!
+//! `ObsMask<G>` wraps a game whose observation is packed little-endian `f32`
+//! values and zeroes the values selected by a fixed boolean mask, one entry
+//! per value. The observation keeps its length and layout, so policies built
+//! for the full observation run unchanged while seeing less of the game. The
+//! wrapped game and its state are untouched: only the emitted observation
+//! changes.
+//!
+//! ```rust,ignore
+//! // Hide TicTacToe's current-player indicator (the last two values)
+//! register_game("tictactoe-masked".to_string(), || {
+//!     let mask = (0..29).map(|i| i >= 27).collect();
+//!     Box::new(GameAdapter::new(ObsMask::new(TicTacToe::new(), mask).unwrap()))
+//! });
+//! ```
+
+use rand_chacha::ChaCha20Rng;
+
+use crate::erased::ErasedGameError;
+use crate::typed::{
+    Capabilities, DecodeError, EncodeError, EngineId, Game, InitialStateKind, ObsBounds, ObsLayout,
+    StepResult,
+};
+
+const F32_SIZE: usize = std::mem::size_of::<f32>();
+
+/// Game wrapper whose observation has the masked values zeroed
+#[derive(Clone)]
+pub struct ObsMask<G: Game> {
+    inner: G,
+    /// `true` for every observation value that is zeroed
+    mask: Vec<bool>,
+}
+
+impl<G: Game> ObsMask<G> {
+    /// Zero the observation values of `inner` where `mask` is `true`
+    ///
+    /// # Errors
+    ///
+    /// Returns `ErasedGameError::Encoding` if the game declares a fixed
+    /// observation size or observation bounds and `mask` does not have one
+    /// entry per `f32` value. Games declaring neither are checked on every
+    /// encode instead.
+    pub fn new(inner: G, mask: Vec<bool>) -> Result<Self, ErasedGameError> {
+        let declared = G::OBS_ENCODED_SIZE
+            .map(|size| size / F32_SIZE)
+            .or_else(|| inner.obs_bounds().map(|bounds| bounds.low.len()));
+        if let Some(values) = declared {
+            if mask.len() != values {
+                return Err(ErasedGameError::Encoding(format!(
+                    "Observation mask has {} entries but the observation has {} values",
+                    mask.len(),
+                    values
+                )));
+            }
+        }
+        Ok(Self { inner, mask })
+    }
+
+    /// The wrapped game
+    pub fn inner(&self) -> &G {
+        &self.inner
+    }
+
+    /// `true` for every observation value that is zeroed
+    pub fn mask(&self) -> &[bool] {
+        &self.mask
+    }
+
+    /// Encoded inner observation with the masked values zeroed
+    fn masked_obs(&self, obs: &G::Obs) -> Vec<u8> {
+        let mut out = Vec::with_capacity(G::OBS_ENCODED_SIZE.unwrap_or(0));
+        // Observations are encoded eagerly so the mask can be applied
+        G::encode_obs(obs, &mut out)
+            .unwrap_or_else(|e| panic!("ObsMask: wrapped game failed to encode obs: {}", e));
+        assert_eq!(
+            out.len(),
+            self.mask.len() * F32_SIZE,
+            "ObsMask: mask has {} entries but the observation is {} bytes",
+            self.mask.len(),
+            out.len()
+        );
+        for (value, _) in out
+            .chunks_exact_mut(F32_SIZE)
+            .zip(&self.mask)
+            .filter(|(_, &masked)| masked)
+        {
+            value.copy_from_slice(&0.0f32.to_le_bytes());
+        }
+        out
+    }
+}
+
+/// The wrapped state, unchanged
+impl<G: Game> Game for ObsMask<G> {
+    type State = G::State;
+    type Action = G::Action;
+    /// Encoded wrapped observation with the masked values zeroed
+    type Obs = Vec<u8>;
+
+    const STATE_ENCODED_SIZE: Option<usize> = G::STATE_ENCODED_SIZE;
+    const OBS_ENCODED_SIZE: Option<usize> = G::OBS_ENCODED_SIZE;
+    const ACTION_ENCODED_SIZE: Option<usize> = G::ACTION_ENCODED_SIZE;
+
+    fn engine_id(&self) -> EngineId {
+        self.inner.engine_id()
+    }
+
+    /// The wrapped capabilities with the mask declared in the obs encoding
+    ///
+    /// `masked(f32x29:v1)` names a `f32x29:v1` observation in which some
+    /// values always read zero; the layout is otherwise the same.
+    fn capabilities(&self) -> Capabilities {
+        let mut caps = self.inner.capabilities();
+        caps.encoding.obs = format!("masked({})", caps.encoding.obs);
+        caps
+    }
+
+    fn initial_state_kind(&self) -> InitialStateKind {
+        self.inner.initial_state_kind()
+    }
+
+    fn action_names(&self) -> Option<Vec<String>> {
+        self.inner.action_names()
+    }
+
+    fn obs_bounds(&self) -> Option<ObsBounds> {
+        self.inner.obs_bounds()
+    }
+
+    fn obs_layout(&self) -> Option<ObsLayout> {
+        self.inner.obs_layout()
+    }
+
+    fn state_space_estimate(&self) -> Option<u64> {
+        self.inner.state_space_estimate()
+    }
+
+    fn supported_schema_versions(&self) -> Vec<u32> {
+        self.inner.supported_schema_versions()
+    }
+
+    fn is_deterministic(&self) -> bool {
+        self.inner.is_deterministic()
+    }
+
+    fn is_state_complete(&self) -> bool {
+        self.inner.is_state_complete()
+    }
+
+    fn current_player(&self, state: &Self::State) -> Option<u32> {
+        self.inner.current_player(state)
+    }
+
+    fn must_pass(&self, state: &Self::State) -> bool {
+        self.inner.must_pass(state)
+    }
+
+    fn legal_actions(&self, state: &Self::State) -> Option<Vec<Self::Action>> {
+        self.inner.legal_actions(state)
+    }
+
+    fn legal_action_count(&self, state: &Self::State) -> Option<u32> {
+        self.inner.legal_action_count(state)
+    }
+
+    fn pass_action(&self) -> Option<Self::Action> {
+        self.inner.pass_action()
+    }
+
+    fn step_info(&self, state: &Self::State) -> Vec<(String, f32)> {
+        self.inner.step_info(state)
+    }
+
+    fn reset(&mut self, rng: &mut ChaCha20Rng, hint: &[u8]) -> (Self::State, Self::Obs) {
+        let (state, obs) = self.inner.reset(rng, hint);
+        let obs = self.masked_obs(&obs);
+        (state, obs)
+    }
+
+    fn step(
+        &mut self,
+        state: &mut Self::State,
+        action: Self::Action,
+        rng: &mut ChaCha20Rng,
+    ) -> StepResult<Self::Obs> {
+        let result = self.inner.step(state, action, rng);
+        StepResult {
+            obs: self.masked_obs(&result.obs),
+            reward: result.reward,
+            done: result.done,
+            truncated: result.truncated,
+            info: result.info,
+        }
+    }
+
+    fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        G::encode_state(state, out)
+    }
+
+    fn decode_state(buf: &[u8]) -> Result<Self::State, DecodeError> {
+        G::decode_state(buf)
+    }
+
+    fn migrate_state(buf: &[u8], from_version: u32) -> Result<Vec<u8>, DecodeError> {
+        G::migrate_state(buf, from_version)
+    }
+
+    fn encode_action(action: &Self::Action, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        G::encode_action(action, out)
+    }
+
+    fn decode_action(buf: &[u8]) -> Result<Self::Action, DecodeError> {
+        G::decode_action(buf)
+    }
+
+    fn encode_obs(obs: &Self::Obs, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        out.extend_from_slice(obs);
+        Ok(())
+    }
+
+    fn warm_up() {
+        G::warm_up()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_game::ValuesGame;
+    use rand::SeedableRng;
+
+    fn decode(buf: &[u8]) -> Vec<f32> {
+        buf.chunks_exact(F32_SIZE)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_mask_length_must_match_the_observation() {
+        let game = ValuesGame::new([1.0; 3]);
+        assert!(matches!(
+            ObsMask::new(game.clone(), vec![true; 2]),
+            Err(ErasedGameError::Encoding(_))
+        ));
+        assert!(ObsMask::new(game, vec![true; 3]).is_ok());
+    }
+
+    #[test]
+    fn test_masked_values_read_zero_on_every_step() {
+        let mut game =
+            ObsMask::new(ValuesGame::new([1.0, 2.0, 3.0]), vec![false, true, false]).unwrap();
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+
+        let (mut state, obs) = game.reset(&mut rng, &[]);
+        assert_eq!(decode(&obs), vec![1.0, 0.0, 3.0]);
+        let result = game.step(&mut state, Some(1), &mut rng);
+        assert_eq!(decode(&result.obs), vec![1.0, 0.0, 3.0]);
+        // The state keeps the hidden value
+        assert_eq!(state, [1.0, 3.0, 3.0]);
+    }
+}
@@ -0,0 +1,139 @@
+//! Optional observation normalization stage
+//!
+//! Games whose observations fall outside `[0, 1]` can declare per-dimension
+//! bounds via `Game::obs_bounds`. When normalization is enabled on the
+//! `GameAdapter`, every encoded observation is mapped affinely from
+//! `[low, high]` onto `[0, 1]` and clamped, so out-of-range values saturate
+//! instead of leaking through to naive policies.
+
+use crate::erased::ErasedGameError;
+use crate::typed::ObsBounds;
+
+const F32_SIZE: usize = std::mem::size_of::<f32>();
+
+/// Per-dimension affine normalization of packed little-endian `f32` observations
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObsNormalize {
+    low: Vec<f32>,
+    scale: Vec<f32>,
+}
+
+impl ObsNormalize {
+    /// Build a normalizer from declared bounds
+    ///
+    /// # Errors
+    ///
+    /// Returns `ErasedGameError::Encoding` if `low` and `high` differ in length
+    /// or any dimension has `high <= low`.
+    pub fn new(bounds: &ObsBounds) -> Result<Self, ErasedGameError> {
+        if bounds.low.len() != bounds.high.len() {
+            return Err(ErasedGameError::Encoding(format!(
+                "obs_low has {} dimensions but obs_high has {}",
+                bounds.low.len(),
+                bounds.high.len()
+            )));
+        }
+
+        let mut scale = Vec::with_capacity(bounds.low.len());
+        for (dim, (&low, &high)) in bounds.low.iter().zip(&bounds.high).enumerate() {
+            let range = high - low;
+            if !range.is_finite() || range <= 0.0 {
+                return Err(ErasedGameError::Encoding(format!(
+                    "Observation dimension {} has invalid range [{}, {}]",
+                    dim, low, high
+                )));
+            }
+            scale.push(1.0 / range);
+        }
+
+        Ok(Self {
+            low: bounds.low.clone(),
+            scale,
+        })
+    }
+
+    /// Number of observation dimensions
+    pub fn dims(&self) -> usize {
+        self.low.len()
+    }
+
+    /// Normalize an encoded observation in place
+    ///
+    /// # Errors
+    ///
+    /// Returns `ErasedGameError::Encoding` if the buffer is not exactly
+    /// `dims()` packed `f32` values.
+    pub fn apply(&self, obs: &mut [u8]) -> Result<(), ErasedGameError> {
+        if obs.len() != self.dims() * F32_SIZE {
+            return Err(ErasedGameError::Encoding(format!(
+                "Expected {} bytes of f32 observation for normalization, got {}",
+                self.dims() * F32_SIZE,
+                obs.len()
+            )));
+        }
+
+        for ((chunk, &low), &scale) in obs
+            .chunks_exact_mut(F32_SIZE)
+            .zip(&self.low)
+            .zip(&self.scale)
+        {
+            let value = f32::from_le_bytes(chunk.try_into().unwrap());
+            let normalized = ((value - low) * scale).clamp(0.0, 1.0);
+            chunk.copy_from_slice(&normalized.to_le_bytes());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(values: &[f32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    fn decode(buf: &[u8]) -> Vec<f32> {
+        buf.chunks_exact(F32_SIZE)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_normalize_clamps_out_of_range_values() {
+        let normalize = ObsNormalize::new(&ObsBounds {
+            low: vec![-2.0, 0.0, 10.0],
+            high: vec![2.0, 4.0, 20.0],
+        })
+        .unwrap();
+
+        let mut obs = encode(&[0.0, 9.0, -5.0]);
+        normalize.apply(&mut obs).unwrap();
+
+        let values = decode(&obs);
+        assert_eq!(values, vec![0.5, 1.0, 0.0]);
+        assert!(values.iter().all(|v| (0.0..=1.0).contains(v)));
+    }
+
+    #[test]
+    fn test_normalize_rejects_bad_bounds_and_lengths() {
+        let mismatched = ObsBounds {
+            low: vec![0.0],
+            high: vec![1.0, 2.0],
+        };
+        assert!(ObsNormalize::new(&mismatched).is_err());
+
+        let empty_range = ObsBounds {
+            low: vec![1.0],
+            high: vec![1.0],
+        };
+        assert!(ObsNormalize::new(&empty_range).is_err());
+
+        let normalize = ObsNormalize::new(&ObsBounds {
+            low: vec![0.0],
+            high: vec![1.0],
+        })
+        .unwrap();
+        assert!(normalize.apply(&mut encode(&[0.5, 0.5])).is_err());
+    }
+}
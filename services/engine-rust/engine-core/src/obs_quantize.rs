@@ -0,0 +1,321 @@
+//! Compact observations by quantizing each value to a byte
+//!
+//! `QuantizeObs<G>` wraps a game whose observation is packed little-endian
+//! `f32` values and emits one `u8` per value instead, a quarter of the size,
+//! for replay storage that cannot afford full precision. Each dimension is
+//! mapped affinely from the game's declared `[low, high]` range
+//! (`Game::obs_bounds`) onto `0..=255`:
+//!
+//! ```text
+//! scale      = (high - low) / 255
+//! zero_point = -low / scale
+//! q          = round(x / scale + zero_point), clamped to 0..=255
+//! x'         = scale * (q - zero_point)
+//! ```
+//!
+//! For `x` within `[low, high]` the dequantized `x'` is within `scale / 2` of
+//! `x`; values outside the range saturate to the nearest bound. The scales
+//! and zero points are reported through `Game::obs_quantization` (and the
+//! `obs_quantization` field of the gRPC capabilities) so consumers can
+//! dequantize, and the obs encoding becomes `quant_u8(<inner encoding>)`.
+//!
+//! ```rust,ignore
+//! register_game("tictactoe-u8".to_string(), || {
+//!     Box::new(GameAdapter::new(QuantizeObs::new(TicTacToe::new()).unwrap()))
+//! });
+//! ```
+
+use rand_chacha::ChaCha20Rng;
+
+use crate::erased::ErasedGameError;
+use crate::typed::{
+    Capabilities, DecodeError, EncodeError, EngineId, Game, InitialStateKind, ObsLayout,
+    ObsQuantization, StepResult,
+};
+
+const F32_SIZE: usize = std::mem::size_of::<f32>();
+
+/// Largest quantized value
+const Q_MAX: f32 = u8::MAX as f32;
+
+/// Game wrapper emitting one quantized `u8` per observation value
+#[derive(Clone)]
+pub struct QuantizeObs<G: Game> {
+    inner: G,
+    quantization: ObsQuantization,
+}
+
+impl<G: Game> QuantizeObs<G> {
+    /// Quantize the observations of `inner` over its declared bounds
+    ///
+    /// # Errors
+    ///
+    /// Returns `ErasedGameError::Encoding` if the game declares no
+    /// observation bounds, `low` and `high` differ in length, a fixed-size
+    /// observation does not hold one `f32` per bound, or any dimension has
+    /// `high <= low`.
+    pub fn new(inner: G) -> Result<Self, ErasedGameError> {
+        let bounds = inner.obs_bounds().ok_or_else(|| {
+            ErasedGameError::Encoding(
+                "Quantizing observations requires the game to declare obs_bounds".to_string(),
+            )
+        })?;
+        if bounds.low.len() != bounds.high.len() {
+            return Err(ErasedGameError::Encoding(format!(
+                "obs_low has {} dimensions but obs_high has {}",
+                bounds.low.len(),
+                bounds.high.len()
+            )));
+        }
+        if let Some(size) = G::OBS_ENCODED_SIZE {
+            if size != bounds.low.len() * F32_SIZE {
+                return Err(ErasedGameError::Encoding(format!(
+                    "Bounds declare {} values but the observation is {} bytes",
+                    bounds.low.len(),
+                    size
+                )));
+            }
+        }
+
+        let mut scale = Vec::with_capacity(bounds.low.len());
+        let mut zero_point = Vec::with_capacity(bounds.low.len());
+        for (dim, (&low, &high)) in bounds.low.iter().zip(&bounds.high).enumerate() {
+            let range = high - low;
+            if !range.is_finite() || range <= 0.0 {
+                return Err(ErasedGameError::Encoding(format!(
+                    "Observation dimension {} has invalid range [{}, {}]",
+                    dim, low, high
+                )));
+            }
+            scale.push(range / Q_MAX);
+            zero_point.push(-low * Q_MAX / range);
+        }
+
+        Ok(Self {
+            inner,
+            quantization: ObsQuantization { scale, zero_point },
+        })
+    }
+
+    /// The wrapped game
+    pub fn inner(&self) -> &G {
+        &self.inner
+    }
+
+    /// Encoded inner observation with each value quantized to a byte
+    fn quantized_obs(&self, obs: &G::Obs) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(G::OBS_ENCODED_SIZE.unwrap_or(0));
+        // Observations are encoded eagerly so the values can be quantized
+        G::encode_obs(obs, &mut encoded)
+            .unwrap_or_else(|e| panic!("QuantizeObs: wrapped game failed to encode obs: {}", e));
+        let dims = self.quantization.scale.len();
+        // Only games without a fixed observation size get here unchecked
+        assert_eq!(
+            encoded.len(),
+            dims * F32_SIZE,
+            "QuantizeObs: bounds declare {} values but the observation is {} bytes",
+            dims,
+            encoded.len()
+        );
+        let ObsQuantization { scale, zero_point } = &self.quantization;
+        encoded
+            .chunks_exact(F32_SIZE)
+            .zip(scale.iter().zip(zero_point))
+            .map(|(chunk, (&scale, &zero_point))| {
+                let value = f32::from_le_bytes(chunk.try_into().unwrap());
+                // NaN saturates to 0 through the float-to-int cast
+                (value / scale + zero_point).round().clamp(0.0, Q_MAX) as u8
+            })
+            .collect()
+    }
+}
+
+impl<G: Game> Game for QuantizeObs<G> {
+    type State = G::State;
+    type Action = G::Action;
+    /// One quantized byte per wrapped observation value
+    type Obs = Vec<u8>;
+
+    const STATE_ENCODED_SIZE: Option<usize> = G::STATE_ENCODED_SIZE;
+    const OBS_ENCODED_SIZE: Option<usize> = match G::OBS_ENCODED_SIZE {
+        Some(size) => Some(size / F32_SIZE),
+        None => None,
+    };
+    const ACTION_ENCODED_SIZE: Option<usize> = G::ACTION_ENCODED_SIZE;
+
+    fn engine_id(&self) -> EngineId {
+        self.inner.engine_id()
+    }
+
+    /// The wrapped capabilities with the byte layout declared in the obs
+    /// encoding, e.g. `quant_u8(f32x29:v1)`
+    fn capabilities(&self) -> Capabilities {
+        let mut caps = self.inner.capabilities();
+        caps.encoding.obs = format!("quant_u8({})", caps.encoding.obs);
+        caps
+    }
+
+    fn initial_state_kind(&self) -> InitialStateKind {
+        self.inner.initial_state_kind()
+    }
+
+    fn action_names(&self) -> Option<Vec<String>> {
+        self.inner.action_names()
+    }
+
+    // `obs_bounds` keeps its default of `None`: the bounds describe `f32`
+    // values, and the quantized observation is bytes.
+
+    fn obs_quantization(&self) -> Option<ObsQuantization> {
+        Some(self.quantization.clone())
+    }
+
+    fn obs_layout(&self) -> Option<ObsLayout> {
+        self.inner.obs_layout()
+    }
+
+    fn state_space_estimate(&self) -> Option<u64> {
+        self.inner.state_space_estimate()
+    }
+
+    fn supported_schema_versions(&self) -> Vec<u32> {
+        self.inner.supported_schema_versions()
+    }
+
+    fn is_deterministic(&self) -> bool {
+        self.inner.is_deterministic()
+    }
+
+    fn is_state_complete(&self) -> bool {
+        self.inner.is_state_complete()
+    }
+
+    fn current_player(&self, state: &Self::State) -> Option<u32> {
+        self.inner.current_player(state)
+    }
+
+    fn must_pass(&self, state: &Self::State) -> bool {
+        self.inner.must_pass(state)
+    }
+
+    fn legal_actions(&self, state: &Self::State) -> Option<Vec<Self::Action>> {
+        self.inner.legal_actions(state)
+    }
+
+    fn legal_action_count(&self, state: &Self::State) -> Option<u32> {
+        self.inner.legal_action_count(state)
+    }
+
+    fn pass_action(&self) -> Option<Self::Action> {
+        self.inner.pass_action()
+    }
+
+    fn step_info(&self, state: &Self::State) -> Vec<(String, f32)> {
+        self.inner.step_info(state)
+    }
+
+    fn reset(&mut self, rng: &mut ChaCha20Rng, hint: &[u8]) -> (Self::State, Self::Obs) {
+        let (state, obs) = self.inner.reset(rng, hint);
+        let obs = self.quantized_obs(&obs);
+        (state, obs)
+    }
+
+    fn step(
+        &mut self,
+        state: &mut Self::State,
+        action: Self::Action,
+        rng: &mut ChaCha20Rng,
+    ) -> StepResult<Self::Obs> {
+        let result = self.inner.step(state, action, rng);
+        StepResult {
+            obs: self.quantized_obs(&result.obs),
+            reward: result.reward,
+            done: result.done,
+            truncated: result.truncated,
+            info: result.info,
+        }
+    }
+
+    fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        G::encode_state(state, out)
+    }
+
+    fn decode_state(buf: &[u8]) -> Result<Self::State, DecodeError> {
+        G::decode_state(buf)
+    }
+
+    fn migrate_state(buf: &[u8], from_version: u32) -> Result<Vec<u8>, DecodeError> {
+        G::migrate_state(buf, from_version)
+    }
+
+    fn encode_action(action: &Self::Action, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        G::encode_action(action, out)
+    }
+
+    fn decode_action(buf: &[u8]) -> Result<Self::Action, DecodeError> {
+        G::decode_action(buf)
+    }
+
+    fn encode_obs(obs: &Self::Obs, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        out.extend_from_slice(obs);
+        Ok(())
+    }
+
+    fn warm_up() {
+        G::warm_up()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_game::ValuesGame;
+    use crate::typed::ObsBounds;
+
+    fn with_bounds(low: Vec<f32>, high: Vec<f32>) -> ValuesGame<2> {
+        ValuesGame {
+            bounds: Some(ObsBounds { low, high }),
+            ..ValuesGame::new([0.0; 2])
+        }
+    }
+
+    #[test]
+    fn test_values_outside_the_bounds_saturate() {
+        let quantize = QuantizeObs::new(ValuesGame::new([0.0; 2])).unwrap();
+        assert_eq!(quantize.quantized_obs(&[-5.0, 25.0]), vec![0, 255]);
+        assert_eq!(quantize.quantized_obs(&[0.0, 10.0]), vec![0, 255]);
+        assert_eq!(
+            quantize.quantized_obs(&[f32::NEG_INFINITY, f32::INFINITY]),
+            vec![0, 255]
+        );
+    }
+
+    #[test]
+    fn test_nan_quantizes_to_zero() {
+        let quantize = QuantizeObs::new(ValuesGame::new([0.0; 2])).unwrap();
+        assert_eq!(quantize.quantized_obs(&[f32::NAN, 2.0]), vec![0, 51]);
+    }
+
+    #[test]
+    fn test_invalid_bounds_are_rejected() {
+        let unbounded = ValuesGame {
+            bounds: None,
+            ..ValuesGame::new([0.0; 2])
+        };
+        let invalid = [
+            unbounded,
+            with_bounds(vec![0.0], vec![1.0, 1.0]),
+            with_bounds(vec![0.0; 3], vec![1.0; 3]),
+            with_bounds(vec![0.0, 1.0], vec![1.0, 1.0]),
+            with_bounds(vec![0.0, 2.0], vec![1.0, 1.0]),
+            with_bounds(vec![0.0, f32::NAN], vec![1.0, 1.0]),
+            with_bounds(vec![0.0, 0.0], vec![1.0, f32::INFINITY]),
+        ];
+        for game in invalid {
+            assert!(matches!(
+                QuantizeObs::new(game),
+                Err(ErasedGameError::Encoding(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,326 @@
+//! Observation stacking (frame stacking) for any typed game
+//!
+//! `ObsStack<G, K>` wraps a game so each observation is the concatenation of
+//! its last `K` encoded observations, oldest first. The frames travel inside
+//! the encoded state, so stacking stays stateless on the server like any
+//! other game. Reset fills every slot with the initial observation. The
+//! wrapped game is untouched: it sees its own state and actions and never
+//! knows it is being stacked.
+//!
+//! ```rust,ignore
+//! register_game("tictactoe-stack2".to_string(), || {
+//!     Box::new(GameAdapter::new(ObsStack::<TicTacToe, 2>::new(TicTacToe::new())))
+//! });
+//! ```
+
+use std::collections::VecDeque;
+
+use rand_chacha::ChaCha20Rng;
+
+use crate::typed::{
+    Capabilities, DecodeError, EncodeError, EngineId, Game, InitialStateKind, ObsBounds, ObsLayout,
+    StepResult,
+};
+
+/// Bytes of each length prefix in the stacked state layout
+pub(crate) const LEN_PREFIX: usize = std::mem::size_of::<u32>();
+
+/// Game wrapper whose observation is its last `K` observations concatenated
+#[derive(Clone)]
+pub struct ObsStack<G: Game, const K: usize> {
+    inner: G,
+}
+
+impl<G: Game, const K: usize> ObsStack<G, K> {
+    /// Stack the last `K` observations of `inner`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `K` is zero.
+    pub fn new(inner: G) -> Self {
+        assert!(K > 0, "ObsStack needs at least one frame");
+        Self { inner }
+    }
+
+    /// The wrapped game
+    pub fn inner(&self) -> &G {
+        &self.inner
+    }
+
+    fn encode_frame(obs: &G::Obs) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(G::OBS_ENCODED_SIZE.unwrap_or(0));
+        // Observations are encoded eagerly so the frames can live in the state.
+        G::encode_obs(obs, &mut frame)
+            .unwrap_or_else(|e| panic!("ObsStack: wrapped game failed to encode obs: {}", e));
+        frame
+    }
+
+    fn stacked_obs(frames: &VecDeque<Vec<u8>>) -> Vec<u8> {
+        frames.iter().flatten().copied().collect()
+    }
+}
+
+/// State of a stacked game: the wrapped state plus the last `K` encoded observations
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackedState<S> {
+    pub inner: S,
+    /// Encoded observations, oldest first
+    pub frames: VecDeque<Vec<u8>>,
+}
+
+/// Layout: `[u32 len][inner state]` followed by `K` x `[u32 len][encoded obs]`,
+/// lengths little-endian
+impl<G: Game, const K: usize> Game for ObsStack<G, K> {
+    type State = StackedState<G::State>;
+    type Action = G::Action;
+    /// Concatenated encoded observations, oldest first
+    type Obs = Vec<u8>;
+
+    const STATE_ENCODED_SIZE: Option<usize> = match (G::STATE_ENCODED_SIZE, G::OBS_ENCODED_SIZE) {
+        (Some(state), Some(obs)) => Some(LEN_PREFIX + state + K * (LEN_PREFIX + obs)),
+        _ => None,
+    };
+    const OBS_ENCODED_SIZE: Option<usize> = match G::OBS_ENCODED_SIZE {
+        Some(obs) => Some(K * obs),
+        None => None,
+    };
+    const ACTION_ENCODED_SIZE: Option<usize> = G::ACTION_ENCODED_SIZE;
+
+    fn engine_id(&self) -> EngineId {
+        self.inner.engine_id()
+    }
+
+    /// The wrapped capabilities with stacked state and obs encodings
+    ///
+    /// `stack2(f32x29:v1)` names two concatenated `f32x29:v1` observations;
+    /// the stacked obs byte length is reported through the encoded sizes.
+    fn capabilities(&self) -> Capabilities {
+        let mut caps = self.inner.capabilities();
+        caps.encoding.state = format!("stack{}({})", K, caps.encoding.state);
+        caps.encoding.obs = format!("stack{}({})", K, caps.encoding.obs);
+        caps
+    }
+
+    fn initial_state_kind(&self) -> InitialStateKind {
+        self.inner.initial_state_kind()
+    }
+
+    fn action_names(&self) -> Option<Vec<String>> {
+        self.inner.action_names()
+    }
+
+    /// The wrapped bounds repeated once per frame
+    fn obs_bounds(&self) -> Option<ObsBounds> {
+        self.inner.obs_bounds().map(|bounds| ObsBounds {
+            low: bounds.low.repeat(K),
+            high: bounds.high.repeat(K),
+        })
+    }
+
+    /// The wrapped segments once per frame, prefixed `frame0.` for the oldest
+    fn obs_layout(&self) -> Option<ObsLayout> {
+        self.inner.obs_layout().map(|layout| ObsLayout {
+            segments: (0..K)
+                .flat_map(|frame| {
+                    layout
+                        .segments
+                        .iter()
+                        .map(move |(name, size)| (format!("frame{}.{}", frame, name), *size))
+                })
+                .collect(),
+        })
+    }
+
+    fn is_deterministic(&self) -> bool {
+        self.inner.is_deterministic()
+    }
+
+    fn is_state_complete(&self) -> bool {
+        self.inner.is_state_complete()
+    }
+
+    fn current_player(&self, state: &Self::State) -> Option<u32> {
+        self.inner.current_player(&state.inner)
+    }
+
+    fn must_pass(&self, state: &Self::State) -> bool {
+        self.inner.must_pass(&state.inner)
+    }
+
+    fn legal_actions(&self, state: &Self::State) -> Option<Vec<Self::Action>> {
+        self.inner.legal_actions(&state.inner)
+    }
+
+    fn legal_action_count(&self, state: &Self::State) -> Option<u32> {
+        self.inner.legal_action_count(&state.inner)
+    }
+
+    fn pass_action(&self) -> Option<Self::Action> {
+        self.inner.pass_action()
+    }
+
+    fn step_info(&self, state: &Self::State) -> Vec<(String, f32)> {
+        self.inner.step_info(&state.inner)
+    }
+
+    fn reset(&mut self, rng: &mut ChaCha20Rng, hint: &[u8]) -> (Self::State, Self::Obs) {
+        let (inner, obs) = self.inner.reset(rng, hint);
+        let frames: VecDeque<Vec<u8>> = vec![Self::encode_frame(&obs); K].into();
+        let stacked = Self::stacked_obs(&frames);
+        (StackedState { inner, frames }, stacked)
+    }
+
+    fn step(
+        &mut self,
+        state: &mut Self::State,
+        action: Self::Action,
+        rng: &mut ChaCha20Rng,
+    ) -> StepResult<Self::Obs> {
+        let result = self.inner.step(&mut state.inner, action, rng);
+        state.frames.pop_front();
+        state.frames.push_back(Self::encode_frame(&result.obs));
+        StepResult {
+            obs: Self::stacked_obs(&state.frames),
+            reward: result.reward,
+            done: result.done,
+            truncated: result.truncated,
+            info: result.info,
+        }
+    }
+
+    fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        if state.frames.len() != K {
+            return Err(EncodeError::InvalidData(format!(
+                "Stacked state holds {} frames, expected {}",
+                state.frames.len(),
+                K
+            )));
+        }
+
+        let start = out.len();
+        out.extend_from_slice(&[0; LEN_PREFIX]);
+        G::encode_state(&state.inner, out)?;
+        write_len(out, start)?;
+
+        for frame in &state.frames {
+            out.extend_from_slice(&encode_len(frame.len())?);
+            out.extend_from_slice(frame);
+        }
+        Ok(())
+    }
+
+    fn decode_state(buf: &[u8]) -> Result<Self::State, DecodeError> {
+        let (inner, mut rest) = read_chunk(buf)?;
+        let inner = G::decode_state(inner)?;
+
+        let mut frames = VecDeque::with_capacity(K);
+        for _ in 0..K {
+            let (frame, next) = read_chunk(rest)?;
+            frames.push_back(frame.to_vec());
+            rest = next;
+        }
+        if !rest.is_empty() {
+            return Err(DecodeError::CorruptedData(format!(
+                "{} trailing bytes after {} stacked frames",
+                rest.len(),
+                K
+            )));
+        }
+        Ok(StackedState { inner, frames })
+    }
+
+    fn encode_action(action: &Self::Action, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        G::encode_action(action, out)
+    }
+
+    fn decode_action(buf: &[u8]) -> Result<Self::Action, DecodeError> {
+        G::decode_action(buf)
+    }
+
+    fn encode_obs(obs: &Self::Obs, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        out.extend_from_slice(obs);
+        Ok(())
+    }
+
+    fn warm_up() {
+        G::warm_up()
+    }
+}
+
+fn encode_len(len: usize) -> Result<[u8; LEN_PREFIX], EncodeError> {
+    u32::try_from(len)
+        .map(u32::to_le_bytes)
+        .map_err(|_| EncodeError::InvalidData(format!("Chunk of {} bytes is too long", len)))
+}
+
+/// Fill in the placeholder length prefix at `start` for the bytes after it
+pub(crate) fn write_len(out: &mut [u8], start: usize) -> Result<(), EncodeError> {
+    let len = encode_len(out.len() - start - LEN_PREFIX)?;
+    out[start..start + LEN_PREFIX].copy_from_slice(&len);
+    Ok(())
+}
+
+/// Split a length-prefixed chunk off the front of `buf`
+pub(crate) fn read_chunk(buf: &[u8]) -> Result<(&[u8], &[u8]), DecodeError> {
+    if buf.len() < LEN_PREFIX {
+        return Err(DecodeError::InvalidLength {
+            expected: LEN_PREFIX,
+            actual: buf.len(),
+        });
+    }
+    let (len, rest) = buf.split_at(LEN_PREFIX);
+    let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+    if rest.len() < len {
+        return Err(DecodeError::CorruptedData(format!(
+            "Chunk declares {} bytes but only {} remain",
+            len,
+            rest.len()
+        )));
+    }
+    Ok(rest.split_at(len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_game::ValuesGame;
+    use rand::SeedableRng;
+
+    #[test]
+    #[should_panic(expected = "ObsStack needs at least one frame")]
+    fn test_zero_frames_panic() {
+        ObsStack::<ValuesGame<2>, 0>::new(ValuesGame::new([0.0; 2]));
+    }
+
+    #[test]
+    fn test_frames_rotate_and_survive_a_state_round_trip() {
+        let mut game = ObsStack::<ValuesGame<2>, 2>::new(ValuesGame::new([1.0, 2.0]));
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let (mut state, obs) = game.reset(&mut rng, &[]);
+        let mut frame = Vec::new();
+        ValuesGame::<2>::encode_obs(&[1.0, 2.0], &mut frame).unwrap();
+        assert_eq!(obs, frame.repeat(2));
+
+        let result = game.step(&mut state, Some(0), &mut rng);
+        let mut next_frame = Vec::new();
+        ValuesGame::<2>::encode_obs(&[2.0, 2.0], &mut next_frame).unwrap();
+        assert_eq!(result.obs, [frame, next_frame].concat());
+
+        let mut buf = Vec::new();
+        ObsStack::<ValuesGame<2>, 2>::encode_state(&state, &mut buf).unwrap();
+        assert_eq!(
+            Some(buf.len()),
+            ObsStack::<ValuesGame<2>, 2>::STATE_ENCODED_SIZE
+        );
+        assert_eq!(
+            ObsStack::<ValuesGame<2>, 2>::decode_state(&buf).unwrap(),
+            state
+        );
+
+        buf.push(0);
+        assert!(matches!(
+            ObsStack::<ValuesGame<2>, 2>::decode_state(&buf),
+            Err(DecodeError::CorruptedData(_))
+        ));
+    }
+}
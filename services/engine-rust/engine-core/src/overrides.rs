@@ -0,0 +1,188 @@
+//! Capability overrides for serving a game under tuned settings
+//!
+//! Experiments sometimes want the same game logic under another env_id or
+//! with a different batch size, without editing the game. `OverriddenGame`
+//! wraps any erased game and rewrites only tuning knobs in what it reports.
+//! Encoding names, schema version, and the action space always come from the
+//! wrapped game, since clients rely on them to read and write the wire format.
+
+use crate::erased::{EncodedSizes, ErasedGame, ErasedGameError, ResetOutcome, StepOutcome};
+use crate::typed::{Capabilities, EngineId, InitialStateKind, ObsLayout, ObsQuantization};
+
+/// Tuning knobs that may be overridden when registering a game
+///
+/// Unset fields keep the wrapped game's value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CapabilityOverrides {
+    pub preferred_batch: Option<u32>,
+    pub max_horizon: Option<u32>,
+    pub build_id: Option<String>,
+    pub action_names: Option<Vec<String>>,
+}
+
+/// Erased game that reports overridden capabilities and otherwise delegates
+pub struct OverriddenGame {
+    inner: Box<dyn ErasedGame>,
+    env_id: String,
+    overrides: CapabilityOverrides,
+}
+
+impl OverriddenGame {
+    /// Serve `inner` as `env_id` with `overrides` applied to its capabilities
+    pub fn new(
+        inner: Box<dyn ErasedGame>,
+        env_id: impl Into<String>,
+        overrides: CapabilityOverrides,
+    ) -> Self {
+        Self {
+            inner,
+            env_id: env_id.into(),
+            overrides,
+        }
+    }
+}
+
+impl ErasedGame for OverriddenGame {
+    fn engine_id(&self) -> EngineId {
+        let inner = self.inner.engine_id();
+        EngineId {
+            env_id: self.env_id.clone(),
+            build_id: self.overrides.build_id.clone().unwrap_or(inner.build_id),
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        let caps = self.inner.capabilities();
+        Capabilities {
+            id: self.engine_id(),
+            encoding: caps.encoding,
+            max_horizon: self.overrides.max_horizon.unwrap_or(caps.max_horizon),
+            action_space: caps.action_space,
+            preferred_batch: self
+                .overrides
+                .preferred_batch
+                .unwrap_or(caps.preferred_batch),
+        }
+    }
+
+    fn encoded_sizes(&self) -> EncodedSizes {
+        self.inner.encoded_sizes()
+    }
+
+    fn initial_state_kind(&self) -> InitialStateKind {
+        self.inner.initial_state_kind()
+    }
+
+    fn action_names(&self) -> Option<Vec<String>> {
+        self.overrides
+            .action_names
+            .clone()
+            .or_else(|| self.inner.action_names())
+    }
+
+    fn state_space_estimate(&self) -> Option<u64> {
+        self.inner.state_space_estimate()
+    }
+
+    fn obs_quantization(&self) -> Option<ObsQuantization> {
+        self.inner.obs_quantization()
+    }
+
+    fn obs_layout(&self) -> Option<ObsLayout> {
+        self.inner.obs_layout()
+    }
+
+    fn supported_schema_versions(&self) -> Vec<u32> {
+        self.inner.supported_schema_versions()
+    }
+
+    fn is_deterministic(&self) -> bool {
+        self.inner.is_deterministic()
+    }
+
+    fn is_state_complete(&self) -> bool {
+        self.inner.is_state_complete()
+    }
+
+    fn legal_action_count(&self, state: &[u8]) -> Result<Option<u32>, ErasedGameError> {
+        self.inner.legal_action_count(state)
+    }
+
+    fn legal_actions(&self, state: &[u8]) -> Result<Option<Vec<Vec<u8>>>, ErasedGameError> {
+        self.inner.legal_actions(state)
+    }
+
+    fn obs_framed(&self) -> bool {
+        self.inner.obs_framed()
+    }
+
+    fn last_rng_draws(&self) -> Option<u64> {
+        self.inner.last_rng_draws()
+    }
+
+    fn last_step_info(&self) -> &[(String, f32)] {
+        self.inner.last_step_info()
+    }
+
+    fn reset(
+        &mut self,
+        seed: u64,
+        hint: &[u8],
+        out_state: &mut Vec<u8>,
+        out_obs: &mut Vec<u8>,
+    ) -> Result<ResetOutcome, ErasedGameError> {
+        self.inner.reset(seed, hint, out_state, out_obs)
+    }
+
+    fn reset_without_state(
+        &mut self,
+        seed: u64,
+        hint: &[u8],
+        out_obs: &mut Vec<u8>,
+    ) -> Result<ResetOutcome, ErasedGameError> {
+        self.inner.reset_without_state(seed, hint, out_obs)
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.inner.reseed(seed)
+    }
+
+    fn step_seeded(
+        &mut self,
+        seed: u64,
+        state: &[u8],
+        action: &[u8],
+        out_state: &mut Vec<u8>,
+        out_obs: &mut Vec<u8>,
+    ) -> Result<StepOutcome, ErasedGameError> {
+        self.inner
+            .step_seeded(seed, state, action, out_state, out_obs)
+    }
+
+    fn fork(&self) -> Box<dyn ErasedGame> {
+        Box::new(OverriddenGame::new(
+            self.inner.fork(),
+            self.env_id.clone(),
+            self.overrides.clone(),
+        ))
+    }
+
+    fn step(
+        &mut self,
+        state: &[u8],
+        action: &[u8],
+        out_state: &mut Vec<u8>,
+        out_obs: &mut Vec<u8>,
+    ) -> Result<StepOutcome, ErasedGameError> {
+        self.inner.step(state, action, out_state, out_obs)
+    }
+
+    fn step_without_state(
+        &mut self,
+        state: &[u8],
+        action: &[u8],
+        out_obs: &mut Vec<u8>,
+    ) -> Result<StepOutcome, ErasedGameError> {
+        self.inner.step_without_state(state, action, out_obs)
+    }
+}
@@ -2,19 +2,212 @@
 //! 
 //! This module provides a thread-safe registry system that allows games to be
 //! registered at compile-time and looked up at runtime by their env_id.
+//!
+//! The free functions operate on one process-wide registry. Embedders that
+//! need several independent game sets in one process (e.g. a multi-tenant
+//! test) create their own `Registry` instances and hand them to the service.
+//!
+//! The global registry is shared by tests running in parallel. Tests that
+//! need exact registry contents hold a `RegistryGuard` (behind the
+//! `test-util` feature), which gives their thread a private registry instead
+//! of clearing the shared one.
 
 use std::collections::HashMap;
-use once_cell::sync::Lazy;
-use std::sync::Mutex;
+use std::fmt;
+use once_cell::sync::{Lazy, OnceCell};
+use std::sync::{Arc, Mutex};
 
 use crate::erased::ErasedGame;
+use crate::overrides::{CapabilityOverrides, OverriddenGame};
 
 /// Factory function type for creating game instances
 pub type GameFactory = fn() -> Box<dyn ErasedGame>;
 
-/// Thread-safe registry mapping env_id to game factory functions
-static REGISTRY: Lazy<Mutex<HashMap<String, GameFactory>>> = 
-    Lazy::new(|| Mutex::new(HashMap::new()));
+/// One-time initialization run before the first instance of a game is created
+pub type GameWarmUp = fn();
+
+/// Registered factory plus its optional warm-up hook and capability overrides
+#[derive(Clone)]
+struct RegistryEntry {
+    factory: GameFactory,
+    warm_up: Option<(GameWarmUp, Arc<OnceCell<()>>)>,
+    overrides: Option<CapabilityOverrides>,
+}
+
+/// Thread-safe map from env_id to game factory functions
+///
+/// Owns its registrations, so independent instances never see each other's
+/// games. The free functions in this module delegate to a process-wide
+/// instance.
+#[derive(Default)]
+pub struct Registry {
+    entries: Mutex<HashMap<String, RegistryEntry>>,
+}
+
+impl Registry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a game under `env_id`, replacing any previous registration
+    pub fn register_game(&self, env_id: String, factory: GameFactory) {
+        self.insert_entry(
+            env_id,
+            RegistryEntry {
+                factory,
+                warm_up: None,
+                overrides: None,
+            },
+        );
+    }
+
+    /// Register a game whose `warm_up` runs once before its first instance
+    pub fn register_game_with_warm_up(
+        &self,
+        env_id: String,
+        factory: GameFactory,
+        warm_up: GameWarmUp,
+    ) {
+        self.insert_entry(
+            env_id,
+            RegistryEntry {
+                factory,
+                warm_up: Some((warm_up, Arc::new(OnceCell::new()))),
+                overrides: None,
+            },
+        );
+    }
+
+    /// Register a game whose reported capabilities are tuned by `overrides`
+    pub fn register_game_with_capabilities(
+        &self,
+        env_id: String,
+        factory: GameFactory,
+        overrides: CapabilityOverrides,
+    ) {
+        self.insert_entry(
+            env_id,
+            RegistryEntry {
+                factory,
+                warm_up: None,
+                overrides: Some(overrides),
+            },
+        );
+    }
+
+    fn insert_entry(&self, env_id: String, entry: RegistryEntry) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.contains_key(&env_id) {
+            eprintln!("Warning: Overriding existing game registration for '{}'", env_id);
+        }
+        entries.insert(env_id, entry);
+    }
+
+    /// Create a new instance of the game registered as `env_id`
+    pub fn create_game(&self, env_id: &str) -> Option<Box<dyn ErasedGame>> {
+        // Release the lock before warming up so slow hooks for one game
+        // don't block lookups of others
+        let entry = self.entries.lock().unwrap().get(env_id).cloned()?;
+
+        if let Some((warm_up, once)) = &entry.warm_up {
+            once.get_or_init(warm_up);
+        }
+
+        let game = (entry.factory)();
+        Some(match entry.overrides {
+            Some(overrides) => Box::new(OverriddenGame::new(game, env_id, overrides)),
+            None => game,
+        })
+    }
+
+    /// Every registered env_id, in no particular order
+    pub fn list_registered_games(&self) -> Vec<String> {
+        self.entries.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Whether `env_id` is registered
+    pub fn is_registered(&self, env_id: &str) -> bool {
+        self.entries.lock().unwrap().contains_key(env_id)
+    }
+
+    /// Remove every registration
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+// Factories are plain function pointers; the env ids are what identifies a registry
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut env_ids = self.list_registered_games();
+        env_ids.sort();
+        f.debug_struct("Registry").field("env_ids", &env_ids).finish()
+    }
+}
+
+/// Process-wide registry behind the free functions
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+#[cfg(any(test, feature = "test-util"))]
+thread_local! {
+    /// Private registry installed by a live `RegistryGuard` on this thread
+    static SCOPED_REGISTRY: std::cell::RefCell<Option<Registry>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Run `f` on the registry the free functions use on this thread
+fn with_registry<R>(f: impl FnOnce(&Registry) -> R) -> R {
+    #[cfg(any(test, feature = "test-util"))]
+    let f = {
+        let mut f = Some(f);
+        let scoped = SCOPED_REGISTRY.with(|scoped| {
+            scoped
+                .borrow()
+                .as_ref()
+                .map(|registry| f.take().unwrap()(registry))
+        });
+        if let Some(result) = scoped {
+            return result;
+        }
+        f.unwrap()
+    };
+    f(&REGISTRY)
+}
+
+/// Gives the current thread a private, empty registry until dropped
+///
+/// Registrations and lookups made on this thread while the guard is alive
+/// never touch the global registry, so parallel tests can register and clear
+/// freely without racing each other. Work moved to other threads (e.g. a
+/// multi-threaded runtime's workers) still sees the global registry. Guards
+/// nest: dropping one restores whatever the thread saw before it.
+#[cfg(any(test, feature = "test-util"))]
+pub struct RegistryGuard {
+    previous: Option<Registry>,
+    /// The guard swaps thread-local state, so it must be dropped on its own thread
+    _not_send: std::marker::PhantomData<*const ()>,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl RegistryGuard {
+    /// Install an empty private registry for the current thread
+    pub fn isolated() -> Self {
+        let previous = SCOPED_REGISTRY.with(|scoped| scoped.replace(Some(Registry::new())));
+        Self {
+            previous,
+            _not_send: std::marker::PhantomData,
+        }
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Drop for RegistryGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        SCOPED_REGISTRY.with(|scoped| *scoped.borrow_mut() = previous);
+    }
+}
 
 /// Register a game with the global registry
 /// 
@@ -34,6 +227,7 @@ static REGISTRY: Lazy<Mutex<HashMap<String, GameFactory>>> =
 /// # use engine_core::adapter::GameAdapter;
 /// # use engine_core::typed::*;
 /// 
+/// # #[derive(Clone)]
 /// # struct MyGame;
 /// # impl Game for MyGame {
 /// #     type State = ();
@@ -42,7 +236,7 @@ static REGISTRY: Lazy<Mutex<HashMap<String, GameFactory>>> =
 /// #     fn engine_id(&self) -> EngineId { todo!() }
 /// #     fn capabilities(&self) -> Capabilities { todo!() }
 /// #     fn reset(&mut self, rng: &mut rand_chacha::ChaCha20Rng, hint: &[u8]) -> (Self::State, Self::Obs) { todo!() }
-/// #     fn step(&mut self, state: &mut Self::State, action: Self::Action, rng: &mut rand_chacha::ChaCha20Rng) -> (Self::Obs, f32, bool) { todo!() }
+/// #     fn step(&mut self, state: &mut Self::State, action: Self::Action, rng: &mut rand_chacha::ChaCha20Rng) -> StepResult<Self::Obs> { todo!() }
 /// #     fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), crate::typed::EncodeError> { todo!() }
 /// #     fn decode_state(buf: &[u8]) -> Result<Self::State, crate::typed::DecodeError> { todo!() }
 /// #     fn encode_action(action: &Self::Action, out: &mut Vec<u8>) -> Result<(), crate::typed::EncodeError> { todo!() }
@@ -57,11 +251,30 @@ static REGISTRY: Lazy<Mutex<HashMap<String, GameFactory>>> =
 /// register_game("my_game".to_string(), my_game_factory);
 /// ```
 pub fn register_game(env_id: String, factory: GameFactory) {
-    let mut registry = REGISTRY.lock().unwrap();
-    if registry.contains_key(&env_id) {
-        eprintln!("Warning: Overriding existing game registration for '{}'", env_id);
-    }
-    registry.insert(env_id, factory);
+    with_registry(|registry| registry.register_game(env_id, factory));
+}
+
+/// Register a game with a warm-up hook
+/// 
+/// `warm_up` runs exactly once for this registration, on the first
+/// `create_game` call for `env_id`. Concurrent first callers block until it
+/// has finished, so every instance observes the warmed-up tables.
+pub fn register_game_with_warm_up(env_id: String, factory: GameFactory, warm_up: GameWarmUp) {
+    with_registry(|registry| registry.register_game_with_warm_up(env_id, factory, warm_up));
+}
+
+/// Register a game whose reported capabilities are tuned by `overrides`
+/// 
+/// Instances are wrapped in an `OverriddenGame` that reports `env_id` and the
+/// overridden tuning knobs. Encoding and action space are never changed, so
+/// the same factory can be served under several env_ids without touching
+/// the wire format.
+pub fn register_game_with_capabilities(
+    env_id: String,
+    factory: GameFactory,
+    overrides: CapabilityOverrides,
+) {
+    with_registry(|registry| registry.register_game_with_capabilities(env_id, factory, overrides));
 }
 
 /// Create a new game instance by env_id
@@ -89,8 +302,7 @@ pub fn register_game(env_id: String, factory: GameFactory) {
 /// }
 /// ```
 pub fn create_game(env_id: &str) -> Option<Box<dyn ErasedGame>> {
-    let registry = REGISTRY.lock().unwrap();
-    registry.get(env_id).map(|factory| factory())
+    with_registry(|registry| registry.create_game(env_id))
 }
 
 /// Get list of all registered environment IDs
@@ -101,8 +313,7 @@ pub fn create_game(env_id: &str) -> Option<Box<dyn ErasedGame>> {
 /// 
 /// A vector of all registered env_id strings.
 pub fn list_registered_games() -> Vec<String> {
-    let registry = REGISTRY.lock().unwrap();
-    registry.keys().cloned().collect()
+    with_registry(|registry| registry.list_registered_games())
 }
 
 /// Check if a game is registered
@@ -115,17 +326,16 @@ pub fn list_registered_games() -> Vec<String> {
 /// 
 /// `true` if the game is registered, `false` otherwise.
 pub fn is_registered(env_id: &str) -> bool {
-    let registry = REGISTRY.lock().unwrap();
-    registry.contains_key(env_id)
+    with_registry(|registry| registry.is_registered(env_id))
 }
 
 /// Clear all registered games (mainly for testing)
 /// 
 /// This function removes all registered games from the registry.
-/// It should primarily be used in test scenarios.
+/// It should primarily be used in test scenarios, under a `RegistryGuard`
+/// so only the test's private registry is cleared.
 pub fn clear_registry() {
-    let mut registry = REGISTRY.lock().unwrap();
-    registry.clear();
+    with_registry(|registry| registry.clear());
 }
 
 /// Convenience macro for registering games
@@ -145,7 +355,11 @@ macro_rules! register_game {
             fn factory() -> Box<dyn $crate::erased::ErasedGame> {
                 Box::new($crate::adapter::GameAdapter::new(<$game_type>::default()))
             }
-            $crate::registry::register_game($env_id.to_string(), factory);
+            $crate::registry::register_game_with_warm_up(
+                $env_id.to_string(),
+                factory,
+                <$game_type as $crate::typed::Game>::warm_up,
+            );
         }
     };
 }
@@ -153,12 +367,12 @@ macro_rules! register_game {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::typed::{Game, EngineId, Capabilities, Encoding, ActionSpace};
+    use crate::typed::{Game, EngineId, Capabilities, Encoding, ActionSpace, StepResult};
     use crate::adapter::GameAdapter;
     use rand_chacha::ChaCha20Rng;
 
     // Test game implementation
-    #[derive(Default)]
+    #[derive(Default, Clone)]
     struct TestGame {
         name: String,
     }
@@ -200,9 +414,15 @@ mod tests {
             (0, vec![0.0])
         }
         
-        fn step(&mut self, state: &mut Self::State, action: Self::Action, _rng: &mut ChaCha20Rng) -> (Self::Obs, f32, bool) {
+        fn step(&mut self, state: &mut Self::State, action: Self::Action, _rng: &mut ChaCha20Rng) -> StepResult<Self::Obs> {
             *state += action as u32;
-            (vec![*state as f32], 1.0, *state >= 10)
+            StepResult {
+                obs: vec![*state as f32],
+                reward: 1.0,
+                done: *state >= 10,
+                truncated: false,
+                info: 0,
+            }
         }
         
         fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), crate::typed::EncodeError> {
@@ -239,8 +459,8 @@ mod tests {
 
     #[test]
     fn test_register_and_create_game() {
-        // Clear registry for clean test
-        clear_registry();
+        // Private registry for a clean test
+        let _registry = RegistryGuard::isolated();
         
         // Register a test game
         fn test_factory() -> Box<dyn ErasedGame> {
@@ -259,7 +479,7 @@ mod tests {
     
     #[test]
     fn test_create_nonexistent_game() {
-        clear_registry();
+        let _registry = RegistryGuard::isolated();
         
         let game = create_game("nonexistent");
         assert!(game.is_none());
@@ -267,7 +487,7 @@ mod tests {
     
     #[test]
     fn test_list_registered_games() {
-        clear_registry();
+        let _registry = RegistryGuard::isolated();
         
         fn factory1() -> Box<dyn ErasedGame> {
             Box::new(GameAdapter::new(TestGame::new("game1".to_string())))
@@ -287,7 +507,7 @@ mod tests {
     
     #[test]
     fn test_is_registered() {
-        clear_registry();
+        let _registry = RegistryGuard::isolated();
         
         fn factory() -> Box<dyn ErasedGame> {
             Box::new(GameAdapter::new(TestGame::new("registered_game".to_string())))
@@ -302,6 +522,8 @@ mod tests {
     
     #[test]
     fn test_clear_registry() {
+        let _registry = RegistryGuard::isolated();
+
         fn factory() -> Box<dyn ErasedGame> {
             Box::new(GameAdapter::new(TestGame::new("temp_game".to_string())))
         }
@@ -313,4 +535,158 @@ mod tests {
         assert!(!is_registered("temp_game"));
         assert!(list_registered_games().is_empty());
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_warm_up_runs_once_under_concurrent_create() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Barrier;
+        
+        static WARM_UPS: AtomicUsize = AtomicUsize::new(0);
+        
+        fn factory() -> Box<dyn ErasedGame> {
+            Box::new(GameAdapter::new(TestGame::new("warm_game".to_string())))
+        }
+        
+        register_game_with_warm_up("warm_game".to_string(), factory, || {
+            WARM_UPS.fetch_add(1, Ordering::SeqCst);
+        });
+        
+        let barrier = Arc::new(Barrier::new(16));
+        let handles: Vec<_> = (0..16)
+            .map(|_| {
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    create_game("warm_game").is_some()
+                })
+            })
+            .collect();
+        
+        for handle in handles {
+            assert!(handle.join().unwrap());
+        }
+        assert_eq!(WARM_UPS.load(Ordering::SeqCst), 1);
+        
+        create_game("warm_game").unwrap();
+        assert_eq!(WARM_UPS.load(Ordering::SeqCst), 1);
+    }
+    
+    #[test]
+    fn test_capability_overrides_keep_encoding() {
+        fn factory() -> Box<dyn ErasedGame> {
+            Box::new(GameAdapter::new(TestGame::new("base_game".to_string())))
+        }
+        
+        register_game_with_capabilities(
+            "tuned_game".to_string(),
+            factory,
+            CapabilityOverrides {
+                preferred_batch: Some(256),
+                max_horizon: Some(7),
+                ..Default::default()
+            },
+        );
+        
+        let mut game = create_game("tuned_game").unwrap();
+        let base = factory().capabilities();
+        let caps = game.capabilities();
+        assert_eq!(caps.id.env_id, "tuned_game");
+        assert_eq!(caps.id.build_id, base.id.build_id);
+        assert_eq!(caps.preferred_batch, 256);
+        assert_eq!(caps.max_horizon, 7);
+        assert_eq!(caps.encoding, base.encoding);
+        assert_eq!(caps.action_space, base.action_space);
+        
+        // Game logic is untouched
+        let (mut state, mut obs) = (Vec::new(), Vec::new());
+        game.reset(0, &[], &mut state, &mut obs).unwrap();
+        let outcome = game.step(&state.clone(), &[3], &mut state, &mut obs).unwrap();
+        assert_eq!(outcome.reward, 1.0);
+    }
+
+    #[test]
+    fn test_guarded_threads_register_without_interfering() {
+        use std::sync::Barrier;
+
+        fn game_a() -> Box<dyn ErasedGame> {
+            Box::new(GameAdapter::new(TestGame::new("game_a".to_string())))
+        }
+        fn game_b() -> Box<dyn ErasedGame> {
+            Box::new(GameAdapter::new(TestGame::new("game_b".to_string())))
+        }
+
+        // Both threads register the same env_id, then clear, at the same time
+        let barrier = Arc::new(Barrier::new(2));
+        let handles: Vec<_> = [("game_a", game_a as GameFactory), ("game_b", game_b)]
+            .into_iter()
+            .map(|(expected, factory)| {
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    let _registry = RegistryGuard::isolated();
+                    register_game("guarded_game".to_string(), factory);
+                    barrier.wait();
+                    let created = create_game("guarded_game").unwrap().engine_id().env_id;
+                    assert_eq!(created, expected);
+                    assert_eq!(list_registered_games(), vec!["guarded_game".to_string()]);
+                    barrier.wait();
+                    clear_registry();
+                    barrier.wait();
+                    assert!(!is_registered("guarded_game"));
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        // Neither thread touched the global registry
+        assert!(!is_registered("guarded_game"));
+    }
+
+    #[test]
+    fn test_nested_guards_restore_the_outer_registry() {
+        fn factory() -> Box<dyn ErasedGame> {
+            Box::new(GameAdapter::new(TestGame::new("outer_game".to_string())))
+        }
+
+        let _outer = RegistryGuard::isolated();
+        register_game("outer_game".to_string(), factory);
+        {
+            let _inner = RegistryGuard::isolated();
+            assert!(!is_registered("outer_game"));
+        }
+        assert!(is_registered("outer_game"));
+    }
+
+    #[test]
+    fn test_independent_registries_do_not_share_games() {
+        fn factory_a() -> Box<dyn ErasedGame> {
+            Box::new(GameAdapter::new(TestGame::new("tenant_a_game".to_string())))
+        }
+        fn factory_b() -> Box<dyn ErasedGame> {
+            Box::new(GameAdapter::new(TestGame::new("tenant_b_game".to_string())))
+        }
+
+        let tenant_a = Registry::new();
+        let tenant_b = Registry::new();
+        tenant_a.register_game("tenant_a_game".to_string(), factory_a);
+        tenant_b.register_game("tenant_b_game".to_string(), factory_b);
+
+        assert_eq!(tenant_a.list_registered_games(), vec!["tenant_a_game".to_string()]);
+        assert_eq!(tenant_b.list_registered_games(), vec!["tenant_b_game".to_string()]);
+        assert!(tenant_a.create_game("tenant_b_game").is_none());
+        assert!(tenant_b.create_game("tenant_a_game").is_none());
+        assert_eq!(
+            tenant_a.create_game("tenant_a_game").unwrap().engine_id().env_id,
+            "tenant_a_game"
+        );
+
+        // The global registry sees neither
+        assert!(!is_registered("tenant_a_game"));
+        assert!(!is_registered("tenant_b_game"));
+
+        tenant_a.clear();
+        assert!(!tenant_a.is_registered("tenant_a_game"));
+        assert!(tenant_b.is_registered("tenant_b_game"));
+    }
+}
@@ -0,0 +1,146 @@
+//! Serde-backed state encoding for prototyping games
+//!
+//! Hand-written `encode_state`/`decode_state` pairs are the right choice for
+//! shipped games, since their byte layout is documented and stable. While a
+//! game is still changing shape, `serde_state_codec!()` fills both methods in
+//! from the state's `Serialize`/`Deserialize` impls using bincode. Only the
+//! state bytes change; the adapter, service, and clients behave exactly as
+//! they would with a hand-written codec.
+//!
+//! ```rust
+//! # use engine_core::typed::*;
+//! # use rand_chacha::ChaCha20Rng;
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Position {
+//!     cells: Vec<u8>,
+//!     to_move: u8,
+//! }
+//!
+//! # struct Prototype;
+//! impl Game for Prototype {
+//!     type State = Position;
+//! #   type Action = u8;
+//! #   type Obs = Vec<f32>;
+//! #   fn engine_id(&self) -> EngineId { todo!() }
+//! #   fn capabilities(&self) -> Capabilities { todo!() }
+//! #   fn reset(&mut self, rng: &mut ChaCha20Rng, hint: &[u8]) -> (Self::State, Self::Obs) { todo!() }
+//! #   fn step(
+//! #       &mut self,
+//! #       state: &mut Self::State,
+//! #       action: Self::Action,
+//! #       rng: &mut ChaCha20Rng,
+//! #   ) -> StepResult<Self::Obs> { todo!() }
+//! #   fn encode_action(action: &Self::Action, out: &mut Vec<u8>) -> Result<(), EncodeError> { todo!() }
+//! #   fn decode_action(buf: &[u8]) -> Result<Self::Action, DecodeError> { todo!() }
+//! #   fn encode_obs(obs: &Self::Obs, out: &mut Vec<u8>) -> Result<(), EncodeError> { todo!() }
+//!     // ...
+//!
+//!     engine_core::serde_state_codec!();
+//! }
+//! ```
+
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::typed::{DecodeError, EncodeError};
+
+/// A game state encoded with bincode through its serde impls
+///
+/// Encoding borrows the state (`SerdeState(&state)`), decoding produces an
+/// owned one. Trailing bytes are rejected so truncated or concatenated
+/// buffers fail to decode instead of silently losing data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerdeState<S>(pub S);
+
+fn options() -> impl Options {
+    bincode::DefaultOptions::new().reject_trailing_bytes()
+}
+
+impl<S: Serialize> SerdeState<S> {
+    /// Append the bincode encoding of the wrapped state to `out`
+    pub fn encode(&self, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        options()
+            .serialize_into(out, &self.0)
+            .map_err(|e| EncodeError::SerializationError(e.to_string()))
+    }
+}
+
+impl<S: DeserializeOwned> SerdeState<S> {
+    /// Decode a state previously written by `encode`
+    pub fn decode(buf: &[u8]) -> Result<Self, DecodeError> {
+        options()
+            .deserialize(buf)
+            .map(SerdeState)
+            .map_err(|e| DecodeError::DeserializationError(e.to_string()))
+    }
+}
+
+/// Implement `Game::encode_state` and `Game::decode_state` with `SerdeState`
+///
+/// Use inside an `impl Game` block whose `State` implements
+/// `serde::Serialize` and `serde::de::DeserializeOwned`.
+#[macro_export]
+macro_rules! serde_state_codec {
+    () => {
+        fn encode_state(
+            state: &Self::State,
+            out: &mut Vec<u8>,
+        ) -> Result<(), $crate::typed::EncodeError> {
+            $crate::serde_state::SerdeState(state).encode(out)
+        }
+
+        fn decode_state(buf: &[u8]) -> Result<Self::State, $crate::typed::DecodeError> {
+            $crate::serde_state::SerdeState::decode(buf).map(|state| state.0)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Board {
+        cells: Vec<u8>,
+        to_move: u8,
+        winner: Option<u8>,
+    }
+
+    #[test]
+    fn test_serde_state_roundtrip() {
+        let board = Board {
+            cells: vec![0, 1, 2, 0, 1],
+            to_move: 2,
+            winner: None,
+        };
+
+        let mut buf = Vec::new();
+        SerdeState(&board).encode(&mut buf).unwrap();
+        assert_eq!(SerdeState::<Board>::decode(&buf).unwrap().0, board);
+    }
+
+    #[test]
+    fn test_serde_state_rejects_truncated_and_padded_buffers() {
+        let board = Board {
+            cells: vec![1; 9],
+            to_move: 1,
+            winner: Some(1),
+        };
+        let mut buf = Vec::new();
+        SerdeState(&board).encode(&mut buf).unwrap();
+
+        let truncated = &buf[..buf.len() - 1];
+        assert!(matches!(
+            SerdeState::<Board>::decode(truncated),
+            Err(DecodeError::DeserializationError(_))
+        ));
+
+        buf.push(0);
+        assert!(matches!(
+            SerdeState::<Board>::decode(&buf),
+            Err(DecodeError::DeserializationError(_))
+        ));
+    }
+}
@@ -0,0 +1,277 @@
+//! Episode progress observations for feed-forward policies
+//!
+//! `StepNumberObs<G>` wraps a game and appends one little-endian `f32` to
+//! every observation: the number of steps taken so far divided by the game's
+//! `max_horizon`, clamped to `[0, 1]`. Reset reports 0. Like `ObsStack`, the
+//! step count travels inside the encoded state, so the server stays
+//! stateless and any client-held state resumes with the right progress.
+//!
+//! ```rust,ignore
+//! register_game("tictactoe-progress".to_string(), || {
+//!     Box::new(GameAdapter::new(StepNumberObs::new(TicTacToe::new())))
+//! });
+//! ```
+
+use rand_chacha::ChaCha20Rng;
+
+use crate::obs_stack::{read_chunk, write_len, LEN_PREFIX};
+use crate::typed::{
+    Capabilities, DecodeError, EncodeError, EngineId, Game, InitialStateKind, ObsBounds, ObsLayout,
+    StepResult,
+};
+
+/// Bytes of the encoded step count
+const STEP_SIZE: usize = std::mem::size_of::<u32>();
+
+const F32_SIZE: usize = std::mem::size_of::<f32>();
+
+/// Game wrapper whose observation ends with the normalized step number
+#[derive(Clone)]
+pub struct StepNumberObs<G: Game> {
+    inner: G,
+    /// Horizon the step count is normalized by, read once from `inner`
+    max_horizon: u32,
+}
+
+impl<G: Game> StepNumberObs<G> {
+    /// Append the normalized step number to the observations of `inner`
+    ///
+    /// A game reporting a `max_horizon` of zero is normalized as if it were
+    /// one, so the scalar is 1 from the first step on.
+    pub fn new(inner: G) -> Self {
+        let max_horizon = inner.capabilities().max_horizon;
+        Self { inner, max_horizon }
+    }
+
+    /// The wrapped game
+    pub fn inner(&self) -> &G {
+        &self.inner
+    }
+
+    /// `step / max_horizon`, clamped to `[0, 1]`
+    pub fn progress(&self, step: u32) -> f32 {
+        (step as f32 / self.max_horizon.max(1) as f32).min(1.0)
+    }
+
+    /// Encoded inner observation followed by the progress scalar
+    fn progress_obs(&self, obs: &G::Obs, step: u32) -> Vec<u8> {
+        let mut out = Vec::with_capacity(G::OBS_ENCODED_SIZE.unwrap_or(0) + F32_SIZE);
+        // Observations are encoded eagerly so the scalar can be appended
+        G::encode_obs(obs, &mut out)
+            .unwrap_or_else(|e| panic!("StepNumberObs: wrapped game failed to encode obs: {}", e));
+        out.extend_from_slice(&self.progress(step).to_le_bytes());
+        out
+    }
+}
+
+/// State of a step-numbered game: the wrapped state plus the steps taken
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepNumberState<S> {
+    pub inner: S,
+    /// Steps taken since reset
+    pub step: u32,
+}
+
+/// Layout: `[u32 len][inner state][u32 step]`, little-endian
+impl<G: Game> Game for StepNumberObs<G> {
+    type State = StepNumberState<G::State>;
+    type Action = G::Action;
+    /// Encoded wrapped observation followed by one `f32` progress value
+    type Obs = Vec<u8>;
+
+    const STATE_ENCODED_SIZE: Option<usize> = match G::STATE_ENCODED_SIZE {
+        Some(state) => Some(LEN_PREFIX + state + STEP_SIZE),
+        None => None,
+    };
+    const OBS_ENCODED_SIZE: Option<usize> = match G::OBS_ENCODED_SIZE {
+        Some(obs) => Some(obs + F32_SIZE),
+        None => None,
+    };
+    const ACTION_ENCODED_SIZE: Option<usize> = G::ACTION_ENCODED_SIZE;
+
+    fn engine_id(&self) -> EngineId {
+        self.inner.engine_id()
+    }
+
+    /// The wrapped capabilities with the step number declared in the encodings
+    ///
+    /// `stepnum(f32x29:v1)` names a `f32x29:v1` observation followed by one
+    /// `f32` progress value.
+    fn capabilities(&self) -> Capabilities {
+        let mut caps = self.inner.capabilities();
+        caps.encoding.state = format!("stepnum({})", caps.encoding.state);
+        caps.encoding.obs = format!("stepnum({})", caps.encoding.obs);
+        caps
+    }
+
+    fn initial_state_kind(&self) -> InitialStateKind {
+        self.inner.initial_state_kind()
+    }
+
+    fn action_names(&self) -> Option<Vec<String>> {
+        self.inner.action_names()
+    }
+
+    /// The wrapped bounds followed by `[0, 1]` for the progress value
+    fn obs_bounds(&self) -> Option<ObsBounds> {
+        self.inner.obs_bounds().map(|mut bounds| {
+            bounds.low.push(0.0);
+            bounds.high.push(1.0);
+            bounds
+        })
+    }
+
+    /// The wrapped segments followed by `step_number`
+    fn obs_layout(&self) -> Option<ObsLayout> {
+        self.inner.obs_layout().map(|mut layout| {
+            layout.segments.push(("step_number".to_string(), 1));
+            layout
+        })
+    }
+
+    fn is_deterministic(&self) -> bool {
+        self.inner.is_deterministic()
+    }
+
+    fn is_state_complete(&self) -> bool {
+        self.inner.is_state_complete()
+    }
+
+    fn current_player(&self, state: &Self::State) -> Option<u32> {
+        self.inner.current_player(&state.inner)
+    }
+
+    fn must_pass(&self, state: &Self::State) -> bool {
+        self.inner.must_pass(&state.inner)
+    }
+
+    fn legal_actions(&self, state: &Self::State) -> Option<Vec<Self::Action>> {
+        self.inner.legal_actions(&state.inner)
+    }
+
+    fn legal_action_count(&self, state: &Self::State) -> Option<u32> {
+        self.inner.legal_action_count(&state.inner)
+    }
+
+    fn pass_action(&self) -> Option<Self::Action> {
+        self.inner.pass_action()
+    }
+
+    fn step_info(&self, state: &Self::State) -> Vec<(String, f32)> {
+        self.inner.step_info(&state.inner)
+    }
+
+    fn reset(&mut self, rng: &mut ChaCha20Rng, hint: &[u8]) -> (Self::State, Self::Obs) {
+        let (inner, obs) = self.inner.reset(rng, hint);
+        let obs = self.progress_obs(&obs, 0);
+        (StepNumberState { inner, step: 0 }, obs)
+    }
+
+    fn step(
+        &mut self,
+        state: &mut Self::State,
+        action: Self::Action,
+        rng: &mut ChaCha20Rng,
+    ) -> StepResult<Self::Obs> {
+        let result = self.inner.step(&mut state.inner, action, rng);
+        state.step = state.step.saturating_add(1);
+        StepResult {
+            obs: self.progress_obs(&result.obs, state.step),
+            reward: result.reward,
+            done: result.done,
+            truncated: result.truncated,
+            info: result.info,
+        }
+    }
+
+    fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        let start = out.len();
+        out.extend_from_slice(&[0; LEN_PREFIX]);
+        G::encode_state(&state.inner, out)?;
+        write_len(out, start)?;
+        out.extend_from_slice(&state.step.to_le_bytes());
+        Ok(())
+    }
+
+    fn decode_state(buf: &[u8]) -> Result<Self::State, DecodeError> {
+        let (inner, rest) = read_chunk(buf)?;
+        let inner = G::decode_state(inner)?;
+
+        let step: [u8; STEP_SIZE] = rest.try_into().map_err(|_| DecodeError::InvalidLength {
+            expected: STEP_SIZE,
+            actual: rest.len(),
+        })?;
+        Ok(StepNumberState {
+            inner,
+            step: u32::from_le_bytes(step),
+        })
+    }
+
+    fn encode_action(action: &Self::Action, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        G::encode_action(action, out)
+    }
+
+    fn decode_action(buf: &[u8]) -> Result<Self::Action, DecodeError> {
+        G::decode_action(buf)
+    }
+
+    fn encode_obs(obs: &Self::Obs, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        out.extend_from_slice(obs);
+        Ok(())
+    }
+
+    fn warm_up() {
+        G::warm_up()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_game::ValuesGame;
+    use rand::SeedableRng;
+
+    fn progress(obs: &[u8]) -> f32 {
+        f32::from_le_bytes(obs[obs.len() - F32_SIZE..].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_zero_max_horizon_reports_full_progress_after_one_step() {
+        let inner = ValuesGame {
+            max_horizon: 0,
+            ..ValuesGame::new([0.0; 2])
+        };
+        let mut game = StepNumberObs::new(inner);
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+
+        let (mut state, obs) = game.reset(&mut rng, &[]);
+        assert_eq!(progress(&obs), 0.0);
+        let result = game.step(&mut state, Some(0), &mut rng);
+        assert_eq!(progress(&result.obs), 1.0);
+    }
+
+    #[test]
+    fn test_step_count_survives_a_state_round_trip() {
+        let mut game = StepNumberObs::new(ValuesGame::new([0.0; 2]));
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let (mut state, _) = game.reset(&mut rng, &[]);
+        game.step(&mut state, Some(1), &mut rng);
+        game.step(&mut state, None, &mut rng);
+
+        let mut buf = Vec::new();
+        StepNumberObs::<ValuesGame<2>>::encode_state(&state, &mut buf).unwrap();
+        assert_eq!(
+            Some(buf.len()),
+            StepNumberObs::<ValuesGame<2>>::STATE_ENCODED_SIZE
+        );
+        let decoded = StepNumberObs::<ValuesGame<2>>::decode_state(&buf).unwrap();
+        assert_eq!(
+            decoded,
+            StepNumberState {
+                inner: [0.0, 1.0],
+                step: 2
+            }
+        );
+        assert_eq!(game.progress(decoded.step), 0.2);
+    }
+}
@@ -0,0 +1,135 @@
+//! Minimal game shared by the observation wrapper unit tests
+
+use rand_chacha::ChaCha20Rng;
+
+use crate::typed::{
+    ActionSpace, Capabilities, DecodeError, EncodeError, Encoding, EngineId, Game, ObsBounds,
+    StepResult,
+};
+
+const F32_SIZE: usize = std::mem::size_of::<f32>();
+
+/// Game whose state and observation are `N` `f32` values
+///
+/// Reset starts from `start`; action `Some(i)` adds one to value `i`, and
+/// `None` is a pass that encodes to no bytes and changes nothing.
+#[derive(Clone)]
+pub(crate) struct ValuesGame<const N: usize> {
+    pub start: [f32; N],
+    pub bounds: Option<ObsBounds>,
+    pub max_horizon: u32,
+}
+
+impl<const N: usize> ValuesGame<N> {
+    /// Start from `start` with every value bounded to `[0, 10]`
+    pub fn new(start: [f32; N]) -> Self {
+        Self {
+            start,
+            bounds: Some(ObsBounds {
+                low: vec![0.0; N],
+                high: vec![10.0; N],
+            }),
+            max_horizon: 10,
+        }
+    }
+}
+
+impl<const N: usize> Game for ValuesGame<N> {
+    type State = [f32; N];
+    type Action = Option<u8>;
+    type Obs = [f32; N];
+
+    const STATE_ENCODED_SIZE: Option<usize> = Some(N * F32_SIZE);
+    const OBS_ENCODED_SIZE: Option<usize> = Some(N * F32_SIZE);
+
+    fn engine_id(&self) -> EngineId {
+        EngineId {
+            env_id: "values".to_string(),
+            build_id: "0.1.0".to_string(),
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            id: self.engine_id(),
+            encoding: Encoding {
+                state: format!("f32x{}:v1", N),
+                action: "u8:v1".to_string(),
+                obs: format!("f32x{}:v1", N),
+                schema_version: 1,
+            },
+            max_horizon: self.max_horizon,
+            action_space: ActionSpace::Discrete(N as u32),
+            preferred_batch: 1,
+        }
+    }
+
+    fn obs_bounds(&self) -> Option<ObsBounds> {
+        self.bounds.clone()
+    }
+
+    fn pass_action(&self) -> Option<Self::Action> {
+        Some(None)
+    }
+
+    fn reset(&mut self, _rng: &mut ChaCha20Rng, _hint: &[u8]) -> (Self::State, Self::Obs) {
+        (self.start, self.start)
+    }
+
+    fn step(
+        &mut self,
+        state: &mut Self::State,
+        action: Self::Action,
+        _rng: &mut ChaCha20Rng,
+    ) -> StepResult<Self::Obs> {
+        if let Some(index) = action {
+            state[index as usize] += 1.0;
+        }
+        StepResult {
+            obs: *state,
+            reward: 0.0,
+            done: false,
+            truncated: false,
+            info: 0,
+        }
+    }
+
+    fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        Self::encode_obs(state, out)
+    }
+
+    fn decode_state(buf: &[u8]) -> Result<Self::State, DecodeError> {
+        if buf.len() != N * F32_SIZE {
+            return Err(DecodeError::InvalidLength {
+                expected: N * F32_SIZE,
+                actual: buf.len(),
+            });
+        }
+        let mut state = [0.0; N];
+        for (value, chunk) in state.iter_mut().zip(buf.chunks_exact(F32_SIZE)) {
+            *value = f32::from_le_bytes(chunk.try_into().unwrap());
+        }
+        Ok(state)
+    }
+
+    fn encode_action(action: &Self::Action, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        out.extend(action);
+        Ok(())
+    }
+
+    fn decode_action(buf: &[u8]) -> Result<Self::Action, DecodeError> {
+        match buf {
+            [] => Ok(None),
+            &[index] => Ok(Some(index)),
+            _ => Err(DecodeError::InvalidLength {
+                expected: 1,
+                actual: buf.len(),
+            }),
+        }
+    }
+
+    fn encode_obs(obs: &Self::Obs, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        out.extend(obs.iter().flat_map(|value| value.to_le_bytes()));
+        Ok(())
+    }
+}
@@ -33,6 +33,86 @@ pub enum ActionSpace {
     },
 }
 
+/// Packed auxiliary info bits returned with each step (game-specific semantics)
+pub type InfoPayload = u64;
+
+/// Result of a typed `Game::step`
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepResult<O> {
+    /// Observation after the step
+    pub obs: O,
+    /// Reward received from this step
+    pub reward: f32,
+    /// Whether the episode reached a terminal state
+    pub done: bool,
+    /// Whether the episode was cut short (e.g. a horizon limit) rather than terminated
+    pub truncated: bool,
+    /// Auxiliary info bits
+    pub info: InfoPayload,
+}
+
+/// How a game's reset initial states are distributed
+///
+/// Purely informational: it lets tooling (e.g. curriculum schedulers) decide
+/// whether varying the seed or hint actually varies the start of an episode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InitialStateKind {
+    /// Every reset produces the same initial state
+    Fixed,
+    /// The initial state is drawn from the seeded RNG
+    #[default]
+    SeededRandom,
+    /// The initial state is selected by the reset hint
+    HintDriven,
+}
+
+/// Per-dimension observation ranges declared by a game
+///
+/// Describes an observation encoded as packed little-endian `f32` values, one
+/// per dimension, expected to lie within `[low[i], high[i]]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObsBounds {
+    pub low: Vec<f32>,
+    pub high: Vec<f32>,
+}
+
+/// Per-dimension affine map from quantized `u8` observation values to `f32`
+///
+/// Describes an observation encoded as one `u8` per dimension; value `q` of
+/// dimension `i` stands for `scale[i] * (q - zero_point[i])`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObsQuantization {
+    pub scale: Vec<f32>,
+    pub zero_point: Vec<f32>,
+}
+
+impl ObsQuantization {
+    /// Map a quantized observation back to one `f32` per dimension
+    pub fn dequantize(&self, obs: &[u8]) -> Vec<f32> {
+        obs.iter()
+            .zip(self.scale.iter().zip(&self.zero_point))
+            .map(|(&q, (&scale, &zero_point))| scale * (f32::from(q) - zero_point))
+            .collect()
+    }
+}
+
+/// Named consecutive segments of an observation, in order
+///
+/// Each segment is `(name, size)` with the size counted in observation
+/// values (one `f32` each for an `f32x29:v1` observation), so the sizes sum
+/// to the number of values in the observation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObsLayout {
+    pub segments: Vec<(String, usize)>,
+}
+
+impl ObsLayout {
+    /// Observation values covered by all segments
+    pub fn total(&self) -> usize {
+        self.segments.iter().map(|(_, size)| size).sum()
+    }
+}
+
 /// Game capabilities and configuration
 #[derive(Debug, Clone, PartialEq)]
 pub struct Capabilities {
@@ -43,6 +123,18 @@ pub struct Capabilities {
     pub preferred_batch: u32,
 }
 
+impl Capabilities {
+    /// Whether data encoded for `self` can be exchanged with `other` unchanged
+    ///
+    /// True only when the state, action, and obs encodings, the schema
+    /// version, and the action space all match. Tuning fields that do not
+    /// affect the bytes on the wire (engine id, `max_horizon`,
+    /// `preferred_batch`) are ignored.
+    pub fn is_wire_compatible(&self, other: &Capabilities) -> bool {
+        self.encoding == other.encoding && self.action_space == other.action_space
+    }
+}
+
 /// Main trait for game implementations
 ///
 /// Games should implement this trait with their specific types for State, Action, and Obs.
@@ -94,7 +186,7 @@ pub struct Capabilities {
 /// #       state: &mut Self::State,
 /// #       action: Self::Action,
 /// #       rng: &mut ChaCha20Rng,
-/// #   ) -> (Self::Obs, f32, bool, u64) { todo!() }
+/// #   ) -> StepResult<Self::Obs> { todo!() }
 /// #   fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> { todo!() }
 /// #   fn decode_state(buf: &[u8]) -> Result<Self::State, DecodeError> { todo!() }
 /// #   fn encode_action(action: &Self::Action, out: &mut Vec<u8>) -> Result<(), EncodeError> { todo!() }
@@ -112,12 +204,138 @@ pub trait Game: Send + Sync + 'static {
     /// Observation type - often contiguous arrays of f32
     type Obs: Send + Sync + 'static;
 
+    /// Encoded state size in bytes, or `None` if it varies
+    const STATE_ENCODED_SIZE: Option<usize> = None;
+
+    /// Encoded observation size in bytes, or `None` if it varies
+    const OBS_ENCODED_SIZE: Option<usize> = None;
+
+    /// Encoded action size in bytes, or `None` if it varies
+    const ACTION_ENCODED_SIZE: Option<usize> = None;
+
     /// Get engine identification information
     fn engine_id(&self) -> EngineId;
 
     /// Get game capabilities and configuration
     fn capabilities(&self) -> Capabilities;
 
+    /// Describe how `reset` initial states are distributed
+    ///
+    /// Informational only; it does not affect execution. Defaults to
+    /// `SeededRandom`, the conservative assumption that the seed matters.
+    fn initial_state_kind(&self) -> InitialStateKind {
+        InitialStateKind::default()
+    }
+
+    /// Human-readable names for discrete actions, indexed by action number
+    ///
+    /// Only meaningful for `ActionSpace::Discrete`; the adapter drops names for
+    /// any other space. Defaults to `None`.
+    fn action_names(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Declared observation ranges used by the adapter's optional normalization
+    ///
+    /// Only consulted when normalization is enabled with
+    /// `GameAdapter::with_obs_normalization`. Defaults to `None`, which passes
+    /// observations through unchanged.
+    fn obs_bounds(&self) -> Option<ObsBounds> {
+        None
+    }
+
+    /// How to dequantize the observation, for games emitting quantized `u8`
+    /// observations
+    ///
+    /// Defaults to `None`: the observation is not quantized.
+    fn obs_quantization(&self) -> Option<ObsQuantization> {
+        None
+    }
+
+    /// Names and sizes of the observation's segments, e.g. board and legal
+    /// move mask, so clients can tell them apart
+    ///
+    /// Defaults to `None`, which leaves the observation an unnamed run of
+    /// values.
+    fn obs_layout(&self) -> Option<ObsLayout> {
+        None
+    }
+
+    /// Upper bound on the number of distinct states the game can reach
+    ///
+    /// Informational only; it lets tooling decide whether exhaustive testing
+    /// is feasible. Defaults to `None`, which games with unbounded or
+    /// continuous state spaces should keep.
+    fn state_space_estimate(&self) -> Option<u64> {
+        None
+    }
+
+    /// Whether `step` ignores the RNG, so `(state, action)` fixes the result
+    ///
+    /// Defaults to `false`. Together with `is_state_complete` this allows the
+    /// server to memoize step results.
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+
+    /// Whether the encoded state captures everything `step` depends on
+    ///
+    /// Return `false` if the game instance keeps mutable data between calls.
+    /// Defaults to `false`.
+    fn is_state_complete(&self) -> bool {
+        false
+    }
+
+    /// Player to move in `state` for multi-player games
+    ///
+    /// Lets self-play clients route each decision to the right policy.
+    /// Defaults to `None` for single-agent games.
+    fn current_player(&self, _state: &Self::State) -> Option<u32> {
+        None
+    }
+
+    /// Whether the player to move in `state` has no legal moves but the game goes on
+    ///
+    /// While this holds the player must pass: the only accepted action is an
+    /// empty action buffer, which the adapter steps as `pass_action()` so the
+    /// game can advance the turn. Any other action is rejected. Never return
+    /// `true` for a terminal state. Defaults to `false`.
+    fn must_pass(&self, _state: &Self::State) -> bool {
+        false
+    }
+
+    /// Legal actions in `state`, or `None` if the game does not enumerate them
+    ///
+    /// Terminal states have no legal actions. Defaults to `None`.
+    fn legal_actions(&self, _state: &Self::State) -> Option<Vec<Self::Action>> {
+        None
+    }
+
+    /// Number of legal actions in `state`, or `None` if unknown
+    ///
+    /// Defaults to the length of `legal_actions`. Override it when the count
+    /// is cheaper to compute than the actions themselves (e.g. a popcount).
+    fn legal_action_count(&self, state: &Self::State) -> Option<u32> {
+        self.legal_actions(state).map(|actions| actions.len() as u32)
+    }
+
+    /// Action stepped when the player passes
+    ///
+    /// Must be `Some` for any game whose `must_pass` can return `true`.
+    fn pass_action(&self) -> Option<Self::Action> {
+        None
+    }
+
+    /// Named diagnostics for the state a step just produced
+    ///
+    /// Complements the `info` bits of `StepResult` with values too rich for
+    /// a bitmask (e.g. `("captured", 1.0)`). Called after every step;
+    /// `GameAdapter::last_step_info` holds the result, and the server sends
+    /// it as the `step_info` map of the step response. Defaults to empty.
+    fn step_info(&self, _state: &Self::State) -> Vec<(String, f32)> {
+        Vec::new()
+    }
+
     /// Reset the game to initial state
     ///
     /// # Arguments
@@ -140,13 +358,13 @@ pub trait Game: Send + Sync + 'static {
     ///
     /// # Returns
     ///
-    /// A tuple of (observation, reward, done, info)
+    /// The observation, reward, termination flags, and info bits for this step
     fn step(
         &mut self,
         state: &mut Self::State,
         action: Self::Action,
         rng: &mut ChaCha20Rng,
-    ) -> (Self::Obs, f32, bool, u64);
+    ) -> StepResult<Self::Obs>;
 
     // Encoding/Decoding hooks for serialization
 
@@ -164,6 +382,58 @@ pub trait Game: Send + Sync + 'static {
 
     /// Encode observation to bytes
     fn encode_obs(obs: &Self::Obs, out: &mut Vec<u8>) -> Result<(), EncodeError>;
+
+    /// Encode observation into the start of `out`, returning the bytes written
+    ///
+    /// Lets callers reuse a preallocated slice instead of growing a `Vec`.
+    /// The default encodes through `encode_obs` into a temporary `Vec` and
+    /// copies it, so it works for variable-size observations; games with a
+    /// fixed `OBS_ENCODED_SIZE` can override it to write in place. Fails with
+    /// `EncodeError::BufferTooSmall` when `out` cannot hold the observation.
+    fn encode_obs_into(obs: &Self::Obs, out: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut buf = Vec::with_capacity(Self::OBS_ENCODED_SIZE.unwrap_or(0));
+        Self::encode_obs(obs, &mut buf)?;
+        let available = out.len();
+        let dest = out.get_mut(..buf.len()).ok_or(EncodeError::BufferTooSmall {
+            needed: buf.len(),
+            available,
+        })?;
+        dest.copy_from_slice(&buf);
+        Ok(buf.len())
+    }
+
+    /// Migrate state bytes from an older schema version to the current format
+    ///
+    /// Called when incoming state was encoded with `from_version`, which differs
+    /// from the `schema_version` declared in `capabilities()`. Implementations
+    /// return bytes that `decode_state` accepts. The default rejects any other version.
+    fn migrate_state(buf: &[u8], from_version: u32) -> Result<Vec<u8>, DecodeError> {
+        let _ = buf;
+        Err(DecodeError::UnsupportedVersion {
+            version: from_version,
+        })
+    }
+
+    /// Schema versions whose encoded state this game accepts
+    ///
+    /// Games that upgrade older versions in `migrate_state` list them here so
+    /// clients can check the server still supports their encoding. The
+    /// default is only the `schema_version` declared in `capabilities()`;
+    /// `GameAdapter` always adds that version if an override leaves it out.
+    fn supported_schema_versions(&self) -> Vec<u32> {
+        vec![self.capabilities().encoding.schema_version]
+    }
+
+    /// Build shared tables (e.g. zobrist keys, line masks) before first use
+    ///
+    /// When registered through `register_game!`, the registry runs this exactly
+    /// once per env_id on the first `create_game`, so instance construction can
+    /// stay cheap. The default does nothing.
+    fn warm_up()
+    where
+        Self: Sized,
+    {
+    }
 }
 
 /// Error type for encoding operations
@@ -243,14 +513,15 @@ mod tests {
             state: &mut Self::State,
             action: Self::Action,
             _rng: &mut ChaCha20Rng,
-        ) -> (Self::Obs, f32, bool, u64) {
+        ) -> StepResult<Self::Obs> {
             state.0 += action.0 as u32;
-            (
-                TestObs(vec![state.0 as f32]),
-                1.0,
-                state.0 >= 10,
-                state.0 as u64,
-            )
+            StepResult {
+                obs: TestObs(vec![state.0 as f32]),
+                reward: 1.0,
+                done: state.0 >= 10,
+                truncated: false,
+                info: state.0 as u64,
+            }
         }
 
         fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> {
@@ -334,9 +605,58 @@ mod tests {
         let mut rng = ChaCha20Rng::seed_from_u64(7);
         let mut state = TestState(0);
 
-        let (_obs, _reward, done, info) = game.step(&mut state, TestAction(2), &mut rng);
+        let result = game.step(&mut state, TestAction(2), &mut rng);
+
+        assert!(!result.done);
+        assert!(!result.truncated);
+        assert_eq!(result.info, state.0 as u64);
+    }
+
+    fn wire_caps() -> Capabilities {
+        Capabilities {
+            id: EngineId {
+                env_id: "test".to_string(),
+                build_id: "1".to_string(),
+            },
+            encoding: Encoding {
+                state: "u32:v1".to_string(),
+                action: "u8:v1".to_string(),
+                obs: "f32x1:v1".to_string(),
+                schema_version: 1,
+            },
+            max_horizon: 100,
+            action_space: ActionSpace::Discrete(4),
+            preferred_batch: 32,
+        }
+    }
+
+    #[test]
+    fn test_wire_compatible_ignores_non_wire_fields() {
+        let mut other = wire_caps();
+        other.id.build_id = "2".to_string();
+        other.max_horizon = 500;
+        other.preferred_batch = 1;
+
+        assert!(wire_caps().is_wire_compatible(&other));
+        assert!(other.is_wire_compatible(&wire_caps()));
+    }
 
-        assert!(!done);
-        assert_eq!(info, state.0 as u64);
+    #[test]
+    fn test_wire_incompatible_when_encoding_or_action_space_differs() {
+        let caps = wire_caps();
+        let changes: [fn(&mut Capabilities); 6] = [
+            |c| c.encoding.state = "u32:v2".to_string(),
+            |c| c.encoding.action = "u16:v1".to_string(),
+            |c| c.encoding.obs = "f32x2:v1".to_string(),
+            |c| c.encoding.schema_version = 2,
+            |c| c.action_space = ActionSpace::Discrete(5),
+            |c| c.action_space = ActionSpace::MultiDiscrete(vec![4]),
+        ];
+
+        for (i, change) in changes.iter().enumerate() {
+            let mut other = wire_caps();
+            change(&mut other);
+            assert!(!caps.is_wire_compatible(&other), "change {} went unnoticed", i);
+        }
     }
 }
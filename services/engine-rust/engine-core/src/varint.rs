@@ -0,0 +1,146 @@
+//! Variable-length (LEB128) encoding of discrete actions
+//!
+//! Discrete actions are normally encoded in a fixed width chosen from the
+//! size of the action space. Games with very large action spaces, or ones
+//! whose common actions are small indices, can encode them as unsigned
+//! LEB128 instead: seven bits per byte, least significant group first, with
+//! the high bit set on every byte but the last. Indices below 128 take one
+//! byte and any `u32` fits in five.
+//!
+//! A game opts in by declaring `VARINT_ACTION_ENCODING` as its action
+//! encoding in `capabilities()` and encoding its actions with
+//! `encode_discrete_action` / `decode_discrete_action`. Clients select the
+//! same format from that encoding name, so both sides agree through the
+//! capabilities alone.
+//!
+//! Decoding only accepts the shortest encoding of each index, so every action
+//! has exactly one byte form and byte-keyed caches see it once.
+
+use crate::typed::{DecodeError, EncodeError};
+
+/// Action encoding name declaring LEB128 discrete actions
+pub const VARINT_ACTION_ENCODING: &str = "discrete_varint:v1";
+
+/// Longest LEB128 encoding of a `u32`
+pub const MAX_VARINT_LEN: usize = 5;
+
+const PAYLOAD_BITS: u32 = 7;
+const CONTINUATION: u8 = 0x80;
+
+/// Append `index` as LEB128, failing if it is not below `n`
+pub fn encode_discrete_action(index: u32, n: u32, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+    if index >= n {
+        return Err(EncodeError::InvalidData(format!(
+            "Action index {} out of range for n={}",
+            index, n
+        )));
+    }
+    let mut rest = index;
+    while rest >= u32::from(CONTINUATION) {
+        out.push(rest as u8 | CONTINUATION);
+        rest >>= PAYLOAD_BITS;
+    }
+    out.push(rest as u8);
+    Ok(())
+}
+
+/// Decode a LEB128 action that must span all of `buf` and be below `n`
+///
+/// # Errors
+///
+/// Returns `DecodeError::InvalidLength` for an empty or unterminated buffer,
+/// and `CorruptedData` for trailing bytes, overlong or non-shortest
+/// encodings, and indices of `n` or more.
+pub fn decode_discrete_action(buf: &[u8], n: u32) -> Result<u32, DecodeError> {
+    let end =
+        buf.iter()
+            .position(|byte| byte & CONTINUATION == 0)
+            .ok_or(DecodeError::InvalidLength {
+                expected: buf.len() + 1,
+                actual: buf.len(),
+            })?;
+    if end + 1 != buf.len() {
+        return Err(DecodeError::CorruptedData(format!(
+            "Varint action ends after {} bytes but the buffer has {}",
+            end + 1,
+            buf.len()
+        )));
+    }
+    if end >= MAX_VARINT_LEN || (end > 0 && buf[end] == 0) {
+        return Err(DecodeError::CorruptedData(format!(
+            "Varint action {:02x?} is not the shortest encoding of a u32",
+            buf
+        )));
+    }
+
+    let index = buf.iter().enumerate().try_fold(0u32, |index, (i, &byte)| {
+        let group = u32::from(byte & !CONTINUATION);
+        let shift = i as u32 * PAYLOAD_BITS;
+        group
+            .checked_shl(shift)
+            .filter(|shifted| shifted >> shift == group)
+            .map(|shifted| index | shifted)
+    });
+    match index {
+        Some(index) if index < n => Ok(index),
+        Some(index) => Err(DecodeError::CorruptedData(format!(
+            "Action index {} out of range for n={}",
+            index, n
+        ))),
+        None => Err(DecodeError::CorruptedData(format!(
+            "Varint action {:02x?} overflows a u32",
+            buf
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_across_byte_width_boundaries() {
+        let cases = [
+            (0, 1),
+            (127, 1),
+            (128, 2),
+            (16_383, 2),
+            (16_384, 3),
+            (u32::MAX - 1, 5),
+        ];
+        for (index, len) in cases {
+            let mut buf = Vec::new();
+            encode_discrete_action(index, u32::MAX, &mut buf).unwrap();
+            assert_eq!(buf.len(), len, "index {}", index);
+            assert_eq!(decode_discrete_action(&buf, u32::MAX).unwrap(), index);
+        }
+
+        let mut buf = Vec::new();
+        encode_discrete_action(300, 1000, &mut buf).unwrap();
+        assert_eq!(buf, [0xac, 0x02]);
+    }
+
+    #[test]
+    fn test_rejects_malformed_and_out_of_range_actions() {
+        assert!(encode_discrete_action(9, 9, &mut Vec::new()).is_err());
+
+        for bad in [
+            &[][..],
+            &[0x80],                         // unterminated
+            &[0x01, 0x00],                   // trailing byte
+            &[0x80, 0x00],                   // overlong zero
+            &[0xff, 0xff, 0xff, 0xff, 0x10], // overflows u32
+            &[0x80, 0x80, 0x80, 0x80, 0x80, 0x01],
+        ] {
+            assert!(
+                decode_discrete_action(bad, u32::MAX).is_err(),
+                "{:02x?}",
+                bad
+            );
+        }
+        assert!(matches!(
+            decode_discrete_action(&[0x09], 9),
+            Err(DecodeError::CorruptedData(_))
+        ));
+    }
+}
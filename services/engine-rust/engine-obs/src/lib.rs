@@ -0,0 +1,413 @@
+//! Typed observations for Rust consumers of the engine
+//!
+//! The engine sends observations as raw bytes whose meaning is described by
+//! the game's gRPC `Capabilities`. `ObsSpec` reads that description once and
+//! then turns each observation into `f32` values:
+//!
+//! - the number of values comes from the capabilities: the `obs_layout`
+//!   segments, the `obs_quantization` parameters, and the fixed `sizes.obs`,
+//!   whichever are declared, and they must agree;
+//! - framed observations (`obs_length_prefixed`) have their length prefix
+//!   checked and stripped;
+//! - `f32` observations are read in the byte order named by the encoding
+//!   (`f32x29be:v1` is big-endian), and quantized ones are dequantized.
+//!
+//! An observation whose length does not match the capabilities is rejected
+//! with `ObsError::LengthMismatch` rather than truncated or padded.
+//!
+//! With the `ndarray` feature, observations convert to `ndarray::Array1<f32>`
+//! (`to_array`), or are viewed in place without copying when the bytes
+//! already are native `f32`s (`view`). Capabilities only describe a flat
+//! observation, so arrays are one-dimensional; slice them by segment with
+//! `ObsSpec::segment` or reshape them as the game documents.
+//!
+//! ```rust,ignore
+//! let spec = ObsSpec::from_capabilities(&capabilities)?;
+//! let obs = spec.to_array(&response.obs)?;
+//! let legal = obs.slice(ndarray::s![spec.segment("legal").unwrap()]);
+//! ```
+
+use std::ops::Range;
+
+use engine_proto::{Capabilities, ObsQuantization};
+
+const F32_SIZE: usize = std::mem::size_of::<f32>();
+
+/// Bytes taken by the length prefix of a framed observation
+const FRAME_HEADER_LEN: usize = std::mem::size_of::<u32>();
+
+/// Why an observation could not be read
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ObsError {
+    #[error(
+        "Capabilities do not declare the observation size (no obs_layout, obs_quantization, or fixed sizes.obs)"
+    )]
+    UnknownSize,
+    #[error("Capabilities disagree on the observation size: {0}")]
+    InconsistentSize(String),
+    #[error(
+        "Observation is {actual} bytes but capabilities declare {dims} values ({expected} bytes)"
+    )]
+    LengthMismatch {
+        dims: usize,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("Malformed framed observation: {0}")]
+    Frame(String),
+}
+
+/// How each observation value is encoded
+#[derive(Debug, Clone, PartialEq)]
+enum ObsValues {
+    /// Packed `f32`s in the given byte order
+    F32 { big_endian: bool },
+    /// One `u8` per value: `value = scale[i] * (q - zero_point[i])`
+    Quantized(ObsQuantization),
+}
+
+/// Shape and encoding of a game's observations, read from its capabilities
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObsSpec {
+    dims: usize,
+    values: ObsValues,
+    framed: bool,
+    /// Named segments in order; empty when the game declares none
+    segments: Vec<(String, usize)>,
+}
+
+impl ObsSpec {
+    /// Read the observation shape and encoding from `capabilities`
+    ///
+    /// # Errors
+    ///
+    /// Returns `ObsError::UnknownSize` if the capabilities declare neither
+    /// segments, quantization, nor a fixed obs size, and
+    /// `ObsError::InconsistentSize` if the declared sizes disagree.
+    pub fn from_capabilities(capabilities: &Capabilities) -> Result<Self, ObsError> {
+        let values = match &capabilities.obs_quantization {
+            Some(quantization) => {
+                if quantization.scale.len() != quantization.zero_point.len() {
+                    return Err(ObsError::InconsistentSize(format!(
+                        "obs_quantization has {} scales but {} zero points",
+                        quantization.scale.len(),
+                        quantization.zero_point.len()
+                    )));
+                }
+                ObsValues::Quantized(quantization.clone())
+            }
+            None => ObsValues::F32 {
+                big_endian: capabilities
+                    .enc
+                    .as_ref()
+                    .is_some_and(|enc| is_big_endian(&enc.obs)),
+            },
+        };
+        let framed = capabilities.obs_length_prefixed;
+        let value_size = match values {
+            ObsValues::F32 { .. } => F32_SIZE,
+            ObsValues::Quantized(_) => 1,
+        };
+
+        // Every declared size is a candidate; they must all agree
+        let mut declared: Vec<(&str, usize)> = Vec::new();
+        if !capabilities.obs_layout.is_empty() {
+            let total = capabilities
+                .obs_layout
+                .iter()
+                .map(|segment| segment.size as usize)
+                .sum();
+            declared.push(("obs_layout", total));
+        }
+        if let ObsValues::Quantized(quantization) = &values {
+            declared.push(("obs_quantization", quantization.scale.len()));
+        }
+        if let Some(bytes) = capabilities.sizes.as_ref().and_then(|sizes| sizes.obs) {
+            let header = if framed { FRAME_HEADER_LEN } else { 0 };
+            let payload = (bytes as usize).checked_sub(header).ok_or_else(|| {
+                ObsError::InconsistentSize(format!(
+                    "sizes.obs of {} bytes is shorter than the frame header",
+                    bytes
+                ))
+            })?;
+            if payload % value_size != 0 {
+                return Err(ObsError::InconsistentSize(format!(
+                    "sizes.obs of {} bytes is not a whole number of {}-byte values",
+                    payload, value_size
+                )));
+            }
+            declared.push(("sizes.obs", payload / value_size));
+        }
+
+        let &(_, dims) = declared.first().ok_or(ObsError::UnknownSize)?;
+        if declared.iter().any(|&(_, size)| size != dims) {
+            let sizes: Vec<String> = declared
+                .iter()
+                .map(|(source, size)| format!("{}={}", source, size))
+                .collect();
+            return Err(ObsError::InconsistentSize(sizes.join(", ")));
+        }
+
+        Ok(Self {
+            dims,
+            values,
+            framed,
+            segments: capabilities
+                .obs_layout
+                .iter()
+                .map(|segment| (segment.name.clone(), segment.size as usize))
+                .collect(),
+        })
+    }
+
+    /// Number of values in each observation
+    pub fn dims(&self) -> usize {
+        self.dims
+    }
+
+    /// Whether observations are quantized to one byte per value
+    pub fn is_quantized(&self) -> bool {
+        matches!(self.values, ObsValues::Quantized(_))
+    }
+
+    /// Value indices of the named `obs_layout` segment
+    pub fn segment(&self, name: &str) -> Option<Range<usize>> {
+        let mut start = 0;
+        for (segment, size) in &self.segments {
+            if segment == name {
+                return Some(start..start + size);
+            }
+            start += size;
+        }
+        None
+    }
+
+    /// Decode an observation into its values
+    ///
+    /// # Errors
+    ///
+    /// Returns `ObsError::LengthMismatch` if `obs` is not exactly `dims()`
+    /// values, and `ObsError::Frame` if a framed observation's prefix does
+    /// not match its length.
+    pub fn decode(&self, obs: &[u8]) -> Result<Vec<f32>, ObsError> {
+        let payload = self.payload(obs)?;
+        Ok(match &self.values {
+            ObsValues::F32 { big_endian } => payload
+                .chunks_exact(F32_SIZE)
+                .map(|chunk| {
+                    let bytes = chunk.try_into().unwrap();
+                    if *big_endian {
+                        f32::from_be_bytes(bytes)
+                    } else {
+                        f32::from_le_bytes(bytes)
+                    }
+                })
+                .collect(),
+            ObsValues::Quantized(quantization) => payload
+                .iter()
+                .zip(quantization.scale.iter().zip(&quantization.zero_point))
+                .map(|(&q, (&scale, &zero_point))| scale * (q as f32 - zero_point))
+                .collect(),
+        })
+    }
+
+    /// The value bytes of `obs`, checked against the declared size
+    fn payload<'a>(&self, obs: &'a [u8]) -> Result<&'a [u8], ObsError> {
+        let payload = if self.framed {
+            if obs.len() < FRAME_HEADER_LEN {
+                return Err(ObsError::Frame(format!(
+                    "needs a {}-byte length prefix, got {} bytes",
+                    FRAME_HEADER_LEN,
+                    obs.len()
+                )));
+            }
+            let (header, payload) = obs.split_at(FRAME_HEADER_LEN);
+            let len = u32::from_le_bytes(header.try_into().unwrap()) as usize;
+            if len != payload.len() {
+                return Err(ObsError::Frame(format!(
+                    "prefix declares {} bytes but {} follow",
+                    len,
+                    payload.len()
+                )));
+            }
+            payload
+        } else {
+            obs
+        };
+
+        let expected = match self.values {
+            ObsValues::F32 { .. } => self.dims * F32_SIZE,
+            ObsValues::Quantized(_) => self.dims,
+        };
+        if payload.len() != expected {
+            return Err(ObsError::LengthMismatch {
+                dims: self.dims,
+                expected,
+                actual: payload.len(),
+            });
+        }
+        Ok(payload)
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl ObsSpec {
+    /// Decode an observation into a `dims()`-long array
+    ///
+    /// # Errors
+    ///
+    /// As `decode`.
+    pub fn to_array(&self, obs: &[u8]) -> Result<ndarray::Array1<f32>, ObsError> {
+        self.decode(obs).map(ndarray::Array1::from_vec)
+    }
+
+    /// View an observation as an array without copying it
+    ///
+    /// Returns `Ok(None)` when the values cannot be borrowed as they are:
+    /// quantized observations, a byte order other than the host's, or bytes
+    /// not aligned for `f32`. Fall back to `to_array` in that case.
+    ///
+    /// # Errors
+    ///
+    /// As `decode`.
+    pub fn view<'a>(
+        &self,
+        obs: &'a [u8],
+    ) -> Result<Option<ndarray::ArrayView1<'a, f32>>, ObsError> {
+        let payload = self.payload(obs)?;
+        let ObsValues::F32 { big_endian } = self.values else {
+            return Ok(None);
+        };
+        if big_endian != cfg!(target_endian = "big") {
+            return Ok(None);
+        }
+        // SAFETY: every bit pattern is a valid `f32`, and `align_to` only
+        // returns the middle slice at `f32` alignment
+        let (head, values, tail) = unsafe { payload.align_to::<f32>() };
+        if !head.is_empty() || !tail.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(ndarray::ArrayView1::from(values)))
+    }
+}
+
+/// Whether an encoding name is the big-endian `f32x<N>be` form written by
+/// `Endianness::encoding_name`, with or without a version tag, e.g.
+/// `f32x29be:v1`
+fn is_big_endian(encoding: &str) -> bool {
+    let name = encoding
+        .rsplit_once(':')
+        .map_or(encoding, |(name, _version)| name);
+    name.strip_prefix("f32x")
+        .and_then(|name| name.strip_suffix("be"))
+        .is_some_and(|count| !count.is_empty() && count.bytes().all(|b| b.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine_proto::{EncodedSizes, Encoding, ObsSegment};
+
+    fn capabilities(obs: &str, bytes: u32, segments: &[(&str, u32)]) -> Capabilities {
+        Capabilities {
+            enc: Some(Encoding {
+                obs: obs.to_string(),
+                ..Default::default()
+            }),
+            sizes: Some(EncodedSizes {
+                obs: Some(bytes),
+                ..Default::default()
+            }),
+            obs_layout: segments
+                .iter()
+                .map(|&(name, size)| ObsSegment {
+                    name: name.to_string(),
+                    size,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn f32_bytes(values: &[f32], big_endian: bool) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| {
+                if big_endian {
+                    value.to_be_bytes()
+                } else {
+                    value.to_le_bytes()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_reads_the_declared_byte_order_and_segments() {
+        let spec = ObsSpec::from_capabilities(&capabilities("f32x3:v1", 12, &[("a", 1), ("b", 2)]))
+            .unwrap();
+        assert_eq!(spec.dims(), 3);
+        assert_eq!(spec.segment("b"), Some(1..3));
+        assert_eq!(spec.segment("c"), None);
+        let values = [1.0, -2.5, 0.25];
+        assert_eq!(spec.decode(&f32_bytes(&values, false)).unwrap(), values);
+
+        let spec = ObsSpec::from_capabilities(&capabilities("f32x3be:v1", 12, &[])).unwrap();
+        assert_eq!(spec.decode(&f32_bytes(&values, true)).unwrap(), values);
+    }
+
+    #[test]
+    fn test_only_the_f32_be_form_is_big_endian() {
+        assert!(is_big_endian("f32x29be:v1"));
+        assert!(is_big_endian("f32x29be"));
+        assert!(!is_big_endian("f32x29:v1"));
+        assert!(!is_big_endian("f32xbe:v1"));
+        assert!(!is_big_endian("probe:v1"));
+        assert!(!is_big_endian("u8x29be:v1"));
+    }
+
+    #[test]
+    fn test_length_mismatch_and_disagreeing_sizes_are_rejected() {
+        let spec = ObsSpec::from_capabilities(&capabilities("f32x3:v1", 12, &[])).unwrap();
+        let err = spec.decode(&[0; 8]).unwrap_err();
+        assert_eq!(
+            err,
+            ObsError::LengthMismatch {
+                dims: 3,
+                expected: 12,
+                actual: 8
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Observation is 8 bytes but capabilities declare 3 values (12 bytes)"
+        );
+
+        let err =
+            ObsSpec::from_capabilities(&capabilities("f32x3:v1", 12, &[("a", 4)])).unwrap_err();
+        assert!(matches!(err, ObsError::InconsistentSize(_)), "{}", err);
+        assert_eq!(
+            ObsSpec::from_capabilities(&Capabilities::default()),
+            Err(ObsError::UnknownSize)
+        );
+    }
+
+    #[test]
+    fn test_framed_and_quantized_observations_decode() {
+        let mut caps = capabilities("quant_u8(f32x2:v1)", 2 + 4, &[]);
+        caps.obs_length_prefixed = true;
+        caps.obs_quantization = Some(ObsQuantization {
+            scale: vec![0.5, 2.0],
+            zero_point: vec![0.0, 10.0],
+        });
+        let spec = ObsSpec::from_capabilities(&caps).unwrap();
+        assert!(spec.is_quantized());
+
+        let mut obs = 2u32.to_le_bytes().to_vec();
+        obs.extend_from_slice(&[4, 12]);
+        assert_eq!(spec.decode(&obs).unwrap(), vec![2.0, 4.0]);
+
+        obs[0] = 3;
+        assert!(matches!(spec.decode(&obs), Err(ObsError::Frame(_))));
+    }
+}
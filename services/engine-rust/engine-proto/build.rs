@@ -1,3 +1,6 @@
+use std::env;
+use std::path::PathBuf;
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let proto_file = "../../../proto/engine/v1/engine.proto";
     let proto_dir = "../../../proto";
@@ -5,9 +8,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Tell cargo to invalidate the built crate whenever the proto file changes
     println!("cargo:rerun-if-changed={}", proto_file);
     
+    // Emit the descriptor set so downstream crates can generate their own clients
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+    
     tonic_build::configure()
         .build_server(true)
         .build_client(true)
+        .file_descriptor_set_path(out_dir.join("engine_descriptor.bin"))
         .compile(&[proto_file], &[proto_dir])?;
     
     Ok(())
@@ -1,6 +1,29 @@
 // Generated protobuf code
 tonic::include_proto!("engine.v1");
 
+/// Encoded `FileDescriptorSet` for `engine.proto`, for client codegen and reflection
+pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("engine_descriptor");
+
 // Re-export commonly used types for convenience  
 pub use engine_server::Engine;
-pub use engine_client::EngineClient;
\ No newline at end of file
+pub use engine_client::EngineClient;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+    use prost_types::FileDescriptorSet;
+
+    #[test]
+    fn test_file_descriptor_set_parses() {
+        assert!(!FILE_DESCRIPTOR_SET.is_empty());
+
+        let set = FileDescriptorSet::decode(FILE_DESCRIPTOR_SET).unwrap();
+        let file = set
+            .file
+            .iter()
+            .find(|file| file.package() == "engine.v1")
+            .expect("engine.v1 descriptor present");
+        assert!(file.service.iter().any(|service| service.name() == "Engine"));
+    }
+}
\ No newline at end of file
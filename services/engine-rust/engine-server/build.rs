@@ -0,0 +1,37 @@
+//! Injects the git commit the server is built from as `ENGINE_GIT_COMMIT`
+//!
+//! An `ENGINE_GIT_COMMIT` set in the build environment wins (container builds
+//! have no `.git`); otherwise the commit is read with `git rev-parse`, falling
+//! back to "unknown".
+
+use std::env;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=ENGINE_GIT_COMMIT");
+
+    let commit = env::var("ENGINE_GIT_COMMIT")
+        .ok()
+        .filter(|commit| !commit.is_empty())
+        .or_else(git_commit)
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=ENGINE_GIT_COMMIT={}", commit);
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let out = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!out.is_empty()).then_some(out)
+}
+
+fn git_commit() -> Option<String> {
+    // HEAD's reflog changes on every commit and checkout
+    if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]) {
+        println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+        println!("cargo:rerun-if-changed={}/logs/HEAD", git_dir);
+    }
+    git(&["rev-parse", "HEAD"])
+}
@@ -0,0 +1,125 @@
+//! Global admission control for the engine server
+//!
+//! `InFlightLimitLayer` wraps the whole gRPC router, so every method shares
+//! one budget of concurrent requests. Requests beyond the budget are rejected
+//! immediately with `RESOURCE_EXHAUSTED` instead of queueing, which keeps a
+//! flood of clients from growing the buffer pool and game cache without
+//! bound. The limit is read from `ENGINE_MAX_IN_FLIGHT`; unset means no limit.
+
+use std::env;
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use tokio::sync::Semaphore;
+use tonic::body::BoxBody;
+use tonic::codegen::http::{Request, Response};
+use tonic::Status;
+use tower::{Layer, Service};
+
+/// Environment variable with the maximum number of concurrent requests
+pub const MAX_IN_FLIGHT_ENV: &str = "ENGINE_MAX_IN_FLIGHT";
+
+/// Tower layer that caps concurrent requests across all methods
+#[derive(Debug, Clone)]
+pub struct InFlightLimitLayer {
+    /// `None` admits everything
+    permits: Option<Arc<Semaphore>>,
+    max_in_flight: usize,
+}
+
+impl InFlightLimitLayer {
+    /// Admit at most `max_in_flight` concurrent requests
+    pub fn new(max_in_flight: usize) -> Self {
+        Self {
+            permits: Some(Arc::new(Semaphore::new(max_in_flight))),
+            max_in_flight,
+        }
+    }
+
+    /// Admit every request
+    pub fn unlimited() -> Self {
+        Self {
+            permits: None,
+            max_in_flight: usize::MAX,
+        }
+    }
+
+    /// Build the layer from `ENGINE_MAX_IN_FLIGHT`, unlimited when unset
+    pub fn from_env() -> Result<Self, Box<dyn Error>> {
+        match env::var(MAX_IN_FLIGHT_ENV) {
+            Ok(limit) => {
+                let limit: usize = limit
+                    .parse()
+                    .map_err(|e| format!("Invalid {}={:?}: {}", MAX_IN_FLIGHT_ENV, limit, e))?;
+                if limit == 0 {
+                    return Err(format!("{} must be at least 1", MAX_IN_FLIGHT_ENV).into());
+                }
+                Ok(Self::new(limit))
+            }
+            Err(_) => Ok(Self::unlimited()),
+        }
+    }
+}
+
+impl<S> Layer<S> for InFlightLimitLayer {
+    type Service = InFlightLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        InFlightLimit {
+            inner,
+            permits: self.permits.clone(),
+            max_in_flight: self.max_in_flight,
+        }
+    }
+}
+
+/// Service produced by `InFlightLimitLayer`
+#[derive(Debug, Clone)]
+pub struct InFlightLimit<S> {
+    inner: S,
+    permits: Option<Arc<Semaphore>>,
+    max_in_flight: usize,
+}
+
+type BoxFuture<T, E> = Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'static>>;
+
+impl<S, ReqBody> Service<Request<ReqBody>> for InFlightLimit<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let permit = match &self.permits {
+            None => None,
+            Some(permits) => match permits.clone().try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    let status = Status::resource_exhausted(format!(
+                        "Server busy: {} requests already in flight, retry later",
+                        self.max_in_flight
+                    ));
+                    return Box::pin(async move { Ok(status.to_http()) });
+                }
+            },
+        };
+
+        // The permit is held until the handler has produced its response
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let response = response.await;
+            drop(permit);
+            response
+        })
+    }
+}
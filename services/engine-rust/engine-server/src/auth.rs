@@ -0,0 +1,119 @@
+//! Token authentication for the engine server
+//!
+//! `AuthLayer` wraps the whole gRPC router, so every method requires the same
+//! `authorization: Bearer <token>` metadata before a request reaches game
+//! logic. Missing or wrong tokens are rejected with `UNAUTHENTICATED`. The
+//! token is read from `ENGINE_AUTH_TOKEN`; unset leaves the server open, which
+//! keeps local development friction-free. The check is a single header lookup
+//! and byte comparison per request.
+
+use std::env;
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use tonic::body::BoxBody;
+use tonic::codegen::http::{Request, Response};
+use tonic::Status;
+use tower::{Layer, Service};
+
+/// Environment variable with the token clients must present
+pub const AUTH_TOKEN_ENV: &str = "ENGINE_AUTH_TOKEN";
+
+/// Metadata key carrying the client's credentials
+pub const AUTHORIZATION_HEADER: &str = "authorization";
+
+/// Tower layer that rejects requests without the configured token
+#[derive(Debug, Clone)]
+pub struct AuthLayer {
+    /// Full expected header value (`Bearer <token>`); `None` admits everything
+    expected: Option<Arc<[u8]>>,
+}
+
+impl AuthLayer {
+    /// Require `authorization: Bearer <token>` on every request
+    pub fn new(token: &str) -> Self {
+        Self {
+            expected: Some(format!("Bearer {}", token).into_bytes().into()),
+        }
+    }
+
+    /// Admit every request without checking credentials
+    pub fn open() -> Self {
+        Self { expected: None }
+    }
+
+    /// Build the layer from `ENGINE_AUTH_TOKEN`, open when unset
+    pub fn from_env() -> Result<Self, Box<dyn Error>> {
+        match env::var(AUTH_TOKEN_ENV) {
+            Ok(token) if token.trim().is_empty() => {
+                Err(format!("{} must not be empty", AUTH_TOKEN_ENV).into())
+            }
+            Ok(token) => Ok(Self::new(&token)),
+            Err(_) => Ok(Self::open()),
+        }
+    }
+
+    /// Whether requests must carry a token
+    pub fn is_enforced(&self) -> bool {
+        self.expected.is_some()
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = Auth<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Auth {
+            inner,
+            expected: self.expected.clone(),
+        }
+    }
+}
+
+/// Service produced by `AuthLayer`
+#[derive(Debug, Clone)]
+pub struct Auth<S> {
+    inner: S,
+    expected: Option<Arc<[u8]>>,
+}
+
+type BoxFuture<T, E> = Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'static>>;
+
+impl<S, ReqBody> Service<Request<ReqBody>> for Auth<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        if let Some(expected) = &self.expected {
+            let status = match request.headers().get(AUTHORIZATION_HEADER) {
+                None => Some(Status::unauthenticated("Missing authorization metadata")),
+                Some(value) if !constant_time_eq(value.as_bytes(), expected) => {
+                    Some(Status::unauthenticated("Invalid authorization token"))
+                }
+                Some(_) => None,
+            };
+            if let Some(status) = status {
+                return Box::pin(async move { Ok(status.to_http()) });
+            }
+        }
+
+        Box::pin(self.inner.call(request))
+    }
+}
+
+/// Compare without exiting early so response timing does not leak the token
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
@@ -2,30 +2,136 @@
 //! 
 //! This module provides a thread-safe buffer pool that enables allocation-free operation
 //! in the hot paths of the gRPC service by reusing byte vectors.
+//!
+//! Each buffer type also tracks its demand: an exponential moving average of
+//! the number of checked-out buffers, sampled on every get and return. The
+//! average depends only on the sequence of pool operations, never on wall
+//! clock time or thread timing, so a scripted sequence of gets and returns
+//! always leaves the pool in the same state. With adaptive retention enabled
+//! the pool uses that average to decide how many returned buffers to keep.
 
 use std::sync::{Arc, Mutex};
 
+/// Default initial capacity in bytes for pre-allocated buffers
+pub const DEFAULT_BUFFER_CAPACITY: usize = 512;
+
+/// Default weight of the newest sample in the demand average
+pub const DEFAULT_DEMAND_ALPHA: f64 = 0.125;
+
+/// Pre-allocation counts for a `BufferPool`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferPoolConfig {
+    pub state_count: usize,
+    pub obs_count: usize,
+    pub action_count: usize,
+    pub initial_capacity: usize,
+}
+
 /// Thread-safe buffer pool for reusing byte vectors
 /// 
 /// The buffer pool maintains separate pools for different types of buffers
 /// to optimize allocation patterns and reduce fragmentation.
 #[derive(Debug, Clone)]
 pub struct BufferPool {
-    state_buffers: Arc<Mutex<Vec<Vec<u8>>>>,
-    obs_buffers: Arc<Mutex<Vec<Vec<u8>>>>,
-    action_buffers: Arc<Mutex<Vec<Vec<u8>>>>,
+    state_buffers: Arc<Mutex<Shelf>>,
+    obs_buffers: Arc<Mutex<Shelf>>,
+    action_buffers: Arc<Mutex<Shelf>>,
+    /// Hand out fresh buffers and drop returned ones instead of pooling
+    disabled: bool,
+    /// Weight of the newest sample in each demand average
+    demand_alpha: f64,
+    /// Idle buffers always kept per type when adaptive retention is on
+    adaptive_min_retained: Option<usize>,
+}
+
+/// Idle buffers of one type plus the demand seen for them
+#[derive(Debug, Default)]
+struct Shelf {
+    idle: Vec<Vec<u8>>,
+    /// Buffers handed out and not yet returned
+    checked_out: usize,
+    /// Moving average of `checked_out`, sampled on every get and return
+    demand_ema: f64,
+}
+
+impl Shelf {
+    fn with_buffers(count: usize, initial_capacity: usize) -> Self {
+        Self {
+            idle: (0..count).map(|_| Vec::with_capacity(initial_capacity)).collect(),
+            ..Self::default()
+        }
+    }
+
+    fn sample(&mut self, alpha: f64) {
+        self.demand_ema += alpha * (self.checked_out as f64 - self.demand_ema);
+    }
+
+    fn take(&mut self, alpha: f64) -> Vec<u8> {
+        self.checked_out += 1;
+        self.sample(alpha);
+        self.idle.pop().unwrap_or_default()
+    }
+
+    /// Returns of buffers the pool never handed out still count, but the
+    /// checked-out count never goes below zero
+    fn put(&mut self, mut buf: Vec<u8>, alpha: f64, min_retained: Option<usize>) {
+        self.checked_out = self.checked_out.saturating_sub(1);
+        self.sample(alpha);
+        if let Some(min_retained) = min_retained {
+            let limit = min_retained.max(self.demand_ema.ceil() as usize);
+            if self.idle.len() >= limit {
+                return;
+            }
+        }
+        buf.clear();
+        self.idle.push(buf);
+    }
 }
 
 impl BufferPool {
     /// Create a new buffer pool
     pub fn new() -> Self {
+        Self::with_capacity(0, 0, 0, 0)
+    }
+    
+    /// Create a pool that never reuses buffers
+    /// 
+    /// Every `get_*_buffer` call allocates a fresh vector and every
+    /// `return_*_buffer` call drops the buffer, so the pool never holds
+    /// anything. Useful when chasing a suspected buffer-reuse bug.
+    pub fn disabled() -> Self {
         Self {
-            state_buffers: Arc::new(Mutex::new(Vec::new())),
-            obs_buffers: Arc::new(Mutex::new(Vec::new())),
-            action_buffers: Arc::new(Mutex::new(Vec::new())),
+            disabled: true,
+            ..Self::new()
         }
     }
     
+    /// Whether pooling is turned off (see `disabled`)
+    pub fn is_disabled(&self) -> bool {
+        self.disabled
+    }
+    
+    /// Weight the newest sample of each demand average by `alpha`
+    /// 
+    /// # Panics
+    /// 
+    /// Panics unless `alpha` is in `(0, 1]`.
+    pub fn with_demand_alpha(mut self, alpha: f64) -> Self {
+        assert!(alpha > 0.0 && alpha <= 1.0, "demand alpha must be in (0, 1], got {}", alpha);
+        self.demand_alpha = alpha;
+        self
+    }
+    
+    /// Keep only as many returned buffers as recent demand calls for
+    /// 
+    /// A returned buffer is dropped when its type already has
+    /// `max(min_retained, ceil(demand average))` idle buffers, so a burst of
+    /// traffic does not pin its peak buffer count forever.
+    pub fn with_adaptive_retention(mut self, min_retained: usize) -> Self {
+        self.adaptive_min_retained = Some(min_retained);
+        self
+    }
+    
     /// Create a new buffer pool with pre-allocated buffers
     /// 
     /// This method pre-allocates buffers to reduce allocation overhead during startup.
@@ -42,26 +148,46 @@ impl BufferPool {
         action_count: usize, 
         initial_capacity: usize
     ) -> Self {
-        let mut state_buffers = Vec::with_capacity(state_count);
-        let mut obs_buffers = Vec::with_capacity(obs_count);
-        let mut action_buffers = Vec::with_capacity(action_count);
-        
-        for _ in 0..state_count {
-            state_buffers.push(Vec::with_capacity(initial_capacity));
-        }
-        
-        for _ in 0..obs_count {
-            obs_buffers.push(Vec::with_capacity(initial_capacity));
-        }
-        
-        for _ in 0..action_count {
-            action_buffers.push(Vec::with_capacity(initial_capacity));
-        }
+        let shelf = |count| Arc::new(Mutex::new(Shelf::with_buffers(count, initial_capacity)));
         
         Self {
-            state_buffers: Arc::new(Mutex::new(state_buffers)),
-            obs_buffers: Arc::new(Mutex::new(obs_buffers)),
-            action_buffers: Arc::new(Mutex::new(action_buffers)),
+            state_buffers: shelf(state_count),
+            obs_buffers: shelf(obs_count),
+            action_buffers: shelf(action_count),
+            disabled: false,
+            demand_alpha: DEFAULT_DEMAND_ALPHA,
+            adaptive_min_retained: None,
+        }
+    }
+    
+    /// Create a buffer pool pre-allocated according to `config`
+    pub fn from_config(config: &BufferPoolConfig) -> Self {
+        Self::with_capacity(
+            config.state_count,
+            config.obs_count,
+            config.action_count,
+            config.initial_capacity,
+        )
+    }
+    
+    /// Recommend pool sizes for an expected workload
+    /// 
+    /// Every in-flight item of a request checks out one state and one
+    /// observation buffer, so those scale with `concurrency * batch_size`.
+    /// Action bytes are read straight from the request and only need staging
+    /// space when re-encoded, so half as many action buffers are kept.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `concurrency` - Expected number of concurrent requests
+    /// * `batch_size` - Expected items per request (1 for unbatched calls)
+    pub fn recommend_capacity(concurrency: usize, batch_size: usize) -> BufferPoolConfig {
+        let in_flight = concurrency.saturating_mul(batch_size);
+        BufferPoolConfig {
+            state_count: in_flight,
+            obs_count: in_flight,
+            action_count: in_flight.div_ceil(2),
+            initial_capacity: DEFAULT_BUFFER_CAPACITY,
         }
     }
     
@@ -69,71 +195,101 @@ impl BufferPool {
     /// 
     /// If no buffer is available in the pool, returns a new empty vector.
     pub fn get_state_buffer(&self) -> Vec<u8> {
-        self.state_buffers
-            .lock()
-            .unwrap()
-            .pop()
-            .unwrap_or_else(Vec::new)
+        if self.disabled {
+            return Vec::new();
+        }
+        self.state_buffers.lock().unwrap().take(self.demand_alpha)
     }
     
     /// Return a state buffer to the pool
     /// 
-    /// The buffer is cleared before being returned to the pool.
-    pub fn return_state_buffer(&self, mut buf: Vec<u8>) {
-        buf.clear();
-        self.state_buffers.lock().unwrap().push(buf);
+    /// The buffer is cleared before being returned to the pool, or dropped
+    /// if adaptive retention decides the pool already holds enough.
+    pub fn return_state_buffer(&self, buf: Vec<u8>) {
+        if self.disabled {
+            return;
+        }
+        self.state_buffers
+            .lock()
+            .unwrap()
+            .put(buf, self.demand_alpha, self.adaptive_min_retained);
     }
     
     /// Get an observation buffer from the pool
     pub fn get_obs_buffer(&self) -> Vec<u8> {
-        self.obs_buffers
-            .lock()
-            .unwrap()
-            .pop()
-            .unwrap_or_else(Vec::new)
+        if self.disabled {
+            return Vec::new();
+        }
+        self.obs_buffers.lock().unwrap().take(self.demand_alpha)
+    }
+    
+    /// Get an observation buffer holding `len` zeroed bytes
+    ///
+    /// Sized for `Game::encode_obs_into` on games with a fixed
+    /// `OBS_ENCODED_SIZE`; truncate it to the bytes written afterwards.
+    pub fn get_obs_slice(&self, len: usize) -> Vec<u8> {
+        let mut buf = self.get_obs_buffer();
+        buf.resize(len, 0);
+        buf
     }
     
     /// Return an observation buffer to the pool
-    pub fn return_obs_buffer(&self, mut buf: Vec<u8>) {
-        buf.clear();
-        self.obs_buffers.lock().unwrap().push(buf);
+    pub fn return_obs_buffer(&self, buf: Vec<u8>) {
+        if self.disabled {
+            return;
+        }
+        self.obs_buffers
+            .lock()
+            .unwrap()
+            .put(buf, self.demand_alpha, self.adaptive_min_retained);
     }
     
     /// Get an action buffer from the pool
     pub fn get_action_buffer(&self) -> Vec<u8> {
-        self.action_buffers
-            .lock()
-            .unwrap()
-            .pop()
-            .unwrap_or_else(Vec::new)
+        if self.disabled {
+            return Vec::new();
+        }
+        self.action_buffers.lock().unwrap().take(self.demand_alpha)
     }
     
     /// Return an action buffer to the pool
-    pub fn return_action_buffer(&self, mut buf: Vec<u8>) {
-        buf.clear();
-        self.action_buffers.lock().unwrap().push(buf);
+    pub fn return_action_buffer(&self, buf: Vec<u8>) {
+        if self.disabled {
+            return;
+        }
+        self.action_buffers
+            .lock()
+            .unwrap()
+            .put(buf, self.demand_alpha, self.adaptive_min_retained);
     }
     
     /// Get statistics about the buffer pool
     pub fn stats(&self) -> BufferPoolStats {
-        let state_count = self.state_buffers.lock().unwrap().len();
-        let obs_count = self.obs_buffers.lock().unwrap().len();
-        let action_count = self.action_buffers.lock().unwrap().len();
+        let state = self.state_buffers.lock().unwrap();
+        let obs = self.obs_buffers.lock().unwrap();
+        let action = self.action_buffers.lock().unwrap();
         
         BufferPoolStats {
-            available_state_buffers: state_count,
-            available_obs_buffers: obs_count,
-            available_action_buffers: action_count,
+            available_state_buffers: state.idle.len(),
+            available_obs_buffers: obs.idle.len(),
+            available_action_buffers: action.idle.len(),
+            checked_out_state_buffers: state.checked_out,
+            checked_out_obs_buffers: obs.checked_out,
+            checked_out_action_buffers: action.checked_out,
+            state_demand_ema: state.demand_ema,
+            obs_demand_ema: obs.demand_ema,
+            action_demand_ema: action.demand_ema,
         }
     }
     
     /// Clear all buffers from the pool
     /// 
     /// This is primarily useful for testing or memory pressure situations.
+    /// Demand tracking is kept: buffers still checked out are returned later.
     pub fn clear(&self) {
-        self.state_buffers.lock().unwrap().clear();
-        self.obs_buffers.lock().unwrap().clear();
-        self.action_buffers.lock().unwrap().clear();
+        self.state_buffers.lock().unwrap().idle.clear();
+        self.obs_buffers.lock().unwrap().idle.clear();
+        self.action_buffers.lock().unwrap().idle.clear();
     }
 }
 
@@ -144,11 +300,19 @@ impl Default for BufferPool {
 }
 
 /// Statistics about buffer pool usage
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BufferPoolStats {
     pub available_state_buffers: usize,
     pub available_obs_buffers: usize,
     pub available_action_buffers: usize,
+    /// Buffers handed out and not yet returned
+    pub checked_out_state_buffers: usize,
+    pub checked_out_obs_buffers: usize,
+    pub checked_out_action_buffers: usize,
+    /// Moving average of checked-out buffers, sampled on every get and return
+    pub state_demand_ema: f64,
+    pub obs_demand_ema: f64,
+    pub action_demand_ema: f64,
 }
 
 /// RAII wrapper for automatic buffer return
@@ -245,6 +409,98 @@ mod tests {
         assert!(buf.capacity() >= 128);
     }
     
+    #[test]
+    fn test_obs_slice_is_sized_from_pooled_buffer() {
+        let pool = BufferPool::with_capacity(0, 1, 0, 128);
+
+        let mut buf = pool.get_obs_slice(116);
+        assert_eq!(buf, vec![0; 116]);
+        assert!(buf.capacity() >= 128);
+        assert_eq!(pool.stats().available_obs_buffers, 0);
+
+        buf.fill(7);
+        pool.return_obs_buffer(buf);
+        assert_eq!(pool.get_obs_slice(4), vec![0; 4]);
+    }
+    
+    #[test]
+    fn test_recommend_capacity_scales_with_workload() {
+        let small = BufferPool::recommend_capacity(2, 4);
+        assert_eq!(small.state_count, 8);
+        assert_eq!(small.obs_count, 8);
+        assert_eq!(small.action_count, 4);
+        
+        let more_concurrent = BufferPool::recommend_capacity(4, 4);
+        let larger_batches = BufferPool::recommend_capacity(2, 8);
+        assert_eq!(more_concurrent, larger_batches);
+        assert_eq!(more_concurrent.state_count, 2 * small.state_count);
+        assert_eq!(more_concurrent.action_count, 2 * small.action_count);
+        
+        // Odd workloads still get an action buffer for every other item
+        assert_eq!(BufferPool::recommend_capacity(1, 3).action_count, 2);
+        
+        let pool = BufferPool::from_config(&small);
+        let stats = pool.stats();
+        assert_eq!(stats.available_state_buffers, 8);
+        assert_eq!(stats.available_action_buffers, 4);
+    }
+    
+    #[test]
+    fn test_disabled_pool_drops_returned_buffers() {
+        let pool = BufferPool::disabled();
+        assert!(pool.is_disabled());
+        
+        let mut buf = pool.get_state_buffer();
+        buf.extend_from_slice(b"test data");
+        pool.return_state_buffer(buf);
+        pool.return_obs_buffer(Vec::with_capacity(64));
+        pool.return_action_buffer(Vec::new());
+        
+        assert_eq!(pool.stats(), BufferPool::new().stats());
+        assert_eq!(pool.get_state_buffer().capacity(), 0);
+    }
+    
+    #[test]
+    fn test_scripted_sequence_yields_exact_adaptive_state() {
+        let script = |pool: &BufferPool| {
+            let a = pool.get_state_buffer();
+            let b = pool.get_state_buffer();
+            let c = pool.get_state_buffer();
+            pool.return_state_buffer(a);
+            pool.return_state_buffer(b);
+            // Demand has fallen to ~0.77, so the third idle buffer is dropped
+            pool.return_state_buffer(c);
+            pool.get_state_buffer()
+        };
+        let pool = BufferPool::new().with_demand_alpha(0.5).with_adaptive_retention(1);
+        let _held = script(&pool);
+        
+        let expected = BufferPoolStats {
+            available_state_buffers: 1,
+            available_obs_buffers: 0,
+            available_action_buffers: 0,
+            checked_out_state_buffers: 1,
+            checked_out_obs_buffers: 0,
+            checked_out_action_buffers: 0,
+            // 0.5, 1.25, 2.125, 2.0625, 1.53125, 0.765625, 0.8828125
+            state_demand_ema: 0.8828125,
+            obs_demand_ema: 0.0,
+            action_demand_ema: 0.0,
+        };
+        assert_eq!(pool.stats(), expected);
+        
+        // The same script from the same starting point lands in the same state
+        let replay = BufferPool::new().with_demand_alpha(0.5).with_adaptive_retention(1);
+        let _held = script(&replay);
+        assert_eq!(replay.stats(), expected);
+        
+        // Without adaptive retention every returned buffer is kept
+        let unbounded = BufferPool::new().with_demand_alpha(0.5);
+        let _held = script(&unbounded);
+        assert_eq!(unbounded.stats().available_state_buffers, 2);
+        assert_eq!(unbounded.stats().state_demand_ema, 0.8828125);
+    }
+    
     #[test]
     fn test_multiple_buffer_types() {
         let pool = BufferPool::new();
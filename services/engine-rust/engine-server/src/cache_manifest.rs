@@ -0,0 +1,155 @@
+//! Cache manifests for warm restarts
+//!
+//! A restarted engine starts with an empty game cache, so the first request
+//! for every env pays for constructing its game. A manifest lists the
+//! `(env_id, build_id)` keys that were cached when the server stopped so the
+//! next server can warm the same keys before serving (enabled in the binary
+//! with `ENGINE_CACHE_MANIFEST=<path>`). Only identities are stored: game
+//! state never leaves the process, and every game is rebuilt from its
+//! registered factory.
+//!
+//! Manifests are stored as text, one key per line with the `env_id` and
+//! `build_id` separated by a tab, e.g. `tictactoe<TAB>v1`. Lines starting
+//! with `#` are comments.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use engine_proto::EngineId;
+
+/// Why a manifest failed to load or save
+#[derive(Debug, thiserror::Error)]
+pub enum CacheManifestError {
+    #[error("Failed to access cache manifest {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Malformed cache manifest at line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error("Cannot store {field} {value:?} in a cache manifest: it contains a tab or newline")]
+    Unrepresentable { field: &'static str, value: String },
+    #[error("Failed to read the game cache: {0}")]
+    Cache(String),
+}
+
+/// The game cache keys to warm after a restart
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheManifest {
+    pub ids: Vec<EngineId>,
+}
+
+impl CacheManifest {
+    /// Read a manifest file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CacheManifestError> {
+        let path = path.as_ref();
+        fs::read_to_string(path)
+            .map_err(|source| CacheManifestError::Io {
+                path: path.display().to_string(),
+                source,
+            })?
+            .parse()
+    }
+
+    /// Write the manifest to `path`, replacing any existing file
+    ///
+    /// # Errors
+    ///
+    /// Returns `CacheManifestError::Unrepresentable` without writing anything
+    /// if an id contains a tab or newline
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CacheManifestError> {
+        for id in &self.ids {
+            check_field("env_id", &id.env_id)?;
+            check_field("build_id", &id.build_id)?;
+        }
+        let path = path.as_ref();
+        fs::write(path, self.to_string()).map_err(|source| CacheManifestError::Io {
+            path: path.display().to_string(),
+            source,
+        })
+    }
+}
+
+fn check_field(field: &'static str, value: &str) -> Result<(), CacheManifestError> {
+    if value.contains(['\t', '\n', '\r']) {
+        return Err(CacheManifestError::Unrepresentable {
+            field,
+            value: value.to_string(),
+        });
+    }
+    Ok(())
+}
+
+impl fmt::Display for CacheManifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# Engine game cache keys: env_id<TAB>build_id")?;
+        for id in &self.ids {
+            writeln!(f, "{}\t{}", id.env_id, id.build_id)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for CacheManifest {
+    type Err = CacheManifestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ids = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (env_id, build_id) = line.split_once('\t').ok_or(CacheManifestError::Parse {
+                line: i + 1,
+                message: "expected env_id<TAB>build_id".to_string(),
+            })?;
+            if env_id.is_empty() || build_id.contains('\t') {
+                return Err(CacheManifestError::Parse {
+                    line: i + 1,
+                    message: format!("invalid key {:?}", line),
+                });
+            }
+            ids.push(EngineId {
+                env_id: env_id.to_string(),
+                build_id: build_id.to_string(),
+            });
+        }
+        Ok(Self { ids })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_round_trips_through_text() {
+        let manifest = CacheManifest {
+            ids: vec![
+                EngineId {
+                    env_id: "tictactoe".to_string(),
+                    build_id: "v1".to_string(),
+                },
+                EngineId {
+                    env_id: "tictactoe".to_string(),
+                    build_id: String::new(),
+                },
+            ],
+        };
+        assert_eq!(
+            manifest.to_string().parse::<CacheManifest>().unwrap(),
+            manifest
+        );
+
+        let err = "tictactoe v1\n".parse::<CacheManifest>().unwrap_err();
+        assert!(
+            matches!(err, CacheManifestError::Parse { line: 1, .. }),
+            "{}",
+            err
+        );
+    }
+}
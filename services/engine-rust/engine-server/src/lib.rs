@@ -3,9 +3,20 @@
 //! This crate provides the gRPC server implementation for the Cartridge engine service.
 
 pub mod service;
+pub mod admission;
+pub mod auth;
 pub mod buffers;
+pub mod cache_manifest;
 pub mod registry_init;
+pub mod runtime;
+pub mod self_check;
+pub mod session;
+pub mod logging;
+pub mod obs_delta;
+pub mod payload_stats;
+pub mod step_cache;
+pub mod tls;
 
 // Re-export main types
 pub use service::EngineService;
-pub use buffers::BufferPool;
\ No newline at end of file
+pub use buffers::{BufferPool, BufferPoolConfig};
\ No newline at end of file
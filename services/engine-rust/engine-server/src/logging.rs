@@ -0,0 +1,189 @@
+//! Tracing subscriber setup for the engine server binary
+//!
+//! The output format is selected with the `LOG_FORMAT` environment variable
+//! (`pretty` or `json`) and the filter is read from `RUST_LOG`, defaulting to `info`.
+//! An invalid `RUST_LOG` also falls back to `info`, with a warning once logging is up.
+
+use std::env;
+use std::str::FromStr;
+
+use tracing::{warn, Subscriber};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::EnvFilter;
+
+/// Environment variable selecting the log output format
+pub const LOG_FORMAT_ENV: &str = "LOG_FORMAT";
+
+/// Default filter directive when `RUST_LOG` is not set
+const DEFAULT_FILTER: &str = "info";
+
+/// Supported log output formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable single-line output
+    #[default]
+    Pretty,
+    /// Newline-delimited JSON for log aggregators
+    Json,
+}
+
+impl LogFormat {
+    /// Read the format from `LOG_FORMAT`, defaulting to `Pretty` when unset
+    pub fn from_env() -> Result<Self, String> {
+        match env::var(LOG_FORMAT_ENV) {
+            Ok(value) => value.parse(),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!(
+                "Invalid {}: '{}' (expected 'pretty' or 'json')",
+                LOG_FORMAT_ENV, other
+            )),
+        }
+    }
+}
+
+/// Parse a `RUST_LOG` value into a filter, defaulting to `info` when unset
+///
+/// An invalid value also yields the default filter, returned together with
+/// the parse error so the caller can report it.
+pub fn parse_filter(directives: Option<&str>) -> (EnvFilter, Option<String>) {
+    match directives.map(EnvFilter::try_new) {
+        Some(Ok(filter)) => (filter, None),
+        Some(Err(e)) => (EnvFilter::new(DEFAULT_FILTER), Some(e.to_string())),
+        None => (EnvFilter::new(DEFAULT_FILTER), None),
+    }
+}
+
+/// Build a subscriber for the given format and filter without installing it
+pub fn build_subscriber(format: LogFormat, filter: EnvFilter) -> Box<dyn Subscriber + Send + Sync> {
+    build_subscriber_with_writer(format, filter, std::io::stdout)
+}
+
+/// `build_subscriber` writing to `writer` instead of stdout
+fn build_subscriber_with_writer<W>(
+    format: LogFormat,
+    filter: EnvFilter,
+    writer: W,
+) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer);
+    match format {
+        LogFormat::Pretty => Box::new(builder.finish()),
+        LogFormat::Json => Box::new(builder.json().finish()),
+    }
+}
+
+/// Install the global tracing subscriber from `LOG_FORMAT` and `RUST_LOG`
+///
+/// This should be called once at startup before any spans or events are emitted.
+pub fn init_tracing() -> Result<(), Box<dyn std::error::Error>> {
+    let format = LogFormat::from_env()?;
+    let directives = env::var(EnvFilter::DEFAULT_ENV).ok();
+    let (filter, invalid) = parse_filter(directives.as_deref());
+
+    tracing::subscriber::set_global_default(build_subscriber(format, filter))?;
+    if let Some(error) = invalid {
+        warn!(
+            rust_log = directives.as_deref().unwrap_or_default(),
+            "Ignoring invalid {} ({}), logging at '{}'",
+            EnvFilter::DEFAULT_ENV,
+            error,
+            DEFAULT_FILTER
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    /// Writer that captures formatted tracing output in memory
+    #[derive(Clone, Default)]
+    struct CaptureWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for CaptureWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for CaptureWriter {
+        type Writer = CaptureWriter;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    /// Log one event at each of `info` and `debug`, returning the output
+    fn capture(format: LogFormat, filter: EnvFilter) -> String {
+        let writer = CaptureWriter::default();
+        let subscriber = build_subscriber_with_writer(format, filter, writer.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("info event");
+            tracing::debug!("debug event");
+        });
+        let output = writer.0.lock().unwrap().clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!("pretty".parse::<LogFormat>().unwrap(), LogFormat::Pretty);
+        assert_eq!("JSON".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+
+    #[test]
+    fn test_parse_filter_falls_back_to_info() {
+        let (filter, invalid) = parse_filter(None);
+        assert_eq!(filter.to_string(), "info");
+        assert!(invalid.is_none());
+
+        let (filter, invalid) = parse_filter(Some("debug"));
+        assert_eq!(filter.to_string(), "debug");
+        assert!(invalid.is_none());
+
+        let (filter, invalid) = parse_filter(Some("engine_server=loud"));
+        assert_eq!(filter.to_string(), "info");
+        assert!(invalid.is_some());
+    }
+
+    #[test]
+    fn test_build_subscriber_uses_the_format_and_filter() {
+        let json = capture(LogFormat::Json, EnvFilter::new("info"));
+        let lines: Vec<&str> = json.lines().collect();
+        assert_eq!(lines.len(), 1, "debug is filtered out: {}", json);
+        assert!(lines[0].starts_with('{'), "not JSON: {}", json);
+        assert!(lines[0].contains("\"message\":\"info event\""));
+
+        let pretty = capture(LogFormat::Pretty, EnvFilter::new("debug"));
+        let lines: Vec<&str> = pretty.lines().collect();
+        assert_eq!(lines.len(), 2, "debug is enabled: {}", pretty);
+        assert!(!lines[0].starts_with('{'));
+        assert!(lines[0].ends_with("info event"), "{}", pretty);
+        assert!(lines[1].ends_with("debug event"), "{}", pretty);
+    }
+}
@@ -3,33 +3,155 @@
 //! Main entry point for the Cartridge engine server.
 
 use std::env;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::signal;
 use tonic::transport::Server;
+use tracing::info;
 use engine_proto::engine_server::EngineServer;
-use engine_server::{EngineService, registry_init};
+use engine_server::admission::InFlightLimitLayer;
+use engine_server::auth::AuthLayer;
+use engine_server::session::SessionRecorder;
+use engine_server::{BufferPool, EngineService, logging, registry_init, runtime, self_check, tls};
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Diagnostic mode: ENGINE_SINGLE_THREADED serves every request on one thread
+    let single_threaded = runtime::single_threaded_from_env();
+    runtime::build(single_threaded)?.block_on(serve(single_threaded))
+}
+
+async fn serve(single_threaded: bool) -> Result<(), Box<dyn std::error::Error>> {
+    // Initialize tracing (LOG_FORMAT selects pretty or json output)
+    logging::init_tracing()?;
     
     // Initialize the game registry
     registry_init::initialize_registry();
     
+    // Catch games that do not reproduce from their seed before serving them
+    let nondeterministic = self_check::probe_registered_games();
+    if !nondeterministic.is_empty() && self_check::require_deterministic_from_env() {
+        return Err(format!(
+            "{} game(s) failed the determinism probe and {} is set",
+            nondeterministic.len(),
+            self_check::REQUIRE_DETERMINISTIC_ENV
+        )
+        .into());
+    }
+    
+    // Periodically exercise every registered game off the request path
+    if let Some(interval) = self_check::interval_from_env()? {
+        self_check::spawn(interval);
+        info!(?interval, "Self-checking registered games");
+    }
+    
     // Get server address from environment or use default
     let addr = env::var("ENGINE_SERVER_ADDR")
         .unwrap_or_else(|_| "0.0.0.0:50051".to_string())
         .parse()?;
     
-    // Create the service
-    let engine_service = EngineService::new();
+    // Size the buffer pool from the expected workload when one is configured
+    let mut engine_service = match env::var("ENGINE_POOL_CONCURRENCY") {
+        Ok(concurrency) => {
+            let batch_size = env::var("ENGINE_POOL_BATCH_SIZE")
+                .map(|size| size.parse())
+                .unwrap_or(Ok(1))?;
+            let config = BufferPool::recommend_capacity(concurrency.parse()?, batch_size);
+            EngineService::with_buffer_pool(BufferPool::from_config(&config))
+        }
+        Err(_) => EngineService::new(),
+    };
+    
+    // Debugging aid: allocate fresh buffers for every request
+    if matches!(env::var("ENGINE_BUFFER_POOL_DISABLED").as_deref(), Ok("1" | "true")) {
+        engine_service = EngineService::with_buffer_pool(BufferPool::disabled());
+    }
+    
+    // Memoize pure game steps when a cache size is configured
+    if let Ok(capacity) = env::var("ENGINE_STEP_CACHE_CAPACITY") {
+        let capacity: usize = capacity.parse()?;
+        if capacity > 0 {
+            engine_service = engine_service.with_step_cache(capacity);
+        }
+    }
+    
+    // Bound how long requests queue on the game cache lock before failing fast
+    if let Ok(timeout_ms) = env::var("ENGINE_CACHE_LOCK_TIMEOUT_MS") {
+        let timeout = Duration::from_millis(timeout_ms.parse()?);
+        engine_service = engine_service.with_cache_lock_timeout(timeout);
+    }
+    
+    // Evict cached games idle for longer than the TTL (0 = keep them forever)
+    if let Ok(ttl_secs) = env::var("ENGINE_GAME_TTL_SECS") {
+        let ttl = Duration::from_secs(ttl_secs.parse()?);
+        engine_service = engine_service.with_game_ttl(ttl);
+    }
     
-    println!("Engine server starting on {}", addr);
+    // Debugging aid: check every delta obs against the full obs (debug builds only)
+    if matches!(env::var("ENGINE_VALIDATE_OBS_DELTAS").as_deref(), Ok("1" | "true")) {
+        engine_service = engine_service.with_obs_delta_validation();
+    }
     
-    // Start the server
-    Server::builder()
-        .add_service(EngineServer::new(engine_service))
-        .serve(addr)
+    // Record every reset and step for replay in regression tests
+    if let Ok(path) = env::var("ENGINE_RECORD_SESSION") {
+        engine_service = engine_service.with_session_recorder(SessionRecorder::create(&path)?);
+        info!(%path, "Recording session");
+    }
+    
+    // Rebuild the games cached before the last shutdown, and record the
+    // cached games again when this server stops
+    let cache_manifest = env::var("ENGINE_CACHE_MANIFEST").ok();
+    if let Some(path) = cache_manifest.as_deref().filter(|path| Path::new(path).exists()) {
+        let results = engine_service.warm_from_manifest(path).await?;
+        let warmed = results.iter().filter(|r| r.code == tonic::Code::Ok as i32).count();
+        info!(warmed, total = results.len(), %path, "Warmed cached games from the manifest");
+    }
+    let engine_service = Arc::new(engine_service);
+    if engine_service.spawn_game_ttl_sweeper().is_some() {
+        info!("Evicting cached games idle for longer than the configured TTL");
+    }
+    
+    // TLS is opt-in via ENGINE_TLS_* so local development stays plaintext
+    let mut builder = Server::builder();
+    if let Some(tls_config) = tls::server_tls_config_from_env()? {
+        builder = builder.tls_config(tls_config)?;
+        info!(%addr, "Engine server starting (TLS)");
+    } else {
+        info!(%addr, "Engine server starting");
+    }
+    
+    // Token auth (ENGINE_AUTH_TOKEN) runs first so unauthenticated requests
+    // never take an admission permit
+    let auth = AuthLayer::from_env()?;
+    if auth.is_enforced() {
+        info!("Requests must carry the configured authorization token");
+    }
+    
+    if single_threaded {
+        info!(env = runtime::SINGLE_THREADED_ENV, "Serving all requests on a single thread");
+    }
+    
+    // Admission control wraps the router so every method shares one budget
+    let in_flight_limit = InFlightLimitLayer::from_env()?;
+    
+    // Start the server, stopping on ctrl+c or SIGTERM (sent by container runtimes)
+    let mut terminate = signal::unix::signal(signal::unix::SignalKind::terminate())?;
+    builder
+        .layer(auth)
+        .layer(in_flight_limit)
+        .add_service(EngineServer::from_arc(Arc::clone(&engine_service)))
+        .serve_with_shutdown(addr, async move {
+            tokio::select! {
+                result = signal::ctrl_c() => result.expect("Failed to listen for ctrl+c"),
+                _ = terminate.recv() => {}
+            }
+        })
         .await?;
     
+    if let Some(path) = cache_manifest {
+        let manifest = engine_service.dump_cache_manifest(&path).await?;
+        info!(total = manifest.ids.len(), %path, "Wrote cached games to the manifest");
+    }
+    
     Ok(())
 }
\ No newline at end of file
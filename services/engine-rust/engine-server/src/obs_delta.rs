@@ -0,0 +1,236 @@
+//! Sparse observation deltas for slowly-changing observations
+//!
+//! Clients that opt in with `StepRequest.delta_obs` send the hash of the last
+//! observation they hold. If the server still has that observation cached, the
+//! step response carries only the bytes that changed; otherwise it falls back
+//! to the full observation.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use engine_core::adapter::stable_hash;
+
+/// Default number of observations retained as delta bases
+pub const DEFAULT_OBS_CACHE_CAPACITY: usize = 4096;
+
+/// Hash observation bytes with 64-bit FNV-1a (`engine_core::adapter::stable_hash`)
+///
+/// The algorithm is fixed so clients in any language can compute the same value.
+pub fn obs_hash(obs: &[u8]) -> u64 {
+    stable_hash(obs)
+}
+
+/// Byte-level difference between two equally sized observations
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ObsDiff {
+    /// Byte offsets that changed, in ascending order
+    pub indices: Vec<u32>,
+    /// New byte value at each offset in `indices`
+    pub values: Vec<u8>,
+}
+
+impl ObsDiff {
+    /// Compute the diff from `base` to `new`
+    ///
+    /// Returns `None` when the lengths differ, since a sparse diff cannot
+    /// describe a resize.
+    pub fn compute(base: &[u8], new: &[u8]) -> Option<Self> {
+        if base.len() != new.len() {
+            return None;
+        }
+
+        let mut diff = ObsDiff::default();
+        for (idx, (&old, &value)) in base.iter().zip(new).enumerate() {
+            if old != value {
+                diff.indices.push(idx as u32);
+                diff.values.push(value);
+            }
+        }
+        Some(diff)
+    }
+
+    /// Approximate wire size of the diff in bytes
+    pub fn encoded_len(&self) -> usize {
+        // Offsets are varint-encoded; four bytes is a safe upper bound for small obs
+        self.indices.len() * 4 + self.values.len()
+    }
+
+    /// Reconstruct the full observation by applying the diff to `base`
+    pub fn apply(&self, base: &[u8]) -> Result<Vec<u8>, String> {
+        if self.indices.len() != self.values.len() {
+            return Err(format!(
+                "Delta has {} indices but {} values",
+                self.indices.len(),
+                self.values.len()
+            ));
+        }
+
+        let mut obs = base.to_vec();
+        for (&idx, &value) in self.indices.iter().zip(&self.values) {
+            let slot = obs.get_mut(idx as usize).ok_or_else(|| {
+                format!(
+                    "Delta index {} out of bounds for obs of {} bytes",
+                    idx,
+                    base.len()
+                )
+            })?;
+            *slot = value;
+        }
+        Ok(obs)
+    }
+}
+
+/// Bounded cache of recently sent observations keyed by `obs_hash`
+///
+/// Entries are evicted oldest-first once `capacity` is reached.
+#[derive(Debug)]
+pub struct ObsCache {
+    capacity: usize,
+    inner: Mutex<ObsCacheInner>,
+}
+
+#[derive(Debug, Default)]
+struct ObsCacheInner {
+    entries: HashMap<u64, Vec<u8>>,
+    order: VecDeque<u64>,
+}
+
+impl ObsCache {
+    /// Create a cache retaining at most `capacity` observations
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(ObsCacheInner::default()),
+        }
+    }
+
+    /// Remember `obs` as a delta base and return its hash
+    pub fn insert(&self, obs: &[u8]) -> u64 {
+        let hash = obs_hash(obs);
+        if self.capacity == 0 {
+            return hash;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        if inner.entries.contains_key(&hash) {
+            return hash;
+        }
+
+        while inner.order.len() >= self.capacity {
+            if let Some(oldest) = inner.order.pop_front() {
+                inner.entries.remove(&oldest);
+            }
+        }
+        inner.entries.insert(hash, obs.to_vec());
+        inner.order.push_back(hash);
+        hash
+    }
+
+    /// Diff `new` against the cached observation with hash `base_hash`
+    ///
+    /// Returns `None` when the base is unknown, the lengths differ, or the diff
+    /// would not be smaller than sending `new` in full.
+    pub fn diff_against(&self, base_hash: u64, new: &[u8]) -> Option<ObsDiff> {
+        let inner = self.inner.lock().unwrap();
+        let base = inner.entries.get(&base_hash)?;
+        ObsDiff::compute(base, new).filter(|diff| diff.encoded_len() < new.len())
+    }
+
+    /// Check that applying `diff` to the cached base `base_hash` rebuilds `full`
+    ///
+    /// A safety net for the delta encoding: the error names the first byte at
+    /// which the reconstruction differs. Passes trivially when the base has
+    /// already been evicted, since there is nothing left to compare against.
+    pub fn verify_diff(&self, base_hash: u64, diff: &ObsDiff, full: &[u8]) -> Result<(), String> {
+        let inner = self.inner.lock().unwrap();
+        let Some(base) = inner.entries.get(&base_hash) else {
+            return Ok(());
+        };
+        let rebuilt = diff.apply(base)?;
+        if rebuilt.len() != full.len() {
+            return Err(format!(
+                "Delta rebuilds {} bytes but the full obs has {}",
+                rebuilt.len(),
+                full.len()
+            ));
+        }
+        match rebuilt.iter().zip(full).position(|(a, b)| a != b) {
+            Some(idx) => Err(format!(
+                "Delta rebuilds byte {} as {:#04x} but the full obs has {:#04x}",
+                idx, rebuilt[idx], full[idx]
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Number of cached observations
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// Whether the cache holds no observations
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for ObsCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_OBS_CACHE_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_obs_hash_is_fnv1a() {
+        // Reference values for 64-bit FNV-1a
+        assert_eq!(obs_hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(obs_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_diff_roundtrip() {
+        let base = vec![0u8, 1, 2, 3, 4, 5];
+        let new = vec![0u8, 9, 2, 3, 7, 5];
+
+        let diff = ObsDiff::compute(&base, &new).unwrap();
+        assert_eq!(diff.indices, vec![1, 4]);
+        assert_eq!(diff.values, vec![9, 7]);
+        assert_eq!(diff.apply(&base).unwrap(), new);
+
+        assert!(ObsDiff::compute(&base, &new[..5]).is_none());
+    }
+
+    #[test]
+    fn test_cache_evicts_oldest() {
+        let cache = ObsCache::new(2);
+        let first = cache.insert(&[1]);
+        cache.insert(&[2]);
+        cache.insert(&[3]);
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.diff_against(first, &[1]).is_none());
+    }
+
+    #[test]
+    fn test_verify_diff_catches_corrupted_delta() {
+        let cache = ObsCache::new(4);
+        let base = vec![0u8; 16];
+        let mut new = base.clone();
+        new[3] = 9;
+        let base_hash = cache.insert(&base);
+
+        let mut diff = cache.diff_against(base_hash, &new).unwrap();
+        cache.verify_diff(base_hash, &diff, &new).unwrap();
+
+        diff.values[0] = 8;
+        let err = cache.verify_diff(base_hash, &diff, &new).unwrap_err();
+        assert!(err.contains("byte 3"), "{}", err);
+
+        diff.indices[0] = 16;
+        assert!(cache.verify_diff(base_hash, &diff, &new).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! Per-environment payload size statistics for capacity planning
+//!
+//! The service records the byte length of the state, action, and observation
+//! payloads of every reset and step, so message limits and the buffer pool
+//! can be sized from real traffic. Only lengths are recorded; payloads are
+//! never copied. Sizes are bucketed by powers of two, which keeps recording
+//! constant-time and the histograms small.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Power-of-two buckets needed to cover every `usize` length
+pub const BUCKETS: usize = usize::BITS as usize + 1;
+
+/// Histogram of payload sizes in bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeHistogram {
+    pub count: u64,
+    pub sum: u64,
+    /// Smallest payload seen; zero while `count` is zero
+    pub min: u64,
+    pub max: u64,
+    /// `buckets[0]` counts empty payloads and `buckets[i]` sizes in `[2^(i-1), 2^i)`
+    pub buckets: [u64; BUCKETS],
+}
+
+impl SizeHistogram {
+    /// Bucket index holding payloads of `len` bytes
+    pub fn bucket(len: usize) -> usize {
+        (usize::BITS - len.leading_zeros()) as usize
+    }
+
+    fn record(&mut self, len: usize) {
+        let len_u64 = len as u64;
+        self.min = if self.count == 0 {
+            len_u64
+        } else {
+            self.min.min(len_u64)
+        };
+        self.max = self.max.max(len_u64);
+        self.count += 1;
+        self.sum += len_u64;
+        self.buckets[Self::bucket(len)] += 1;
+    }
+}
+
+impl Default for SizeHistogram {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            sum: 0,
+            min: 0,
+            max: 0,
+            buckets: [0; BUCKETS],
+        }
+    }
+}
+
+/// Payload sizes seen for one environment
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvPayloads {
+    /// Initial state returned by reset
+    pub reset_state: SizeHistogram,
+    /// Initial observation returned by reset
+    pub reset_obs: SizeHistogram,
+    /// State sent with a step request
+    pub step_request_state: SizeHistogram,
+    /// Action sent with a step request
+    pub step_request_action: SizeHistogram,
+    /// Next state returned by a step
+    pub step_response_state: SizeHistogram,
+    /// Observation returned by a step
+    pub step_response_obs: SizeHistogram,
+}
+
+/// Byte lengths of one step's payloads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepSizes {
+    pub request_state: usize,
+    pub request_action: usize,
+    pub response_state: usize,
+    pub response_obs: usize,
+}
+
+/// Payload size histograms keyed by env_id
+#[derive(Debug, Default)]
+pub struct PayloadStats {
+    envs: Mutex<HashMap<String, EnvPayloads>>,
+}
+
+impl PayloadStats {
+    /// Record the payloads a reset of `env_id` returned
+    pub fn record_reset(&self, env_id: &str, state: usize, obs: usize) {
+        self.update(env_id, |env| {
+            env.reset_state.record(state);
+            env.reset_obs.record(obs);
+        });
+    }
+
+    /// Record the payloads a step of `env_id` received and returned
+    pub fn record_step(&self, env_id: &str, sizes: StepSizes) {
+        self.update(env_id, |env| {
+            env.step_request_state.record(sizes.request_state);
+            env.step_request_action.record(sizes.request_action);
+            env.step_response_state.record(sizes.response_state);
+            env.step_response_obs.record(sizes.response_obs);
+        });
+    }
+
+    /// Snapshot of every environment's histograms, sorted by env_id
+    pub fn snapshot(&self) -> Vec<(String, EnvPayloads)> {
+        let mut envs: Vec<_> = self
+            .envs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(env_id, payloads)| (env_id.clone(), payloads.clone()))
+            .collect();
+        envs.sort_by(|a, b| a.0.cmp(&b.0));
+        envs
+    }
+
+    fn update(&self, env_id: &str, record: impl FnOnce(&mut EnvPayloads)) {
+        let mut envs = self.envs.lock().unwrap();
+        // Look up by &str first so the hot path never allocates a key
+        match envs.get_mut(env_id) {
+            Some(env) => record(env),
+            None => record(envs.entry(env_id.to_string()).or_default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_by_power_of_two() {
+        assert_eq!(SizeHistogram::bucket(0), 0);
+        assert_eq!(SizeHistogram::bucket(1), 1);
+        assert_eq!(SizeHistogram::bucket(3), 2);
+        assert_eq!(SizeHistogram::bucket(64), 7);
+        assert_eq!(SizeHistogram::bucket(116), 7);
+        assert_eq!(SizeHistogram::bucket(usize::MAX), BUCKETS - 1);
+
+        let stats = PayloadStats::default();
+        stats.record_reset("b", 12, 116);
+        stats.record_reset("a", 0, 4);
+        stats.record_reset("a", 12, 8);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.len(), 2);
+        let (env_id, a) = &snapshot[0];
+        assert_eq!(env_id, "a");
+        assert_eq!(
+            (
+                a.reset_state.count,
+                a.reset_state.sum,
+                a.reset_state.min,
+                a.reset_state.max
+            ),
+            (2, 12, 0, 12)
+        );
+        assert_eq!(a.reset_state.buckets[0], 1);
+        assert_eq!(a.reset_state.buckets[4], 1);
+        assert_eq!(a.reset_obs.buckets[3..5], [1, 1]);
+        assert_eq!(a.step_response_obs, SizeHistogram::default());
+    }
+}
@@ -1,26 +1,147 @@
 //! Game registry initialization
 //! 
 //! This module initializes the global game registry by registering all available games.
+//! Each game is behind a `game-*` Cargo feature of this crate, so only the enabled
+//! games are compiled in and registered.
+//!
+//! Setting `ENGINE_ENV_SEED_MIXING=1` mixes each game's env_id into its reset
+//! seeds (see `GameAdapter::with_env_seed_mixing`), so games reset with the
+//! same seed draw uncorrelated streams. It changes every seeded trajectory, so
+//! it is off by default.
 
-use engine_core::{GameAdapter, register_game};
-use games_tictactoe::TicTacToe;
+use std::env;
 
-/// Initialize the global game registry with all available games
+// Unused when every game feature is turned off
+#[allow(unused_imports)]
+use engine_core::{Game, GameAdapter, register_game};
+use engine_core::registry::{create_game, list_registered_games};
+use tracing::{info, warn};
+
+/// Environment variable that enables env_id seed mixing for every game
+pub const ENV_SEED_MIXING_ENV: &str = "ENGINE_ENV_SEED_MIXING";
+
+/// Whether `ENGINE_ENV_SEED_MIXING` asks for env_id seed mixing
+pub fn env_seed_mixing_from_env() -> bool {
+    matches!(env::var(ENV_SEED_MIXING_ENV).as_deref(), Ok("1" | "true"))
+}
+
+/// Wrap a registered game in the adapter options the server is configured with
+#[allow(dead_code)] // Unused when every game feature is turned off
+fn adapt<T: Game>(game: T, env_seed_mixing: bool) -> GameAdapter<T> {
+    let adapter = GameAdapter::new(game);
+    if env_seed_mixing {
+        adapter.with_env_seed_mixing()
+    } else {
+        adapter
+    }
+}
+
+/// Initialize the global game registry with all enabled games
 /// 
 /// This function should be called once at startup to register all game implementations
 /// with the global registry.
 pub fn initialize_registry() {
-    // Register TicTacToe game
+    #[cfg(feature = "game-tictactoe")]
     register_game(
         "tictactoe".to_string(), 
-        || Box::new(GameAdapter::new(TicTacToe::new()))
+        || Box::new(adapt(games_tictactoe::TicTacToe::new(), env_seed_mixing_from_env()))
     );
     
-    println!("Initialized game registry with {} games", 
-             engine_core::registry::list_registered_games().len());
-    
-    // Log registered games
-    for game_id in engine_core::registry::list_registered_games() {
-        println!("  - {}", game_id);
+    // Further games follow the same pattern, e.g.
+    // #[cfg(feature = "game-connect4")]
+    // register_game("connect4".to_string(), || Box::new(adapt(Connect4::new(), env_seed_mixing_from_env())));
+
+    if env_seed_mixing_from_env() {
+        info!("Mixing each env_id into its games' reset seeds");
+    }
+    log_registered_games();
+}
+
+/// Emit one structured event per registered game plus a summary event
+///
+/// Each per-game event carries the env_id and its capabilities as fields so
+/// startup is observable in log aggregators.
+pub fn log_registered_games() {
+    let mut game_ids = list_registered_games();
+    game_ids.sort();
+
+    for env_id in &game_ids {
+        let Some(game) = create_game(env_id) else {
+            warn!(env_id = %env_id, "Registered game could not be instantiated");
+            continue;
+        };
+
+        let caps = game.capabilities();
+        info!(
+            env_id = %env_id,
+            build_id = %caps.id.build_id,
+            state_encoding = %caps.encoding.state,
+            action_encoding = %caps.encoding.action,
+            obs_encoding = %caps.encoding.obs,
+            schema_version = caps.encoding.schema_version,
+            max_horizon = caps.max_horizon,
+            preferred_batch = caps.preferred_batch,
+            action_space = ?caps.action_space,
+            "Registered game"
+        );
+    }
+
+    info!(count = game_ids.len(), games = ?game_ids, "Initialized game registry");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::fmt::MakeWriter;
+
+    /// Writer that captures formatted tracing output in memory
+    #[derive(Clone, Default)]
+    struct CaptureWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for CaptureWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for CaptureWriter {
+        type Writer = CaptureWriter;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[test]
+    fn test_initialize_registry_logs_structured_games() {
+        let writer = CaptureWriter::default();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_writer(writer.clone())
+            .finish();
+
+        tracing::subscriber::with_default(subscriber, initialize_registry);
+
+        let output = String::from_utf8(writer.0.lock().unwrap().clone()).unwrap();
+        for env_id in list_registered_games() {
+            assert!(
+                output.contains(&format!("\"env_id\":\"{}\"", env_id)),
+                "missing env_id field for {} in {}",
+                env_id,
+                output
+            );
+        }
+        if cfg!(feature = "game-tictactoe") {
+            assert!(output.contains("\"max_horizon\":9"));
+        }
+        assert!(output.contains("Initialized game registry"));
+        assert!(output.contains("\"count\":"));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,34 @@
+//! Tokio runtime selection for the engine server
+//!
+//! The server runs on the multi-threaded runtime by default. Setting
+//! `ENGINE_SINGLE_THREADED=1` serves every request on one thread instead, a
+//! diagnostic mode for telling nondeterminism in game code apart from
+//! interleaving between worker threads. It trades away all request
+//! parallelism, so it is not meant for production.
+
+use std::env;
+use std::io;
+
+use tokio::runtime::{Builder, Runtime};
+
+/// Environment variable that selects the current-thread runtime
+pub const SINGLE_THREADED_ENV: &str = "ENGINE_SINGLE_THREADED";
+
+/// Whether `ENGINE_SINGLE_THREADED` asks for the current-thread runtime
+pub fn single_threaded_from_env() -> bool {
+    matches!(env::var(SINGLE_THREADED_ENV).as_deref(), Ok("1" | "true"))
+}
+
+/// Build the server runtime
+///
+/// The multi-threaded runtime matches what `#[tokio::main]` builds; the
+/// current-thread runtime polls the server and every request on the calling
+/// thread.
+pub fn build(single_threaded: bool) -> io::Result<Runtime> {
+    let mut builder = if single_threaded {
+        Builder::new_current_thread()
+    } else {
+        Builder::new_multi_thread()
+    };
+    builder.enable_all().build()
+}
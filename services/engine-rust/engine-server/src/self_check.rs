@@ -0,0 +1,397 @@
+//! Periodic self-check of registered games
+//!
+//! A game can register cleanly and still be unusable, for example when its
+//! state fails to decode what its own reset encoded. With
+//! `ENGINE_SELF_CHECK_INTERVAL_SECS` set, the server periodically creates a
+//! fresh instance of every registered game, resets it with a fixed seed, and
+//! steps the initial state with its first legal action, logging an error for
+//! every game that fails.
+//!
+//! Checks run on the blocking thread pool, never on the request path, and use
+//! instances created directly from the registry: the service's game cache is
+//! never touched and every instance is dropped once its check completes.
+//!
+//! At startup the server also probes every registered game for determinism:
+//! two fresh instances play the same seed and first few legal actions in
+//! lockstep, and a warning is logged for every game whose runs diverge. The
+//! probe costs a reset and `DETERMINISM_PROBE_STEPS` steps per instance and,
+//! like the periodic check, never touches the game cache. It only fails
+//! startup when `ENGINE_REQUIRE_DETERMINISTIC` is set.
+
+use std::env;
+use std::error::Error;
+use std::time::Duration;
+
+use engine_core::determinism::{verify_determinism, DeterminismError};
+use engine_core::erased::ErasedGameError;
+use engine_core::{registry, ErasedGame};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, warn};
+
+/// Environment variable with the seconds between self-check passes
+pub const SELF_CHECK_INTERVAL_ENV: &str = "ENGINE_SELF_CHECK_INTERVAL_SECS";
+
+/// Environment variable that makes a failed determinism probe fail startup
+pub const REQUIRE_DETERMINISTIC_ENV: &str = "ENGINE_REQUIRE_DETERMINISTIC";
+
+/// Seed every checked game is reset with, so failures reproduce
+pub const SELF_CHECK_SEED: u64 = 0x5e1f_c4ec;
+
+/// Steps the determinism probe plays after the reset
+pub const DETERMINISM_PROBE_STEPS: usize = 2;
+
+/// Why a registered game failed its self-check
+#[derive(Debug, thiserror::Error)]
+pub enum SelfCheckError {
+    #[error("Game factory is no longer registered")]
+    NotRegistered,
+    #[error("Reset failed: {0}")]
+    Reset(#[source] ErasedGameError),
+    #[error("Listing legal actions failed: {0}")]
+    LegalActions(#[source] ErasedGameError),
+    #[error("Initial state has no legal actions")]
+    NoLegalActions,
+    #[error("Step failed: {0}")]
+    Step(#[source] ErasedGameError),
+    #[error("{0}")]
+    Nondeterministic(#[source] DeterminismError),
+}
+
+/// A registered game that failed its self-check
+#[derive(Debug)]
+pub struct SelfCheckFailure {
+    pub env_id: String,
+    pub error: SelfCheckError,
+}
+
+/// Read the self-check interval from `ENGINE_SELF_CHECK_INTERVAL_SECS`
+///
+/// Returns `None`, disabling the self-check, when the variable is unset.
+pub fn interval_from_env() -> Result<Option<Duration>, Box<dyn Error>> {
+    match env::var(SELF_CHECK_INTERVAL_ENV) {
+        Ok(secs) => {
+            let secs: u64 = secs
+                .parse()
+                .map_err(|e| format!("Invalid {}={:?}: {}", SELF_CHECK_INTERVAL_ENV, secs, e))?;
+            if secs == 0 {
+                return Err(format!("{} must be at least 1", SELF_CHECK_INTERVAL_ENV).into());
+            }
+            Ok(Some(Duration::from_secs(secs)))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Whether `ENGINE_REQUIRE_DETERMINISTIC` is set to `1` or `true`
+pub fn require_deterministic_from_env() -> bool {
+    matches!(
+        env::var(REQUIRE_DETERMINISTIC_ENV).as_deref(),
+        Ok("1" | "true")
+    )
+}
+
+/// Reset `game` with `SELF_CHECK_SEED` and step once with a legal action
+///
+/// Games that do not enumerate their legal actions are only reset.
+pub fn check_game(game: &mut dyn ErasedGame) -> Result<(), SelfCheckError> {
+    let mut state = Vec::new();
+    let mut obs = Vec::new();
+    game.reset(SELF_CHECK_SEED, &[], &mut state, &mut obs)
+        .map_err(SelfCheckError::Reset)?;
+
+    let actions = match game
+        .legal_actions(&state)
+        .map_err(SelfCheckError::LegalActions)?
+    {
+        Some(actions) => actions,
+        None => return Ok(()),
+    };
+    let action = actions.first().ok_or(SelfCheckError::NoLegalActions)?;
+
+    let (mut next_state, mut next_obs) = (Vec::new(), Vec::new());
+    game.step(&state, action, &mut next_state, &mut next_obs)
+        .map_err(SelfCheckError::Step)?;
+    Ok(())
+}
+
+/// Check every registered game, logging and returning the failures
+pub fn check_registered_games() -> Vec<SelfCheckFailure> {
+    let mut failures = Vec::new();
+    for env_id in registry::list_registered_games() {
+        let result = match registry::create_game(&env_id) {
+            Some(mut game) => check_game(game.as_mut()),
+            None => Err(SelfCheckError::NotRegistered),
+        };
+        match result {
+            Ok(()) => debug!(env_id = %env_id, "Game passed self-check"),
+            Err(e) => {
+                error!(env_id = %env_id, error = %e, "Game failed self-check");
+                failures.push(SelfCheckFailure { env_id, error: e });
+            }
+        }
+    }
+    failures
+}
+
+/// Play two fresh instances of `env_id` in lockstep and require identical
+/// outputs
+///
+/// The actions are the first legal action of each state, found by a third
+/// instance, for up to `DETERMINISM_PROBE_STEPS` steps. Games that do not
+/// enumerate their legal actions are only compared on the reset.
+pub fn probe_determinism(env_id: &str) -> Result<(), SelfCheckError> {
+    let create = || registry::create_game(env_id).ok_or(SelfCheckError::NotRegistered);
+
+    let mut scout = create()?;
+    let mut actions = Vec::new();
+    let (mut state, mut obs) = (Vec::new(), Vec::new());
+    scout
+        .reset(SELF_CHECK_SEED, &[], &mut state, &mut obs)
+        .map_err(SelfCheckError::Reset)?;
+    while actions.len() < DETERMINISM_PROBE_STEPS {
+        let legal = scout
+            .legal_actions(&state)
+            .map_err(SelfCheckError::LegalActions)?;
+        let Some(action) = legal.and_then(|legal| legal.into_iter().next()) else {
+            break;
+        };
+        let mut next_state = Vec::new();
+        let outcome = scout
+            .step(&state, &action, &mut next_state, &mut obs)
+            .map_err(SelfCheckError::Step)?;
+        actions.push(action);
+        state = next_state;
+        if outcome.done {
+            break;
+        }
+    }
+
+    let (mut first, mut second) = (create()?, create()?);
+    verify_determinism(
+        first.as_mut(),
+        second.as_mut(),
+        SELF_CHECK_SEED,
+        &[],
+        &actions,
+    )
+    .map_err(SelfCheckError::Nondeterministic)
+}
+
+/// Probe every registered game for determinism, warning about and returning
+/// the failures
+pub fn probe_registered_games() -> Vec<SelfCheckFailure> {
+    let mut failures = Vec::new();
+    for env_id in registry::list_registered_games() {
+        match probe_determinism(&env_id) {
+            Ok(()) => debug!(env_id = %env_id, "Game passed determinism probe"),
+            Err(e) => {
+                warn!(env_id = %env_id, error = %e, "Game failed determinism probe");
+                failures.push(SelfCheckFailure { env_id, error: e });
+            }
+        }
+    }
+    failures
+}
+
+/// Run `check_registered_games` every `interval` on the blocking thread pool
+///
+/// The first pass runs immediately. Must be called within a Tokio runtime.
+pub fn spawn(interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            if let Err(e) = tokio::task::spawn_blocking(check_registered_games).await {
+                error!(error = %e, "Self-check pass panicked");
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine_core::erased::{ResetOutcome, StepOutcome};
+    use engine_core::registry::RegistryGuard;
+    use engine_core::typed::{Capabilities, EngineId};
+    use engine_core::{register_game, GameAdapter};
+    use games_tictactoe::TicTacToe;
+    use std::io;
+    use std::sync::atomic::{AtomicU8, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::fmt::MakeWriter;
+
+    /// TicTacToe whose reset emits a state its own step cannot decode
+    struct TruncatedStateGame(GameAdapter<TicTacToe>);
+
+    impl ErasedGame for TruncatedStateGame {
+        fn engine_id(&self) -> EngineId {
+            self.0.engine_id()
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            self.0.capabilities()
+        }
+
+        fn reset(
+            &mut self,
+            seed: u64,
+            hint: &[u8],
+            out_state: &mut Vec<u8>,
+            out_obs: &mut Vec<u8>,
+        ) -> Result<ResetOutcome, ErasedGameError> {
+            let outcome = self.0.reset(seed, hint, out_state, out_obs)?;
+            out_state.pop();
+            Ok(outcome)
+        }
+
+        fn legal_actions(&self, state: &[u8]) -> Result<Option<Vec<Vec<u8>>>, ErasedGameError> {
+            self.0.legal_actions(state)
+        }
+
+        fn fork(&self) -> Box<dyn ErasedGame> {
+            Box::new(TruncatedStateGame(GameAdapter::new(TicTacToe::new())))
+        }
+
+        fn step(
+            &mut self,
+            state: &[u8],
+            action: &[u8],
+            out_state: &mut Vec<u8>,
+            out_obs: &mut Vec<u8>,
+        ) -> Result<StepOutcome, ErasedGameError> {
+            self.0.step(state, action, out_state, out_obs)
+        }
+    }
+
+    #[test]
+    fn test_self_check_reports_broken_games_only() {
+        let _registry = RegistryGuard::isolated();
+        register_game("tictactoe".to_string(), || {
+            Box::new(GameAdapter::new(TicTacToe::new()))
+        });
+        register_game("truncated".to_string(), || {
+            Box::new(TruncatedStateGame(GameAdapter::new(TicTacToe::new())))
+        });
+
+        let failures = check_registered_games();
+        assert_eq!(failures.len(), 1, "{:?}", failures);
+        assert_eq!(failures[0].env_id, "truncated");
+        // The truncated state is rejected before a step is attempted
+        assert!(
+            matches!(failures[0].error, SelfCheckError::LegalActions(_)),
+            "{}",
+            failures[0].error
+        );
+    }
+
+    static RESETS: AtomicU8 = AtomicU8::new(0);
+
+    /// TicTacToe whose reset obs ends with a process-wide reset counter
+    struct HiddenCounterGame(GameAdapter<TicTacToe>);
+
+    impl ErasedGame for HiddenCounterGame {
+        fn engine_id(&self) -> EngineId {
+            self.0.engine_id()
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            self.0.capabilities()
+        }
+
+        fn reset(
+            &mut self,
+            seed: u64,
+            hint: &[u8],
+            out_state: &mut Vec<u8>,
+            out_obs: &mut Vec<u8>,
+        ) -> Result<ResetOutcome, ErasedGameError> {
+            let outcome = self.0.reset(seed, hint, out_state, out_obs)?;
+            out_obs.push(RESETS.fetch_add(1, Ordering::SeqCst));
+            Ok(outcome)
+        }
+
+        fn legal_actions(&self, state: &[u8]) -> Result<Option<Vec<Vec<u8>>>, ErasedGameError> {
+            self.0.legal_actions(state)
+        }
+
+        fn fork(&self) -> Box<dyn ErasedGame> {
+            Box::new(HiddenCounterGame(GameAdapter::new(TicTacToe::new())))
+        }
+
+        fn step(
+            &mut self,
+            state: &[u8],
+            action: &[u8],
+            out_state: &mut Vec<u8>,
+            out_obs: &mut Vec<u8>,
+        ) -> Result<StepOutcome, ErasedGameError> {
+            self.0.step(state, action, out_state, out_obs)
+        }
+    }
+
+    /// Writer that captures formatted tracing output in memory
+    #[derive(Clone, Default)]
+    struct CaptureWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for CaptureWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for CaptureWriter {
+        type Writer = CaptureWriter;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[test]
+    fn test_determinism_probe_warns_about_nondeterministic_games_only() {
+        let _registry = RegistryGuard::isolated();
+        register_game("tictactoe".to_string(), || {
+            Box::new(GameAdapter::new(TicTacToe::new()))
+        });
+        register_game("hidden-counter".to_string(), || {
+            Box::new(HiddenCounterGame(GameAdapter::new(TicTacToe::new())))
+        });
+
+        let writer = CaptureWriter::default();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_writer(writer.clone())
+            .finish();
+        let failures = tracing::subscriber::with_default(subscriber, probe_registered_games);
+
+        assert_eq!(failures.len(), 1, "{:?}", failures);
+        assert_eq!(failures[0].env_id, "hidden-counter");
+        assert!(
+            matches!(
+                failures[0].error,
+                SelfCheckError::Nondeterministic(DeterminismError::Diverged { field: "obs", .. })
+            ),
+            "{}",
+            failures[0].error
+        );
+
+        let output = String::from_utf8(writer.0.lock().unwrap().clone()).unwrap();
+        let warnings: Vec<&str> = output
+            .lines()
+            .filter(|line| line.contains("\"level\":\"WARN\""))
+            .collect();
+        assert_eq!(warnings.len(), 1, "{}", output);
+        assert!(
+            warnings[0].contains("\"env_id\":\"hidden-counter\""),
+            "{}",
+            output
+        );
+        assert!(warnings[0].contains("determinism probe"), "{}", output);
+    }
+}
@@ -3,34 +3,147 @@
 //! This module provides the Tonic-based gRPC server implementation that handles
 //! all engine service methods with proper error handling and buffer management.
 
-use std::collections::{hash_map::Entry, HashMap};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use engine_core::registry::{create_game, is_registered};
+use engine_core::registry::{self, Registry};
+use engine_core::erased::{AutoReset, EncodedSizes};
+use engine_core::typed::InitialStateKind;
 use engine_core::ErasedGame;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use engine_proto::{
-    engine_server::Engine, BoxSpec as ProtoBoxSpec, Capabilities, Encoding as ProtoEncoding,
-    EngineId, MultiDiscrete as ProtoMultiDiscrete, ResetRequest, ResetResponse, StepRequest,
-    StepResponse,
+    engine_server::Engine, BoxSpec as ProtoBoxSpec, BufferPoolStats as ProtoBufferPoolStats,
+    CachedGame, Capabilities, DrainBuffersRequest, DrainBuffersResponse, Encoding as ProtoEncoding, EngineId,
+    EncodedSizes as ProtoEncodedSizes, EnvPayloadStats, GetPayloadStatsRequest, GetPayloadStatsResponse,
+    GetServerInfoRequest, GetServerInfoResponse, GetSupportedVersionsResponse, LegalActionCountRequest, LegalActionCountResponse,
+    InitialStateKind as ProtoInitialStateKind, ObsQuantization as ProtoObsQuantization, ObsSegment, ListCachedGamesRequest, ListCachedGamesResponse, MultiDiscrete as ProtoMultiDiscrete,
+    MultiStepRequest, MultiStepResponse, MultiStepResult, multi_step_result, ObsDelta, ReseedRequest, ReseedResponse, ResetRequest,
+    PayloadHistogram, ResetResponse, StepAutoResetRequest, StepAutoResetResponse, StepError, StepRequest,
+    StepResponse, StreamEpisodeRequest, Transition, WarmCacheRequest, WarmCacheResponse,
+    WarmCacheResult,
 };
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, MutexGuard, OnceCell};
+use tokio::task::JoinHandle;
+use tokio_stream::Stream;
 use tonic::{Request, Response, Result as TonicResult, Status};
+use tracing::{debug, info_span, Instrument};
+
+use crate::buffers::{BufferPool, BufferPoolStats, DEFAULT_BUFFER_CAPACITY};
+use crate::cache_manifest::{CacheManifest, CacheManifestError};
+use crate::obs_delta::ObsCache;
+use crate::payload_stats::{PayloadStats, SizeHistogram, StepSizes};
+use crate::session::{Exchange, SessionRecorder};
+use crate::step_cache::{CachedStep, StepCache};
+
+/// Cache key identifying a game instance by `(env_id, build_id)`
+type CacheKey = (String, String);
+
+/// Cached game entries by key
+type GameCache = HashMap<CacheKey, Arc<GameEntry>>;
+
+/// Transitions of a `StreamEpisode` call, ending with an error status on failure
+type TransitionStream = Pin<Box<dyn Stream<Item = Result<Transition, Status>> + Send>>;
+
+/// gRPC metadata header carrying a client's correlation id for a step
+pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
+
+/// Version of the engine server crate, fixed at build time
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Git commit the server was built from, injected by the build script
+pub const ENGINE_GIT_COMMIT: &str = env!("ENGINE_GIT_COMMIT");
+
+/// How long a request waits for the game cache lock before failing fast
+pub const DEFAULT_CACHE_LOCK_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Cached game instance for a single `(env_id, build_id)` key
+///
+/// The map lock is only held long enough to look up or insert the entry;
+/// construction is guarded by the per-entry `OnceCell` so concurrent resets
+/// for the same key build the game exactly once and then share it.
+#[derive(Default)]
+struct GameEntry {
+    game: OnceCell<Mutex<Box<dyn ErasedGame>>>,
+    /// Unix time in milliseconds of the last lookup that handed out this entry
+    last_access_ms: AtomicU64,
+    /// Inline size of the game, recorded once it is constructed
+    game_bytes: AtomicU64,
+}
+
+// The game itself is not `Debug`; report only whether it has been built
+impl fmt::Debug for GameEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GameEntry")
+            .field("initialized", &self.game.initialized())
+            .finish_non_exhaustive()
+    }
+}
+
+impl GameEntry {
+    /// Record that a request is using this entry now
+    fn touch(&self) {
+        self.last_access_ms.store(unix_time_ms(), Ordering::Relaxed);
+    }
+
+    /// Lock the game for exclusive use
+    ///
+    /// Entries handed out by the service are always initialized.
+    async fn lock(&self) -> MutexGuard<'_, Box<dyn ErasedGame>> {
+        self.game
+            .get()
+            .expect("cached game entry is initialized")
+            .lock()
+            .await
+    }
+}
 
-use crate::buffers::BufferPool;
+/// Current Unix time in milliseconds, or 0 if the clock is before the epoch
+fn unix_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
 
 /// Engine gRPC service implementation
 #[derive(Debug)]
 pub struct EngineService {
     buffer_pool: BufferPool,
-    game_cache: Arc<Mutex<HashMap<(String, String), Box<dyn ErasedGame>>>>,
+    game_cache: Arc<Mutex<GameCache>>,
+    cache_lock_timeout: Duration,
+    /// Idle time after which cached games are evicted; `None` keeps them forever
+    game_ttl: Option<Duration>,
+    obs_cache: Arc<ObsCache>,
+    step_cache: Option<Arc<StepCache>>,
+    /// Re-check every delta against the full obs (debug builds only)
+    validate_obs_deltas: bool,
+    payload_stats: Arc<PayloadStats>,
+    session_recorder: Option<Arc<SessionRecorder>>,
+    /// Games to serve; `None` uses the global registry
+    registry: Option<Arc<Registry>>,
+    started_at: Instant,
 }
 
 impl EngineService {
     /// Create a new engine service
     pub fn new() -> Self {
         Self {
-            buffer_pool: BufferPool::with_capacity(100, 100, 50, 512),
+            buffer_pool: BufferPool::with_capacity(100, 100, 50, DEFAULT_BUFFER_CAPACITY),
             game_cache: Arc::new(Mutex::new(HashMap::new())),
+            cache_lock_timeout: DEFAULT_CACHE_LOCK_TIMEOUT,
+            game_ttl: None,
+            obs_cache: Arc::new(ObsCache::default()),
+            step_cache: None,
+            validate_obs_deltas: false,
+            payload_stats: Arc::new(PayloadStats::default()),
+            session_recorder: None,
+            registry: None,
+            started_at: Instant::now(),
         }
     }
 
@@ -39,11 +152,308 @@ impl EngineService {
         Self {
             buffer_pool,
             game_cache: Arc::new(Mutex::new(HashMap::new())),
+            cache_lock_timeout: DEFAULT_CACHE_LOCK_TIMEOUT,
+            game_ttl: None,
+            obs_cache: Arc::new(ObsCache::default()),
+            step_cache: None,
+            validate_obs_deltas: false,
+            payload_stats: Arc::new(PayloadStats::default()),
+            session_recorder: None,
+            registry: None,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Enable step memoization for pure games, bounded to `capacity` results
+    ///
+    /// Only games reporting both `is_deterministic` and `is_state_complete`
+    /// are cached; every other game always executes.
+    pub fn with_step_cache(mut self, capacity: usize) -> Self {
+        self.step_cache = Some(Arc::new(StepCache::new(capacity)));
+        self
+    }
+
+    /// Check every delta observation sent against the full observation
+    ///
+    /// A mismatch is logged and fails the step with `INTERNAL` instead of
+    /// sending a delta the client would rebuild wrongly. The check costs a
+    /// copy of the base per delta and is compiled out of release builds, where
+    /// this setting has no effect.
+    pub fn with_obs_delta_validation(mut self) -> Self {
+        self.validate_obs_deltas = true;
+        self
+    }
+
+    /// Step cache in use, if memoization is enabled
+    pub fn step_cache(&self) -> Option<&StepCache> {
+        self.step_cache.as_deref()
+    }
+
+    /// Serve the games in `registry` instead of the global registry
+    ///
+    /// Lets one process run several independent engines with different game
+    /// sets.
+    pub fn with_registry(mut self, registry: Arc<Registry>) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    fn is_registered(&self, env_id: &str) -> bool {
+        match &self.registry {
+            Some(own) => own.is_registered(env_id),
+            None => registry::is_registered(env_id),
+        }
+    }
+
+    fn create_game(&self, env_id: &str) -> Option<Box<dyn ErasedGame>> {
+        match &self.registry {
+            Some(own) => own.create_game(env_id),
+            None => registry::create_game(env_id),
+        }
+    }
+
+    fn registered_game_count(&self) -> usize {
+        match &self.registry {
+            Some(own) => own.list_registered_games().len(),
+            None => registry::list_registered_games().len(),
+        }
+    }
+
+    /// Append every reset and step exchange to `recorder` for later replay
+    pub fn with_session_recorder(mut self, recorder: SessionRecorder) -> Self {
+        self.session_recorder = Some(Arc::new(recorder));
+        self
+    }
+
+    /// Fail requests with `RESOURCE_EXHAUSTED` when the game cache lock is
+    /// still contended after `timeout`
+    pub fn with_cache_lock_timeout(mut self, timeout: Duration) -> Self {
+        self.cache_lock_timeout = timeout;
+        self
+    }
+
+    /// Evict cached games that no request has used for `ttl`
+    ///
+    /// A zero `ttl` disables expiry, which is the default. Expired games are
+    /// removed by `evict_expired_games`, which `spawn_game_ttl_sweeper` runs
+    /// in the background. A step for an evicted game fails with
+    /// `FAILED_PRECONDITION` until the client resets it again.
+    pub fn with_game_ttl(mut self, ttl: Duration) -> Self {
+        self.game_ttl = (!ttl.is_zero()).then_some(ttl);
+        self
+    }
+
+    /// Remove every cached game not used within the TTL
+    ///
+    /// Returns how many games were evicted. The sweep never waits for the
+    /// cache lock: while requests hold it nothing is evicted, and the next
+    /// sweep tries again. Requests already holding an evicted game finish
+    /// with it.
+    pub fn evict_expired_games(&self) -> usize {
+        let Some(ttl) = self.game_ttl else {
+            return 0;
+        };
+        let Ok(mut cache) = self.game_cache.try_lock() else {
+            return 0;
+        };
+
+        let now = unix_time_ms();
+        let ttl_ms = ttl.as_millis() as u64;
+        let before = cache.len();
+        cache.retain(|_, entry| {
+            // Entries still being constructed have not been touched yet
+            let last_access = entry.last_access_ms.load(Ordering::Relaxed);
+            !entry.game.initialized()
+                || last_access == 0
+                || now.saturating_sub(last_access) < ttl_ms
+        });
+        before - cache.len()
+    }
+
+    /// Run `evict_expired_games` every half TTL on a background task
+    ///
+    /// An idle game is therefore evicted between one and one and a half TTLs
+    /// after its last use. Returns `None` without spawning when no TTL is
+    /// set. The task holds only a weak reference and stops once the service
+    /// is dropped. Must be called within a Tokio runtime.
+    pub fn spawn_game_ttl_sweeper(self: &Arc<Self>) -> Option<JoinHandle<()>> {
+        let ttl = self.game_ttl?;
+        let service = Arc::downgrade(self);
+        Some(tokio::spawn(async move {
+            let mut ticks = tokio::time::interval((ttl / 2).max(Duration::from_millis(1)));
+            loop {
+                ticks.tick().await;
+                let Some(service) = service.upgrade() else {
+                    return;
+                };
+                let evicted = service.evict_expired_games();
+                if evicted > 0 {
+                    debug!(evicted, "Evicted cached games idle for longer than the TTL");
+                }
+            }
+        }))
+    }
+
+    /// Lock the game cache, waiting at most `cache_lock_timeout`
+    ///
+    /// An uncontended lock is taken without waiting. When the deadline passes
+    /// the request fails with `RESOURCE_EXHAUSTED`, which clients can retry and
+    /// tell apart from game errors (`INTERNAL`) or bad requests.
+    async fn lock_game_cache(&self) -> Result<MutexGuard<'_, GameCache>, Status> {
+        if let Ok(cache) = self.game_cache.try_lock() {
+            return Ok(cache);
+        }
+
+        tokio::time::timeout(self.cache_lock_timeout, self.game_cache.lock())
+            .await
+            .map_err(|_| {
+                Status::resource_exhausted(format!(
+                    "Game cache busy: lock not acquired within {}ms, retry later",
+                    self.cache_lock_timeout.as_millis()
+                ))
+            })
+    }
+
+    /// Get the cached game for a key, constructing it on first use
+    ///
+    /// Only one caller runs the factory for a given key; concurrent callers
+    /// wait for that construction and then share the same instance.
+    async fn get_or_create_game(&self, key: CacheKey) -> Result<Arc<GameEntry>, Status> {
+        let entry = {
+            let mut cache = self.lock_game_cache().await?;
+            Arc::clone(cache.entry(key.clone()).or_default())
+        };
+
+        entry
+            .game
+            .get_or_try_init(|| async {
+                let game = self.create_game(&key.0)
+                    .ok_or_else(|| Status::not_found(format!("Unknown env_id: {}", key.0)))?;
+                let game_bytes = std::mem::size_of_val(&*game) as u64;
+                entry.game_bytes.store(game_bytes, Ordering::Relaxed);
+                Ok::<_, Status>(Mutex::new(game))
+            })
+            .await?;
+
+        entry.touch();
+        Ok(entry)
+    }
+
+    /// Get the cached game for a key if it has already been initialized
+    async fn get_cached_game(&self, key: &CacheKey) -> Result<Option<Arc<GameEntry>>, Status> {
+        let cache = self.lock_game_cache().await?;
+        let entry = cache
+            .get(key)
+            .filter(|entry| entry.game.initialized())
+            .cloned();
+        if let Some(entry) = &entry {
+            entry.touch();
+        }
+        Ok(entry)
+    }
+
+    /// Describe every initialized game in the cache
+    ///
+    /// The cache lock is held only while the entries are cloned out; sizes
+    /// and access times are read afterwards without locking any game.
+    async fn cached_games(&self) -> Result<Vec<CachedGame>, Status> {
+        let mut entries: Vec<(CacheKey, Arc<GameEntry>)> = {
+            let cache = self.lock_game_cache().await?;
+            cache
+                .iter()
+                .filter(|(_, entry)| entry.game.initialized())
+                .map(|(key, entry)| (key.clone(), Arc::clone(entry)))
+                .collect()
+        };
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let games = entries
+            .into_iter()
+            .map(|((env_id, build_id), entry)| {
+                // Approximate: the entry, its key, and the game's inline size;
+                // heap memory owned by the game itself is not counted.
+                let approx_bytes = std::mem::size_of::<GameEntry>() as u64
+                    + (env_id.len() + build_id.len()) as u64
+                    + entry.game_bytes.load(Ordering::Relaxed);
+                CachedGame {
+                    id: Some(EngineId { env_id, build_id }),
+                    last_access_unix_ms: entry.last_access_ms.load(Ordering::Relaxed),
+                    approx_bytes,
+                }
+            })
+            .collect();
+        Ok(games)
+    }
+
+    /// Create and cache the game for `engine_id` unless it is already cached
+    async fn warm_game(&self, engine_id: EngineId) -> Result<(), Status> {
+        if !self.is_registered(&engine_id.env_id) {
+            return Err(Status::not_found(format!(
+                "Unknown env_id: {}",
+                engine_id.env_id
+            )));
+        }
+
+        self.get_or_create_game((engine_id.env_id, engine_id.build_id))
+            .await?;
+        Ok(())
+    }
+
+    /// Warm every id in order, reporting each outcome in a result
+    async fn warm_games(&self, ids: Vec<EngineId>) -> Vec<WarmCacheResult> {
+        // A failing element is reported in its result and never fails the call
+        let mut results = Vec::new();
+        for id in ids {
+            let (code, message) = match self.warm_game(id.clone()).await {
+                Ok(()) => (tonic::Code::Ok, String::new()),
+                Err(status) => (status.code(), status.message().to_string()),
+            };
+            results.push(WarmCacheResult {
+                id: Some(id),
+                code: code as i32,
+                message,
+            });
         }
+        results
+    }
+
+    /// Write the key of every initialized game in the cache to `path`
+    ///
+    /// Meant for shutdown: a later server passes the file to
+    /// `warm_from_manifest` to rebuild the same games before serving. Only
+    /// the `(env_id, build_id)` keys are written, never game state.
+    pub async fn dump_cache_manifest(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<CacheManifest, CacheManifestError> {
+        let ids = self
+            .cached_games()
+            .await
+            .map_err(|status| CacheManifestError::Cache(status.message().to_string()))?
+            .into_iter()
+            .filter_map(|game| game.id)
+            .collect();
+        let manifest = CacheManifest { ids };
+        manifest.save(path)?;
+        Ok(manifest)
+    }
+
+    /// Warm every key listed in the manifest at `path`
+    ///
+    /// Keys whose env is no longer registered are reported in their result
+    /// like in `WarmCache` and do not stop the remaining keys from warming.
+    pub async fn warm_from_manifest(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<Vec<WarmCacheResult>, CacheManifestError> {
+        let manifest = CacheManifest::load(path)?;
+        Ok(self.warm_games(manifest.ids).await)
     }
 
-    /// Convert internal capabilities to protobuf format
-    fn capabilities_to_proto(caps: &engine_core::typed::Capabilities) -> Capabilities {
+    /// Convert a game's capabilities and descriptive metadata to protobuf format
+    fn capabilities_to_proto(game: &dyn ErasedGame) -> Capabilities {
+        let caps = game.capabilities();
+        let initial_state_kind = Self::initial_state_kind_to_proto(game.initial_state_kind());
         let encoding = ProtoEncoding {
             state: caps.encoding.state.clone(),
             action: caps.encoding.action.clone(),
@@ -78,93 +488,194 @@ impl EngineService {
             max_horizon: caps.max_horizon,
             action_space,
             preferred_batch: caps.preferred_batch,
+            sizes: Some(Self::sizes_to_proto(&game.encoded_sizes())),
+            initial_state_kind: initial_state_kind as i32,
+            action_names: game.action_names().unwrap_or_default(),
+            obs_length_prefixed: game.obs_framed(),
+            state_space_estimate: game.state_space_estimate(),
+            obs_quantization: game.obs_quantization().map(|q| ProtoObsQuantization {
+                scale: q.scale,
+                zero_point: q.zero_point,
+            }),
+            obs_layout: game
+                .obs_layout()
+                .map(|layout| {
+                    layout
+                        .segments
+                        .into_iter()
+                        .map(|(name, size)| ObsSegment {
+                            name,
+                            size: size as u32,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
         }
     }
-}
 
-impl Default for EngineService {
-    fn default() -> Self {
-        Self::new()
+    /// Convert the initial state kind to protobuf format
+    fn initial_state_kind_to_proto(kind: InitialStateKind) -> ProtoInitialStateKind {
+        match kind {
+            InitialStateKind::Fixed => ProtoInitialStateKind::Fixed,
+            InitialStateKind::SeededRandom => ProtoInitialStateKind::SeededRandom,
+            InitialStateKind::HintDriven => ProtoInitialStateKind::HintDriven,
+        }
     }
-}
-
-#[tonic::async_trait]
-impl Engine for EngineService {
-    async fn get_capabilities(
-        &self,
-        request: Request<EngineId>,
-    ) -> TonicResult<Response<Capabilities>> {
-        let engine_id = request.into_inner();
 
-        // Validate env_id
-        if !is_registered(&engine_id.env_id) {
-            return Err(Status::not_found(format!(
-                "Unknown env_id: {}",
-                engine_id.env_id
-            )));
+    /// Resolve the seed for a reset request
+    ///
+    /// A zero seed means "don't care" and is replaced by a fresh non-zero seed
+    /// drawn from entropy, unless the client asks for the literal value with
+    /// `strict_seed`.
+    fn resolve_seed(seed: u64, strict_seed: bool) -> u64 {
+        if seed == 0 && !strict_seed {
+            rand::thread_rng().gen_range(1..=u64::MAX)
+        } else {
+            seed
         }
-
-        // Create game instance to get capabilities
-        let game = create_game(&engine_id.env_id)
-            .ok_or_else(|| Status::internal("Failed to create game instance"))?;
-
-        let capabilities = game.capabilities();
-        let proto_caps = Self::capabilities_to_proto(&capabilities);
-
-        Ok(Response::new(proto_caps))
     }
 
-    async fn reset(&self, request: Request<ResetRequest>) -> TonicResult<Response<ResetResponse>> {
-        let req = request.into_inner();
+    /// Play one episode on a cached game, yielding each transition as it is made
+    ///
+    /// The policy picks uniformly among the game's legal actions, passing with
+    /// an empty action when the engine reports `must_pass`. The game is locked
+    /// per step rather than for the whole episode, so a long stream never
+    /// starves other clients of the same game, and only the current step's
+    /// payloads are held.
+    fn episode_stream(
+        entry: Arc<GameEntry>,
+        seed: u64,
+        hint: Vec<u8>,
+        policy_seed: u64,
+        max_steps: u32,
+    ) -> TransitionStream {
+        Box::pin(async_stream::try_stream! {
+            let (mut state, mut obs) = (Vec::new(), Vec::new());
+            let reset = entry
+                .lock()
+                .await
+                .reset(seed, &hint, &mut state, &mut obs)
+                .map_err(|e| Status::internal(format!("Reset failed: {}", e)))?;
+            let mut must_pass = reset.must_pass;
+            let mut policy = StdRng::seed_from_u64(policy_seed);
+
+            for step_number in 0u32.. {
+                if max_steps != 0 && step_number == max_steps {
+                    break;
+                }
+
+                let mut game = entry.lock().await;
+                let action = if must_pass {
+                    Vec::new()
+                } else {
+                    let mut actions = game
+                        .legal_actions(&state)
+                        .map_err(|e| Status::internal(format!("Legal actions failed: {}", e)))?
+                        .ok_or_else(|| {
+                            Status::failed_precondition(
+                                "StreamEpisode requires a game that enumerates its legal actions",
+                            )
+                        })?;
+                    if actions.is_empty() {
+                        Err(Status::failed_precondition(format!(
+                            "No legal actions at step {} of an unfinished episode",
+                            step_number
+                        )))?;
+                    }
+                    actions.swap_remove(policy.gen_range(0..actions.len()))
+                };
+                let (mut next_state, mut next_obs) = (Vec::new(), Vec::new());
+                let outcome = game
+                    .step(&state, &action, &mut next_state, &mut next_obs)
+                    .map_err(|e| Status::internal(format!("Step failed: {}", e)))?;
+                drop(game);
+
+                must_pass = outcome.must_pass;
+                yield Transition {
+                    step_number,
+                    state: std::mem::replace(&mut state, next_state.clone()),
+                    action,
+                    next_state,
+                    obs: std::mem::replace(&mut obs, next_obs.clone()),
+                    next_obs,
+                    reward: outcome.reward,
+                    done: outcome.done,
+                    truncated: outcome.truncated,
+                    info: outcome.info,
+                };
+                if outcome.done {
+                    break;
+                }
+            }
+        })
+    }
 
+    /// Reset a cached game, creating it on first use
+    async fn reset_game(&self, req: ResetRequest) -> Result<ResetResponse, Status> {
         let engine_id = req
             .id
             .ok_or_else(|| Status::invalid_argument("Missing engine_id"))?;
 
-        let env_id = engine_id.env_id.clone();
-        let build_id = engine_id.build_id.clone();
+        if !self.is_registered(&engine_id.env_id) {
+            return Err(Status::not_found(format!(
+                "Unknown env_id: {}",
+                engine_id.env_id
+            )));
+        }
+
+        let key = (engine_id.env_id.clone(), engine_id.build_id);
+        let entry = self.get_or_create_game(key).await?;
 
         // Get buffers from pool
         let mut state_buf = self.buffer_pool.get_state_buffer();
         let mut obs_buf = self.buffer_pool.get_obs_buffer();
 
-        let mut cache = self.game_cache.lock().await;
+        let actual_seed = Self::resolve_seed(req.seed, req.strict_seed);
 
-        let game = match cache.entry((env_id.clone(), build_id)) {
-            Entry::Occupied(entry) => entry.into_mut(),
-            Entry::Vacant(entry) => {
-                let game = create_game(&env_id)
-                    .ok_or_else(|| Status::not_found(format!("Unknown env_id: {}", env_id)))?;
-                entry.insert(game)
-            }
-        };
+        let mut game = entry.lock().await;
 
-        // Perform reset
-        game.reset(req.seed, &req.hint, &mut state_buf, &mut obs_buf)
-            .map_err(|e| Status::internal(format!("Reset failed: {}", e)))?;
+        // Perform reset, skipping state encoding when the client doesn't want it back
+        let return_state = req.return_state.unwrap_or(true);
+        let outcome = if return_state {
+            game.reset(actual_seed, &req.hint, &mut state_buf, &mut obs_buf)
+        } else {
+            game.reset_without_state(actual_seed, &req.hint, &mut obs_buf)
+        }
+        .map_err(|e| Status::internal(format!("Reset failed: {}", e)))?;
+
+        drop(game);
 
-        drop(cache);
+        // Remember the initial obs so the first step can be delta-encoded
+        self.obs_cache.insert(&obs_buf);
 
         let response = ResetResponse {
-            state: state_buf.clone(),
+            state: if return_state {
+                state_buf.clone()
+            } else {
+                Vec::new()
+            },
             obs: obs_buf.clone(),
+            actual_seed,
+            current_player: outcome.current_player,
+            must_pass: outcome.must_pass,
         };
+        self.payload_stats
+            .record_reset(&engine_id.env_id, response.state.len(), response.obs.len());
 
         // Return buffers to pool
         self.buffer_pool.return_state_buffer(state_buf);
         self.buffer_pool.return_obs_buffer(obs_buf);
 
-        Ok(Response::new(response))
+        Ok(response)
     }
 
-    async fn step(&self, request: Request<StepRequest>) -> TonicResult<Response<StepResponse>> {
-        let req = request.into_inner();
-
+    /// Step a cached game; the `step` RPC wraps this in a correlation span
+    async fn step_game(&self, req: StepRequest) -> Result<StepResponse, Status> {
         let engine_id = req
             .id
             .ok_or_else(|| Status::invalid_argument("Missing engine_id"))?;
 
-        if !is_registered(&engine_id.env_id) {
+        if !self.is_registered(&engine_id.env_id) {
             return Err(Status::not_found(format!(
                 "Unknown env_id: {}",
                 engine_id.env_id
@@ -173,152 +684,692 @@ impl Engine for EngineService {
 
         let key = (engine_id.env_id.clone(), engine_id.build_id.clone());
 
-        let mut cache = self.game_cache.lock().await;
-        let game = match cache.get_mut(&key) {
-            Some(game) => game,
+        let entry = match self.get_cached_game(&key).await? {
+            Some(entry) => entry,
             None => {
                 return Err(Status::failed_precondition(
                     "Game not initialized - call reset before step",
                 ))
             }
         };
+        let mut game = entry.lock().await;
 
         // Get buffers from pool
         let mut new_state_buf = self.buffer_pool.get_state_buffer();
         let mut obs_buf = self.buffer_pool.get_obs_buffer();
 
-        // Perform step
-        let (reward, done, info) = game
-            .step(&req.state, &req.action, &mut new_state_buf, &mut obs_buf)
-            .map_err(|e| Status::internal(format!("Step failed: {}", e)))?;
+        // Pure games are memoized on (env_id, state, action); anything that
+        // might depend on RNG or hidden instance state always executes.
+        let step_cache = self
+            .step_cache
+            .as_ref()
+            .filter(|_| game.is_deterministic() && game.is_state_complete());
+        let step_key = step_cache
+            .map(|_| (engine_id.env_id.clone(), req.state.clone(), req.action.clone()));
+        let cached = step_cache.zip(step_key.as_ref()).and_then(|(cache, key)| cache.get(key));
+
+        // Perform step, skipping state encoding when the client doesn't want it back
+        let return_state = req.return_state.unwrap_or(true);
+        let (outcome, step_info) = if let Some(hit) = cached {
+            new_state_buf.extend_from_slice(&hit.state);
+            obs_buf.extend_from_slice(&hit.obs);
+            (hit.outcome, hit.step_info)
+        } else if let (Some(cache), Some(key)) = (step_cache, step_key) {
+            // Cached results always carry the full state so later hits can return it
+            let outcome = game
+                .step(&req.state, &req.action, &mut new_state_buf, &mut obs_buf)
+                .map_err(|e| Status::internal(format!("Step failed: {}", e)))?;
+            let step_info = game.last_step_info().to_vec();
+            cache.insert(
+                key,
+                CachedStep {
+                    state: new_state_buf.clone(),
+                    obs: obs_buf.clone(),
+                    outcome,
+                    step_info: step_info.clone(),
+                },
+            );
+            (outcome, step_info)
+        } else if let Some(seed) = req.step_seed {
+            // The state is encoded even when not returned; seeded steps are rare
+            let outcome = game
+                .step_seeded(seed, &req.state, &req.action, &mut new_state_buf, &mut obs_buf)
+                .map_err(|e| Status::internal(format!("Step failed: {}", e)))?;
+            (outcome, game.last_step_info().to_vec())
+        } else if return_state {
+            let outcome = game
+                .step(&req.state, &req.action, &mut new_state_buf, &mut obs_buf)
+                .map_err(|e| Status::internal(format!("Step failed: {}", e)))?;
+            (outcome, game.last_step_info().to_vec())
+        } else {
+            let outcome = game
+                .step_without_state(&req.state, &req.action, &mut obs_buf)
+                .map_err(|e| Status::internal(format!("Step failed: {}", e)))?;
+            (outcome, game.last_step_info().to_vec())
+        };
 
-        drop(cache);
+        drop(game);
+
+        // Send a sparse delta when the client's base obs is still cached; any
+        // hash mismatch falls back to the full observation.
+        let (obs, obs_delta, obs_hash) = if req.delta_obs {
+            let obs_hash = self.obs_cache.insert(&obs_buf);
+            match self.obs_cache.diff_against(req.prev_obs_hash, &obs_buf) {
+                Some(diff) => {
+                    #[cfg(debug_assertions)]
+                    if self.validate_obs_deltas {
+                        self.obs_cache
+                            .verify_diff(req.prev_obs_hash, &diff, &obs_buf)
+                            .map_err(|e| {
+                                tracing::error!(
+                                    env_id = %engine_id.env_id,
+                                    error = %e,
+                                    "Obs delta does not match full obs"
+                                );
+                                Status::internal(format!("Obs delta validation failed: {}", e))
+                            })?;
+                    }
+                    (
+                        Vec::new(),
+                        Some(ObsDelta {
+                            base_hash: req.prev_obs_hash,
+                            indices: diff.indices,
+                            values: diff.values,
+                        }),
+                        obs_hash,
+                    )
+                }
+                None => (obs_buf.clone(), None, obs_hash),
+            }
+        } else {
+            (obs_buf.clone(), None, 0)
+        };
 
         let response = StepResponse {
-            state: new_state_buf.clone(),
-            obs: obs_buf.clone(),
-            reward,
-            done,
-            info,
+            state: if return_state {
+                new_state_buf.clone()
+            } else {
+                Vec::new()
+            },
+            obs,
+            reward: outcome.reward,
+            done: outcome.done,
+            info: outcome.info,
+            obs_delta,
+            obs_hash,
+            truncated: outcome.truncated,
+            current_player: outcome.current_player,
+            must_pass: outcome.must_pass,
+            terminal_obs: outcome.terminal_obs,
+            bootstrap_obs: outcome.truncated.then(|| obs_buf.clone()),
+            step_info: step_info.into_iter().collect(),
         };
+        self.payload_stats.record_step(
+            &engine_id.env_id,
+            StepSizes {
+                request_state: req.state.len(),
+                request_action: req.action.len(),
+                response_state: response.state.len(),
+                response_obs: response.obs.len(),
+            },
+        );
 
         // Return buffers to pool
         self.buffer_pool.return_state_buffer(new_state_buf);
         self.buffer_pool.return_obs_buffer(obs_buf);
 
-        Ok(Response::new(response))
+        Ok(response)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use engine_core::registry::{clear_registry, register_game};
-    use engine_core::typed::{
-        ActionSpace, Capabilities as TypedCapabilities, DecodeError, EncodeError, Encoding,
-        EngineId as TypedEngineId, Game,
-    };
-    use engine_core::GameAdapter;
-    use games_tictactoe::TicTacToe;
-    use rand::RngCore;
+    /// Step a cached game and reset it in the same call if the episode ends
+    async fn step_auto_reset_game(
+        &self,
+        req: StepAutoResetRequest,
+    ) -> Result<StepAutoResetResponse, Status> {
+        let engine_id = req
+            .id
+            .ok_or_else(|| Status::invalid_argument("Missing engine_id"))?;
 
-    fn setup_test_registry() {
-        clear_registry();
-        register_game("tictactoe".to_string(), || {
-            Box::new(GameAdapter::new(TicTacToe::new()))
-        });
-    }
+        if !self.is_registered(&engine_id.env_id) {
+            return Err(Status::not_found(format!(
+                "Unknown env_id: {}",
+                engine_id.env_id
+            )));
+        }
 
-    fn setup_rng_test_registry() {
-        clear_registry();
-        register_game("rng-test".to_string(), || {
-            Box::new(GameAdapter::new(RngStepGame::default()))
-        });
-    }
+        let key = (engine_id.env_id, engine_id.build_id);
+        let entry = self.get_cached_game(&key).await?.ok_or_else(|| {
+            Status::failed_precondition("Game not initialized - call reset before step")
+        })?;
 
-    #[derive(Default)]
-    struct RngStepGame {
-        step_calls: u32,
-    }
+        let mut new_state_buf = self.buffer_pool.get_state_buffer();
+        let mut obs_buf = self.buffer_pool.get_obs_buffer();
+        let mut reset_state_buf = self.buffer_pool.get_state_buffer();
+        let mut reset_obs_buf = self.buffer_pool.get_obs_buffer();
+
+        // Resolved up front so the reported seed is the one a reset would use
+        let reset_seed = Self::resolve_seed(req.reset_seed, false);
+
+        let mut game = entry.lock().await;
+        let outcome = game
+            .step_auto_reset(
+                &req.state,
+                &req.action,
+                &mut new_state_buf,
+                &mut obs_buf,
+                AutoReset {
+                    seed: reset_seed,
+                    hint: &req.reset_hint,
+                    out_state: &mut reset_state_buf,
+                    out_obs: &mut reset_obs_buf,
+                },
+            )
+            .map_err(|e| Status::internal(format!("Step failed: {}", e)))?;
+        let step_info = game.last_step_info().iter().cloned().collect();
+        drop(game);
 
-    #[derive(Clone, Copy)]
-    struct RngState(u64);
+        let step = StepResponse {
+            state: new_state_buf.clone(),
+            obs: obs_buf.clone(),
+            reward: outcome.step.reward,
+            done: outcome.step.done,
+            info: outcome.step.info,
+            obs_delta: None,
+            obs_hash: 0,
+            truncated: outcome.step.truncated,
+            current_player: outcome.step.current_player,
+            must_pass: outcome.step.must_pass,
+            terminal_obs: outcome.step.terminal_obs,
+            bootstrap_obs: outcome.step.truncated.then(|| obs_buf.clone()),
+            step_info,
+        };
+        self.payload_stats.record_step(
+            &key.0,
+            StepSizes {
+                request_state: req.state.len(),
+                request_action: req.action.len(),
+                response_state: step.state.len(),
+                response_obs: step.obs.len(),
+            },
+        );
+        let reset = outcome.reset.map(|reset| {
+            // Remember the new initial obs so the next step can be delta-encoded
+            self.obs_cache.insert(&reset_obs_buf);
+            self.payload_stats
+                .record_reset(&key.0, reset_state_buf.len(), reset_obs_buf.len());
+            ResetResponse {
+                state: reset_state_buf.clone(),
+                obs: reset_obs_buf.clone(),
+                actual_seed: reset_seed,
+                current_player: reset.current_player,
+                must_pass: reset.must_pass,
+            }
+        });
 
-    #[derive(Clone, Copy)]
-    struct RngObs(f32);
+        self.buffer_pool.return_state_buffer(new_state_buf);
+        self.buffer_pool.return_obs_buffer(obs_buf);
+        self.buffer_pool.return_state_buffer(reset_state_buf);
+        self.buffer_pool.return_obs_buffer(reset_obs_buf);
 
-    impl Game for RngStepGame {
-        type State = RngState;
-        type Action = ();
-        type Obs = RngObs;
+        Ok(StepAutoResetResponse {
+            step: Some(step),
+            reset,
+        })
+    }
 
-        fn engine_id(&self) -> TypedEngineId {
-            TypedEngineId {
-                env_id: "rng-test".to_string(),
-                build_id: "test-build".to_string(),
-            }
+    /// Convert encoded sizes to protobuf format
+    fn sizes_to_proto(sizes: &EncodedSizes) -> ProtoEncodedSizes {
+        ProtoEncodedSizes {
+            state: sizes.state.map(|size| size as u32),
+            obs: sizes.obs.map(|size| size as u32),
+            action: sizes.action.map(|size| size as u32),
         }
+    }
 
-        fn capabilities(&self) -> TypedCapabilities {
-            TypedCapabilities {
-                id: self.engine_id(),
-                encoding: Encoding {
-                    state: "rng-state".to_string(),
-                    action: "rng-action".to_string(),
-                    obs: "rng-obs".to_string(),
-                    schema_version: 1,
-                },
-                max_horizon: 100,
-                action_space: ActionSpace::Discrete(1),
-                preferred_batch: 1,
-            }
+    /// Convert a payload size histogram to protobuf format
+    fn histogram_to_proto(histogram: &SizeHistogram) -> PayloadHistogram {
+        let used = histogram
+            .buckets
+            .iter()
+            .rposition(|&count| count > 0)
+            .map_or(0, |last| last + 1);
+        PayloadHistogram {
+            count: histogram.count,
+            sum_bytes: histogram.sum,
+            min_bytes: histogram.min,
+            max_bytes: histogram.max,
+            buckets: histogram.buckets[..used].to_vec(),
         }
+    }
 
-        fn reset(
-            &mut self,
-            rng: &mut rand_chacha::ChaCha20Rng,
-            _hint: &[u8],
-        ) -> (Self::State, Self::Obs) {
-            self.step_calls = 0;
-            let state = RngState(rng.next_u64());
-            let obs = RngObs(rng.next_u32() as f32);
-            (state, obs)
+    /// Convert buffer pool statistics to protobuf format
+    fn buffer_stats_to_proto(stats: &BufferPoolStats) -> ProtoBufferPoolStats {
+        ProtoBufferPoolStats {
+            available_state_buffers: stats.available_state_buffers as u32,
+            available_obs_buffers: stats.available_obs_buffers as u32,
+            available_action_buffers: stats.available_action_buffers as u32,
         }
+    }
+}
 
-        fn step(
-            &mut self,
-            state: &mut Self::State,
-            _action: Self::Action,
-            rng: &mut rand_chacha::ChaCha20Rng,
-        ) -> (Self::Obs, f32, bool) {
-            self.step_calls += 1;
-            let random = rng.next_u32();
-            state.0 = random as u64;
-            let obs = RngObs(random as f32);
-            let reward = random as f32 + self.step_calls as f32;
-            (obs, reward, false)
-        }
+impl Default for EngineService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> {
-            out.extend_from_slice(&state.0.to_le_bytes());
-            Ok(())
-        }
+#[tonic::async_trait]
+impl Engine for EngineService {
+    async fn get_capabilities(
+        &self,
+        request: Request<EngineId>,
+    ) -> TonicResult<Response<Capabilities>> {
+        let engine_id = request.into_inner();
 
-        fn decode_state(buf: &[u8]) -> Result<Self::State, DecodeError> {
-            if buf.len() != 8 {
-                return Err(DecodeError::InvalidLength {
-                    expected: 8,
-                    actual: buf.len(),
-                });
-            }
-            let mut array = [0u8; 8];
-            array.copy_from_slice(buf);
-            Ok(RngState(u64::from_le_bytes(array)))
+        // Validate env_id
+        if !self.is_registered(&engine_id.env_id) {
+            return Err(Status::not_found(format!(
+                "Unknown env_id: {}",
+                engine_id.env_id
+            )));
         }
 
-        fn encode_action(_action: &Self::Action, _out: &mut Vec<u8>) -> Result<(), EncodeError> {
-            Ok(())
-        }
+        // Create game instance to get capabilities
+        let game = self.create_game(&engine_id.env_id)
+            .ok_or_else(|| Status::internal("Failed to create game instance"))?;
+
+        let proto_caps = Self::capabilities_to_proto(game.as_ref());
+
+        Ok(Response::new(proto_caps))
+    }
+
+    async fn get_supported_versions(
+        &self,
+        request: Request<EngineId>,
+    ) -> TonicResult<Response<GetSupportedVersionsResponse>> {
+        let engine_id = request.into_inner();
+
+        if !self.is_registered(&engine_id.env_id) {
+            return Err(Status::not_found(format!(
+                "Unknown env_id: {}",
+                engine_id.env_id
+            )));
+        }
+
+        let game = self.create_game(&engine_id.env_id)
+            .ok_or_else(|| Status::internal("Failed to create game instance"))?;
+
+        Ok(Response::new(GetSupportedVersionsResponse {
+            versions: game.supported_schema_versions(),
+        }))
+    }
+
+    async fn reset(&self, request: Request<ResetRequest>) -> TonicResult<Response<ResetResponse>> {
+        let req = request.into_inner();
+        let recorded = self.session_recorder.as_ref().map(|_| req.clone());
+        let result = self.reset_game(req).await;
+        if let (Some(recorder), Some(req)) = (&self.session_recorder, recorded) {
+            recorder.record(&Exchange::Reset {
+                request: req,
+                response: result.clone().map_err(|status| status.code()),
+            });
+        }
+        result.map(Response::new)
+    }
+
+    async fn step(&self, request: Request<StepRequest>) -> TonicResult<Response<StepResponse>> {
+        // Clients may tag a step with a correlation id; it is optional and
+        // echoed back unchanged on both success and error
+        let correlation_id = request.metadata().get(CORRELATION_ID_HEADER).cloned();
+        let span = info_span!(
+            "step",
+            correlation_id = correlation_id
+                .as_ref()
+                .and_then(|id| id.to_str().ok())
+                .unwrap_or_default()
+        );
+
+        let req = request.into_inner();
+        let recorded = self.session_recorder.as_ref().map(|_| req.clone());
+        let result = self.step_game(req).instrument(span.clone()).await;
+        if let (Some(recorder), Some(req)) = (&self.session_recorder, recorded) {
+            recorder.record(&Exchange::Step {
+                request: req,
+                response: result.clone().map_err(|status| status.code()),
+            });
+        }
+        span.in_scope(|| match &result {
+            Ok(response) => debug!(done = response.done, "step completed"),
+            Err(status) => debug!(code = ?status.code(), "step failed"),
+        });
+
+        let mut result = result.map(Response::new);
+        if let Some(id) = correlation_id {
+            let metadata = match &mut result {
+                Ok(response) => response.metadata_mut(),
+                Err(status) => status.metadata_mut(),
+            };
+            metadata.insert(CORRELATION_ID_HEADER, id);
+        }
+        result
+    }
+
+    async fn multi_step(
+        &self,
+        request: Request<MultiStepRequest>,
+    ) -> TonicResult<Response<MultiStepResponse>> {
+        // Each element goes through the same cache lookup as `step`, so games
+        // of every env stay cached per (env_id, build_id). A failing element
+        // becomes an error result and never fails the call.
+        let mut results = Vec::new();
+        for step in request.into_inner().steps {
+            let result = match self.step_game(step).await {
+                Ok(response) => multi_step_result::Result::Step(response),
+                Err(status) => multi_step_result::Result::Error(StepError {
+                    code: status.code() as i32,
+                    message: status.message().to_string(),
+                }),
+            };
+            results.push(MultiStepResult {
+                result: Some(result),
+            });
+        }
+
+        Ok(Response::new(MultiStepResponse { results }))
+    }
+
+    async fn step_auto_reset(
+        &self,
+        request: Request<StepAutoResetRequest>,
+    ) -> TonicResult<Response<StepAutoResetResponse>> {
+        self.step_auto_reset_game(request.into_inner())
+            .await
+            .map(Response::new)
+    }
+
+    async fn reseed(
+        &self,
+        request: Request<ReseedRequest>,
+    ) -> TonicResult<Response<ReseedResponse>> {
+        let req = request.into_inner();
+
+        let engine_id = req
+            .id
+            .ok_or_else(|| Status::invalid_argument("Missing engine_id"))?;
+
+        let key = (engine_id.env_id, engine_id.build_id);
+        let entry = self.get_cached_game(&key).await?.ok_or_else(|| {
+            Status::failed_precondition("Game not initialized - call reset before reseed")
+        })?;
+
+        // Only the RNG changes; client-held state stays valid for the next step
+        let mut game = entry.lock().await;
+        game.reseed(req.seed);
+
+        Ok(Response::new(ReseedResponse {
+            deterministic: game.is_deterministic(),
+        }))
+    }
+
+    async fn drain_buffers(
+        &self,
+        _request: Request<DrainBuffersRequest>,
+    ) -> TonicResult<Response<DrainBuffersResponse>> {
+        // Safe under load: buffers checked out by in-flight requests are simply
+        // returned to the emptied pool, and later gets allocate fresh ones.
+        let before = self.buffer_pool.stats();
+        self.buffer_pool.clear();
+        let after = self.buffer_pool.stats();
+
+        Ok(Response::new(DrainBuffersResponse {
+            before: Some(Self::buffer_stats_to_proto(&before)),
+            after: Some(Self::buffer_stats_to_proto(&after)),
+        }))
+    }
+
+    async fn list_cached_games(
+        &self,
+        _request: Request<ListCachedGamesRequest>,
+    ) -> TonicResult<Response<ListCachedGamesResponse>> {
+        Ok(Response::new(ListCachedGamesResponse {
+            games: self.cached_games().await?,
+        }))
+    }
+
+    async fn warm_cache(
+        &self,
+        request: Request<WarmCacheRequest>,
+    ) -> TonicResult<Response<WarmCacheResponse>> {
+        let results = self.warm_games(request.into_inner().ids).await;
+        Ok(Response::new(WarmCacheResponse { results }))
+    }
+
+    async fn get_payload_stats(
+        &self,
+        _request: Request<GetPayloadStatsRequest>,
+    ) -> TonicResult<Response<GetPayloadStatsResponse>> {
+        let envs = self
+            .payload_stats
+            .snapshot()
+            .into_iter()
+            .map(|(env_id, payloads)| EnvPayloadStats {
+                env_id,
+                reset_state: Some(Self::histogram_to_proto(&payloads.reset_state)),
+                reset_obs: Some(Self::histogram_to_proto(&payloads.reset_obs)),
+                step_request_state: Some(Self::histogram_to_proto(&payloads.step_request_state)),
+                step_request_action: Some(Self::histogram_to_proto(&payloads.step_request_action)),
+                step_response_state: Some(Self::histogram_to_proto(&payloads.step_response_state)),
+                step_response_obs: Some(Self::histogram_to_proto(&payloads.step_response_obs)),
+            })
+            .collect();
+
+        Ok(Response::new(GetPayloadStatsResponse { envs }))
+    }
+
+    type StreamEpisodeStream = TransitionStream;
+
+    async fn stream_episode(
+        &self,
+        request: Request<StreamEpisodeRequest>,
+    ) -> TonicResult<Response<Self::StreamEpisodeStream>> {
+        let req = request.into_inner();
+
+        let engine_id = req
+            .id
+            .ok_or_else(|| Status::invalid_argument("Missing engine_id"))?;
+
+        if !self.is_registered(&engine_id.env_id) {
+            return Err(Status::not_found(format!(
+                "Unknown env_id: {}",
+                engine_id.env_id
+            )));
+        }
+
+        let entry = self
+            .get_or_create_game((engine_id.env_id, engine_id.build_id))
+            .await?;
+        let seed = Self::resolve_seed(req.seed, false);
+
+        Ok(Response::new(Self::episode_stream(
+            entry,
+            seed,
+            req.hint,
+            req.policy_seed,
+            req.max_steps,
+        )))
+    }
+
+    async fn legal_action_count(
+        &self,
+        request: Request<LegalActionCountRequest>,
+    ) -> TonicResult<Response<LegalActionCountResponse>> {
+        let req = request.into_inner();
+
+        let engine_id = req
+            .id
+            .ok_or_else(|| Status::invalid_argument("Missing engine_id"))?;
+
+        if !self.is_registered(&engine_id.env_id) {
+            return Err(Status::not_found(format!(
+                "Unknown env_id: {}",
+                engine_id.env_id
+            )));
+        }
+
+        // The state travels with the request, so no prior reset is needed
+        let entry = self
+            .get_or_create_game((engine_id.env_id, engine_id.build_id))
+            .await?;
+        let game = entry.lock().await;
+        let count = game
+            .legal_action_count(&req.state)
+            .map_err(|e| Status::internal(format!("Legal action count failed: {}", e)))?;
+
+        Ok(Response::new(LegalActionCountResponse { count }))
+    }
+
+    async fn get_server_info(
+        &self,
+        _request: Request<GetServerInfoRequest>,
+    ) -> TonicResult<Response<GetServerInfoResponse>> {
+        Ok(Response::new(GetServerInfoResponse {
+            version: ENGINE_VERSION.to_string(),
+            git_commit: ENGINE_GIT_COMMIT.to_string(),
+            registered_games: self.registered_game_count() as u32,
+            uptime_secs: self.started_at.elapsed().as_secs(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine_core::overrides::CapabilityOverrides;
+    use engine_core::registry::{
+        create_game, register_game, register_game_with_capabilities, RegistryGuard,
+    };
+    use engine_core::typed::{
+        ActionSpace, Capabilities as TypedCapabilities, DecodeError, EncodeError, Encoding,
+        EngineId as TypedEngineId, Game, StepResult,
+    };
+    use engine_core::GameAdapter;
+    use games_tictactoe::TicTacToe;
+    use crate::obs_delta::{obs_hash, ObsDiff};
+    use rand::RngCore;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn setup_test_registry() -> RegistryGuard {
+        let registry = RegistryGuard::isolated();
+        register_game("tictactoe".to_string(), || {
+            Box::new(GameAdapter::new(TicTacToe::new()))
+        });
+        registry
+    }
+
+    fn setup_rng_test_registry() -> RegistryGuard {
+        let registry = RegistryGuard::isolated();
+        register_game("rng-test".to_string(), || {
+            Box::new(GameAdapter::new(RngStepGame::default()))
+        });
+        registry
+    }
+
+    static COUNTING_FACTORY_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn setup_counting_test_registry() {
+        register_game("counting-test".to_string(), || {
+            COUNTING_FACTORY_CALLS.fetch_add(1, Ordering::SeqCst);
+            Box::new(GameAdapter::new(RngStepGame::default()))
+        });
+    }
+
+    #[derive(Default, Clone)]
+    struct RngStepGame {
+        step_calls: u32,
+    }
+
+    #[derive(Clone, Copy)]
+    struct RngState(u64);
+
+    #[derive(Clone, Copy)]
+    struct RngObs(f32);
+
+    impl Game for RngStepGame {
+        type State = RngState;
+        type Action = ();
+        type Obs = RngObs;
+
+        fn engine_id(&self) -> TypedEngineId {
+            TypedEngineId {
+                env_id: "rng-test".to_string(),
+                build_id: "test-build".to_string(),
+            }
+        }
+
+        fn capabilities(&self) -> TypedCapabilities {
+            TypedCapabilities {
+                id: self.engine_id(),
+                encoding: Encoding {
+                    state: "rng-state".to_string(),
+                    action: "rng-action".to_string(),
+                    obs: "rng-obs".to_string(),
+                    schema_version: 1,
+                },
+                max_horizon: 100,
+                action_space: ActionSpace::Discrete(1),
+                preferred_batch: 1,
+            }
+        }
+
+        fn reset(
+            &mut self,
+            rng: &mut rand_chacha::ChaCha20Rng,
+            _hint: &[u8],
+        ) -> (Self::State, Self::Obs) {
+            self.step_calls = 0;
+            let state = RngState(rng.next_u64());
+            let obs = RngObs(rng.next_u32() as f32);
+            (state, obs)
+        }
+
+        fn step(
+            &mut self,
+            state: &mut Self::State,
+            _action: Self::Action,
+            rng: &mut rand_chacha::ChaCha20Rng,
+        ) -> StepResult<Self::Obs> {
+            self.step_calls += 1;
+            let random = rng.next_u32();
+            state.0 = random as u64;
+            StepResult {
+                obs: RngObs(random as f32),
+                reward: random as f32 + self.step_calls as f32,
+                done: false,
+                truncated: false,
+                info: random as u64,
+            }
+        }
+
+        fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+            out.extend_from_slice(&state.0.to_le_bytes());
+            Ok(())
+        }
+
+        fn decode_state(buf: &[u8]) -> Result<Self::State, DecodeError> {
+            if buf.len() != 8 {
+                return Err(DecodeError::InvalidLength {
+                    expected: 8,
+                    actual: buf.len(),
+                });
+            }
+            let mut array = [0u8; 8];
+            array.copy_from_slice(buf);
+            Ok(RngState(u64::from_le_bytes(array)))
+        }
+
+        fn encode_action(_action: &Self::Action, _out: &mut Vec<u8>) -> Result<(), EncodeError> {
+            Ok(())
+        }
 
         fn decode_action(buf: &[u8]) -> Result<Self::Action, DecodeError> {
             if buf.is_empty() {
@@ -339,7 +1390,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_get_capabilities() {
-        setup_test_registry();
+        let _registry = setup_test_registry();
 
         let service = EngineService::new();
         let request = Request::new(EngineId {
@@ -350,14 +1401,59 @@ mod tests {
         let response = service.get_capabilities(request).await.unwrap();
         let caps = response.into_inner();
 
+        assert_eq!(caps.initial_state_kind(), ProtoInitialStateKind::Fixed);
+        assert_eq!(caps.action_names.len(), 9);
+        assert_eq!(caps.action_names[4], "cell 4");
+        assert_eq!(caps.state_space_estimate, Some(19_683));
+        let segments: Vec<(&str, u32)> = caps
+            .obs_layout
+            .iter()
+            .map(|segment| (segment.name.as_str(), segment.size))
+            .collect();
+        assert_eq!(segments, vec![("board", 18), ("legal", 9), ("player", 2)]);
         assert!(caps.id.is_some());
         assert_eq!(caps.id.unwrap().env_id, "tictactoe");
         assert_eq!(caps.max_horizon, 9);
+        assert!(!caps.obs_length_prefixed, "obs framing is opt-in");
+
+        let sizes = caps.sizes.unwrap();
+        assert_eq!(sizes.state, Some(12));
+        assert_eq!(sizes.obs, Some(116));
+        assert_eq!(sizes.action, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_get_capabilities_reflects_registered_overrides() {
+        register_game_with_capabilities(
+            "ttt-big-batch".to_string(),
+            || Box::new(GameAdapter::new(TicTacToe::new())),
+            CapabilityOverrides {
+                preferred_batch: Some(256),
+                ..Default::default()
+            },
+        );
+
+        let service = EngineService::new();
+        let caps = service
+            .get_capabilities(Request::new(EngineId {
+                env_id: "ttt-big-batch".to_string(),
+                build_id: "test".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(caps.id.unwrap().env_id, "ttt-big-batch");
+        assert_eq!(caps.preferred_batch, 256);
+        // Everything else, including the wire format, is still tictactoe's
+        assert_eq!(caps.max_horizon, 9);
+        assert_eq!(caps.enc.unwrap().action, TicTacToe::new().capabilities().encoding.action);
+        assert_eq!(caps.sizes.unwrap().action, Some(1));
     }
 
     #[tokio::test]
     async fn test_get_capabilities_unknown_game() {
-        setup_test_registry();
+        let _registry = setup_test_registry();
 
         let service = EngineService::new();
         let request = Request::new(EngineId {
@@ -374,7 +1470,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_reset() {
-        setup_test_registry();
+        let _registry = setup_test_registry();
 
         let service = EngineService::new();
         let request = Request::new(ResetRequest {
@@ -384,6 +1480,8 @@ mod tests {
             }),
             seed: 42,
             hint: Vec::new(),
+            strict_seed: false,
+            return_state: None,
         });
 
         let response = service.reset(request).await.unwrap();
@@ -392,15 +1490,17 @@ mod tests {
         assert!(!reset_resp.state.is_empty());
         assert!(!reset_resp.obs.is_empty());
 
-        // TicTacToe state should be 11 bytes
-        assert_eq!(reset_resp.state.len(), 11);
+        // TicTacToe state should be a version byte followed by 11 bytes
+        assert_eq!(reset_resp.state.len(), 12);
         // TicTacToe obs should be 29 * 4 = 116 bytes (29 f32 values)
         assert_eq!(reset_resp.obs.len(), 116);
+        // X moves first
+        assert_eq!(reset_resp.current_player, Some(1));
     }
 
     #[tokio::test]
     async fn test_step() {
-        setup_test_registry();
+        let _registry = setup_test_registry();
 
         let service = EngineService::new();
 
@@ -412,6 +1512,8 @@ mod tests {
             }),
             seed: 42,
             hint: Vec::new(),
+            strict_seed: false,
+            return_state: None,
         });
 
         let reset_response = service.reset(reset_request).await.unwrap();
@@ -425,6 +1527,10 @@ mod tests {
             }),
             state: reset_resp.state,
             action: vec![4], // Place in center
+            return_state: None,
+            delta_obs: false,
+            prev_obs_hash: 0,
+            step_seed: None,
         });
 
         let step_response = service.step(step_request).await.unwrap();
@@ -435,40 +1541,586 @@ mod tests {
         assert!(!step_resp.done); // Game should not be done after one move
         assert_eq!(step_resp.reward, 0.0); // No reward for ongoing game
         assert_eq!(step_resp.info & 0x1FF, 0x1FFu64 & !(1u64 << 4));
+        assert_eq!(step_resp.current_player, Some(2)); // O to move
     }
 
     #[tokio::test]
-    async fn test_step_invalid_engine() {
-        setup_test_registry();
+    async fn test_reset_without_returning_state() {
+        let _registry = setup_test_registry();
 
         let service = EngineService::new();
-        let request = Request::new(StepRequest {
+        let reset = |return_state| ResetRequest {
             id: Some(EngineId {
-                env_id: "unknown".to_string(),
+                env_id: "tictactoe".to_string(),
                 build_id: "test".to_string(),
             }),
-            state: vec![0; 11],
-            action: vec![0],
-        });
+            seed: 42,
+            hint: Vec::new(),
+            strict_seed: false,
+            return_state,
+        };
 
-        let result = service.step(request).await;
-        assert!(result.is_err());
+        let full = service
+            .reset(Request::new(reset(None)))
+            .await
+            .unwrap()
+            .into_inner();
+        let obs_only = service
+            .reset(Request::new(reset(Some(false))))
+            .await
+            .unwrap()
+            .into_inner();
 
-        let err = result.unwrap_err();
-        assert_eq!(err.code(), tonic::Code::NotFound);
+        assert!(!full.state.is_empty());
+        assert!(obs_only.state.is_empty());
+        assert_eq!(obs_only.obs.len(), 29 * 4);
+        assert_eq!(obs_only.obs, full.obs);
     }
 
     #[tokio::test]
-    async fn test_buffer_pool_integration() {
-        setup_test_registry();
+    async fn test_step_without_returning_state() {
+        let _registry = setup_test_registry();
 
-        let buffer_pool = BufferPool::with_capacity(2, 2, 2, 64);
-        let service = EngineService::with_buffer_pool(buffer_pool.clone());
+        let service = EngineService::new();
+        let engine_id = EngineId {
+            env_id: "tictactoe".to_string(),
+            build_id: "test".to_string(),
+        };
 
-        let initial_stats = buffer_pool.stats();
-        assert_eq!(initial_stats.available_state_buffers, 2);
+        let reset_resp = service
+            .reset(Request::new(ResetRequest {
+                id: Some(engine_id.clone()),
+                seed: 42,
+                hint: Vec::new(),
+                strict_seed: false,
+                return_state: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
 
-        // Perform reset - should use and return buffers
+        let full = service
+            .step(Request::new(StepRequest {
+                id: Some(engine_id.clone()),
+                state: reset_resp.state.clone(),
+                action: vec![4],
+                return_state: None,
+                delta_obs: false,
+                prev_obs_hash: 0,
+                step_seed: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let lean = service
+            .step(Request::new(StepRequest {
+                id: Some(engine_id.clone()),
+                state: reset_resp.state.clone(),
+                action: vec![4],
+                return_state: Some(false),
+                delta_obs: false,
+                prev_obs_hash: 0,
+                step_seed: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert!(lean.state.is_empty());
+        assert_eq!(lean.obs, full.obs);
+        assert_eq!(lean.reward, full.reward);
+        assert_eq!(lean.done, full.done);
+        assert_eq!(lean.info, full.info);
+
+        // The client keeps stepping from its own copy of the state
+        let next = service
+            .step(Request::new(StepRequest {
+                id: Some(engine_id),
+                state: full.state,
+                action: vec![0],
+                return_state: Some(false),
+                delta_obs: false,
+                prev_obs_hash: 0,
+                step_seed: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert!(next.state.is_empty());
+        assert_eq!(next.obs.len(), 116);
+        assert_eq!(next.info & 0x1FF, 0x1FFu64 & !(1u64 << 4) & !1u64);
+    }
+
+    #[tokio::test]
+    async fn test_step_without_state_preserves_rng_progression() {
+        let _registry = setup_rng_test_registry();
+
+        let engine_id = EngineId {
+            env_id: "rng-test".to_string(),
+            build_id: "test-build".to_string(),
+        };
+
+        let mut rewards = Vec::new();
+        for return_state in [true, false] {
+            let service = EngineService::new();
+            let reset = service
+                .reset(Request::new(ResetRequest {
+                    id: Some(engine_id.clone()),
+                    seed: 11,
+                    hint: Vec::new(),
+                    strict_seed: false,
+                    return_state: None,
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+
+            let mut run = Vec::new();
+            for _ in 0..3 {
+                let step = service
+                    .step(Request::new(StepRequest {
+                        id: Some(engine_id.clone()),
+                        state: reset.state.clone(),
+                        action: Vec::new(),
+                        return_state: Some(return_state),
+                        delta_obs: false,
+                        prev_obs_hash: 0,
+                        step_seed: None,
+                    }))
+                    .await
+                    .unwrap()
+                    .into_inner();
+                run.push(step.reward);
+            }
+            rewards.push(run);
+        }
+
+        assert_eq!(rewards[0], rewards[1]);
+    }
+
+    #[tokio::test]
+    async fn test_step_delta_obs_reconstructs_full_obs() {
+        let _registry = setup_test_registry();
+
+        // Validation passes for every correctly encoded delta
+        let service = EngineService::new().with_obs_delta_validation();
+        let engine_id = EngineId {
+            env_id: "tictactoe".to_string(),
+            build_id: "test".to_string(),
+        };
+
+        let reset_resp = service
+            .reset(Request::new(ResetRequest {
+                id: Some(engine_id.clone()),
+                seed: 42,
+                hint: Vec::new(),
+                strict_seed: false,
+                return_state: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let step_request = |delta_obs: bool, prev_obs_hash: u64| StepRequest {
+            id: Some(engine_id.clone()),
+            state: reset_resp.state.clone(),
+            action: vec![4],
+            return_state: None,
+            delta_obs,
+            prev_obs_hash,
+            step_seed: None,
+        };
+
+        let full = service
+            .step(Request::new(step_request(false, 0)))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let delta_resp = service
+            .step(Request::new(step_request(true, obs_hash(&reset_resp.obs))))
+            .await
+            .unwrap()
+            .into_inner();
+
+        // A single move touches a handful of f32 values out of 29
+        assert!(delta_resp.obs.is_empty());
+        let delta = delta_resp.obs_delta.unwrap();
+        assert_eq!(delta.base_hash, obs_hash(&reset_resp.obs));
+        assert!(delta.indices.len() <= 16, "diff too large: {:?}", delta.indices);
+
+        let diff = ObsDiff {
+            indices: delta.indices,
+            values: delta.values,
+        };
+        let reconstructed = diff.apply(&reset_resp.obs).unwrap();
+        assert_eq!(reconstructed, full.obs);
+        assert_eq!(delta_resp.obs_hash, obs_hash(&full.obs));
+
+        // An unknown base hash falls back to the full observation
+        let fallback = service
+            .step(Request::new(step_request(true, 0xdead_beef)))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(fallback.obs_delta.is_none());
+        assert_eq!(fallback.obs, full.obs);
+    }
+
+    #[tokio::test]
+    async fn test_zero_seed_reset_draws_reproducible_seed() {
+        let _registry = setup_rng_test_registry();
+
+        let service = EngineService::new();
+        let reset = |seed: u64, strict_seed: bool| ResetRequest {
+            id: Some(EngineId {
+                env_id: "rng-test".to_string(),
+                build_id: "test-build".to_string(),
+            }),
+            seed,
+            hint: Vec::new(),
+            strict_seed,
+            return_state: None,
+        };
+
+        let first = service.reset(Request::new(reset(0, false))).await.unwrap().into_inner();
+        let second = service.reset(Request::new(reset(0, false))).await.unwrap().into_inner();
+
+        assert_ne!(first.actual_seed, 0);
+        assert_ne!(first.actual_seed, second.actual_seed);
+        assert_ne!(first.state, second.state);
+
+        // Replaying the returned seed reproduces each episode
+        for original in [&first, &second] {
+            let replay = service
+                .reset(Request::new(reset(original.actual_seed, false)))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(replay.actual_seed, original.actual_seed);
+            assert_eq!(replay.state, original.state);
+            assert_eq!(replay.obs, original.obs);
+        }
+
+        // strict_seed keeps a literal zero seed deterministic
+        let strict_a = service.reset(Request::new(reset(0, true))).await.unwrap().into_inner();
+        let strict_b = service.reset(Request::new(reset(0, true))).await.unwrap().into_inner();
+        assert_eq!(strict_a.actual_seed, 0);
+        assert_eq!(strict_a.state, strict_b.state);
+    }
+
+    #[tokio::test]
+    async fn test_step_cache_serves_repeated_pure_steps() {
+        let _registry = setup_test_registry();
+
+        let service = EngineService::new().with_step_cache(16);
+        let engine_id = EngineId {
+            env_id: "tictactoe".to_string(),
+            build_id: "test".to_string(),
+        };
+
+        let reset_resp = service
+            .reset(Request::new(ResetRequest {
+                id: Some(engine_id.clone()),
+                seed: 42,
+                hint: Vec::new(),
+                strict_seed: false,
+                return_state: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let step_request = || StepRequest {
+            id: Some(engine_id.clone()),
+            state: reset_resp.state.clone(),
+            action: vec![4],
+            return_state: None,
+            delta_obs: false,
+            prev_obs_hash: 0,
+            step_seed: None,
+        };
+
+        let first = service.step(Request::new(step_request())).await.unwrap().into_inner();
+        let second = service.step(Request::new(step_request())).await.unwrap().into_inner();
+
+        let cache = service.step_cache().unwrap();
+        assert_eq!(cache.misses(), 1);
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(first, second);
+    }
+
+    #[tokio::test]
+    async fn test_step_cache_skips_stochastic_games() {
+        let _registry = setup_rng_test_registry();
+
+        let service = EngineService::new().with_step_cache(16);
+        let engine_id = EngineId {
+            env_id: "rng-test".to_string(),
+            build_id: "test-build".to_string(),
+        };
+
+        let reset_resp = service
+            .reset(Request::new(ResetRequest {
+                id: Some(engine_id.clone()),
+                seed: 7,
+                hint: Vec::new(),
+                strict_seed: false,
+                return_state: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        for _ in 0..2 {
+            service
+                .step(Request::new(StepRequest {
+                    id: Some(engine_id.clone()),
+                    state: reset_resp.state.clone(),
+                    action: Vec::new(),
+                    return_state: None,
+                    delta_obs: false,
+                    prev_obs_hash: 0,
+                    step_seed: None,
+                }))
+                .await
+                .unwrap();
+        }
+
+        let cache = service.step_cache().unwrap();
+        assert_eq!(cache.hits(), 0);
+        assert!(cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_step_decode_error_reports_env_and_input_length() {
+        let _registry = setup_test_registry();
+
+        let service = EngineService::new();
+        let engine_id = EngineId {
+            env_id: "tictactoe".to_string(),
+            build_id: "test".to_string(),
+        };
+
+        let reset_resp = service
+            .reset(Request::new(ResetRequest {
+                id: Some(engine_id.clone()),
+                seed: 42,
+                hint: Vec::new(),
+                strict_seed: false,
+                return_state: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let err = service
+            .step(Request::new(StepRequest {
+                id: Some(engine_id),
+                state: reset_resp.state,
+                action: vec![4, 0, 0],
+                return_state: None,
+                delta_obs: false,
+                prev_obs_hash: 0,
+                step_seed: None,
+            }))
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.code(), tonic::Code::Internal);
+        assert!(
+            err.message().contains("env=tictactoe, action_len=3"),
+            "unexpected message: {}",
+            err.message()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_step_invalid_engine() {
+        let _registry = setup_test_registry();
+
+        let service = EngineService::new();
+        let request = Request::new(StepRequest {
+            id: Some(EngineId {
+                env_id: "unknown".to_string(),
+                build_id: "test".to_string(),
+            }),
+            state: vec![0; 11],
+            action: vec![0],
+            return_state: None,
+            delta_obs: false,
+            prev_obs_hash: 0,
+            step_seed: None,
+        });
+
+        let result = service.step(request).await;
+        assert!(result.is_err());
+
+        let err = result.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_step_auto_reset_reports_terminal_step_and_fresh_episode() {
+        let _registry = setup_test_registry();
+        let service = EngineService::new();
+        let id = EngineId {
+            env_id: "tictactoe".to_string(),
+            build_id: "test".to_string(),
+        };
+
+        let initial = service
+            .reset(Request::new(ResetRequest {
+                id: Some(id.clone()),
+                seed: 42,
+                hint: Vec::new(),
+                strict_seed: false,
+                return_state: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        // X takes the top row: X 0, O 3, X 1, O 4, X 2
+        let mut state = initial.state.clone();
+        let mut last = None;
+        for action in [0u8, 3, 1, 4, 2] {
+            let response = service
+                .step_auto_reset(Request::new(StepAutoResetRequest {
+                    id: Some(id.clone()),
+                    state: state.clone(),
+                    action: vec![action],
+                    reset_seed: 7,
+                    reset_hint: Vec::new(),
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            let step = response.step.clone().unwrap();
+            assert_eq!(response.reset.is_some(), step.done, "action {}", action);
+            state = step.state;
+            last = Some(response);
+        }
+
+        let last = last.unwrap();
+        let terminal = last.step.unwrap();
+        assert!(terminal.done);
+        assert_eq!(terminal.reward, 1.0);
+        assert_ne!(terminal.obs, initial.obs, "terminal obs shows the finished board");
+
+        let reset = last.reset.unwrap();
+        assert_eq!(reset.actual_seed, 7);
+        assert_eq!(reset.obs, initial.obs, "next episode starts on an empty board");
+        assert_eq!(reset.state, initial.state);
+    }
+
+    #[tokio::test]
+    async fn test_step_echoes_correlation_id() {
+        let _registry = setup_test_registry();
+
+        let service = EngineService::new();
+        let engine_id = EngineId {
+            env_id: "tictactoe".to_string(),
+            build_id: "test".to_string(),
+        };
+        let reset_resp = service
+            .reset(Request::new(ResetRequest {
+                id: Some(engine_id.clone()),
+                seed: 42,
+                hint: Vec::new(),
+                strict_seed: false,
+                return_state: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let step = |action: u8, correlation_id: Option<&'static str>| {
+            let mut request = Request::new(StepRequest {
+                id: Some(engine_id.clone()),
+                state: reset_resp.state.clone(),
+                action: vec![action],
+                return_state: None,
+                delta_obs: false,
+                prev_obs_hash: 0,
+                step_seed: None,
+            });
+            if let Some(id) = correlation_id {
+                request
+                    .metadata_mut()
+                    .insert(CORRELATION_ID_HEADER, id.parse().unwrap());
+            }
+            request
+        };
+
+        let response = service.step(step(4, Some("00c0ffee"))).await.unwrap();
+        assert_eq!(response.metadata().get(CORRELATION_ID_HEADER).unwrap(), "00c0ffee");
+
+        // The id is optional, and failed steps still carry it back
+        let response = service.step(step(4, None)).await.unwrap();
+        assert!(response.metadata().get(CORRELATION_ID_HEADER).is_none());
+
+        let err = service.step(step(9, Some("0badf00d"))).await.unwrap_err();
+        assert_eq!(err.metadata().get(CORRELATION_ID_HEADER).unwrap(), "0badf00d");
+    }
+
+    #[tokio::test]
+    async fn test_contended_game_cache_fails_fast_with_resource_exhausted() {
+        let _registry = setup_test_registry();
+
+        let service = EngineService::new().with_cache_lock_timeout(Duration::from_millis(20));
+        let engine_id = EngineId {
+            env_id: "tictactoe".to_string(),
+            build_id: "test".to_string(),
+        };
+        let reset_resp = service
+            .reset(Request::new(ResetRequest {
+                id: Some(engine_id.clone()),
+                seed: 42,
+                hint: Vec::new(),
+                strict_seed: false,
+                return_state: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        // Hold the cache lock so the step cannot look its game up
+        let _held = service.game_cache.lock().await;
+        let err = service
+            .step(Request::new(StepRequest {
+                id: Some(engine_id),
+                state: reset_resp.state,
+                action: vec![4, 0, 0, 0],
+                return_state: None,
+                delta_obs: false,
+                prev_obs_hash: 0,
+                step_seed: None,
+            }))
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.code(), tonic::Code::ResourceExhausted);
+        assert!(
+            err.message().contains("retry"),
+            "unexpected message: {}",
+            err.message()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_buffer_pool_integration() {
+        let _registry = setup_test_registry();
+
+        let buffer_pool = BufferPool::with_capacity(2, 2, 2, 64);
+        let service = EngineService::with_buffer_pool(buffer_pool.clone());
+
+        let initial_stats = buffer_pool.stats();
+        assert_eq!(initial_stats.available_state_buffers, 2);
+
+        // Perform reset - should use and return buffers
         let request = Request::new(ResetRequest {
             id: Some(EngineId {
                 env_id: "tictactoe".to_string(),
@@ -476,6 +2128,8 @@ mod tests {
             }),
             seed: 42,
             hint: Vec::new(),
+            strict_seed: false,
+            return_state: None,
         });
 
         let _response = service.reset(request).await.unwrap();
@@ -486,9 +2140,95 @@ mod tests {
         assert_eq!(final_stats.available_obs_buffers, 2);
     }
 
+    #[tokio::test]
+    async fn test_disabled_buffer_pool_never_retains_buffers() {
+        let _registry = setup_test_registry();
+
+        let buffer_pool = BufferPool::disabled();
+        let disabled = EngineService::with_buffer_pool(buffer_pool.clone());
+        let pooled = EngineService::new();
+        let id = EngineId {
+            env_id: "tictactoe".to_string(),
+            build_id: "test".to_string(),
+        };
+        let empty_stats = BufferPool::new().stats();
+
+        let mut responses = Vec::new();
+        for service in [&disabled, &pooled] {
+            let reset = service
+                .reset(Request::new(ResetRequest {
+                    id: Some(id.clone()),
+                    seed: 42,
+                    hint: Vec::new(),
+                    strict_seed: false,
+                    return_state: None,
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            let step = service
+                .step(Request::new(StepRequest {
+                    id: Some(id.clone()),
+                    state: reset.state.clone(),
+                    action: vec![4],
+                    return_state: None,
+                    delta_obs: false,
+                    prev_obs_hash: 0,
+                    step_seed: None,
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(buffer_pool.stats(), empty_stats);
+            responses.push((reset, step));
+        }
+
+        // Pooling is an optimisation only: results are identical either way
+        assert_eq!(responses[0], responses[1]);
+    }
+
+    #[tokio::test]
+    async fn test_drain_buffers_empties_pool() {
+        let _registry = setup_test_registry();
+
+        let buffer_pool = BufferPool::with_capacity(4, 3, 2, 64);
+        let service = EngineService::with_buffer_pool(buffer_pool.clone());
+
+        let response = service
+            .drain_buffers(Request::new(DrainBuffersRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let before = response.before.unwrap();
+        assert_eq!(before.available_state_buffers, 4);
+        assert_eq!(before.available_obs_buffers, 3);
+        assert_eq!(before.available_action_buffers, 2);
+        assert_eq!(response.after.unwrap(), ProtoBufferPoolStats::default());
+        assert_eq!(buffer_pool.stats().available_state_buffers, 0);
+
+        // Requests after a drain allocate fresh buffers and repopulate the pool
+        let request = Request::new(ResetRequest {
+            id: Some(EngineId {
+                env_id: "tictactoe".to_string(),
+                build_id: "test".to_string(),
+            }),
+            seed: 42,
+            hint: Vec::new(),
+            strict_seed: false,
+            return_state: None,
+        });
+        let reset_resp = service.reset(request).await.unwrap().into_inner();
+        assert!(!reset_resp.state.is_empty());
+
+        let stats = buffer_pool.stats();
+        assert_eq!(stats.available_state_buffers, 1);
+        assert_eq!(stats.available_obs_buffers, 1);
+    }
+
     #[tokio::test]
     async fn test_step_rng_progression_is_deterministic() {
-        setup_rng_test_registry();
+        let _registry = setup_rng_test_registry();
 
         let service = EngineService::new();
         let engine_id = EngineId {
@@ -500,6 +2240,8 @@ mod tests {
             id: Some(engine_id.clone()),
             seed: 7,
             hint: Vec::new(),
+            strict_seed: false,
+            return_state: None,
         });
 
         let reset_response = service.reset(reset_request).await.unwrap();
@@ -509,6 +2251,10 @@ mod tests {
             id: Some(engine_id.clone()),
             state: reset_data.state.clone(),
             action: Vec::new(),
+            return_state: None,
+            delta_obs: false,
+            prev_obs_hash: 0,
+            step_seed: None,
         });
 
         let first_step = service.step(first_step_request).await.unwrap().into_inner();
@@ -517,6 +2263,10 @@ mod tests {
             id: Some(engine_id.clone()),
             state: first_step.state.clone(),
             action: Vec::new(),
+            return_state: None,
+            delta_obs: false,
+            prev_obs_hash: 0,
+            step_seed: None,
         });
 
         let second_step = service
@@ -534,6 +2284,8 @@ mod tests {
             id: Some(engine_id.clone()),
             seed: 7,
             hint: Vec::new(),
+            strict_seed: false,
+            return_state: None,
         });
 
         let reset_again_data = service_again.reset(reset_again).await.unwrap().into_inner();
@@ -543,6 +2295,10 @@ mod tests {
                 id: Some(engine_id.clone()),
                 state: reset_again_data.state.clone(),
                 action: Vec::new(),
+                return_state: None,
+                delta_obs: false,
+                prev_obs_hash: 0,
+                step_seed: None,
             }))
             .await
             .unwrap()
@@ -553,6 +2309,10 @@ mod tests {
                 id: Some(engine_id.clone()),
                 state: first_again.state.clone(),
                 action: Vec::new(),
+                return_state: None,
+                delta_obs: false,
+                prev_obs_hash: 0,
+                step_seed: None,
             }))
             .await
             .unwrap()
@@ -563,4 +2323,979 @@ mod tests {
         assert_eq!(second_step.reward, second_again.reward);
         assert_eq!(second_step.info, second_again.info);
     }
+
+    /// Reset `engine_id` on a fresh service, optionally reseed, then step once from the reset state
+    async fn reset_reseed_step(
+        engine_id: &EngineId,
+        action: Vec<u8>,
+        reseed: Option<u64>,
+    ) -> (ResetResponse, StepResponse) {
+        let service = EngineService::new();
+        let reset = service
+            .reset(Request::new(ResetRequest {
+                id: Some(engine_id.clone()),
+                seed: 11,
+                hint: Vec::new(),
+                strict_seed: false,
+                return_state: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        if let Some(seed) = reseed {
+            let response = service
+                .reseed(Request::new(ReseedRequest {
+                    id: Some(engine_id.clone()),
+                    seed,
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(response.deterministic, engine_id.env_id == "tictactoe");
+        }
+
+        let step = service
+            .step(Request::new(StepRequest {
+                id: Some(engine_id.clone()),
+                state: reset.state.clone(),
+                action,
+                return_state: None,
+                delta_obs: false,
+                prev_obs_hash: 0,
+                step_seed: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        (reset, step)
+    }
+
+    #[tokio::test]
+    async fn test_reseed_changes_stochastic_steps_only() {
+        let _registry = setup_test_registry();
+        register_game("rng-test".to_string(), || {
+            Box::new(GameAdapter::new(RngStepGame::default()))
+        });
+
+        let rng_id = EngineId {
+            env_id: "rng-test".to_string(),
+            build_id: "reseed".to_string(),
+        };
+        let (_, original) = reset_reseed_step(&rng_id, Vec::new(), None).await;
+        let (_, reseeded) = reset_reseed_step(&rng_id, Vec::new(), Some(99)).await;
+        let (_, reseeded_again) = reset_reseed_step(&rng_id, Vec::new(), Some(99)).await;
+
+        assert_ne!(original.info, reseeded.info, "reseed should switch the RNG stream");
+        assert_eq!(reseeded.info, reseeded_again.info, "the new stream is reproducible");
+        // Step count survives the reseed: the game was not reset
+        assert_eq!(reseeded.reward, reseeded.info as f32 + 1.0);
+
+        let ttt_id = EngineId {
+            env_id: "tictactoe".to_string(),
+            build_id: "reseed".to_string(),
+        };
+        let (reset, original) = reset_reseed_step(&ttt_id, vec![4], None).await;
+        let (reseed_reset, reseeded) = reset_reseed_step(&ttt_id, vec![4], Some(99)).await;
+
+        assert_eq!(reset.state, reseed_reset.state);
+        assert_eq!(original.state, reseeded.state);
+        assert_eq!(original.obs, reseeded.obs);
+        assert_eq!(original.info, reseeded.info);
+    }
+
+    #[tokio::test]
+    async fn test_step_seed_reproduces_a_stochastic_step_without_advancing_the_stream() {
+        let _registry = setup_rng_test_registry();
+        let engine_id = EngineId {
+            env_id: "rng-test".to_string(),
+            build_id: "step-seed".to_string(),
+        };
+        let step_request = |state: &[u8], step_seed: Option<u64>| {
+            Request::new(StepRequest {
+                id: Some(engine_id.clone()),
+                state: state.to_vec(),
+                action: Vec::new(),
+                return_state: None,
+                delta_obs: false,
+                prev_obs_hash: 0,
+                step_seed,
+            })
+        };
+        let reset_request = || {
+            Request::new(ResetRequest {
+                id: Some(engine_id.clone()),
+                seed: 7,
+                hint: Vec::new(),
+                strict_seed: false,
+                return_state: None,
+            })
+        };
+
+        let service = EngineService::new();
+        let reset = service.reset(reset_request()).await.unwrap().into_inner();
+        let seeded = service
+            .step(step_request(&reset.state, Some(99)))
+            .await
+            .unwrap()
+            .into_inner();
+        let seeded_again = service
+            .step(step_request(&reset.state, Some(99)))
+            .await
+            .unwrap()
+            .into_inner();
+        let other_seed = service
+            .step(step_request(&reset.state, Some(100)))
+            .await
+            .unwrap()
+            .into_inner();
+        let unseeded = service
+            .step(step_request(&reset.state, None))
+            .await
+            .unwrap()
+            .into_inner();
+
+        // Reward also counts the game's steps, so compare what the RNG decides
+        assert_eq!(seeded.state, seeded_again.state);
+        assert_eq!(seeded.obs, seeded_again.obs);
+        assert_eq!(seeded.info, seeded_again.info);
+        assert_ne!(seeded.info, other_seed.info);
+
+        // Seeded steps leave the stream where a fresh service's first step finds it
+        let fresh = EngineService::new();
+        let reset_again = fresh.reset(reset_request()).await.unwrap().into_inner();
+        let fresh_step = fresh
+            .step(step_request(&reset_again.state, None))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(unseeded.state, fresh_step.state);
+        assert_eq!(unseeded.info, fresh_step.info);
+    }
+
+    #[tokio::test]
+    async fn test_reseed_requires_cached_game() {
+        let service = EngineService::new();
+        let status = service
+            .reseed(Request::new(ReseedRequest {
+                id: Some(EngineId {
+                    env_id: "tictactoe".to_string(),
+                    build_id: "never-reset".to_string(),
+                }),
+                seed: 1,
+            }))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_resets_construct_game_once() {
+        setup_counting_test_registry();
+
+        let service = Arc::new(EngineService::new());
+        let engine_id = EngineId {
+            env_id: "counting-test".to_string(),
+            build_id: "test-build".to_string(),
+        };
+
+        let handles: Vec<_> = (0..32u64)
+            .map(|seed| {
+                let service = Arc::clone(&service);
+                let engine_id = engine_id.clone();
+                tokio::spawn(async move {
+                    service
+                        .reset(Request::new(ResetRequest {
+                            id: Some(engine_id),
+                            seed,
+                            hint: Vec::new(),
+                            strict_seed: false,
+                            return_state: None,
+                        }))
+                        .await
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            let response = handle.await.unwrap().unwrap().into_inner();
+            assert_eq!(response.state.len(), 9); // version byte + u64
+        }
+
+        assert_eq!(COUNTING_FACTORY_CALLS.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_list_cached_games_reports_each_reset_env() {
+        let _registry = setup_test_registry();
+        register_game("rng-test".to_string(), || {
+            Box::new(GameAdapter::new(RngStepGame::default()))
+        });
+
+        let service = EngineService::new();
+        let empty = service
+            .list_cached_games(Request::new(ListCachedGamesRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(empty.games.is_empty());
+
+        let now_ms = || SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+        let before = now_ms();
+        for env_id in ["tictactoe", "rng-test"] {
+            service
+                .reset(Request::new(ResetRequest {
+                    id: Some(EngineId {
+                        env_id: env_id.to_string(),
+                        build_id: "listing".to_string(),
+                    }),
+                    seed: 1,
+                    hint: Vec::new(),
+                    strict_seed: false,
+                    return_state: None,
+                }))
+                .await
+                .unwrap();
+        }
+        let after = now_ms();
+
+        let games = service
+            .list_cached_games(Request::new(ListCachedGamesRequest {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .games;
+
+        let ids: Vec<(String, String)> = games
+            .iter()
+            .map(|game| {
+                let id = game.id.clone().unwrap();
+                (id.env_id, id.build_id)
+            })
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                ("rng-test".to_string(), "listing".to_string()),
+                ("tictactoe".to_string(), "listing".to_string()),
+            ]
+        );
+        for game in &games {
+            assert!((before..=after).contains(&game.last_access_unix_ms));
+            assert!(game.approx_bytes > 0);
+        }
+    }
+
+    /// Counts steps and truncates the episode once `HORIZON` steps are taken,
+    /// reporting the steps left as `steps_left` step info
+    #[derive(Clone)]
+    struct HorizonGame;
+
+    impl HorizonGame {
+        const HORIZON: u8 = 2;
+    }
+
+    impl Game for HorizonGame {
+        type State = u8;
+        type Action = ();
+        type Obs = u8;
+
+        fn engine_id(&self) -> TypedEngineId {
+            TypedEngineId {
+                env_id: "horizon-test".to_string(),
+                build_id: "test-build".to_string(),
+            }
+        }
+
+        fn capabilities(&self) -> TypedCapabilities {
+            TypedCapabilities {
+                id: self.engine_id(),
+                encoding: Encoding {
+                    state: "u8".to_string(),
+                    action: "none".to_string(),
+                    obs: "u8".to_string(),
+                    schema_version: 1,
+                },
+                max_horizon: Self::HORIZON as u32,
+                action_space: ActionSpace::Discrete(1),
+                preferred_batch: 1,
+            }
+        }
+
+        fn reset(
+            &mut self,
+            _rng: &mut rand_chacha::ChaCha20Rng,
+            _hint: &[u8],
+        ) -> (Self::State, Self::Obs) {
+            (0, 0)
+        }
+
+        fn step(
+            &mut self,
+            state: &mut Self::State,
+            _action: Self::Action,
+            _rng: &mut rand_chacha::ChaCha20Rng,
+        ) -> StepResult<Self::Obs> {
+            *state += 1;
+            StepResult {
+                obs: *state,
+                reward: 0.0,
+                done: false,
+                truncated: *state >= Self::HORIZON,
+                info: 0,
+            }
+        }
+
+        fn step_info(&self, state: &Self::State) -> Vec<(String, f32)> {
+            vec![(
+                "steps_left".to_string(),
+                Self::HORIZON.saturating_sub(*state) as f32,
+            )]
+        }
+
+        fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+            out.push(*state);
+            Ok(())
+        }
+
+        fn decode_state(buf: &[u8]) -> Result<Self::State, DecodeError> {
+            buf.first().copied().ok_or(DecodeError::InvalidLength {
+                expected: 1,
+                actual: 0,
+            })
+        }
+
+        fn encode_action(_action: &Self::Action, _out: &mut Vec<u8>) -> Result<(), EncodeError> {
+            Ok(())
+        }
+
+        fn decode_action(_buf: &[u8]) -> Result<Self::Action, DecodeError> {
+            Ok(())
+        }
+
+        fn encode_obs(obs: &Self::Obs, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+            out.push(*obs);
+            Ok(())
+        }
+    }
+
+    /// Reset `env_id` on a fresh service and play `actions`, returning every step
+    async fn play(env_id: &str, actions: &[&[u8]]) -> Vec<StepResponse> {
+        let service = EngineService::new();
+        let id = EngineId {
+            env_id: env_id.to_string(),
+            build_id: "terminal-obs".to_string(),
+        };
+        let mut state = service
+            .reset(Request::new(ResetRequest {
+                id: Some(id.clone()),
+                seed: 1,
+                hint: Vec::new(),
+                strict_seed: false,
+                return_state: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .state;
+
+        let mut steps = Vec::new();
+        for action in actions {
+            let step = service
+                .step(Request::new(StepRequest {
+                    id: Some(id.clone()),
+                    state,
+                    action: action.to_vec(),
+                    return_state: None,
+                    delta_obs: false,
+                    prev_obs_hash: 0,
+                    step_seed: None,
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            state = step.state.clone();
+            steps.push(step);
+        }
+        steps
+    }
+
+    #[tokio::test]
+    async fn test_step_response_carries_game_step_info_alongside_info_bits() {
+        let _registry = setup_test_registry();
+        register_game("horizon-test".to_string(), || {
+            Box::new(GameAdapter::new(HorizonGame))
+        });
+
+        let steps = play("horizon-test", &[&[], &[]]).await;
+        let steps_left: Vec<_> = steps
+            .iter()
+            .map(|step| step.step_info.get("steps_left").copied())
+            .collect();
+        assert_eq!(steps_left, vec![Some(1.0), Some(0.0)]);
+
+        // Games without step info still report their info bits
+        let steps = play("tictactoe", &[&[4]]).await;
+        assert!(steps[0].step_info.is_empty());
+        assert_ne!(steps[0].info, 0);
+    }
+
+    #[tokio::test]
+    async fn test_terminal_obs_marks_wins_and_truncation_carries_bootstrap_obs() {
+        let _registry = setup_test_registry();
+        register_game("horizon-test".to_string(), || {
+            Box::new(GameAdapter::new(HorizonGame))
+        });
+
+        // X takes the top row
+        let win = play("tictactoe", &[&[0], &[3], &[1], &[4], &[2]]).await;
+        let (last, earlier) = win.split_last().unwrap();
+        assert!(last.done && !last.truncated);
+        assert!(last.terminal_obs);
+        assert_eq!(last.bootstrap_obs, None);
+        assert!(earlier.iter().all(|step| !step.terminal_obs && step.bootstrap_obs.is_none()));
+
+        let truncated = play("horizon-test", &[&[], &[]]).await;
+        assert!(!truncated[0].truncated && truncated[0].bootstrap_obs.is_none());
+        let last = &truncated[1];
+        assert!(last.truncated);
+        assert!(!last.terminal_obs);
+        assert_eq!(last.bootstrap_obs.as_deref(), Some(&[HorizonGame::HORIZON][..]));
+        assert_eq!(last.bootstrap_obs.as_ref(), Some(&last.obs));
+    }
+
+    #[tokio::test]
+    async fn test_get_supported_versions_includes_migrated_versions() {
+        let _registry = setup_test_registry();
+        let service = EngineService::new();
+        let id = |env_id: &str| EngineId {
+            env_id: env_id.to_string(),
+            build_id: "test".to_string(),
+        };
+
+        let versions = service
+            .get_supported_versions(Request::new(id("tictactoe")))
+            .await
+            .unwrap()
+            .into_inner()
+            .versions;
+        let caps = service
+            .get_capabilities(Request::new(id("tictactoe")))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(versions[0], 1);
+        assert_eq!(versions.last(), caps.enc.map(|enc| enc.schema_version).as_ref());
+
+        let err = service
+            .get_supported_versions(Request::new(id("missing")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_warm_cache_caches_known_envs_and_reports_unknown_ones() {
+        let _registry = setup_test_registry();
+        let service = EngineService::new();
+        let id = |env_id: &str| EngineId {
+            env_id: env_id.to_string(),
+            build_id: "warm".to_string(),
+        };
+
+        let results = service
+            .warm_cache(Request::new(WarmCacheRequest {
+                ids: vec![id("tictactoe"), id("missing")],
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .results;
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].id, Some(id("tictactoe")));
+        assert_eq!(results[0].code, tonic::Code::Ok as i32);
+        assert_eq!(results[1].id, Some(id("missing")));
+        assert_eq!(results[1].code, tonic::Code::NotFound as i32);
+        assert!(results[1].message.contains("missing"), "{}", results[1].message);
+
+        let games = service
+            .list_cached_games(Request::new(ListCachedGamesRequest {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .games;
+        let ids: Vec<EngineId> = games.into_iter().filter_map(|game| game.id).collect();
+        assert_eq!(ids, vec![id("tictactoe")]);
+    }
+
+    #[tokio::test]
+    async fn test_cache_manifest_rewarms_the_same_games_in_a_fresh_service() {
+        let _registry = setup_test_registry();
+        register_game("horizon-test".to_string(), || {
+            Box::new(GameAdapter::new(HorizonGame))
+        });
+        let id = |env_id: &str| EngineId {
+            env_id: env_id.to_string(),
+            build_id: "manifest".to_string(),
+        };
+        async fn list(service: &EngineService) -> Vec<EngineId> {
+            let request = Request::new(ListCachedGamesRequest {});
+            let games = service.list_cached_games(request).await.unwrap().into_inner().games;
+            games.into_iter().filter_map(|game| game.id).collect()
+        }
+
+        let service = EngineService::new();
+        service
+            .warm_cache(Request::new(WarmCacheRequest {
+                ids: vec![id("tictactoe"), id("horizon-test")],
+            }))
+            .await
+            .unwrap();
+        let path = std::env::temp_dir()
+            .join(format!("engine-cache-manifest-{}.txt", std::process::id()));
+        let manifest = service.dump_cache_manifest(&path).await.unwrap();
+        assert_eq!(manifest.ids, vec![id("horizon-test"), id("tictactoe")]);
+
+        let restarted = EngineService::new();
+        assert!(list(&restarted).await.is_empty());
+        let results = restarted.warm_from_manifest(&path).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(results.iter().all(|r| r.code == tonic::Code::Ok as i32), "{:?}", results);
+        assert_eq!(list(&restarted).await, manifest.ids);
+    }
+
+    // Time is paused so the sweeper ticks on virtual time, while the game
+    // ages come from last-access stamps the test sets
+    #[tokio::test(start_paused = true)]
+    async fn test_game_ttl_sweeper_evicts_only_untouched_games() {
+        let _registry = setup_test_registry();
+        let id = |build_id: &str| EngineId {
+            env_id: "tictactoe".to_string(),
+            build_id: build_id.to_string(),
+        };
+        let ttl = Duration::from_secs(60);
+        let service = Arc::new(EngineService::new().with_game_ttl(ttl));
+        service
+            .warm_cache(Request::new(WarmCacheRequest {
+                ids: vec![id("idle"), id("busy")],
+            }))
+            .await
+            .unwrap();
+
+        // The busy game was just used; the idle one a TTL and a second ago
+        let idle = ("tictactoe".to_string(), "idle".to_string());
+        let busy = ("tictactoe".to_string(), "busy".to_string());
+        let ttl_ms = ttl.as_millis() as u64;
+        async fn backdate(service: &EngineService, key: &(String, String), age_ms: u64) {
+            service.game_cache.lock().await[key]
+                .last_access_ms
+                .store(unix_time_ms() - age_ms, Ordering::Relaxed);
+        }
+        backdate(&service, &idle, ttl_ms + 1000).await;
+        backdate(&service, &busy, 0).await;
+
+        let sweeper = service.spawn_game_ttl_sweeper().unwrap();
+        tokio::time::sleep(ttl).await;
+        sweeper.abort();
+
+        let cached: Vec<EngineId> = service
+            .list_cached_games(Request::new(ListCachedGamesRequest {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .games
+            .into_iter()
+            .filter_map(|game| game.id)
+            .collect();
+        assert_eq!(cached, vec![id("busy")]);
+
+        // Games expire once unused for the full TTL
+        backdate(&service, &busy, ttl_ms - 1000).await;
+        assert_eq!(service.evict_expired_games(), 0);
+        backdate(&service, &busy, ttl_ms).await;
+        assert_eq!(service.evict_expired_games(), 1);
+
+        // A zero TTL never expires anything
+        let service = EngineService::new().with_game_ttl(Duration::ZERO);
+        assert!(Arc::new(service).spawn_game_ttl_sweeper().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_multi_step_steps_different_envs_and_isolates_errors() {
+        let _registry = setup_test_registry();
+        register_game("horizon-test".to_string(), || {
+            Box::new(GameAdapter::new(HorizonGame))
+        });
+
+        let service = EngineService::new();
+        let id = |env_id: &str| EngineId {
+            env_id: env_id.to_string(),
+            build_id: "multi-step".to_string(),
+        };
+        let mut states = Vec::new();
+        for env_id in ["tictactoe", "horizon-test"] {
+            let reset = service
+                .reset(Request::new(ResetRequest {
+                    id: Some(id(env_id)),
+                    seed: 7,
+                    hint: Vec::new(),
+                    strict_seed: false,
+                    return_state: None,
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            states.push(reset.state);
+        }
+        let step = |env_id: &str, state: &[u8], action: &[u8]| StepRequest {
+            id: Some(id(env_id)),
+            state: state.to_vec(),
+            action: action.to_vec(),
+            return_state: None,
+            delta_obs: false,
+            prev_obs_hash: 0,
+            step_seed: None,
+        };
+
+        let results = service
+            .multi_step(Request::new(MultiStepRequest {
+                steps: vec![
+                    step("tictactoe", &states[0], &[4]),
+                    step("no-such-env", &[], &[]),
+                    step("horizon-test", &states[1], &[]),
+                ],
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .results;
+        assert_eq!(results.len(), 3);
+
+        let single = service
+            .step(Request::new(step("tictactoe", &states[0], &[4])))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(
+            results[0].result,
+            Some(multi_step_result::Result::Step(single))
+        );
+
+        match &results[1].result {
+            Some(multi_step_result::Result::Error(error)) => {
+                assert_eq!(error.code, tonic::Code::NotFound as i32);
+                assert!(error.message.contains("no-such-env"), "{}", error.message);
+            }
+            other => panic!("expected an error for the unknown env, got {:?}", other),
+        }
+
+        match &results[2].result {
+            Some(multi_step_result::Result::Step(response)) => {
+                assert_eq!(response.obs, vec![1]);
+                assert!(!response.done && !response.truncated);
+            }
+            other => panic!("expected the horizon game to step, got {:?}", other),
+        }
+
+        // Both envs were served from the instances created by their resets
+        let cached = service.cached_games().await.unwrap();
+        let multi_step_games = cached
+            .iter()
+            .filter(|game| game.id.as_ref().unwrap().build_id == "multi-step")
+            .count();
+        assert_eq!(multi_step_games, 2);
+    }
+
+    #[tokio::test]
+    async fn test_forked_cached_game_behaves_like_a_fresh_one() {
+        let _registry = setup_test_registry();
+
+        let service = EngineService::new();
+        let key = ("tictactoe".to_string(), "fork".to_string());
+        let entry = service.get_or_create_game(key).await.unwrap();
+
+        // Use the cached instance first so a fork can't pass by luck
+        let mut state = Vec::new();
+        let mut obs = Vec::new();
+        let mut cached = entry.lock().await;
+        cached.reset(99, &[], &mut state, &mut obs).unwrap();
+        cached
+            .step(&state.clone(), &[4], &mut Vec::new(), &mut Vec::new())
+            .unwrap();
+
+        let mut games = [cached.fork(), create_game("tictactoe").unwrap()];
+        drop(cached);
+        assert_eq!(games[0].capabilities(), games[1].capabilities());
+
+        let mut transcripts = Vec::new();
+        for game in &mut games {
+            let (mut state, mut obs) = (Vec::new(), Vec::new());
+            let reset = game.reset(7, &[], &mut state, &mut obs).unwrap();
+            let mut steps = Vec::new();
+            for action in [0u8, 3, 1, 4, 2] {
+                let (mut next_state, mut next_obs) = (Vec::new(), Vec::new());
+                let outcome = game
+                    .step(&state, &[action], &mut next_state, &mut next_obs)
+                    .unwrap();
+                steps.push((next_state.clone(), next_obs, outcome));
+                state = next_state;
+            }
+            transcripts.push((reset, obs, steps));
+        }
+        assert_eq!(transcripts[0], transcripts[1]);
+        assert!(transcripts[0].2.last().unwrap().2.done);
+    }
+
+    #[tokio::test]
+    async fn test_payload_stats_record_tictactoe_sizes() {
+        let _registry = setup_test_registry();
+
+        let service = EngineService::new();
+        assert!(service
+            .get_payload_stats(Request::new(GetPayloadStatsRequest {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .envs
+            .is_empty());
+
+        let id = EngineId {
+            env_id: "tictactoe".to_string(),
+            build_id: "payload-stats".to_string(),
+        };
+        let mut state = service
+            .reset(Request::new(ResetRequest {
+                id: Some(id.clone()),
+                seed: 3,
+                hint: Vec::new(),
+                strict_seed: false,
+                return_state: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .state;
+        for action in [0u8, 3, 1] {
+            state = service
+                .step(Request::new(StepRequest {
+                    id: Some(id.clone()),
+                    state,
+                    action: vec![action],
+                    return_state: None,
+                    delta_obs: false,
+                    prev_obs_hash: 0,
+                    step_seed: None,
+                }))
+                .await
+                .unwrap()
+                .into_inner()
+                .state;
+        }
+
+        let envs = service
+            .get_payload_stats(Request::new(GetPayloadStatsRequest {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .envs;
+        assert_eq!(envs.len(), 1);
+        let stats = &envs[0];
+        assert_eq!(stats.env_id, "tictactoe");
+
+        // 29 f32 values per observation
+        let obs = stats.step_response_obs.as_ref().unwrap();
+        assert_eq!((obs.count, obs.min_bytes, obs.max_bytes, obs.sum_bytes), (3, 116, 116, 348));
+        assert_eq!(obs.buckets.len(), 8);
+        assert_eq!(obs.buckets[7], 3);
+        assert_eq!(stats.reset_obs.as_ref().unwrap().max_bytes, 116);
+
+        let action = stats.step_request_action.as_ref().unwrap();
+        assert_eq!((action.count, action.min_bytes, action.max_bytes), (3, 1, 1));
+        let state_len = state.len() as u64;
+        let next_state = stats.step_response_state.as_ref().unwrap();
+        assert_eq!((next_state.min_bytes, next_state.max_bytes), (state_len, state_len));
+        assert_eq!(stats.step_request_state.as_ref().unwrap().max_bytes, state_len);
+    }
+
+    #[tokio::test]
+    async fn test_legal_action_count_tracks_tictactoe_board() {
+        let _registry = setup_test_registry();
+        let service = EngineService::new();
+        let id = EngineId {
+            env_id: "tictactoe".to_string(),
+            build_id: "legal-count".to_string(),
+        };
+        let count = |state: Vec<u8>| {
+            let request = LegalActionCountRequest {
+                id: Some(id.clone()),
+                state,
+            };
+            let service = &service;
+            async move {
+                service
+                    .legal_action_count(Request::new(request))
+                    .await
+                    .map(|r| r.into_inner().count)
+            }
+        };
+
+        let state = service
+            .reset(Request::new(ResetRequest {
+                id: Some(id.clone()),
+                seed: 5,
+                hint: Vec::new(),
+                strict_seed: false,
+                return_state: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .state;
+        assert_eq!(count(state.clone()).await.unwrap(), Some(9));
+
+        let state = service
+            .step(Request::new(StepRequest {
+                id: Some(id.clone()),
+                state,
+                action: vec![4],
+                return_state: None,
+                delta_obs: false,
+                prev_obs_hash: 0,
+                step_seed: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .state;
+        assert_eq!(count(state).await.unwrap(), Some(8));
+
+        let err = count(vec![0xff]).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Internal);
+    }
+
+    #[tokio::test]
+    async fn test_stream_episode_ends_with_terminal_transition() {
+        use tokio_stream::StreamExt;
+
+        let _registry = setup_test_registry();
+        let service = EngineService::new();
+        let request = |policy_seed| StreamEpisodeRequest {
+            id: Some(EngineId {
+                env_id: "tictactoe".to_string(),
+                build_id: "stream".to_string(),
+            }),
+            seed: 9,
+            hint: Vec::new(),
+            policy_seed,
+            max_steps: 0,
+        };
+
+        let transitions: Vec<Transition> = service
+            .stream_episode(Request::new(request(1)))
+            .await
+            .unwrap()
+            .into_inner()
+            .collect::<Result<_, _>>()
+            .await
+            .unwrap();
+
+        // A tictactoe game needs at least five moves and at most nine
+        assert!((5..=9).contains(&transitions.len()), "{}", transitions.len());
+        let (last, rest) = transitions.split_last().unwrap();
+        assert!(last.done);
+        assert!(rest.iter().all(|t| !t.done));
+        for (i, pair) in transitions.windows(2).enumerate() {
+            assert_eq!(pair[0].step_number, i as u32);
+            assert_eq!(pair[0].next_state, pair[1].state);
+            assert_eq!(pair[0].next_obs, pair[1].obs);
+        }
+
+        // The same seeds replay the same episode
+        let replayed: Vec<Transition> = service
+            .stream_episode(Request::new(request(1)))
+            .await
+            .unwrap()
+            .into_inner()
+            .collect::<Result<_, _>>()
+            .await
+            .unwrap();
+        assert_eq!(replayed, transitions);
+
+        let mut limited = request(1);
+        limited.max_steps = 2;
+        let stream = service.stream_episode(Request::new(limited)).await.unwrap();
+        let items: Vec<_> = stream.into_inner().collect().await;
+        assert_eq!(items.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stream_episode_reports_failure_as_final_status() {
+        use tokio_stream::StreamExt;
+
+        // RngStepGame cannot enumerate legal actions for the policy
+        let _registry = setup_rng_test_registry();
+        let service = EngineService::new();
+        let items: Vec<Result<Transition, Status>> = service
+            .stream_episode(Request::new(StreamEpisodeRequest {
+                id: Some(EngineId {
+                    env_id: "rng-test".to_string(),
+                    build_id: "stream".to_string(),
+                }),
+                seed: 9,
+                hint: Vec::new(),
+                policy_seed: 1,
+                max_steps: 0,
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .collect()
+            .await;
+
+        assert_eq!(items.len(), 1);
+        let status = items[0].as_ref().unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+    }
+
+    #[tokio::test]
+    async fn test_services_with_own_registries_serve_disjoint_games() {
+        let tictactoe_only = Arc::new(Registry::new());
+        tictactoe_only.register_game("tictactoe".to_string(), || {
+            Box::new(GameAdapter::new(TicTacToe::new()))
+        });
+        let rng_only = Arc::new(Registry::new());
+        rng_only.register_game("rng-test".to_string(), || {
+            Box::new(GameAdapter::new(RngStepGame::default()))
+        });
+        let tenant_a = EngineService::new().with_registry(tictactoe_only);
+        let tenant_b = EngineService::new().with_registry(rng_only);
+
+        let reset = |env_id: &str| ResetRequest {
+            id: Some(EngineId {
+                env_id: env_id.to_string(),
+                build_id: "tenant".to_string(),
+            }),
+            seed: 42,
+            hint: Vec::new(),
+            strict_seed: false,
+            return_state: None,
+        };
+        tenant_a.reset(Request::new(reset("tictactoe"))).await.unwrap();
+        tenant_b.reset(Request::new(reset("rng-test"))).await.unwrap();
+
+        let status = tenant_a.reset(Request::new(reset("rng-test"))).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+        let status = tenant_b.reset(Request::new(reset("tictactoe"))).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        let info = tenant_a
+            .get_server_info(Request::new(GetServerInfoRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(info.registered_games, 1);
+    }
 }
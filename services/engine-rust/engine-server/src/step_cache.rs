@@ -0,0 +1,173 @@
+//! Memoization of step results for pure deterministic games
+//!
+//! For games that declare themselves deterministic and state-complete,
+//! `(state, action)` fully determines the step result, so repeated identical
+//! steps (common in tree search) can be served from a bounded LRU cache
+//! without running the game.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use engine_core::erased::StepOutcome;
+
+/// Cache key: `(env_id, state_bytes, action_bytes)`
+pub type StepKey = (String, Vec<u8>, Vec<u8>);
+
+/// Result of a single step as returned to clients
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedStep {
+    pub state: Vec<u8>,
+    pub obs: Vec<u8>,
+    pub outcome: StepOutcome,
+    /// The game's `step_info` for the step
+    pub step_info: Vec<(String, f32)>,
+}
+
+/// Bounded least-recently-used cache of step results
+#[derive(Debug)]
+pub struct StepCache {
+    capacity: usize,
+    inner: Mutex<StepCacheInner>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct StepCacheInner {
+    entries: HashMap<StepKey, (CachedStep, u64)>,
+    /// Last-use tick to key, oldest first
+    recency: BTreeMap<u64, StepKey>,
+    tick: u64,
+}
+
+impl StepCache {
+    /// Create a cache holding at most `capacity` step results
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(StepCacheInner::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Look up a step result, marking it as recently used
+    pub fn get(&self, key: &StepKey) -> Option<CachedStep> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+
+        let Some((step, last_used)) = inner.entries.get_mut(key) else {
+            drop(inner);
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        let previous = std::mem::replace(last_used, tick);
+        let step = step.clone();
+
+        let key = inner.recency.remove(&previous).expect("recency entry exists");
+        inner.recency.insert(tick, key);
+        drop(inner);
+
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(step)
+    }
+
+    /// Store a step result, evicting the least recently used entry when full
+    pub fn insert(&self, key: StepKey, step: CachedStep) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+
+        if let Some((_, previous)) = inner.entries.insert(key.clone(), (step, tick)) {
+            inner.recency.remove(&previous);
+        }
+        inner.recency.insert(tick, key);
+
+        while inner.entries.len() > self.capacity {
+            let Some((_, oldest)) = inner.recency.pop_first() else {
+                break;
+            };
+            inner.entries.remove(&oldest);
+        }
+    }
+
+    /// Number of lookups served from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of lookups that missed the cache
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Number of cached step results
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// Whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(state: u8) -> StepKey {
+        ("test".to_string(), vec![state], vec![0])
+    }
+
+    fn step(reward: f32) -> CachedStep {
+        CachedStep {
+            state: vec![1],
+            obs: vec![2],
+            outcome: StepOutcome {
+                reward,
+                done: false,
+                truncated: false,
+                terminal_obs: false,
+                info: 0,
+                current_player: None,
+                must_pass: false,
+            },
+            step_info: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_cache_counts_hits_and_misses() {
+        let cache = StepCache::new(4);
+        assert!(cache.get(&key(1)).is_none());
+
+        cache.insert(key(1), step(1.0));
+        assert_eq!(cache.get(&key(1)), Some(step(1.0)));
+
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(cache.misses(), 1);
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let cache = StepCache::new(2);
+        cache.insert(key(1), step(1.0));
+        cache.insert(key(2), step(2.0));
+
+        // Touch key 1 so key 2 becomes the eviction candidate
+        assert!(cache.get(&key(1)).is_some());
+        cache.insert(key(3), step(3.0));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&key(1)).is_some());
+        assert!(cache.get(&key(2)).is_none());
+        assert!(cache.get(&key(3)).is_some());
+    }
+}
@@ -0,0 +1,69 @@
+//! Optional TLS configuration for the engine server
+//!
+//! TLS is enabled when `ENGINE_TLS_CERT` and `ENGINE_TLS_KEY` point at a PEM
+//! certificate chain and private key. Setting `ENGINE_TLS_CLIENT_CA` as well
+//! requires clients to present a certificate signed by that CA (mutual TLS).
+//! With none of these set the server stays plaintext.
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+
+/// Environment variable with the server certificate chain (PEM)
+pub const TLS_CERT_ENV: &str = "ENGINE_TLS_CERT";
+
+/// Environment variable with the server private key (PEM)
+pub const TLS_KEY_ENV: &str = "ENGINE_TLS_KEY";
+
+/// Environment variable with the CA used to verify client certificates (PEM)
+pub const TLS_CLIENT_CA_ENV: &str = "ENGINE_TLS_CLIENT_CA";
+
+/// Build the server TLS configuration from the environment
+///
+/// Returns `Ok(None)` when no TLS variables are set so local development keeps
+/// using plaintext.
+pub fn server_tls_config_from_env() -> Result<Option<ServerTlsConfig>, Box<dyn Error>> {
+    let cert = env::var(TLS_CERT_ENV).ok();
+    let key = env::var(TLS_KEY_ENV).ok();
+    let client_ca = env::var(TLS_CLIENT_CA_ENV).ok();
+
+    match (cert, key) {
+        (Some(cert), Some(key)) => Ok(Some(server_tls_config(
+            Path::new(&cert),
+            Path::new(&key),
+            client_ca.as_deref().map(Path::new),
+        )?)),
+        (None, None) if client_ca.is_none() => Ok(None),
+        _ => Err(format!(
+            "{} and {} must both be set to enable TLS ({} requires them)",
+            TLS_CERT_ENV, TLS_KEY_ENV, TLS_CLIENT_CA_ENV
+        )
+        .into()),
+    }
+}
+
+/// Build a server TLS configuration from PEM files
+///
+/// When `client_ca` is given, clients must authenticate with a certificate
+/// issued by that CA.
+pub fn server_tls_config(
+    cert: &Path,
+    key: &Path,
+    client_ca: Option<&Path>,
+) -> Result<ServerTlsConfig, Box<dyn Error>> {
+    let identity = Identity::from_pem(read_pem(cert)?, read_pem(key)?);
+    let mut config = ServerTlsConfig::new().identity(identity);
+
+    if let Some(client_ca) = client_ca {
+        config = config.client_ca_root(Certificate::from_pem(read_pem(client_ca)?));
+    }
+
+    Ok(config)
+}
+
+fn read_pem(path: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e).into())
+}
@@ -0,0 +1,86 @@
+//! Integration test: the auth layer rejects requests without the configured
+//! token with `UNAUTHENTICATED` at the transport layer
+
+use std::net::TcpListener;
+use std::time::Duration;
+
+use engine_proto::engine_client::EngineClient;
+use engine_proto::engine_server::EngineServer;
+use engine_proto::GetServerInfoRequest;
+use engine_server::auth::AuthLayer;
+use engine_server::EngineService;
+use tokio::sync::oneshot;
+use tonic::transport::{Channel, Endpoint, Server};
+use tonic::{Code, Request, Status};
+
+/// Serve an engine behind `auth` and connect to it
+async fn serve(auth: AuthLayer) -> (Channel, oneshot::Sender<()>, tokio::task::JoinHandle<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(async move {
+        Server::builder()
+            .layer(auth)
+            .add_service(EngineServer::new(EngineService::new()))
+            .serve_with_shutdown(addr, async {
+                let _ = shutdown_rx.await;
+            })
+            .await
+            .unwrap();
+    });
+
+    // Connect eagerly so no request races the server's bind
+    let endpoint = Endpoint::new(format!("http://{}", addr)).unwrap();
+    let channel = loop {
+        match endpoint.connect().await {
+            Ok(channel) => break channel,
+            Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+        }
+    };
+    (channel, shutdown_tx, server)
+}
+
+async fn server_info(channel: &Channel, authorization: Option<&str>) -> Result<(), Status> {
+    let mut request = Request::new(GetServerInfoRequest {});
+    if let Some(value) = authorization {
+        request
+            .metadata_mut()
+            .insert("authorization", value.parse().unwrap());
+    }
+    EngineClient::new(channel.clone())
+        .get_server_info(request)
+        .await
+        .map(|_| ())
+}
+
+#[tokio::test]
+async fn test_requests_need_the_configured_token() {
+    let (channel, shutdown_tx, server) = serve(AuthLayer::new("s3cret")).await;
+
+    let missing = server_info(&channel, None).await.unwrap_err();
+    assert_eq!(missing.code(), Code::Unauthenticated, "{}", missing);
+
+    for wrong in ["Bearer wrong", "s3cret", "Bearer s3cret2", "Bearer s3cre"] {
+        let status = server_info(&channel, Some(wrong)).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated, "{}: {}", wrong, status);
+    }
+
+    server_info(&channel, Some("Bearer s3cret")).await.unwrap();
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_open_server_skips_the_check() {
+    let open = AuthLayer::open();
+    assert!(!open.is_enforced());
+    let (channel, shutdown_tx, server) = serve(open).await;
+
+    server_info(&channel, None).await.unwrap();
+    server_info(&channel, Some("Bearer anything")).await.unwrap();
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
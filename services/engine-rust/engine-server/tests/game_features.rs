@@ -0,0 +1,23 @@
+//! Integration test: `initialize_registry` registers exactly the games whose
+//! `game-*` features are enabled
+//!
+//! Runs in its own process so the registry holds only what
+//! `initialize_registry` put there. Exercise other feature sets with e.g.
+//! `cargo test -p engine-server --no-default-features --test game_features`.
+
+use engine_core::registry::list_registered_games;
+use engine_server::registry_init::initialize_registry;
+
+#[test]
+fn test_registry_contains_exactly_the_enabled_games() {
+    initialize_registry();
+
+    let expected: &[&str] = &[
+        #[cfg(feature = "game-tictactoe")]
+        "tictactoe",
+    ];
+
+    let mut registered = list_registered_games();
+    registered.sort();
+    assert_eq!(registered, expected);
+}
@@ -0,0 +1,124 @@
+//! Integration test: the in-flight request limit rejects excess concurrent
+//! requests with `RESOURCE_EXHAUSTED` at the transport layer
+
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+
+use engine_core::erased::{ErasedGameError, ResetOutcome, StepOutcome};
+use engine_core::typed::{Capabilities, EngineId as TypedEngineId};
+use engine_core::{register_game, ErasedGame, GameAdapter};
+use engine_proto::engine_client::EngineClient;
+use engine_proto::engine_server::EngineServer;
+use engine_proto::{EngineId, ResetRequest};
+use engine_server::admission::InFlightLimitLayer;
+use engine_server::EngineService;
+use games_tictactoe::TicTacToe;
+use tokio::sync::oneshot;
+use tonic::transport::{Endpoint, Server};
+use tonic::Code;
+
+/// TicTacToe whose resets take long enough for requests to pile up
+struct SlowResetTicTacToe(GameAdapter<TicTacToe>);
+
+impl ErasedGame for SlowResetTicTacToe {
+    fn engine_id(&self) -> TypedEngineId {
+        self.0.engine_id()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.0.capabilities()
+    }
+
+    fn reset(
+        &mut self,
+        seed: u64,
+        hint: &[u8],
+        out_state: &mut Vec<u8>,
+        out_obs: &mut Vec<u8>,
+    ) -> Result<ResetOutcome, ErasedGameError> {
+        thread::sleep(Duration::from_millis(300));
+        self.0.reset(seed, hint, out_state, out_obs)
+    }
+
+    fn fork(&self) -> Box<dyn ErasedGame> {
+        Box::new(SlowResetTicTacToe(GameAdapter::new(TicTacToe::new())))
+    }
+
+    fn step(
+        &mut self,
+        state: &[u8],
+        action: &[u8],
+        out_state: &mut Vec<u8>,
+        out_obs: &mut Vec<u8>,
+    ) -> Result<StepOutcome, ErasedGameError> {
+        self.0.step(state, action, out_state, out_obs)
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_requests_over_the_limit_are_rejected_with_resource_exhausted() {
+    register_game("slow-tictactoe".to_string(), || {
+        Box::new(SlowResetTicTacToe(GameAdapter::new(TicTacToe::new())))
+    });
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(async move {
+        Server::builder()
+            .layer(InFlightLimitLayer::new(2))
+            .add_service(EngineServer::new(EngineService::new()))
+            .serve_with_shutdown(addr, async {
+                let _ = shutdown_rx.await;
+            })
+            .await
+            .unwrap();
+    });
+
+    // Connect eagerly so no request races the server's bind
+    let endpoint = Endpoint::new(format!("http://{}", addr)).unwrap();
+    let channel = loop {
+        match endpoint.connect().await {
+            Ok(channel) => break channel,
+            Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+        }
+    };
+    let requests: Vec<_> = (0..6)
+        .map(|i| {
+            let mut client = EngineClient::new(channel.clone());
+            tokio::spawn(async move {
+                client
+                    .reset(ResetRequest {
+                        id: Some(EngineId {
+                            env_id: "slow-tictactoe".to_string(),
+                            build_id: format!("limit-test-{}", i),
+                        }),
+                        seed: 42,
+                        hint: Vec::new(),
+                        strict_seed: false,
+                        return_state: None,
+                    })
+                    .await
+            })
+        })
+        .collect();
+
+    let mut admitted = 0;
+    let mut rejected = 0;
+    for request in requests {
+        match request.await.unwrap() {
+            Ok(_) => admitted += 1,
+            Err(status) => {
+                assert_eq!(status.code(), Code::ResourceExhausted, "{}", status);
+                rejected += 1;
+            }
+        }
+    }
+    assert!(admitted >= 2, "requests within the limit are served");
+    assert!(rejected > 0, "requests over the limit are rejected");
+
+    shutdown_tx.send(()).unwrap();
+    server.await.unwrap();
+}
@@ -0,0 +1,214 @@
+//! Integration test: a game using the serde state codec behaves like one with
+//! a hand-written codec when served through `EngineService`
+//!
+//! The same random-walk game is defined twice, differing only in how its
+//! state is encoded. Stepping both through the service must give identical
+//! observations, rewards, and termination; only the state bytes may differ.
+
+use engine_core::typed::{
+    ActionSpace, Capabilities, DecodeError, EncodeError, Encoding, EngineId, Game, StepResult,
+};
+use engine_core::{register_game, GameAdapter};
+use engine_proto::engine_server::Engine;
+use engine_proto::{EngineId as ProtoEngineId, ResetRequest, StepRequest, StepResponse};
+use engine_server::EngineService;
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+use tonic::Request;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct WalkState {
+    position: i32,
+    steps: u32,
+}
+
+/// Walk left (0) or right (1); reaching +3 pays 1, and the walk ends at +-3
+/// or after ten steps
+macro_rules! walk_game {
+    ($name:ident, $env_id:literal, $state_encoding:literal, { $($codec:tt)* }) => {
+        #[derive(Clone)]
+        struct $name;
+
+        impl Game for $name {
+            type State = WalkState;
+            type Action = u8;
+            type Obs = [f32; 2];
+
+            const OBS_ENCODED_SIZE: Option<usize> = Some(8);
+            const ACTION_ENCODED_SIZE: Option<usize> = Some(1);
+
+            fn engine_id(&self) -> EngineId {
+                EngineId {
+                    env_id: $env_id.to_string(),
+                    build_id: "0.1.0".to_string(),
+                }
+            }
+
+            fn capabilities(&self) -> Capabilities {
+                Capabilities {
+                    id: self.engine_id(),
+                    encoding: Encoding {
+                        state: $state_encoding.to_string(),
+                        action: "u8:v1".to_string(),
+                        obs: "f32x2:v1".to_string(),
+                        schema_version: 1,
+                    },
+                    max_horizon: 10,
+                    action_space: ActionSpace::Discrete(2),
+                    preferred_batch: 16,
+                }
+            }
+
+            fn reset(&mut self, _rng: &mut ChaCha20Rng, _hint: &[u8]) -> (Self::State, Self::Obs) {
+                (WalkState { position: 0, steps: 0 }, [0.0, 0.0])
+            }
+
+            fn step(
+                &mut self,
+                state: &mut Self::State,
+                action: Self::Action,
+                _rng: &mut ChaCha20Rng,
+            ) -> StepResult<Self::Obs> {
+                state.position += if action == 1 { 1 } else { -1 };
+                state.steps += 1;
+                StepResult {
+                    obs: [state.position as f32, state.steps as f32],
+                    reward: if state.position == 3 { 1.0 } else { 0.0 },
+                    done: state.position.abs() == 3,
+                    truncated: state.steps == 10 && state.position.abs() != 3,
+                    info: state.steps as u64,
+                }
+            }
+
+            $($codec)*
+
+            fn encode_action(action: &Self::Action, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+                out.push(*action);
+                Ok(())
+            }
+
+            fn decode_action(buf: &[u8]) -> Result<Self::Action, DecodeError> {
+                match buf {
+                    [action @ (0 | 1)] => Ok(*action),
+                    _ => Err(DecodeError::CorruptedData(format!("Invalid action: {:?}", buf))),
+                }
+            }
+
+            fn encode_obs(obs: &Self::Obs, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+                for value in obs {
+                    out.extend_from_slice(&value.to_le_bytes());
+                }
+                Ok(())
+            }
+        }
+    };
+}
+
+walk_game!(ManualWalk, "walk-manual", "walk_state:v1", {
+    fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+        out.extend_from_slice(&state.position.to_le_bytes());
+        out.extend_from_slice(&state.steps.to_le_bytes());
+        Ok(())
+    }
+
+    fn decode_state(buf: &[u8]) -> Result<Self::State, DecodeError> {
+        if buf.len() != 8 {
+            return Err(DecodeError::InvalidLength {
+                expected: 8,
+                actual: buf.len(),
+            });
+        }
+        Ok(WalkState {
+            position: i32::from_le_bytes(buf[0..4].try_into().unwrap()),
+            steps: u32::from_le_bytes(buf[4..8].try_into().unwrap()),
+        })
+    }
+});
+
+walk_game!(SerdeWalk, "walk-serde", "walk_state:bincode", {
+    engine_core::serde_state_codec!();
+});
+
+/// Reset `env_id` and play `actions`, returning every step response
+async fn play(service: &EngineService, env_id: &str, actions: &[u8]) -> Vec<StepResponse> {
+    let id = ProtoEngineId {
+        env_id: env_id.to_string(),
+        build_id: "serde-state-test".to_string(),
+    };
+    let mut state = service
+        .reset(Request::new(ResetRequest {
+            id: Some(id.clone()),
+            seed: 3,
+            hint: Vec::new(),
+            strict_seed: true,
+            return_state: None,
+        }))
+        .await
+        .unwrap()
+        .into_inner()
+        .state;
+
+    let mut responses = Vec::new();
+    for &action in actions {
+        let response = service
+            .step(Request::new(StepRequest {
+                id: Some(id.clone()),
+                state,
+                action: vec![action],
+                return_state: None,
+                delta_obs: false,
+                prev_obs_hash: 0,
+                step_seed: None,
+            }))
+            .await
+            .unwrap_or_else(|e| panic!("{}: step failed: {}", env_id, e))
+            .into_inner();
+        state = response.state.clone();
+        responses.push(response);
+    }
+    responses
+}
+
+#[test]
+fn test_serde_codec_roundtrips_game_state() {
+    let state = WalkState {
+        position: -2,
+        steps: 7,
+    };
+
+    let mut buf = Vec::new();
+    SerdeWalk::encode_state(&state, &mut buf).unwrap();
+    assert_eq!(SerdeWalk::decode_state(&buf).unwrap(), state);
+    assert!(SerdeWalk::decode_state(&buf[..1]).is_err());
+}
+
+#[tokio::test]
+async fn test_serde_state_game_matches_hand_encoded_game_through_service() {
+    register_game("walk-manual".to_string(), || {
+        Box::new(GameAdapter::new(ManualWalk))
+    });
+    register_game("walk-serde".to_string(), || {
+        Box::new(GameAdapter::new(SerdeWalk))
+    });
+    let service = EngineService::new();
+
+    let actions = [1, 0, 1, 1, 1];
+    let manual = play(&service, "walk-manual", &actions).await;
+    let serde = play(&service, "walk-serde", &actions).await;
+
+    for (i, (manual, serde)) in manual.iter().zip(&serde).enumerate() {
+        assert_eq!(manual.obs, serde.obs, "step {}: obs", i);
+        assert_eq!(manual.reward, serde.reward, "step {}: reward", i);
+        assert_eq!(manual.done, serde.done, "step {}: done", i);
+        assert_eq!(manual.truncated, serde.truncated, "step {}: truncated", i);
+        assert_eq!(manual.info, serde.info, "step {}: info", i);
+        assert_ne!(
+            manual.state, serde.state,
+            "step {}: layouts should differ",
+            i
+        );
+    }
+    let last = serde.last().unwrap();
+    assert!(last.done);
+    assert_eq!(last.reward, 1.0);
+}
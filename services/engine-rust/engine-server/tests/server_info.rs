@@ -0,0 +1,37 @@
+//! Integration test: `GetServerInfo` reports the build and the registry
+//!
+//! Runs in its own process so the registry holds exactly the games registered
+//! here.
+
+use engine_core::registry::list_registered_games;
+use engine_core::{register_game, GameAdapter};
+use engine_proto::engine_server::Engine;
+use engine_proto::GetServerInfoRequest;
+use engine_server::service::ENGINE_GIT_COMMIT;
+use engine_server::EngineService;
+use games_tictactoe::TicTacToe;
+use tonic::Request;
+
+#[tokio::test]
+async fn test_server_info_reports_crate_version_and_registered_games() {
+    register_game("tictactoe".to_string(), || {
+        Box::new(GameAdapter::new(TicTacToe::new()))
+    });
+    register_game("tictactoe-draw-penalty".to_string(), || {
+        Box::new(GameAdapter::new(TicTacToe::with_draw_reward(-0.1)))
+    });
+
+    let service = EngineService::new();
+    let info = service
+        .get_server_info(Request::new(GetServerInfoRequest {}))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(info.git_commit, ENGINE_GIT_COMMIT);
+    assert!(!info.git_commit.is_empty());
+    assert_eq!(info.registered_games, 2);
+    assert_eq!(list_registered_games().len(), 2);
+    assert!(info.uptime_secs < 5, "service was just created");
+}
@@ -0,0 +1,120 @@
+//! Integration test: recorded gRPC sessions replay against the current
+//! service, and a divergence names the first differing exchange
+
+use std::path::PathBuf;
+
+use engine_core::{register_game, GameAdapter};
+use engine_proto::engine_server::Engine;
+use engine_proto::{EngineId, ResetRequest, StepRequest};
+use engine_server::session::{replay_session, Exchange, Session, SessionError, SessionRecorder};
+use engine_server::EngineService;
+use games_tictactoe::TicTacToe;
+use tonic::Request;
+
+fn register_tictactoe() {
+    register_game("tictactoe".to_string(), || {
+        Box::new(GameAdapter::new(TicTacToe::new()))
+    });
+}
+
+fn engine_id() -> Option<EngineId> {
+    Some(EngineId {
+        env_id: "tictactoe".to_string(),
+        build_id: "session".to_string(),
+    })
+}
+
+/// Play X to a win along the 2-4-6 diagonal, with one malformed step on the way
+async fn play_recorded_game(service: &EngineService) {
+    let mut state = service
+        .reset(Request::new(ResetRequest {
+            id: engine_id(),
+            seed: 42,
+            hint: Vec::new(),
+            strict_seed: false,
+            return_state: None,
+        }))
+        .await
+        .unwrap()
+        .into_inner()
+        .state;
+
+    for action in [vec![4], vec![0], vec![4, 4], vec![2], vec![1], vec![6]] {
+        let step = service
+            .step(Request::new(StepRequest {
+                id: engine_id(),
+                state: state.clone(),
+                action,
+                return_state: None,
+                delta_obs: false,
+                prev_obs_hash: 0,
+                step_seed: None,
+            }))
+            .await;
+        if let Ok(step) = step {
+            state = step.into_inner().state;
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_recorded_tictactoe_session_replays() {
+    register_tictactoe();
+
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/sessions/tictactoe.session");
+    let session = Session::load(path).unwrap();
+    assert_eq!(session.exchanges.len(), 7);
+    assert!(matches!(
+        session.exchanges.last(),
+        Some(Exchange::Step { response: Ok(step), .. }) if step.done && step.reward == 1.0
+    ));
+
+    if let Err(err) = replay_session(path).await {
+        panic!("{}", err);
+    }
+}
+
+#[tokio::test]
+async fn test_recorder_output_replays_and_reports_first_divergence() {
+    register_tictactoe();
+
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("engine-session-{}.session", std::process::id()));
+    let service =
+        EngineService::new().with_session_recorder(SessionRecorder::create(&path).unwrap());
+    play_recorded_game(&service).await;
+    let mut session = Session::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(session.exchanges.len(), 7);
+    assert!(matches!(
+        &session.exchanges[3],
+        Exchange::Step {
+            response: Err(_),
+            ..
+        }
+    ));
+    session.replay(&EngineService::new()).await.unwrap();
+
+    // Tamper with the second step's reward; replay stops there
+    let Exchange::Step {
+        response: Ok(step), ..
+    } = &mut session.exchanges[2]
+    else {
+        panic!("exchange 3 is a successful step");
+    };
+    step.reward = 5.0;
+    let err = session.replay(&EngineService::new()).await.unwrap_err();
+    assert!(
+        matches!(
+            err,
+            SessionError::Mismatch {
+                index: 3,
+                call: "step",
+                ..
+            }
+        ),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,76 @@
+//! Integration test: the diagnostic current-thread runtime starts the server
+//! and serves requests
+
+use std::net::TcpListener;
+use std::time::Duration;
+
+use engine_core::{register_game, GameAdapter};
+use engine_proto::engine_client::EngineClient;
+use engine_proto::engine_server::EngineServer;
+use engine_proto::{EngineId, ResetRequest};
+use engine_server::{runtime, EngineService};
+use games_tictactoe::TicTacToe;
+use tokio::runtime::RuntimeFlavor;
+use tokio::sync::oneshot;
+use tonic::transport::{Endpoint, Server};
+
+#[test]
+fn test_default_runtime_is_multi_threaded() {
+    let rt = runtime::build(false).unwrap();
+    assert_eq!(rt.handle().runtime_flavor(), RuntimeFlavor::MultiThread);
+}
+
+#[test]
+fn test_single_threaded_runtime_serves_a_reset() {
+    register_game("tictactoe".to_string(), || {
+        Box::new(GameAdapter::new(TicTacToe::new()))
+    });
+
+    let rt = runtime::build(true).unwrap();
+    assert_eq!(rt.handle().runtime_flavor(), RuntimeFlavor::CurrentThread);
+
+    rt.block_on(async {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        let server = tokio::spawn(async move {
+            Server::builder()
+                .add_service(EngineServer::new(EngineService::new()))
+                .serve_with_shutdown(addr, async {
+                    let _ = shutdown_rx.await;
+                })
+                .await
+                .unwrap();
+        });
+
+        // Connect eagerly so no request races the server's bind
+        let endpoint = Endpoint::new(format!("http://{}", addr)).unwrap();
+        let channel = loop {
+            match endpoint.connect().await {
+                Ok(channel) => break channel,
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+
+        let response = EngineClient::new(channel)
+            .reset(ResetRequest {
+                id: Some(EngineId {
+                    env_id: "tictactoe".to_string(),
+                    build_id: "single-threaded".to_string(),
+                }),
+                seed: 42,
+                hint: Vec::new(),
+                strict_seed: false,
+                return_state: None,
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!response.state.is_empty());
+        assert!(!response.obs.is_empty());
+
+        shutdown_tx.send(()).unwrap();
+        server.await.unwrap();
+    });
+}
@@ -0,0 +1,140 @@
+//! Integration test: mutual TLS between a client and the engine server
+
+use std::fs;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+
+use engine_core::{register_game, GameAdapter};
+use engine_proto::engine_client::EngineClient;
+use engine_proto::engine_server::EngineServer;
+use engine_proto::{EngineId, ResetRequest};
+use engine_server::{tls, EngineService};
+use games_tictactoe::TicTacToe;
+use rcgen::{BasicConstraints, Certificate, CertificateParams, IsCa};
+use tokio::sync::oneshot;
+use tonic::transport::{
+    Certificate as TlsCertificate, ClientTlsConfig, Endpoint, Identity, Server,
+};
+
+/// PEM material for a throwaway CA plus server and client leaf certificates
+struct TestPki {
+    ca_cert: String,
+    server_cert: String,
+    server_key: String,
+    client_cert: String,
+    client_key: String,
+}
+
+impl TestPki {
+    fn generate() -> Self {
+        let mut ca_params = CertificateParams::new(Vec::new());
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = Certificate::from_params(ca_params).unwrap();
+
+        let server =
+            Certificate::from_params(CertificateParams::new(vec!["localhost".to_string()]))
+                .unwrap();
+        let client =
+            Certificate::from_params(CertificateParams::new(vec!["actor".to_string()])).unwrap();
+
+        Self {
+            ca_cert: ca.serialize_pem().unwrap(),
+            server_cert: server.serialize_pem_with_signer(&ca).unwrap(),
+            server_key: server.serialize_private_key_pem(),
+            client_cert: client.serialize_pem_with_signer(&ca).unwrap(),
+            client_key: client.serialize_private_key_pem(),
+        }
+    }
+}
+
+fn write_temp(dir: &Path, name: &str, contents: &str) -> PathBuf {
+    let path = dir.join(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn reset_request() -> ResetRequest {
+    ResetRequest {
+        id: Some(EngineId {
+            env_id: "tictactoe".to_string(),
+            build_id: "tls-test".to_string(),
+        }),
+        seed: 42,
+        hint: Vec::new(),
+        strict_seed: false,
+        return_state: None,
+    }
+}
+
+#[tokio::test]
+async fn test_mutual_tls_reset() {
+    register_game("tictactoe".to_string(), || {
+        Box::new(GameAdapter::new(TicTacToe::new()))
+    });
+
+    let pki = TestPki::generate();
+    let dir = std::env::temp_dir().join(format!("engine-tls-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let cert_path = write_temp(&dir, "server.pem", &pki.server_cert);
+    let key_path = write_temp(&dir, "server.key", &pki.server_key);
+    let ca_path = write_temp(&dir, "ca.pem", &pki.ca_cert);
+
+    let tls_config = tls::server_tls_config(&cert_path, &key_path, Some(&ca_path)).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind test listener");
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+
+    let server_handle = tokio::spawn(async move {
+        Server::builder()
+            .tls_config(tls_config)
+            .unwrap()
+            .add_service(EngineServer::new(EngineService::new()))
+            .serve_with_shutdown(addr, async {
+                let _ = shutdown_rx.await;
+            })
+            .await
+            .unwrap();
+    });
+
+    let endpoint = |identity: Option<Identity>| {
+        let mut client_tls = ClientTlsConfig::new()
+            .ca_certificate(TlsCertificate::from_pem(&pki.ca_cert))
+            .domain_name("localhost");
+        if let Some(identity) = identity {
+            client_tls = client_tls.identity(identity);
+        }
+        Endpoint::new(format!("https://{}", addr))
+            .unwrap()
+            .tls_config(client_tls)
+            .unwrap()
+    };
+
+    // Give the server a moment to start accepting connections
+    let mut channel = None;
+    for _ in 0..50 {
+        let identity = Identity::from_pem(&pki.client_cert, &pki.client_key);
+        match endpoint(Some(identity)).connect().await {
+            Ok(connected) => {
+                channel = Some(connected);
+                break;
+            }
+            Err(_) => tokio::time::sleep(std::time::Duration::from_millis(20)).await,
+        }
+    }
+    let mut client = EngineClient::new(channel.expect("failed to connect over TLS"));
+
+    let response = client.reset(reset_request()).await.unwrap().into_inner();
+    assert_eq!(response.actual_seed, 42);
+    assert!(!response.state.is_empty());
+    assert!(!response.obs.is_empty());
+
+    // Without a client certificate the server rejects the connection
+    let mut anonymous = EngineClient::new(endpoint(None).connect_lazy());
+    assert!(anonymous.reset(reset_request()).await.is_err());
+
+    let _ = shutdown_tx.send(());
+    server_handle.await.unwrap();
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -3,11 +3,49 @@
 //! This crate provides a complete reference implementation of TicTacToe
 //! demonstrating how to implement the Game trait for the engine framework.
 
+use engine_core::endian::Endianness;
 use engine_core::typed::{
     ActionSpace, Capabilities, DecodeError, EncodeError, Encoding, EngineId, Game,
+    InitialStateKind, ObsBounds, ObsLayout, StepResult,
 };
 use rand_chacha::ChaCha20Rng;
 
+/// Current state schema version
+///
+/// v1: board (9) + current_player (1) + winner (1)
+/// v2: every field packed into a little-endian u32 (`packed-state` feature)
+const STATE_SCHEMA_VERSION: u32 = if cfg!(feature = "packed-state") { 2 } else { 1 };
+
+/// Encoding name reported in capabilities; always matches `STATE_SCHEMA_VERSION`
+const STATE_ENCODING: &str = if cfg!(feature = "packed-state") {
+    "tictactoe_state:v2"
+} else {
+    "tictactoe_state:v1"
+};
+
+/// Encoded length of a v1 state
+const STATE_V1_LEN: usize = 11;
+
+/// Encoded length of a v2 (packed) state
+const STATE_V2_LEN: usize = 4;
+
+/// Encoded length of the current state
+const STATE_LEN: usize = if cfg!(feature = "packed-state") {
+    STATE_V2_LEN
+} else {
+    STATE_V1_LEN
+};
+
+/// Bit offsets in a v2 state; cell `i` occupies bits `2i..2i+2`
+const PACKED_PLAYER_SHIFT: u32 = 18;
+const PACKED_WINNER_SHIFT: u32 = 20;
+
+/// Number of bits used by a v2 state; higher bits must be zero
+const PACKED_BITS: u32 = 22;
+
+/// Encoded observation length: 29 little-endian `f32` values
+const OBS_LEN: usize = 29 * 4;
+
 /// TicTacToe game state
 ///
 /// Represents the complete state of a TicTacToe game including the board,
@@ -69,6 +107,107 @@ impl State {
             })
     }
 
+    /// Build a state from decoded fields, rejecting out-of-range values
+    fn from_fields(board: [u8; 9], current_player: u8, winner: u8) -> Result<Self, DecodeError> {
+        if current_player != 1 && current_player != 2 {
+            return Err(DecodeError::CorruptedData(format!(
+                "Invalid current_player: {}",
+                current_player
+            )));
+        }
+
+        if winner > 3 {
+            return Err(DecodeError::CorruptedData(format!(
+                "Invalid winner: {}",
+                winner
+            )));
+        }
+
+        for &cell in &board {
+            if cell > 2 {
+                return Err(DecodeError::CorruptedData(format!(
+                    "Invalid board cell: {}",
+                    cell
+                )));
+            }
+        }
+
+        Ok(State {
+            board,
+            current_player,
+            winner,
+        })
+    }
+
+    /// Append the state in the layout of the current schema version
+    fn encode(&self, out: &mut Vec<u8>) {
+        if cfg!(feature = "packed-state") {
+            self.encode_packed(out);
+        } else {
+            self.encode_v1(out);
+        }
+    }
+
+    /// v1 layout: board (9 bytes) + current_player (1 byte) + winner (1 byte)
+    fn encode_v1(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.board);
+        out.push(self.current_player);
+        out.push(self.winner);
+    }
+
+    fn decode_v1(buf: &[u8]) -> Result<Self, DecodeError> {
+        if buf.len() != STATE_V1_LEN {
+            return Err(DecodeError::InvalidLength {
+                expected: STATE_V1_LEN,
+                actual: buf.len(),
+            });
+        }
+
+        let mut board = [0u8; 9];
+        board.copy_from_slice(&buf[0..9]);
+        Self::from_fields(board, buf[9], buf[10])
+    }
+
+    /// v2 layout: 2 bits per cell, then current_player (2 bits) and winner
+    /// (2 bits), as a little-endian u32
+    fn encode_packed(&self, out: &mut Vec<u8>) {
+        let mut packed = self
+            .board
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (idx, &cell)| bits | (cell as u32) << (2 * idx));
+        packed |= (self.current_player as u32) << PACKED_PLAYER_SHIFT;
+        packed |= (self.winner as u32) << PACKED_WINNER_SHIFT;
+        out.extend_from_slice(&packed.to_le_bytes());
+    }
+
+    fn decode_packed(buf: &[u8]) -> Result<Self, DecodeError> {
+        let bytes: [u8; STATE_V2_LEN] = buf.try_into().map_err(|_| DecodeError::InvalidLength {
+            expected: STATE_V2_LEN,
+            actual: buf.len(),
+        })?;
+        let packed = u32::from_le_bytes(bytes);
+
+        if packed >> PACKED_BITS != 0 {
+            return Err(DecodeError::CorruptedData(format!(
+                "Unused bits set in packed state: {:#010x}",
+                packed
+            )));
+        }
+
+        let field = |shift: u32, width: u32| ((packed >> shift) & ((1 << width) - 1)) as u8;
+        let mut board = [0u8; 9];
+        for (idx, cell) in board.iter_mut().enumerate() {
+            *cell = field(2 * idx as u32, 2);
+        }
+
+        Self::from_fields(
+            board,
+            field(PACKED_PLAYER_SHIFT, 2),
+            field(PACKED_WINNER_SHIFT, 2),
+        )
+    }
+
     /// Make a move and return the new state
     pub fn make_move(&self, position: u8) -> State {
         if self.is_done() || position >= 9 || self.board[position as usize] != 0 {
@@ -194,18 +333,65 @@ impl Observation {
     }
 }
 
+/// What an illegal move on an unfinished board costs
+///
+/// Without a penalty an illegal move is a no-op paying 0.0, which gives a
+/// policy nothing to learn from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IllegalMovePenalty {
+    /// Reward paid for the illegal move, typically negative (e.g. -1.0)
+    pub reward: f32,
+    /// End the episode on the illegal move instead of letting the player retry
+    pub ends_episode: bool,
+}
+
 /// TicTacToe game implementation
-#[derive(Debug)]
-pub struct TicTacToe;
+#[derive(Debug, Clone)]
+pub struct TicTacToe {
+    /// Reward paid to the player whose move fills the board without a winner
+    draw_reward: f32,
+    /// Penalty for illegal moves; `None` keeps the lenient no-op
+    illegal_move_penalty: Option<IllegalMovePenalty>,
+}
 
 impl TicTacToe {
     /// Create a new TicTacToe game
     pub fn new() -> Self {
-        Self
+        Self::with_draw_reward(0.0)
+    }
+
+    /// Create a game that pays `draw_reward` for a drawn final move
+    ///
+    /// Wins and losses still pay 1.0 and -1.0. A small negative value
+    /// discourages policies from settling for draws.
+    pub fn with_draw_reward(draw_reward: f32) -> Self {
+        Self {
+            draw_reward,
+            illegal_move_penalty: None,
+        }
+    }
+
+    /// Penalize illegal moves instead of treating them as a free no-op
+    ///
+    /// The board is still left unchanged. Moves submitted after the game has
+    /// ended are not penalized.
+    pub fn with_illegal_move_penalty(mut self, penalty: IllegalMovePenalty) -> Self {
+        self.illegal_move_penalty = Some(penalty);
+        self
+    }
+
+    /// Reward paid for a draw
+    pub fn draw_reward(&self) -> f32 {
+        self.draw_reward
+    }
+
+    /// Penalty for illegal moves, if enabled
+    pub fn illegal_move_penalty(&self) -> Option<IllegalMovePenalty> {
+        self.illegal_move_penalty
     }
 
     /// Calculate reward for the current state
-    fn calculate_reward(state: &State, previous_player: u8) -> f32 {
+    fn calculate_reward(&self, state: &State, previous_player: u8) -> f32 {
         match state.winner {
             0 => 0.0, // Game ongoing
             1 => {
@@ -222,7 +408,7 @@ impl TicTacToe {
                     -1.0
                 }
             } // O wins
-            3 => 0.0, // Draw
+            3 => self.draw_reward, // Draw
             _ => 0.0, // Shouldn't happen
         }
     }
@@ -261,6 +447,10 @@ impl Game for TicTacToe {
     type Action = Action;
     type Obs = Observation;
 
+    const STATE_ENCODED_SIZE: Option<usize> = Some(STATE_LEN);
+    const OBS_ENCODED_SIZE: Option<usize> = Some(OBS_LEN);
+    const ACTION_ENCODED_SIZE: Option<usize> = Some(1);
+
     fn engine_id(&self) -> EngineId {
         EngineId {
             env_id: "tictactoe".to_string(),
@@ -272,10 +462,10 @@ impl Game for TicTacToe {
         Capabilities {
             id: self.engine_id(),
             encoding: Encoding {
-                state: "tictactoe_state:v1".to_string(),
+                state: STATE_ENCODING.to_string(),
                 action: "discrete_position:v1".to_string(),
                 obs: "f32x29:v1".to_string(), // 18 + 9 + 2 = 29 floats
-                schema_version: 1,
+                schema_version: STATE_SCHEMA_VERSION,
             },
             max_horizon: 9,                         // Maximum 9 moves in TicTacToe
             action_space: ActionSpace::Discrete(9), // 9 possible positions
@@ -283,6 +473,76 @@ impl Game for TicTacToe {
         }
     }
 
+    fn initial_state_kind(&self) -> InitialStateKind {
+        // Every episode starts from the empty board
+        InitialStateKind::Fixed
+    }
+
+    fn action_names(&self) -> Option<Vec<String>> {
+        Some((0..9).map(|cell| format!("cell {}", cell)).collect())
+    }
+
+    fn obs_bounds(&self) -> Option<ObsBounds> {
+        // Every feature is a 0/1 indicator
+        Some(ObsBounds {
+            low: vec![0.0; 29],
+            high: vec![1.0; 29],
+        })
+    }
+
+    fn obs_layout(&self) -> Option<ObsLayout> {
+        Some(ObsLayout {
+            segments: vec![
+                ("board".to_string(), 18),
+                ("legal".to_string(), 9),
+                ("player".to_string(), 2),
+            ],
+        })
+    }
+
+    fn state_space_estimate(&self) -> Option<u64> {
+        // Each cell is empty, X, or O; unreachable boards make this an upper bound
+        Some(3u64.pow(9))
+    }
+
+    /// Every version since v1; `migrate_state` upgrades each older one
+    fn supported_schema_versions(&self) -> Vec<u32> {
+        (1..=STATE_SCHEMA_VERSION).collect()
+    }
+
+    fn is_deterministic(&self) -> bool {
+        true
+    }
+
+    fn is_state_complete(&self) -> bool {
+        // TicTacToe holds no per-instance data; the board is the whole state
+        true
+    }
+
+    fn current_player(&self, state: &Self::State) -> Option<u32> {
+        // X (1) moves first; once the game is over this stays on the last mover
+        Some(state.current_player as u32)
+    }
+
+    fn legal_actions(&self, state: &Self::State) -> Option<Vec<Self::Action>> {
+        Some(state.legal_moves().into_iter().map(Action::Place).collect())
+    }
+
+    fn legal_action_count(&self, state: &Self::State) -> Option<u32> {
+        // Counting the empty cells avoids building the move list
+        Some(state.legal_moves_mask().count_ones())
+    }
+
+    fn step_info(&self, state: &Self::State) -> Vec<(String, f32)> {
+        // A win is zero-sum, but a non-zero draw reward is paid to both
+        // players; report it so self-play does not negate it for the opponent
+        if state.winner == 3 && self.draw_reward != 0.0 {
+            vec![("opponent_reward".to_string(), self.draw_reward)]
+        } else {
+            Vec::new()
+        }
+    }
+
     fn reset(&mut self, _rng: &mut ChaCha20Rng, _hint: &[u8]) -> (Self::State, Self::Obs) {
         let state = State::new();
         let obs = Observation::from_state(&state);
@@ -294,69 +554,53 @@ impl Game for TicTacToe {
         state: &mut Self::State,
         action: Self::Action,
         _rng: &mut ChaCha20Rng,
-    ) -> (Self::Obs, f32, bool, u64) {
+    ) -> StepResult<Self::Obs> {
         let previous_player = state.current_player;
-        *state = state.make_move(action.position());
-
-        let obs = Observation::from_state(state);
-        let reward = Self::calculate_reward(state, previous_player);
-        let done = state.is_done();
-        let info = Self::compute_info_bits(state);
-
-        (obs, reward, done, info)
+        let position = action.position();
+        let legal = position < 9 && state.legal_moves_mask() & (1 << position) != 0;
+        if let Some(penalty) = self.illegal_move_penalty.filter(|_| !legal && !state.is_done()) {
+            return StepResult {
+                obs: Observation::from_state(state),
+                reward: penalty.reward,
+                done: penalty.ends_episode,
+                truncated: false,
+                info: Self::compute_info_bits(state),
+            };
+        }
+        *state = state.make_move(position);
+
+        StepResult {
+            obs: Observation::from_state(state),
+            reward: self.calculate_reward(state, previous_player),
+            done: state.is_done(),
+            // Every game ends within nine moves, so episodes are never cut short
+            truncated: false,
+            info: Self::compute_info_bits(state),
+        }
     }
 
     fn encode_state(state: &Self::State, out: &mut Vec<u8>) -> Result<(), EncodeError> {
-        // Simple binary encoding: board (9 bytes) + current_player (1 byte) + winner (1 byte)
-        out.extend_from_slice(&state.board);
-        out.push(state.current_player);
-        out.push(state.winner);
+        state.encode(out);
         Ok(())
     }
 
     fn decode_state(buf: &[u8]) -> Result<Self::State, DecodeError> {
-        if buf.len() != 11 {
-            return Err(DecodeError::InvalidLength {
-                expected: 11,
-                actual: buf.len(),
-            });
-        }
-
-        let mut board = [0u8; 9];
-        board.copy_from_slice(&buf[0..9]);
-
-        let current_player = buf[9];
-        let winner = buf[10];
-
-        // Validate the state
-        if current_player != 1 && current_player != 2 {
-            return Err(DecodeError::CorruptedData(format!(
-                "Invalid current_player: {}",
-                current_player
-            )));
-        }
-
-        if winner > 3 {
-            return Err(DecodeError::CorruptedData(format!(
-                "Invalid winner: {}",
-                winner
-            )));
+        if cfg!(feature = "packed-state") {
+            State::decode_packed(buf)
+        } else {
+            State::decode_v1(buf)
         }
+    }
 
-        for &cell in &board {
-            if cell > 2 {
-                return Err(DecodeError::CorruptedData(format!(
-                    "Invalid board cell: {}",
-                    cell
-                )));
-            }
-        }
+    fn migrate_state(buf: &[u8], from_version: u32) -> Result<Vec<u8>, DecodeError> {
+        let state = match from_version {
+            1 if STATE_SCHEMA_VERSION > 1 => State::decode_v1(buf)?,
+            version => return Err(DecodeError::UnsupportedVersion { version }),
+        };
 
-        Ok(State {
-            board,
-            current_player,
-            winner,
-        })
+        let mut migrated = Vec::with_capacity(STATE_LEN);
+        state.encode(&mut migrated);
+        Ok(migrated)
     }
 
     fn encode_action(action: &Self::Action, out: &mut Vec<u8>) -> Result<(), EncodeError> {
@@ -392,17 +636,34 @@ impl Game for TicTacToe {
 
     fn encode_obs(obs: &Self::Obs, out: &mut Vec<u8>) -> Result<(), EncodeError> {
         // Encode as 29 f32 values in little-endian format
-        for &value in &obs.board_view {
-            out.extend_from_slice(&value.to_le_bytes());
-        }
-        for &value in &obs.legal_moves {
-            out.extend_from_slice(&value.to_le_bytes());
-        }
-        for &value in &obs.current_player {
-            out.extend_from_slice(&value.to_le_bytes());
+        let values = obs
+            .board_view
+            .iter()
+            .chain(&obs.legal_moves)
+            .chain(&obs.current_player);
+        for &value in values {
+            Endianness::Little.put_f32(value, out);
         }
         Ok(())
     }
+
+    /// Write the 29 little-endian `f32` values straight into `out`
+    fn encode_obs_into(obs: &Self::Obs, out: &mut [u8]) -> Result<usize, EncodeError> {
+        let available = out.len();
+        let out = out.get_mut(..OBS_LEN).ok_or(EncodeError::BufferTooSmall {
+            needed: OBS_LEN,
+            available,
+        })?;
+        let values = obs
+            .board_view
+            .iter()
+            .chain(&obs.legal_moves)
+            .chain(&obs.current_player);
+        for (chunk, value) in out.chunks_exact_mut(4).zip(values) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        Ok(OBS_LEN)
+    }
 }
 
 #[cfg(test)]
@@ -434,6 +695,93 @@ mod tests {
         assert_eq!(state.legal_moves_mask(), 0x1FFu16 & !(1u16 << 4));
     }
 
+    #[test]
+    fn test_legal_action_count_counts_empty_cells() {
+        use engine_core::{ErasedGame, GameAdapter};
+
+        let game = TicTacToe::new();
+        let state = State::new();
+        assert_eq!(game.legal_action_count(&state), Some(9));
+
+        // Seven moves without a winner leave cells 6 and 8 empty
+        let near_full = [0, 1, 2, 4, 7, 3, 5]
+            .into_iter()
+            .fold(state, |state, pos| state.make_move(pos));
+        assert!(!near_full.is_done());
+        assert_eq!(game.legal_action_count(&near_full), Some(2));
+        assert_eq!(
+            game.legal_actions(&near_full).map(|actions| actions.len() as u32),
+            game.legal_action_count(&near_full)
+        );
+
+        // Finished games have no legal actions
+        let won = [0, 3, 1, 4, 2]
+            .into_iter()
+            .fold(State::new(), |state, pos| state.make_move(pos));
+        assert_eq!(game.legal_action_count(&won), Some(0));
+
+        let adapter = GameAdapter::new(TicTacToe::new());
+        let mut encoded = vec![STATE_SCHEMA_VERSION as u8];
+        TicTacToe::encode_state(&near_full, &mut encoded).unwrap();
+        assert_eq!(adapter.legal_action_count(&encoded).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_steps_draw_nothing_from_the_rng() {
+        use engine_core::determinism::verify_determinism;
+        use engine_core::{ErasedGame, GameAdapter};
+
+        let mut game = GameAdapter::new(TicTacToe::new()).with_rng_audit();
+        let (mut state, mut obs) = (Vec::new(), Vec::new());
+        game.reset(11, &[], &mut state, &mut obs).unwrap();
+        assert_eq!(game.last_rng_draws(), Some(0));
+
+        let actions: Vec<Vec<u8>> = [4, 0, 8, 2, 6].iter().map(|&pos| vec![pos]).collect();
+        for action in &actions {
+            let mut next_state = Vec::new();
+            game.step(&state, action, &mut next_state, &mut obs).unwrap();
+            assert_eq!(game.last_rng_draws(), Some(0));
+            state = next_state;
+        }
+
+        let mut other = GameAdapter::new(TicTacToe::new()).with_rng_audit();
+        verify_determinism(&mut game, &mut other, 11, &[], &actions).unwrap();
+    }
+
+    #[test]
+    fn test_registered_builds_compare_as_identical() {
+        use engine_core::build_compare::{compare_builds, BuildComparison};
+        use engine_core::{GameAdapter, Registry};
+
+        let registry = Registry::new();
+        registry.register_game("tictactoe".to_string(), || {
+            Box::new(GameAdapter::new(TicTacToe::new()))
+        });
+        registry.register_game("tictactoe-next".to_string(), || {
+            Box::new(GameAdapter::new(TicTacToe::new()))
+        });
+        registry.register_game("tictactoe-framed".to_string(), || {
+            Box::new(GameAdapter::new(TicTacToe::new()).with_obs_framing())
+        });
+
+        let actions: Vec<Vec<u8>> = [4, 0, 8, 2, 6].iter().map(|&pos| vec![pos]).collect();
+        let mut baseline = registry.create_game("tictactoe").unwrap();
+        let mut candidate = registry.create_game("tictactoe-next").unwrap();
+        assert_eq!(
+            compare_builds(&mut *baseline, &mut *candidate, 3, &[], &actions).unwrap(),
+            BuildComparison::Identical
+        );
+
+        let mut framed = registry.create_game("tictactoe-framed").unwrap();
+        assert_eq!(
+            compare_builds(&mut *baseline, &mut *framed, 3, &[], &actions).unwrap(),
+            BuildComparison::Diverged {
+                step: None,
+                field: "obs"
+            }
+        );
+    }
+
     #[test]
     fn test_make_move() {
         let state = State::new();
@@ -486,6 +834,82 @@ mod tests {
         assert!(state.is_done());
     }
 
+    #[test]
+    fn test_illegal_move_penalty_replaces_the_free_no_op() {
+        let illegal_second_move = |mut game: TicTacToe| {
+            let mut rng = ChaCha20Rng::seed_from_u64(0);
+            let (mut state, _) = game.reset(&mut rng, &[]);
+            game.step(&mut state, Action::Place(4), &mut rng);
+            let before = state;
+            let result = game.step(&mut state, Action::Place(4), &mut rng);
+            assert_eq!(state, before, "illegal moves never change the board");
+            result
+        };
+
+        assert_eq!(TicTacToe::new().illegal_move_penalty(), None);
+        let lenient = illegal_second_move(TicTacToe::new());
+        assert_eq!((lenient.reward, lenient.done), (0.0, false));
+
+        let penalty = IllegalMovePenalty {
+            reward: -1.0,
+            ends_episode: false,
+        };
+        let retry = illegal_second_move(TicTacToe::new().with_illegal_move_penalty(penalty));
+        assert_eq!((retry.reward, retry.done), (-1.0, false));
+
+        let ends = IllegalMovePenalty {
+            ends_episode: true,
+            ..penalty
+        };
+        let game = TicTacToe::with_draw_reward(-0.5).with_illegal_move_penalty(ends);
+        assert_eq!(game.draw_reward(), -0.5);
+        let terminal = illegal_second_move(game);
+        assert_eq!((terminal.reward, terminal.done), (-1.0, true));
+
+        // Legal moves are unaffected by the penalty
+        let mut game = TicTacToe::new().with_illegal_move_penalty(ends);
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let (mut state, _) = game.reset(&mut rng, &[]);
+        let result = game.step(&mut state, Action::Place(0), &mut rng);
+        assert_eq!((result.reward, result.done), (0.0, false));
+    }
+
+    #[test]
+    fn test_draw_reward_is_paid_on_the_drawing_move() {
+        // X O X / X O O / O X X
+        let moves = [0, 1, 2, 4, 3, 5, 7, 6, 8];
+        let play = |mut game: TicTacToe| {
+            let mut rng = ChaCha20Rng::seed_from_u64(0);
+            let (mut state, _) = game.reset(&mut rng, &[]);
+            moves
+                .iter()
+                .map(|&position| game.step(&mut state, Action::Place(position), &mut rng))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(TicTacToe::new().draw_reward(), 0.0);
+        let default = play(TicTacToe::new());
+        let penalized = play(TicTacToe::with_draw_reward(-0.25));
+
+        let last = penalized.last().unwrap();
+        assert!(last.done);
+        assert_eq!((last.info >> 20) & 0xF, 3, "game should end in a draw");
+        assert_eq!(last.reward, -0.25);
+        assert_eq!(default.last().unwrap().reward, 0.0);
+        // Only the terminal draw is affected
+        for (plain, drawn) in default.iter().zip(&penalized).take(moves.len() - 1) {
+            assert_eq!(plain.reward, drawn.reward);
+        }
+
+        // The other player is owed the same draw reward, not its negation
+        let drawn = State::from_fields([1, 2, 1, 1, 2, 2, 2, 1, 1], 2, 3).unwrap();
+        assert_eq!(
+            TicTacToe::with_draw_reward(-0.25).step_info(&drawn),
+            vec![("opponent_reward".to_string(), -0.25)]
+        );
+        assert!(TicTacToe::new().step_info(&drawn).is_empty());
+    }
+
     #[test]
     fn test_observation_encoding() {
         let state = State::new();
@@ -508,17 +932,18 @@ mod tests {
         assert_eq!(state, State::new());
 
         let action = Action::Place(4);
-        let (_new_obs, reward, done, info) = game.step(&mut state.clone(), action, &mut rng);
+        let result = game.step(&mut state.clone(), action, &mut rng);
 
         // Should not be done after one move
-        assert!(!done);
+        assert!(!result.done);
+        assert!(!result.truncated);
         // Reward should be 0 for ongoing game
-        assert_eq!(reward, 0.0);
+        assert_eq!(result.reward, 0.0);
 
         // Mask should no longer include the center position
-        assert_eq!(info & 0x1FF, 0x1FFu64 & !(1u64 << 4));
+        assert_eq!(result.info & 0x1FF, 0x1FFu64 & !(1u64 << 4));
         // Next player should be O (value 2)
-        assert_eq!((info >> 16) & 0xF, 2);
+        assert_eq!((result.info >> 16) & 0xF, 2);
     }
 
     #[test]
@@ -563,6 +988,33 @@ mod tests {
         assert_eq!(buf.len(), 116);
     }
 
+    #[test]
+    fn test_observation_slice_encoding_matches_vec_encoding() {
+        let state = State {
+            board: [1, 0, 2, 0, 0, 0, 0, 0, 0],
+            current_player: 2,
+            winner: 0,
+        };
+        let obs = Observation::from_state(&state);
+        let mut expected = Vec::new();
+        TicTacToe::encode_obs(&obs, &mut expected).unwrap();
+
+        let mut slice = [0xff; 120];
+        let written = TicTacToe::encode_obs_into(&obs, &mut slice).unwrap();
+        assert_eq!(written, 116);
+        assert_eq!(&slice[..written], expected.as_slice());
+        assert_eq!(slice[116..], [0xff; 4]);
+
+        let err = TicTacToe::encode_obs_into(&obs, &mut [0; 115]).unwrap_err();
+        assert!(matches!(
+            err,
+            EncodeError::BufferTooSmall {
+                needed: 116,
+                available: 115
+            }
+        ));
+    }
+
     #[test]
     fn test_engine_capabilities() {
         let game = TicTacToe::new();
@@ -577,6 +1029,78 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_action_names_in_index_order() {
+        use engine_core::{ErasedGame, GameAdapter};
+
+        let adapter = GameAdapter::new(TicTacToe::new());
+        let names = ErasedGame::action_names(&adapter).unwrap();
+
+        assert_eq!(names.len(), 9);
+        for (index, name) in names.iter().enumerate() {
+            assert_eq!(name, &format!("cell {}", index));
+        }
+    }
+
+    #[test]
+    fn test_obs_normalization_leaves_observations_unchanged() {
+        use engine_core::{ErasedGame, GameAdapter};
+
+        let mut plain = GameAdapter::new(TicTacToe::new());
+        let mut normalized = GameAdapter::new(TicTacToe::new())
+            .with_obs_normalization()
+            .unwrap();
+
+        let (mut state, mut obs) = (Vec::new(), Vec::new());
+        let (mut normalized_state, mut normalized_obs) = (Vec::new(), Vec::new());
+        plain.reset(42, &[], &mut state, &mut obs).unwrap();
+        normalized
+            .reset(42, &[], &mut normalized_state, &mut normalized_obs)
+            .unwrap();
+        assert_eq!(obs, normalized_obs);
+
+        let (mut next_state, mut next_obs) = (Vec::new(), Vec::new());
+        plain.step(&state, &[4], &mut next_state, &mut next_obs).unwrap();
+        normalized
+            .step(&state, &[4], &mut normalized_state, &mut normalized_obs)
+            .unwrap();
+        assert_eq!(next_obs, normalized_obs);
+    }
+
+    #[test]
+    fn test_initial_state_kind_is_fixed() {
+        use engine_core::{ErasedGame, GameAdapter};
+
+        let game = TicTacToe::new();
+        assert_eq!(game.initial_state_kind(), InitialStateKind::Fixed);
+
+        let adapter = GameAdapter::new(TicTacToe::new());
+        assert_eq!(ErasedGame::initial_state_kind(&adapter), InitialStateKind::Fixed);
+    }
+
+    #[test]
+    fn test_obs_layout_segments_cover_the_observation() {
+        use engine_core::obs_stack::ObsStack;
+
+        let layout = TicTacToe::new().obs_layout().unwrap();
+        assert_eq!(layout.total(), 29);
+        assert_eq!(layout.total() * 4, TicTacToe::OBS_ENCODED_SIZE.unwrap());
+
+        let stacked = ObsStack::<TicTacToe, 2>::new(TicTacToe::new());
+        let layout = stacked.obs_layout().unwrap();
+        assert_eq!(layout.total(), 58);
+        assert_eq!(layout.segments[3], ("frame1.board".to_string(), 18));
+    }
+
+    #[test]
+    fn test_state_space_estimate_is_finite() {
+        use engine_core::{ErasedGame, GameAdapter};
+
+        assert_eq!(TicTacToe::new().state_space_estimate(), Some(19_683));
+        let adapter = GameAdapter::new(TicTacToe::new());
+        assert_eq!(ErasedGame::state_space_estimate(&adapter), Some(19_683));
+    }
+
     #[test]
     fn test_invalid_state_decoding() {
         // Test wrong length
@@ -587,17 +1111,203 @@ mod tests {
         // Test invalid current_player
         let mut buf = vec![0; 11];
         buf[9] = 5; // Invalid player
-        let result = TicTacToe::decode_state(&buf);
+        let result = State::decode_v1(&buf);
         assert!(result.is_err());
 
         // Test invalid winner
         let mut buf = vec![0; 11];
         buf[9] = 1; // Valid player
         buf[10] = 5; // Invalid winner
-        let result = TicTacToe::decode_state(&buf);
+        let result = State::decode_v1(&buf);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_migrate_v1_state_to_current() {
+        // Synthetic v1 state: X in the center, O to move
+        let mut v1 = vec![0, 0, 0, 0, 1, 0, 0, 0, 0];
+        v1.push(2); // current_player
+        v1.push(0); // winner
+
+        if STATE_SCHEMA_VERSION == 1 {
+            // Nothing to migrate; the adapter decodes v1 directly
+            assert!(matches!(
+                TicTacToe::migrate_state(&v1, 1),
+                Err(DecodeError::UnsupportedVersion { version: 1 })
+            ));
+        } else {
+            let migrated = TicTacToe::migrate_state(&v1, 1).unwrap();
+            assert_eq!(migrated.len(), STATE_LEN);
+
+            let state = TicTacToe::decode_state(&migrated).unwrap();
+            assert_eq!(state.board, [0, 0, 0, 0, 1, 0, 0, 0, 0]);
+            assert_eq!(state.current_player, 2);
+            assert_eq!(state.winner, 0);
+            assert!(TicTacToe::migrate_state(&v1[..10], 1).is_err());
+        }
+
+        // Unknown versions are rejected
+        assert!(matches!(
+            TicTacToe::migrate_state(&v1, 0),
+            Err(DecodeError::UnsupportedVersion { version: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_packed_state_roundtrips_every_cell_value() {
+        for pos in 0..9 {
+            for cell in 0..=2 {
+                let mut state = State::new();
+                state.board[pos] = cell;
+                state.current_player = 2;
+                state.winner = cell;
+
+                let mut buf = Vec::new();
+                state.encode_packed(&mut buf);
+                assert_eq!(buf.len(), STATE_V2_LEN);
+                assert_eq!(State::decode_packed(&buf).unwrap(), state);
+
+                let mut v1 = Vec::new();
+                state.encode_v1(&mut v1);
+                assert_eq!(v1.len(), STATE_V1_LEN);
+                assert_eq!(State::decode_v1(&v1).unwrap(), state);
+            }
+        }
+
+        // A full board and a draw use every field at once
+        let state = State {
+            board: [1, 2, 1, 1, 2, 2, 2, 1, 1],
+            current_player: 1,
+            winner: 3,
+        };
+        let mut buf = Vec::new();
+        state.encode_packed(&mut buf);
+        assert_eq!(State::decode_packed(&buf).unwrap(), state);
+    }
+
+    #[test]
+    fn test_packed_state_rejects_corrupted_bits() {
+        let mut buf = Vec::new();
+        State::new().encode_packed(&mut buf);
+        let packed = u32::from_le_bytes(buf.clone().try_into().unwrap());
+
+        let corrupt = |bits: u32| State::decode_packed(&bits.to_le_bytes()).unwrap_err();
+        // Cell 0 holding 3, player 0, and a bit above the layout
+        assert!(matches!(corrupt(packed | 0b11), DecodeError::CorruptedData(_)));
+        assert!(matches!(
+            corrupt(packed & !(0b11 << PACKED_PLAYER_SHIFT)),
+            DecodeError::CorruptedData(_)
+        ));
+        assert!(matches!(corrupt(packed | 1 << PACKED_BITS), DecodeError::CorruptedData(_)));
+        assert!(matches!(
+            State::decode_packed(&buf[..3]),
+            Err(DecodeError::InvalidLength { expected: 4, actual: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_capability_encoding_matches_active_layout() {
+        let caps = TicTacToe::new().capabilities();
+        assert_eq!(caps.encoding.state, STATE_ENCODING);
+        assert!(caps
+            .encoding
+            .state
+            .ends_with(&format!(":v{}", caps.encoding.schema_version)));
+
+        let state = State::new().make_move(8);
+        let mut buf = Vec::new();
+        TicTacToe::encode_state(&state, &mut buf).unwrap();
+        assert_eq!(buf.len(), STATE_LEN);
+        assert_eq!(TicTacToe::decode_state(&buf).unwrap(), state);
+    }
+
+    #[test]
+    fn test_encoded_sizes_match_real_lengths() {
+        use engine_core::erased::EncodedSizes;
+        use engine_core::{ErasedGame, GameAdapter};
+
+        let mut adapter = GameAdapter::new(TicTacToe::new());
+        let sizes = adapter.encoded_sizes();
+        assert_eq!(
+            sizes,
+            EncodedSizes {
+                state: Some(1 + STATE_LEN),
+                obs: Some(116),
+                action: Some(1),
+            }
+        );
+
+        let mut state_buf = Vec::new();
+        let mut obs_buf = Vec::new();
+        adapter.reset(7, &[], &mut state_buf, &mut obs_buf).unwrap();
+        assert_eq!(Some(state_buf.len()), sizes.state);
+        assert_eq!(Some(obs_buf.len()), sizes.obs);
+
+        let mut action_buf = Vec::new();
+        TicTacToe::encode_action(&Action::Place(4), &mut action_buf).unwrap();
+        assert_eq!(Some(action_buf.len()), sizes.action);
+
+        let mut next_state = Vec::new();
+        adapter.step(&state_buf, &action_buf, &mut next_state, &mut obs_buf).unwrap();
+        assert_eq!(Some(next_state.len()), sizes.state);
+        assert_eq!(Some(obs_buf.len()), sizes.obs);
+    }
+
+    #[test]
+    fn test_adapter_decodes_v1_states() {
+        use engine_core::{ErasedGame, GameAdapter};
+
+        let mut adapter = GameAdapter::new(TicTacToe::new());
+        let mut state_buf = Vec::new();
+        let mut obs_buf = Vec::new();
+
+        adapter.reset(42, &[], &mut state_buf, &mut obs_buf).unwrap();
+        assert_eq!(state_buf[0], STATE_SCHEMA_VERSION as u8);
+        assert_eq!(state_buf.len(), 1 + STATE_LEN);
+
+        // A v1 state is decoded directly, or migrated first under `packed-state`
+        let v1_state = vec![1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 2, 0];
+        let mut next_state = Vec::new();
+        adapter.step(&v1_state, &[0], &mut next_state, &mut obs_buf).unwrap();
+        let next = TicTacToe::decode_state(&next_state[1..]).unwrap();
+        assert_eq!(next_state[0], STATE_SCHEMA_VERSION as u8);
+        assert_eq!(next.board, [2, 0, 0, 0, 1, 0, 0, 0, 0]);
+        assert_eq!(next.current_player, 1);
+    }
+
+    #[test]
+    fn test_adapter_rejects_out_of_range_action_before_decoding() {
+        use engine_core::erased::ErasedGameError;
+        use engine_core::{ErasedGame, GameAdapter};
+
+        let mut adapter = GameAdapter::new(TicTacToe::new());
+        let (mut state, mut obs) = (Vec::new(), Vec::new());
+        adapter.reset(0, &[], &mut state, &mut obs).unwrap();
+
+        // decode_action would report Decoding; the generic check answers first
+        let err = adapter.step(&state, &[9], &mut Vec::new(), &mut obs).unwrap_err();
+        assert!(
+            matches!(&err, ErasedGameError::InvalidAction(msg) if msg.contains("n=9")),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_advertises_every_migratable_schema_version() {
+        use engine_core::{ErasedGame, GameAdapter};
+
+        let expected = if cfg!(feature = "packed-state") {
+            vec![1, 2]
+        } else {
+            vec![1]
+        };
+        assert_eq!(TicTacToe::new().supported_schema_versions(), expected);
+        let adapter = GameAdapter::new(TicTacToe::new());
+        assert_eq!(adapter.supported_schema_versions(), expected);
+        assert!(expected.contains(&adapter.capabilities().encoding.schema_version));
+    }
+
     #[test]
     fn test_invalid_action_decoding() {
         // Test wrong length
@@ -627,4 +1337,309 @@ mod tests {
         // Four occupied squares
         assert_eq!((info >> 24) & 0xF, 4);
     }
+
+    /// Replays the golden X win along the 0-4-8 diagonal. A change to
+    /// `check_winner`, rewards, or the encodings shows up as the first step
+    /// that no longer matches. The golden records the v1 state layout.
+    #[test]
+    #[cfg(not(feature = "packed-state"))]
+    fn test_golden_diagonal_win_replays() {
+        use engine_core::golden::GoldenTrajectory;
+        use engine_core::GameAdapter;
+
+        let golden: GoldenTrajectory = include_str!("../golden/x_diagonal_win.golden")
+            .parse()
+            .unwrap();
+        let last = golden.steps.last().unwrap();
+        assert!(last.done);
+        assert_eq!(last.reward, 1.0);
+
+        golden.assert_replays(&mut GameAdapter::new(TicTacToe::new()));
+    }
+
+    /// Reset `plain` and `wrapped` alike, then play `moves` on both
+    ///
+    /// Asserts the wrapper leaves every step outcome unchanged and returns the
+    /// `(plain, wrapped)` observations of the reset followed by each step.
+    fn step_both(
+        plain: &mut dyn engine_core::ErasedGame,
+        wrapped: &mut dyn engine_core::ErasedGame,
+        moves: &[u8],
+    ) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let (mut plain_state, mut plain_obs) = (Vec::new(), Vec::new());
+        let (mut state, mut obs) = (Vec::new(), Vec::new());
+        plain.reset(7, &[], &mut plain_state, &mut plain_obs).unwrap();
+        wrapped.reset(7, &[], &mut state, &mut obs).unwrap();
+        let mut observations = vec![(plain_obs, obs)];
+
+        for &action in moves {
+            let (mut next_plain_state, mut next_plain_obs) = (Vec::new(), Vec::new());
+            let (mut next_state, mut next_obs) = (Vec::new(), Vec::new());
+            let expected = plain
+                .step(&plain_state, &[action], &mut next_plain_state, &mut next_plain_obs)
+                .unwrap();
+            let outcome = wrapped
+                .step(&state, &[action], &mut next_state, &mut next_obs)
+                .unwrap();
+            assert_eq!(outcome, expected);
+
+            observations.push((next_plain_obs, next_obs));
+            plain_state = next_plain_state;
+            state = next_state;
+        }
+        observations
+    }
+
+    #[test]
+    fn test_obs_stack_concatenates_the_last_two_observations() {
+        use engine_core::obs_stack::ObsStack;
+        use engine_core::{ErasedGame, GameAdapter};
+
+        let mut plain = GameAdapter::new(TicTacToe::new());
+        let mut stacked = GameAdapter::new(ObsStack::<TicTacToe, 2>::new(TicTacToe::new()));
+        let frame_len = 29 * 4;
+        assert_eq!(stacked.encoded_sizes().obs, Some(2 * frame_len));
+        assert_eq!(stacked.capabilities().encoding.obs, "stack2(f32x29:v1)");
+
+        let observations = step_both(&mut plain, &mut stacked, &[4, 0, 8]);
+
+        // Reset fills both slots with the initial observation
+        let (plain_obs, obs) = &observations[0];
+        assert_eq!(obs.len(), 2 * frame_len);
+        assert_eq!(&obs[..frame_len], &plain_obs[..]);
+        assert_eq!(&obs[frame_len..], &plain_obs[..]);
+
+        // Oldest frame first, newest last
+        for pair in observations.windows(2) {
+            let (previous, _) = &pair[0];
+            let (plain_obs, obs) = &pair[1];
+            assert_eq!(&obs[..frame_len], &previous[..]);
+            assert_eq!(&obs[frame_len..], &plain_obs[..]);
+        }
+    }
+
+    #[test]
+    fn test_big_endian_obs_reverses_each_value() {
+        use engine_core::endian::Endianness;
+        use engine_core::{ErasedGame, GameAdapter};
+
+        let mut little = GameAdapter::new(TicTacToe::new());
+        let mut big = GameAdapter::new(TicTacToe::new()).with_obs_endianness(Endianness::Big).unwrap();
+        assert_eq!(little.capabilities().encoding.obs, "f32x29:v1");
+        assert_eq!(big.capabilities().encoding.obs, "f32x29be:v1");
+
+        for (little_obs, big_obs) in step_both(&mut little, &mut big, &[4]) {
+            assert_eq!(big_obs.len(), 29 * 4);
+            for (le, be) in little_obs.chunks_exact(4).zip(big_obs.chunks_exact(4)) {
+                let reversed: Vec<u8> = le.iter().rev().copied().collect();
+                assert_eq!(be, &reversed[..]);
+                let value = f32::from_le_bytes(le.try_into().unwrap());
+                assert_eq!(f32::from_be_bytes(be.try_into().unwrap()), value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_action_history_tracks_the_last_three_moves() {
+        use engine_core::action_history::ActionHistory;
+        use engine_core::{ErasedGame, GameAdapter};
+
+        let mut plain = GameAdapter::new(TicTacToe::new());
+        let mut history = GameAdapter::new(ActionHistory::<TicTacToe, 3, 9>::new(TicTacToe::new()));
+        let frame_len = 29 * 4;
+        let block_len = 9 * 4;
+        assert_eq!(history.encoded_sizes().obs, Some(frame_len + 3 * block_len));
+        assert_eq!(history.capabilities().encoding.obs, "history3x9(f32x29:v1)");
+
+        let one_hot = |index: Option<usize>| -> Vec<u8> {
+            (0..9)
+                .flat_map(|i| (if Some(i) == index { 1.0f32 } else { 0.0 }).to_le_bytes())
+                .collect()
+        };
+
+        let moves = [4u8, 0, 8, 2];
+        let observations = step_both(&mut plain, &mut history, &moves);
+
+        // Reset starts with an all-zero history
+        let (plain_obs, obs) = &observations[0];
+        assert_eq!(obs.len(), frame_len + 3 * block_len);
+        assert_eq!(&obs[..frame_len], &plain_obs[..]);
+        assert!(obs[frame_len..].iter().all(|&byte| byte == 0));
+
+        for (played, (plain_obs, obs)) in observations[1..].iter().enumerate() {
+            assert_eq!(obs.len(), frame_len + 3 * block_len);
+            assert_eq!(&obs[..frame_len], &plain_obs[..]);
+
+            // Oldest of the last three moves first, empty slots before any move
+            let recent = &moves[..=played];
+            let mut slots: Vec<Option<usize>> = recent.iter().map(|&m| Some(m as usize)).collect();
+            while slots.len() < 3 {
+                slots.insert(0, None);
+            }
+            let slots = &slots[slots.len() - 3..];
+            for (slot, block) in slots.iter().zip(obs[frame_len..].chunks(block_len)) {
+                assert_eq!(block, &one_hot(*slot)[..]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_step_number_obs_appends_increasing_progress() {
+        use engine_core::step_number::{StepNumberObs, StepNumberState};
+        use engine_core::{ErasedGame, GameAdapter};
+
+        let mut plain = GameAdapter::new(TicTacToe::new());
+        let mut numbered = GameAdapter::new(StepNumberObs::new(TicTacToe::new()));
+        let frame_len = 29 * 4;
+        assert_eq!(numbered.encoded_sizes().obs, Some(frame_len + 4));
+        assert_eq!(numbered.capabilities().encoding.obs, "stepnum(f32x29:v1)");
+
+        let progress = |obs: &[u8]| f32::from_le_bytes(obs[frame_len..].try_into().unwrap());
+
+        let observations = step_both(&mut plain, &mut numbered, &[4, 0, 8]);
+        let mut previous = -1.0;
+        for (taken, (plain_obs, obs)) in observations.iter().enumerate() {
+            assert_eq!(obs.len(), frame_len + 4);
+            assert_eq!(&obs[..frame_len], &plain_obs[..]);
+
+            // Zero at reset, then one ninth of TicTacToe's horizon per move
+            let current = progress(obs);
+            assert_eq!(current, taken as f32 / 9.0);
+            assert!(current > previous);
+            previous = current;
+        }
+
+        // Counts past the horizon stay clamped at 1
+        let mut game = StepNumberObs::new(TicTacToe::new());
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let (inner, _) = game.inner().clone().reset(&mut rng, &[]);
+        let mut state = StepNumberState { inner, step: 40 };
+        let result = game.step(&mut state, Action::Place(4), &mut rng);
+        assert_eq!(state.step, 41);
+        assert_eq!(progress(&result.obs), 1.0);
+    }
+
+    #[test]
+    fn test_obs_mask_zeroes_the_current_player_indicator() {
+        use engine_core::obs_mask::ObsMask;
+        use engine_core::{ErasedGame, GameAdapter};
+
+        let values = |obs: &[u8]| -> Vec<f32> {
+            obs.chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+                .collect()
+        };
+        let mask: Vec<bool> = (0..29).map(|i| i >= 27).collect();
+        assert!(ObsMask::new(TicTacToe::new(), mask[1..].to_vec()).is_err());
+
+        let mut plain = GameAdapter::new(TicTacToe::new());
+        let mut masked = GameAdapter::new(ObsMask::new(TicTacToe::new(), mask).unwrap());
+        assert_eq!(masked.capabilities().encoding.obs, "masked(f32x29:v1)");
+
+        let observations = step_both(&mut plain, &mut masked, &[]);
+        let (plain_obs, obs) = &observations[0];
+
+        let (expected, actual) = (values(plain_obs), values(obs));
+        assert_eq!(expected[27..], [1.0, 0.0]);
+        assert_eq!(actual[27..], [0.0, 0.0]);
+        assert_eq!(actual[..27], expected[..27]);
+    }
+
+    #[test]
+    fn test_quantized_obs_dequantizes_within_half_a_step() {
+        use engine_core::endian::Endianness;
+        use engine_core::erased::ErasedGameError;
+        use engine_core::obs_quantize::QuantizeObs;
+        use engine_core::{ErasedGame, GameAdapter};
+
+        let mut plain = GameAdapter::new(TicTacToe::new());
+        let mut quantized = GameAdapter::new(QuantizeObs::new(TicTacToe::new()).unwrap());
+        assert_eq!(quantized.capabilities().encoding.obs, "quant_u8(f32x29:v1)");
+        assert_eq!(quantized.encoded_sizes().obs, Some(29));
+        let quantization = quantized.obs_quantization().unwrap();
+
+        // X center, O corner, X corner: a mid-game obs with both marks set
+        let observations = step_both(&mut plain, &mut quantized, &[4, 0, 8]);
+        let (obs, quantized_obs) = observations.last().unwrap();
+        assert_eq!(quantized_obs.len(), 29);
+
+        let expected: Vec<f32> = obs
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        let restored = quantization.dequantize(quantized_obs);
+        let tolerance = quantization.scale[0] / 2.0;
+        for (restored, expected) in restored.iter().zip(&expected) {
+            assert!((restored - expected).abs() <= tolerance, "{} vs {}", restored, expected);
+        }
+
+        // Byte-swapping 32-bit words would scramble the quantized bytes
+        let big = GameAdapter::new(QuantizeObs::new(TicTacToe::new()).unwrap())
+            .with_obs_endianness(Endianness::Big);
+        assert!(matches!(big, Err(ErasedGameError::Encoding(_))));
+    }
+
+    #[test]
+    fn test_obs_converts_to_an_ndarray_shaped_by_capabilities() {
+        use engine_core::{ErasedGame, GameAdapter};
+        use engine_obs::{ObsError, ObsSpec};
+        use engine_proto::{Capabilities, EncodedSizes, Encoding, ObsSegment};
+
+        let mut game = GameAdapter::new(TicTacToe::new());
+        let capabilities = Capabilities {
+            enc: Some(Encoding {
+                obs: game.capabilities().encoding.obs,
+                ..Default::default()
+            }),
+            sizes: Some(EncodedSizes {
+                obs: game.encoded_sizes().obs.map(|size| size as u32),
+                ..Default::default()
+            }),
+            obs_layout: game
+                .obs_layout()
+                .unwrap()
+                .segments
+                .into_iter()
+                .map(|(name, size)| ObsSegment {
+                    name,
+                    size: size as u32,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let spec = ObsSpec::from_capabilities(&capabilities).unwrap();
+
+        // X takes the center and O the top-left corner; X to move
+        let (mut state, mut obs) = (Vec::new(), Vec::new());
+        game.reset(3, &[], &mut state, &mut obs).unwrap();
+        for action in [4u8, 0] {
+            let mut next_state = Vec::new();
+            game.step(&state, &[action], &mut next_state, &mut obs).unwrap();
+            state = next_state;
+        }
+
+        let array = spec.to_array(&obs).unwrap();
+        assert_eq!(array.shape(), &[29]);
+        let mut expected = [0.0; 29];
+        expected[4] = 1.0;
+        expected[9] = 1.0;
+        for pos in [1, 2, 3, 5, 6, 7, 8] {
+            expected[18 + pos] = 1.0;
+        }
+        expected[27] = 1.0;
+        assert_eq!(array.to_vec(), expected);
+        assert_eq!(spec.segment("legal"), Some(18..27));
+        if let Some(view) = spec.view(&obs).unwrap() {
+            assert_eq!(view, array);
+        }
+
+        assert_eq!(
+            spec.to_array(&obs[..obs.len() - 4]).unwrap_err(),
+            ObsError::LengthMismatch {
+                dims: 29,
+                expected: 116,
+                actual: 112,
+            }
+        );
+    }
 }